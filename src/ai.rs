@@ -1,13 +1,16 @@
-use crate::base_ai::{
-    average_bw_linear, combine_bw_linear, interleave_bw_linear, jiggle_linear, max_amp_for_linear,
-    AI,
-};
+use crate::base_ai::{jiggle_linear, max_amp_for_linear, AI, INIT_STD};
+use crate::distill::{distill_copy, DistillationConfig, TeacherSample};
+use crate::running_norm::RunningNorm;
+use crate::sim_for_ai::{ACTION_SIZE, OBSERVATION_SIZE};
+use crate::weights::{load_versioned, save_versioned};
+use burn::backend::Autodiff;
 use burn::module::Module;
 use burn::nn::{Initializer, Linear, LinearConfig};
 use burn::prelude::Backend;
 use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
 use burn::tensor::activation::{relu, tanh};
 use burn::tensor::{Distribution, Tensor};
+use rand::rngs::StdRng;
 
 #[derive(Module, Debug)]
 pub struct BigAI<B: Backend> {
@@ -16,70 +19,34 @@ pub struct BigAI<B: Backend> {
     hidden_1: Linear<B>,
     hidden_2: Linear<B>,
     hidden_3: Linear<B>,
+    norm: RunningNorm<B>,
 }
 
 impl<B: Backend> AI<B> for BigAI<B> {
-    fn jiggle(&self, d: &Distribution) -> Self {
+    fn jiggle(&self, d: &Distribution, rng: &mut StdRng) -> Self {
         Self {
-            input: jiggle_linear(&self.input, &d),
-            output: jiggle_linear(&self.output, &d),
-            hidden_1: jiggle_linear(&self.hidden_1, &d),
-            hidden_2: jiggle_linear(&self.hidden_2, &d),
-            hidden_3: jiggle_linear(&self.hidden_3, &d),
+            input: jiggle_linear(&self.input, d, rng),
+            output: jiggle_linear(&self.output, d, rng),
+            hidden_1: jiggle_linear(&self.hidden_1, d, rng),
+            hidden_2: jiggle_linear(&self.hidden_2, d, rng),
+            hidden_3: jiggle_linear(&self.hidden_3, d, rng),
+            norm: self.norm.detached(),
         }
     }
 
-    fn offspring(&self, other_parent: &Self, d: &Distribution) -> Self {
-        Self {
-            input: combine_bw_linear(&self.input, &other_parent.input),
-            output: combine_bw_linear(&self.output, &other_parent.output),
-            hidden_1: combine_bw_linear(&self.hidden_1, &other_parent.hidden_1),
-            hidden_2: combine_bw_linear(&self.hidden_2, &other_parent.hidden_2),
-            hidden_3: combine_bw_linear(&self.hidden_3, &other_parent.hidden_3),
-        }
-        .jiggle(d)
-    }
-
-    fn offspring_iw(&self, other_parent: &Self, d: &Distribution) -> Self {
-        Self {
-            input: interleave_bw_linear(&self.input, &other_parent.input),
-            output: interleave_bw_linear(&self.output, &other_parent.output),
-            hidden_1: interleave_bw_linear(&self.hidden_1, &other_parent.hidden_1),
-            hidden_2: interleave_bw_linear(&self.hidden_2, &other_parent.hidden_2),
-            hidden_3: interleave_bw_linear(&self.hidden_3, &other_parent.hidden_3),
-        }
-        .jiggle(d)
-    }
-
-    fn offspring_aw(&self, other_parent: &Self, d: &Distribution) -> Self {
-        Self {
-            input: average_bw_linear(&self.input, &other_parent.input),
-            output: average_bw_linear(&self.output, &other_parent.output),
-            hidden_1: average_bw_linear(&self.hidden_1, &other_parent.hidden_1),
-            hidden_2: average_bw_linear(&self.hidden_2, &other_parent.hidden_2),
-            hidden_3: average_bw_linear(&self.hidden_3, &other_parent.hidden_3),
-        }
-        .jiggle(d)
-    }
-
-    fn offspring_layers(&self, other_parent: &Self, d: &Distribution) -> Self {
-        Self {
-            input: self.input.clone(),
-            output: other_parent.output.clone(),
-            hidden_1: self.hidden_1.clone(),
-            hidden_2: other_parent.hidden_2.clone(),
-            hidden_3: self.hidden_3.clone(),
-        }
-        .jiggle(d)
-    }
-
     fn apply(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
-        let x = relu(self.input.forward(input));
+        let x = relu(self.input.forward(self.norm.standardize(input)));
         let x = relu(self.hidden_1.forward(x));
         let x = relu(self.hidden_2.forward(x));
         let x = relu(self.hidden_3.forward(x));
-        let x = tanh(self.output.forward(x));
-        x
+        tanh(self.output.forward(x))
+    }
+
+    fn with_observations(&self, observations: &[Vec<f32>]) -> Self {
+        Self {
+            norm: self.norm.updated(observations),
+            ..self.clone()
+        }
     }
 
     fn max_amp(&self) -> f32 {
@@ -93,7 +60,7 @@ impl<B: Backend> AI<B> for BigAI<B> {
         all_maximums
             .iter()
             .max_by(|a, b| {
-                a.partial_cmp(&b)
+                a.partial_cmp(b)
                     .expect("max amplitude comparison failed across all layers")
             })
             .copied()
@@ -101,9 +68,7 @@ impl<B: Backend> AI<B> for BigAI<B> {
     }
 
     fn save_file(&self, filename: &str, recorder: &NamedMpkFileRecorder<FullPrecisionSettings>) {
-        self.clone()
-            .save_file(filename, recorder)
-            .expect("save failed");
+        save_versioned(self, filename, recorder).expect("save failed");
     }
 
     fn load_a_file(
@@ -111,37 +76,96 @@ impl<B: Backend> AI<B> for BigAI<B> {
         filename: &str,
         recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
     ) -> Self {
-        let device = self.input.devices()[0].clone();
-        self.load_file(filename, recorder, &device)
-            .expect("load failed")
+        load_versioned(self, filename, recorder).expect("load failed")
     }
 
     fn network_name(&self) -> &'static str {
         "BigAI"
     }
+
+    fn imitate(
+        &self,
+        samples: &[TeacherSample],
+        config: &DistillationConfig,
+        device: &B::Device,
+        rng: &mut StdRng,
+    ) -> Option<Self> {
+        Some(distill_copy(
+            self,
+            BigAI::<Autodiff<B>>::new(device),
+            samples,
+            config,
+            device,
+            rng,
+        ))
+    }
+
+    fn layers(&self) -> Vec<(&'static str, Linear<B>)> {
+        vec![
+            ("input", self.input.clone()),
+            ("hidden_1", self.hidden_1.clone()),
+            ("hidden_2", self.hidden_2.clone()),
+            ("hidden_3", self.hidden_3.clone()),
+            ("output", self.output.clone()),
+        ]
+    }
+
+    fn activations(&self) -> Vec<&'static str> {
+        vec!["relu", "relu", "relu", "relu", "tanh"]
+    }
+
+    fn with_layers(&self, layers: Vec<Linear<B>>) -> Self {
+        let [input, hidden_1, hidden_2, hidden_3, output] =
+            <[Linear<B>; 5]>::try_from(layers).expect("BigAI has five layers");
+        Self {
+            input,
+            output,
+            hidden_1,
+            hidden_2,
+            hidden_3,
+            norm: self.norm.detached(),
+        }
+    }
 }
 
 impl<B: Backend> BigAI<B> {
     pub fn new(device: &B::Device) -> Self {
-        let input_config = LinearConfig::new(64, 256)
+        let input_config = LinearConfig::new(OBSERVATION_SIZE, 256)
             .with_bias(true)
-            .with_initializer(Initializer::Normal { mean: 0., std: 1. });
+            .with_initializer(Initializer::Normal {
+                mean: 0.,
+                std: INIT_STD,
+            });
 
-        let output_config = LinearConfig::new(32, 7)
+        let output_config = LinearConfig::new(32, ACTION_SIZE)
             .with_bias(true)
-            .with_initializer(Initializer::Normal { mean: 0., std: 1. });
+            .with_initializer(Initializer::Normal {
+                mean: 0.,
+                std: INIT_STD,
+            });
 
         let hidden_1_config = LinearConfig::new(256, 128)
             .with_bias(true)
-            .with_initializer(Initializer::Normal { mean: 0., std: 1. });
-
-        let hidden_2_config = LinearConfig::new(128, 64)
-            .with_bias(true)
-            .with_initializer(Initializer::Normal { mean: 0., std: 1. });
-
-        let hidden_3_config = LinearConfig::new(64, 32)
-            .with_bias(true)
-            .with_initializer(Initializer::Normal { mean: 0., std: 1. });
+            .with_initializer(Initializer::Normal {
+                mean: 0.,
+                std: INIT_STD,
+            });
+
+        let hidden_2_config =
+            LinearConfig::new(128, 64)
+                .with_bias(true)
+                .with_initializer(Initializer::Normal {
+                    mean: 0.,
+                    std: INIT_STD,
+                });
+
+        let hidden_3_config =
+            LinearConfig::new(64, 32)
+                .with_bias(true)
+                .with_initializer(Initializer::Normal {
+                    mean: 0.,
+                    std: INIT_STD,
+                });
 
         Self {
             input: input_config.init(device),
@@ -149,6 +173,7 @@ impl<B: Backend> BigAI<B> {
             hidden_1: hidden_1_config.init(device),
             hidden_2: hidden_2_config.init(device),
             hidden_3: hidden_3_config.init(device),
+            norm: RunningNorm::new(OBSERVATION_SIZE, device),
         }
     }
 }
@@ -0,0 +1,142 @@
+use crate::evolution::Genome;
+use serde::{Deserialize, Serialize};
+
+/// How the age caps grow from one layer to the next, in multiples of the age gap.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgeScheme {
+    /// 1, 2, 3, 4, ...
+    Linear,
+    /// 1, 2, 4, 9, 16, ...
+    #[default]
+    Polynomial,
+    /// 1, 2, 4, 8, 16, ...
+    Exponential,
+}
+
+/// The Age-Layered Population Structure, given as an `[alps]` table of the training
+/// configuration. The islands become layers from the youngest to the oldest: an individual
+/// outgrowing the age cap of its layer moves up to the next one, and the bottom layer starts
+/// over with fresh random individuals every `age_gap` generations, so new material keeps
+/// coming in without having to compete with the long evolved.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlpsConfig {
+    pub age_gap: usize,
+    pub scheme: AgeScheme,
+}
+
+impl Default for AlpsConfig {
+    fn default() -> Self {
+        Self {
+            age_gap: 10,
+            scheme: AgeScheme::Polynomial,
+        }
+    }
+}
+
+impl AlpsConfig {
+    /// The oldest an individual of `layer` may be, for every layer but the top one.
+    pub fn age_cap(&self, layer: usize) -> usize {
+        let multiple = match self.scheme {
+            AgeScheme::Linear => layer + 1,
+            AgeScheme::Polynomial if layer < 2 => layer + 1,
+            AgeScheme::Polynomial => layer * layer,
+            AgeScheme::Exponential => 1 << layer,
+        };
+        self.age_gap * multiple
+    }
+
+    /// Whether the bottom layer starts over once `generation` is over.
+    pub fn reseeds_after(&self, generation: usize) -> bool {
+        self.age_gap > 0 && (generation + 1).is_multiple_of(self.age_gap)
+    }
+
+    /// Moves the individuals past the age cap of their layer, or all of the bottom layer when it
+    /// is reseeded, up to the next layer. There they replace the worst if they score better,
+    /// and are dropped otherwise. Every layer is given sorted best first and is kept so, at
+    /// most as large as it was. A layer left empty starts over with fresh individuals.
+    pub fn promote<A>(&self, layers: &mut [Vec<(f32, Genome<A>)>], reseed: bool) {
+        let sizes: Vec<usize> = layers.iter().map(Vec::len).collect();
+        let top = layers.len().saturating_sub(1);
+        let mut rising: Vec<(f32, Genome<A>)> = Vec::new();
+        for (layer, ranked) in layers.iter_mut().enumerate() {
+            ranked.append(&mut rising);
+            ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+            if layer < top {
+                let cap = if reseed && layer == 0 {
+                    None
+                } else {
+                    Some(self.age_cap(layer))
+                };
+                let (staying, leaving) = std::mem::take(ranked)
+                    .into_iter()
+                    .partition(|(_, genome)| cap.is_some_and(|cap| genome.age <= cap));
+                *ranked = staying;
+                rising = leaving;
+            }
+            ranked.truncate(sizes[layer]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aged(score: f32, age: usize) -> (f32, Genome<usize>) {
+        let mut genome = Genome::new(age);
+        genome.age = age;
+        (score, genome)
+    }
+
+    #[test]
+    fn test_age_caps() {
+        let caps = |scheme| {
+            (0..5)
+                .map(|layer| AlpsConfig { age_gap: 3, scheme }.age_cap(layer))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(caps(AgeScheme::Linear), vec![3, 6, 9, 12, 15]);
+        assert_eq!(caps(AgeScheme::Polynomial), vec![3, 6, 12, 27, 48]);
+        assert_eq!(caps(AgeScheme::Exponential), vec![3, 6, 12, 24, 48]);
+        let alps = AlpsConfig {
+            age_gap: 3,
+            ..AlpsConfig::default()
+        };
+        assert_eq!(
+            (0..9)
+                .filter(|&generation| alps.reseeds_after(generation))
+                .collect::<Vec<_>>(),
+            vec![2, 5, 8]
+        );
+    }
+
+    #[test]
+    fn test_old_individuals_move_up() {
+        let alps = AlpsConfig {
+            age_gap: 2,
+            scheme: AgeScheme::Linear,
+        };
+        let mut layers = vec![
+            vec![aged(0.9, 3), aged(0.5, 1), aged(0.1, 3)],
+            vec![aged(0.8, 4), aged(0.4, 3), aged(0.2, 9)],
+            vec![aged(0.7, 20), aged(0.3, 20)],
+        ];
+        alps.promote(&mut layers, false);
+        let ages = |layer: &Vec<(f32, Genome<usize>)>| {
+            layer
+                .iter()
+                .map(|(score, genome)| (*score, genome.age))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ages(&layers[0]), vec![(0.5, 1)]);
+        // the worst of the risen one is dropped, the layer keeps its size
+        assert_eq!(ages(&layers[1]), vec![(0.9, 3), (0.8, 4), (0.4, 3)]);
+        assert_eq!(ages(&layers[2]), vec![(0.7, 20), (0.3, 20)]);
+
+        alps.promote(&mut layers, true);
+        assert!(layers[0].is_empty());
+        assert_eq!(ages(&layers[1]), vec![(0.9, 3), (0.8, 4), (0.5, 1)]);
+    }
+}
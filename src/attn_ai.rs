@@ -0,0 +1,260 @@
+use crate::base_ai::{jiggle_linear, max_amp_for_linear, AI, INIT_STD};
+use crate::running_norm::RunningNorm;
+use crate::sim_for_ai::{ACTION_SIZE, OBSERVATION_SIZE};
+use crate::weights::{load_versioned, save_versioned};
+use burn::module::Module;
+use burn::nn::{Initializer, Linear, LinearConfig};
+use burn::prelude::Backend;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+use burn::tensor::activation::{softmax, tanh};
+use burn::tensor::{Distribution, Tensor};
+use rand::rngs::StdRng;
+
+/// One token per arm segment.
+const TOKENS: usize = ACTION_SIZE;
+/// The previous and current far corners of a segment.
+const TOKEN_FEATURES: usize = 8;
+const EMBEDDING_SIZE: usize = 8;
+/// The ball and basket slots and the action feedback, which belong to no segment.
+const GLOBAL_FEATURES: usize = OBSERVATION_SIZE - TOKENS * TOKEN_FEATURES;
+
+/// Experimental structure aware network: every segment's corners are embedded as a token, one
+/// self-attention block lets the segments look at each other, and the output head reads the
+/// tokens along with the features that belong to no segment.
+#[derive(Module, Debug)]
+pub struct AttnAI<B: Backend> {
+    embed: Linear<B>,
+    query: Linear<B>,
+    key: Linear<B>,
+    value: Linear<B>,
+    output: Linear<B>,
+    norm: RunningNorm<B>,
+}
+
+/// Pairs up the previous and current corners of every segment into a `[TOKENS, TOKEN_FEATURES]`
+/// tensor.
+fn tokens<B: Backend>(observation: Tensor<B, 1>) -> Tensor<B, 2> {
+    let half = TOKENS * TOKEN_FEATURES / 2;
+    let previous = observation
+        .clone()
+        .narrow(0, 0, half)
+        .reshape([TOKENS, TOKEN_FEATURES / 2]);
+    let current = observation
+        .narrow(0, half, half)
+        .reshape([TOKENS, TOKEN_FEATURES / 2]);
+    Tensor::cat(vec![previous, current], 1)
+}
+
+impl<B: Backend> AI<B> for AttnAI<B> {
+    fn jiggle(&self, d: &Distribution, rng: &mut StdRng) -> Self {
+        Self {
+            embed: jiggle_linear(&self.embed, d, rng),
+            query: jiggle_linear(&self.query, d, rng),
+            key: jiggle_linear(&self.key, d, rng),
+            value: jiggle_linear(&self.value, d, rng),
+            output: jiggle_linear(&self.output, d, rng),
+            norm: self.norm.detached(),
+        }
+    }
+
+    fn apply(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
+        let input = self.norm.standardize(input);
+        let global = input
+            .clone()
+            .narrow(0, TOKENS * TOKEN_FEATURES, GLOBAL_FEATURES);
+        let x = self.embed.forward(tokens(input));
+
+        let scores = self
+            .query
+            .forward(x.clone())
+            .matmul(self.key.forward(x.clone()).transpose())
+            .div_scalar((EMBEDDING_SIZE as f32).sqrt());
+        let x = x.clone() + softmax(scores, 1).matmul(self.value.forward(x));
+
+        tanh(self.output.forward(Tensor::cat(
+            vec![x.reshape([TOKENS * EMBEDDING_SIZE]), global],
+            0,
+        )))
+    }
+
+    fn with_observations(&self, observations: &[Vec<f32>]) -> Self {
+        Self {
+            norm: self.norm.updated(observations),
+            ..self.clone()
+        }
+    }
+
+    fn max_amp(&self) -> f32 {
+        let all_maximums = [
+            max_amp_for_linear(&self.embed),
+            max_amp_for_linear(&self.query),
+            max_amp_for_linear(&self.key),
+            max_amp_for_linear(&self.value),
+            max_amp_for_linear(&self.output),
+        ];
+        all_maximums
+            .iter()
+            .max_by(|a, b| {
+                a.partial_cmp(b)
+                    .expect("max amplitude comparison failed across all layers")
+            })
+            .copied()
+            .expect("no max amplitude found across all layers")
+    }
+
+    fn save_file(&self, filename: &str, recorder: &NamedMpkFileRecorder<FullPrecisionSettings>) {
+        save_versioned(self, filename, recorder).expect("save failed");
+    }
+
+    fn load_a_file(
+        self,
+        filename: &str,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> Self {
+        load_versioned(self, filename, recorder).expect("load failed")
+    }
+
+    fn network_name(&self) -> &'static str {
+        "AttnAI"
+    }
+
+    fn layers(&self) -> Vec<(&'static str, Linear<B>)> {
+        vec![
+            ("embed", self.embed.clone()),
+            ("query", self.query.clone()),
+            ("key", self.key.clone()),
+            ("value", self.value.clone()),
+            ("output", self.output.clone()),
+        ]
+    }
+
+    fn activations(&self) -> Vec<&'static str> {
+        vec!["none", "none", "none", "attention", "tanh"]
+    }
+
+    /// Query, key and value are parallel on the embedding, and the output reads the attended
+    /// embedding with the global part of the observation.
+    fn layer_inputs(&self) -> Vec<Vec<Option<usize>>> {
+        vec![
+            vec![None],
+            vec![Some(0)],
+            vec![Some(0)],
+            vec![Some(0)],
+            vec![Some(0), Some(1), Some(2), Some(3), None],
+        ]
+    }
+
+    /// The segment tokens as wide as the embedding reads them, and whatever the output head
+    /// reads besides the embedded tokens.
+    fn input_size(&self) -> usize {
+        let [token_features, embedding_size] = self.embed.weight.dims();
+        TOKENS * token_features
+            + self.output.weight.dims()[0].saturating_sub(TOKENS * embedding_size)
+    }
+
+    fn with_layers(&self, layers: Vec<Linear<B>>) -> Self {
+        let [embed, query, key, value, output] =
+            <[Linear<B>; 5]>::try_from(layers).expect("AttnAI has five layers");
+        Self {
+            embed,
+            query,
+            key,
+            value,
+            output,
+            norm: self.norm.detached(),
+        }
+    }
+}
+
+impl<B: Backend> AttnAI<B> {
+    pub fn new(device: &B::Device) -> Self {
+        Self::with_init_std(device, INIT_STD)
+    }
+
+    pub fn with_init_std(device: &B::Device, std: f64) -> Self {
+        let config = |d_input, d_output| {
+            LinearConfig::new(d_input, d_output)
+                .with_bias(true)
+                .with_initializer(Initializer::Normal { mean: 0., std })
+        };
+
+        Self {
+            embed: config(TOKEN_FEATURES, EMBEDDING_SIZE).init(device),
+            query: config(EMBEDDING_SIZE, EMBEDDING_SIZE).init(device),
+            key: config(EMBEDDING_SIZE, EMBEDDING_SIZE).init(device),
+            value: config(EMBEDDING_SIZE, EMBEDDING_SIZE).init(device),
+            output: config(TOKENS * EMBEDDING_SIZE + GLOBAL_FEATURES, ACTION_SIZE).init(device),
+            norm: RunningNorm::new(OBSERVATION_SIZE, device),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+
+    #[test]
+    fn test_tokens_pair_up_segment_corners() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let observation: Vec<f32> = (0..OBSERVATION_SIZE).map(|i| i as f32).collect();
+        let tokens: Vec<f32> = tokens(Tensor::<BE, 1>::from_floats(
+            observation.as_slice(),
+            &device,
+        ))
+        .to_data()
+        .to_vec()
+        .unwrap();
+        let half = TOKENS * TOKEN_FEATURES / 2;
+        assert_eq!(
+            tokens[..TOKEN_FEATURES],
+            [
+                0.,
+                1.,
+                2.,
+                3.,
+                half as f32,
+                half as f32 + 1.,
+                half as f32 + 2.,
+                half as f32 + 3.
+            ]
+        );
+
+        // without values the attention adds nothing to the embeddings
+        let attn = AttnAI::<BE>::with_init_std(&device, 0.1);
+        let layers = attn.layers();
+        let mut silenced: Vec<_> = layers.iter().map(|(_, layer)| layer.clone()).collect();
+        silenced[3] = Linear {
+            weight: silenced[3].weight.clone().map(|w| w.zeros_like()),
+            bias: silenced[3].bias.clone().map(|b| b.map(|b| b.zeros_like())),
+        };
+        let silenced = attn.with_layers(silenced);
+
+        // a fresh running norm passes the observation through as it is
+        let embedded = layers[0]
+            .1
+            .forward(Tensor::<BE, 2>::zeros([TOKENS, TOKEN_FEATURES], &device));
+        let head_input = Tensor::cat(
+            vec![
+                embedded.reshape([TOKENS * EMBEDDING_SIZE]),
+                Tensor::zeros([GLOBAL_FEATURES], &device),
+            ],
+            0,
+        );
+        let expected: Vec<f32> = tanh(layers[4].1.forward(head_input))
+            .to_data()
+            .to_vec()
+            .unwrap();
+        let forces: Vec<f32> = silenced
+            .apply(Tensor::zeros([OBSERVATION_SIZE], &device))
+            .to_data()
+            .to_vec()
+            .unwrap();
+        assert_eq!(forces.len(), ACTION_SIZE);
+        for (force, expected) in forces.iter().zip(expected) {
+            assert!((force - expected).abs() < 1e-6);
+        }
+    }
+}
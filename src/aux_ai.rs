@@ -0,0 +1,319 @@
+use crate::base_ai::{jiggle_linear, max_amp_for_linear, AI, INIT_STD};
+use crate::running_norm::RunningNorm;
+use crate::sim_for_ai::{
+    apply_forces_and_step, build_observation, prepare_simulation, split_grip, ACTION_SIZE,
+    OBSERVATION_SIZE,
+};
+use crate::weights::{load_versioned, save_versioned};
+use burn::backend::Autodiff;
+use burn::module::{AutodiffModule, Module};
+use burn::nn::loss::{MseLoss, Reduction};
+use burn::nn::{Initializer, Linear, LinearConfig};
+use burn::optim::{AdamConfig, GradientsParams, Optimizer};
+use burn::prelude::Backend;
+use burn::record::{BinBytesRecorder, FullPrecisionSettings, NamedMpkFileRecorder, Recorder};
+use burn::tensor::activation::{relu, tanh};
+use burn::tensor::{Distribution, ElementConversion, Tensor};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+/// Settings for training the prediction head between generations.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AuxiliaryConfig {
+    pub steps: usize,
+    pub epochs: usize,
+    pub batch_size: usize,
+    pub learning_rate: f64,
+}
+
+impl Default for AuxiliaryConfig {
+    fn default() -> Self {
+        Self {
+            steps: 200,
+            epochs: 5,
+            batch_size: 50,
+            learning_rate: 1e-3,
+        }
+    }
+}
+
+/// An observation and where the ball was, normalized, after the step that followed it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BallSample {
+    pub observation: Vec<f32>,
+    pub next_ball: Vec<f32>,
+}
+
+/// A small network with a second head predicting the next normalized ball position from the
+/// shared trunk. Evolution works on the whole network, and between generations gradient descent
+/// on the prediction trains the trunk and the prediction head, so the trunk picks up features
+/// about the ball faster than selection alone would find them.
+#[derive(Module, Debug)]
+pub struct AuxAI<B: Backend> {
+    input: Linear<B>,
+    hidden: Linear<B>,
+    output: Linear<B>,
+    predict: Linear<B>,
+    norm: RunningNorm<B>,
+}
+
+impl<B: Backend> AuxAI<B> {
+    pub fn new(device: &B::Device) -> Self {
+        Self::with_init_std(device, INIT_STD)
+    }
+
+    pub fn with_init_std(device: &B::Device, std: f64) -> Self {
+        let config = |d_input, d_output| {
+            LinearConfig::new(d_input, d_output)
+                .with_bias(true)
+                .with_initializer(Initializer::Normal { mean: 0., std })
+        };
+
+        Self {
+            input: config(OBSERVATION_SIZE, 128).init(device),
+            hidden: config(128, 14).init(device),
+            output: config(14, ACTION_SIZE).init(device),
+            predict: config(14, 2).init(device),
+            norm: RunningNorm::new(OBSERVATION_SIZE, device),
+        }
+    }
+
+    fn trunk(&self, normalized: Tensor<B, 1>) -> Tensor<B, 1> {
+        let x = relu(self.input.forward(normalized));
+        relu(self.hidden.forward(x))
+    }
+
+    /// Where the prediction head expects the ball after the step, normalized.
+    pub fn predict_ball(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
+        self.predict
+            .forward(self.trunk(self.norm.standardize(input)))
+    }
+
+    /// Lets the network drive a fresh world and records the ball after every step.
+    pub fn collect_ball_samples(
+        &self,
+        config: &AuxiliaryConfig,
+        device: &B::Device,
+    ) -> Vec<BallSample> {
+        let (mut world, mut frames, mut tensor_input) = prepare_simulation();
+        self.reset_state();
+        (0..config.steps)
+            .map(|_| {
+                build_observation(&mut tensor_input, &mut frames, &world);
+                let forces: Vec<f32> = self
+                    .apply(Tensor::from_floats(tensor_input.as_slice(), device))
+                    .to_data()
+                    .convert::<f32>()
+                    .to_vec()
+                    .expect("ai requested forces not available");
+                let (forces, grip) = split_grip(self, &forces);
+                apply_forces_and_step(&mut world, forces, grip);
+                // a world without balls has them at the origin, as its observations do
+                let (x, y) = world
+                    .ball_position()
+                    .map_or((0., 0.), |ball| world.normalize((ball.x, ball.y)));
+                BallSample {
+                    observation: tensor_input.clone(),
+                    next_ball: vec![x, y],
+                }
+            })
+            .collect()
+    }
+
+    /// Fits the prediction head and the trunk to the samples with an MSE loss, in batches
+    /// shuffled by `rng`, and returns the trained network with the mean loss of every epoch.
+    /// The control head is left as it is.
+    pub fn train_on(
+        &self,
+        samples: &[BallSample],
+        config: &AuxiliaryConfig,
+        device: &B::Device,
+        rng: &mut StdRng,
+    ) -> (Self, Vec<f32>) {
+        // the record carries over to the autodiff backend through bytes
+        let recorder = BinBytesRecorder::<FullPrecisionSettings>::default();
+        let bytes = recorder
+            .record(self.clone().into_record(), ())
+            .expect("could not record the network");
+        let mut trainee = AuxAI::<Autodiff<B>>::with_init_std(device, 0.).load_record(
+            recorder
+                .load(bytes, device)
+                .expect("could not load the network"),
+        );
+
+        let mut optimizer = AdamConfig::new().init::<Autodiff<B>, AuxAI<Autodiff<B>>>();
+        let mut order: Vec<usize> = (0..samples.len()).collect();
+        let mut epoch_losses = Vec::with_capacity(config.epochs);
+        for _ in 0..config.epochs {
+            order.shuffle(rng);
+            let mut total_loss = 0_f32;
+            let mut batches = 0;
+            for batch in order.chunks(config.batch_size) {
+                let predictions = batch
+                    .iter()
+                    .map(|&i| {
+                        trainee.predict_ball(Tensor::from_floats(
+                            samples[i].observation.as_slice(),
+                            device,
+                        ))
+                    })
+                    .collect();
+                let targets = batch
+                    .iter()
+                    .map(|&i| Tensor::from_floats(samples[i].next_ball.as_slice(), device))
+                    .collect();
+                let loss = MseLoss::new().forward(
+                    Tensor::<Autodiff<B>, 1>::stack::<2>(predictions, 0),
+                    Tensor::<Autodiff<B>, 1>::stack::<2>(targets, 0),
+                    Reduction::Mean,
+                );
+                total_loss += loss.clone().into_scalar().elem::<f32>();
+                batches += 1;
+
+                let grads = GradientsParams::from_grads(loss.backward(), &trainee);
+                trainee = optimizer.step(config.learning_rate, trainee, grads);
+            }
+            epoch_losses.push(total_loss / batches as f32);
+        }
+        (trainee.valid(), epoch_losses)
+    }
+}
+
+impl<B: Backend> AI<B> for AuxAI<B> {
+    fn jiggle(&self, d: &Distribution, rng: &mut StdRng) -> Self {
+        Self {
+            input: jiggle_linear(&self.input, d, rng),
+            hidden: jiggle_linear(&self.hidden, d, rng),
+            output: jiggle_linear(&self.output, d, rng),
+            predict: jiggle_linear(&self.predict, d, rng),
+            norm: self.norm.detached(),
+        }
+    }
+
+    fn apply(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
+        tanh(
+            self.output
+                .forward(self.trunk(self.norm.standardize(input))),
+        )
+    }
+
+    fn with_observations(&self, observations: &[Vec<f32>]) -> Self {
+        Self {
+            norm: self.norm.updated(observations),
+            ..self.clone()
+        }
+    }
+
+    fn max_amp(&self) -> f32 {
+        let all_maximums = [
+            max_amp_for_linear(&self.input),
+            max_amp_for_linear(&self.hidden),
+            max_amp_for_linear(&self.output),
+            max_amp_for_linear(&self.predict),
+        ];
+        all_maximums
+            .iter()
+            .max_by(|a, b| {
+                a.partial_cmp(b)
+                    .expect("max amplitude comparison failed across all layers")
+            })
+            .copied()
+            .expect("no max amplitude found across all layers")
+    }
+
+    fn train_auxiliary(&self, device: &B::Device, rng: &mut StdRng) -> Self {
+        let config = AuxiliaryConfig::default();
+        let samples = self.collect_ball_samples(&config, device);
+        self.train_on(&samples, &config, device, rng).0
+    }
+
+    fn save_file(&self, filename: &str, recorder: &NamedMpkFileRecorder<FullPrecisionSettings>) {
+        save_versioned(self, filename, recorder).expect("save failed");
+    }
+
+    fn load_a_file(
+        self,
+        filename: &str,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> Self {
+        load_versioned(self, filename, recorder).expect("load failed")
+    }
+
+    fn network_name(&self) -> &'static str {
+        "AuxAI"
+    }
+
+    fn layers(&self) -> Vec<(&'static str, Linear<B>)> {
+        vec![
+            ("input", self.input.clone()),
+            ("hidden", self.hidden.clone()),
+            ("output", self.output.clone()),
+            ("predict", self.predict.clone()),
+        ]
+    }
+
+    fn activations(&self) -> Vec<&'static str> {
+        vec!["relu", "relu", "tanh", "none"]
+    }
+
+    /// The forces and the ball prediction are two heads on the hidden layer.
+    fn layer_inputs(&self) -> Vec<Vec<Option<usize>>> {
+        vec![vec![None], vec![Some(0)], vec![Some(1)], vec![Some(1)]]
+    }
+
+    fn force_layers(&self) -> Vec<usize> {
+        vec![2]
+    }
+
+    /// The forces, the ball prediction is only read by `predict_ball`.
+    fn output_size(&self) -> usize {
+        self.output.weight.dims()[1]
+    }
+
+    fn with_layers(&self, layers: Vec<Linear<B>>) -> Self {
+        let [input, hidden, output, predict] =
+            <[Linear<B>; 4]>::try_from(layers).expect("AuxAI has four layers");
+        Self {
+            input,
+            hidden,
+            output,
+            predict,
+            norm: self.norm.detached(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_prediction_head_learns_the_ball() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let config = AuxiliaryConfig {
+            steps: 40,
+            epochs: 20,
+            batch_size: 10,
+            ..AuxiliaryConfig::default()
+        };
+        let network = AuxAI::<BE>::with_init_std(&device, 0.1);
+        let samples = network.collect_ball_samples(&config, &device);
+        assert_eq!(samples.len(), 40);
+        assert!(samples.iter().all(|sample| sample.next_ball.len() == 2));
+
+        let train = || network.train_on(&samples, &config, &device, &mut StdRng::seed_from_u64(7));
+        let (trained, losses) = train();
+        assert_eq!(losses, train().1);
+        assert!(losses[19] < losses[0], "{losses:?}");
+        let unchanged = |layer: &Linear<BE>, other: &Linear<BE>| {
+            layer.weight.val().to_data() == other.weight.val().to_data()
+        };
+        assert!(unchanged(&trained.output, &network.output));
+        assert!(!unchanged(&trained.predict, &network.predict));
+        assert!(!unchanged(&trained.input, &network.input));
+    }
+}
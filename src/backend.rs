@@ -0,0 +1,238 @@
+use burn::backend::candle::CandleDevice;
+use burn::backend::ndarray::NdArrayDevice;
+use burn::backend::{Candle, NdArray};
+use burn::prelude::Backend;
+use burn::tensor::f16;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Backends a run can be started on. GPU backends are only available when the crate is built
+/// with the matching cargo feature (`wgpu` or `cuda`).
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum BackendChoice {
+    #[default]
+    Candle,
+    NdArray,
+    #[cfg(feature = "wgpu")]
+    Wgpu,
+    #[cfg(feature = "cuda")]
+    Cuda,
+}
+
+impl FromStr for BackendChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "candle" => Ok(BackendChoice::Candle),
+            "ndarray" => Ok(BackendChoice::NdArray),
+            #[cfg(feature = "wgpu")]
+            "wgpu" => Ok(BackendChoice::Wgpu),
+            #[cfg(feature = "cuda")]
+            "cuda" => Ok(BackendChoice::Cuda),
+            other => Err(format!("unknown or disabled backend {other}")),
+        }
+    }
+}
+
+impl Display for BackendChoice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BackendChoice::Candle => "candle",
+            BackendChoice::NdArray => "ndarray",
+            #[cfg(feature = "wgpu")]
+            BackendChoice::Wgpu => "wgpu",
+            #[cfg(feature = "cuda")]
+            BackendChoice::Cuda => "cuda",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Float element the networks compute in. Half precision needs backend support: cuda takes
+/// both, candle on the CPU and wgpu only f16, and ndarray neither.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Precision {
+    #[default]
+    F32,
+    F16,
+    BF16,
+}
+
+impl FromStr for Precision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "f32" => Ok(Precision::F32),
+            "f16" => Ok(Precision::F16),
+            "bf16" => Ok(Precision::BF16),
+            other => Err(format!("unknown precision {other}")),
+        }
+    }
+}
+
+impl Display for Precision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Precision::F32 => "f32",
+            Precision::F16 => "f16",
+            Precision::BF16 => "bf16",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Precision {
+    /// Reads a `precision=<f32|f16|bf16>` argument, defaulting to f32.
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        args.iter()
+            .find_map(|arg| arg.strip_prefix("precision="))
+            .map_or(Ok(Precision::default()), str::parse)
+    }
+}
+
+/// Code generic over the backend, started on whichever one was chosen at runtime.
+pub trait BackendTask {
+    type Output;
+
+    fn run<B: Backend>(self, device: B::Device) -> Self::Output;
+}
+
+impl BackendChoice {
+    /// Reads a `backend=<name>` argument, defaulting to CPU candle.
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        args.iter()
+            .find_map(|arg| arg.strip_prefix("backend="))
+            .map_or(Ok(BackendChoice::default()), str::parse)
+    }
+
+    pub fn run<T: BackendTask>(self, task: T) -> T::Output {
+        self.run_in(Precision::F32, task)
+            .expect("every backend computes in f32")
+    }
+
+    /// Runs the task with the given float element, if the backend supports it.
+    pub fn run_in<T: BackendTask>(
+        self,
+        precision: Precision,
+        task: T,
+    ) -> Result<T::Output, String> {
+        match (self, precision) {
+            (BackendChoice::Candle, Precision::F32) => {
+                Ok(task.run::<Candle<f32, i64>>(CandleDevice::Cpu))
+            }
+            (BackendChoice::Candle, Precision::F16) => {
+                Ok(task.run::<Candle<f16, i64>>(CandleDevice::Cpu))
+            }
+            (BackendChoice::NdArray, Precision::F32) => {
+                Ok(task.run::<NdArray<f32>>(NdArrayDevice::Cpu))
+            }
+            #[cfg(feature = "wgpu")]
+            (BackendChoice::Wgpu, Precision::F32) => {
+                Ok(task.run::<burn::backend::Wgpu>(burn::backend::wgpu::WgpuDevice::default()))
+            }
+            #[cfg(feature = "wgpu")]
+            (BackendChoice::Wgpu, Precision::F16) => Ok(task
+                .run::<burn::backend::Wgpu<f16, i32>>(burn::backend::wgpu::WgpuDevice::default())),
+            #[cfg(feature = "cuda")]
+            (BackendChoice::Cuda, Precision::F32) => {
+                Ok(task.run::<burn::backend::Cuda>(burn::backend::cuda::CudaDevice::default()))
+            }
+            #[cfg(feature = "cuda")]
+            (BackendChoice::Cuda, Precision::F16) => Ok(task
+                .run::<burn::backend::Cuda<f16, i32>>(burn::backend::cuda::CudaDevice::default())),
+            #[cfg(feature = "cuda")]
+            (BackendChoice::Cuda, Precision::BF16) => {
+                Ok(task.run::<burn::backend::Cuda<burn::tensor::bf16, i32>>(
+                    burn::backend::cuda::CudaDevice::default(),
+                ))
+            }
+            (backend, precision) => Err(format!("{backend} does not compute in {precision}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_ai::AI;
+    use crate::sim_for_ai::test_ai;
+    use crate::small_ai::SmallAI;
+    use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+    use burn::tensor::Distribution;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::env::temp_dir;
+    use std::path::PathBuf;
+
+    struct DeviceName;
+
+    impl BackendTask for DeviceName {
+        type Output = String;
+
+        fn run<B: Backend>(self, device: B::Device) -> String {
+            B::name(&device)
+        }
+    }
+
+    /// Scores a saved small network in whatever precision the backend computes.
+    struct Score(PathBuf);
+
+    impl BackendTask for Score {
+        type Output = f32;
+
+        fn run<B: Backend>(self, device: B::Device) -> f32 {
+            let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
+            let network =
+                SmallAI::<B>::new(&device).load_a_file(self.0.to_str().unwrap(), &recorder);
+            test_ai(&network, &device)
+        }
+    }
+
+    #[test]
+    fn test_half_precision_scores_like_f32() {
+        let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
+        let path = temp_dir().join("half_precision_test");
+        let score = |precision| {
+            BackendChoice::Candle
+                .run_in(precision, Score(path.clone()))
+                .unwrap()
+        };
+        // drawn from a seeded generator, as the backend's own initialization cannot be seeded
+        SmallAI::<Candle<f32, i64>>::with_init_std(&CandleDevice::Cpu, 0.)
+            .jiggle(
+                &Distribution::Normal(0., 0.1),
+                &mut StdRng::seed_from_u64(0),
+            )
+            .save_file(path.to_str().unwrap(), &recorder);
+        let full = score(Precision::F32);
+        let half = score(Precision::F16);
+        std::fs::remove_file(path.with_extension("mpk")).unwrap();
+        assert!((full - half).abs() < 0.02, "f32 {full}, f16 {half}");
+
+        assert!(BackendChoice::NdArray
+            .run_in(Precision::F16, DeviceName)
+            .is_err());
+        assert!(BackendChoice::Candle
+            .run_in(Precision::BF16, DeviceName)
+            .is_err());
+        assert_eq!(
+            Precision::from_args(&["precision=bf16".to_string()]),
+            Ok(Precision::BF16)
+        );
+    }
+
+    #[test]
+    fn test_backend_from_args() {
+        let args = |arg: &str| vec!["eval".to_string(), arg.to_string()];
+        assert_eq!(
+            BackendChoice::from_args(&args("resume")),
+            Ok(BackendChoice::Candle)
+        );
+        let ndarray = BackendChoice::from_args(&args("backend=ndarray")).unwrap();
+        assert_eq!(ndarray.to_string(), "ndarray");
+        assert!(ndarray.run(DeviceName).contains("ndarray"));
+        assert!(BackendChoice::from_args(&args("backend=tpu")).is_err());
+    }
+}
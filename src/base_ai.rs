@@ -1,22 +1,23 @@
-use burn::module::{Module, Param};
+use crate::distill::{DistillationConfig, TeacherSample};
+use crate::manifest::Manifest;
+use burn::module::{Module, ModuleDisplay, Param};
 use burn::nn::Linear;
 use burn::prelude::Backend;
 use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
 use burn::tensor::Distribution::Uniform;
-use burn::tensor::{Distribution, Tensor};
-use regex::Regex;
+use burn::tensor::{Bool, Distribution, Int, Tensor, TensorData};
+use rand::rngs::StdRng;
+use rand::Rng;
 use std::fmt::Debug;
-use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::ops::Deref;
-use std::sync::LazyLock;
-
-pub trait AI<B: Backend>: Module<B> + Debug {
-    fn jiggle(&self, d: &Distribution) -> Self;
-    fn offspring(&self, other_parent: &Self, d: &Distribution) -> Self;
-    fn offspring_iw(&self, other_parent: &Self, d: &Distribution) -> Self;
-    fn offspring_aw(&self, other_parent: &Self, d: &Distribution) -> Self;
-    fn offspring_layers(&self, other_parent: &Self, d: &Distribution) -> Self;
+use std::path::Path;
+
+pub trait AI<B: Backend>: Module<B> + ModuleDisplay + Debug {
+    fn jiggle(&self, d: &Distribution, rng: &mut StdRng) -> Self;
     fn apply(&self, input: Tensor<B, 1>) -> Tensor<B, 1>;
+    /// Forgets whatever a stateful network remembers, before starting a new episode.
+    fn reset_state(&self) {}
     fn max_amp(&self) -> f32;
 
     fn save_file(&self, filename: &str, recorder: &NamedMpkFileRecorder<FullPrecisionSettings>);
@@ -27,22 +28,492 @@ pub trait AI<B: Backend>: Module<B> + Debug {
     ) -> Self;
 
     fn network_name(&self) -> &'static str;
+
+    /// Length of the observation `apply` takes. By default the inputs of the first layer.
+    fn input_size(&self) -> usize {
+        let (_, first) = self
+            .layers()
+            .into_iter()
+            .next()
+            .expect("network has no layers");
+        first.weight.dims()[0]
+    }
+
+    /// Number of forces `apply` returns. By default the outputs of the last layer.
+    fn output_size(&self) -> usize {
+        let (_, last) = self
+            .layers()
+            .into_iter()
+            .last()
+            .expect("network has no layers");
+        last.weight.dims()[1]
+    }
+
+    /// Whether the last output is a grip intent rather than a joint force, see `GripAI`.
+    fn grips(&self) -> bool {
+        false
+    }
+
+    /// The linear layers with their field names, in the order the input flows through them.
+    fn layers(&self) -> Vec<(&'static str, Linear<B>)>;
+    /// The activation applied to each layer's output, in the order `layers` lists them, for
+    /// describing the network. `"none"` for layers whose output is used as is.
+    fn activations(&self) -> Vec<&'static str>;
+    /// What each layer reads, in the order `layers` lists them: other layers by their index, and
+    /// the observation as `None`, for describing the network. By default every layer reads the
+    /// one before it.
+    fn layer_inputs(&self) -> Vec<Vec<Option<usize>>> {
+        (0..self.layers().len())
+            .map(|i| vec![i.checked_sub(1)])
+            .collect()
+    }
+    /// The layers the forces are read from, by default the last one.
+    fn force_layers(&self) -> Vec<usize> {
+        vec![self.layers().len() - 1]
+    }
+    /// A copy of this network with its layers replaced, given in the order `layers` lists them.
+    fn with_layers(&self, layers: Vec<Linear<B>>) -> Self;
+
+    /// Trains whatever auxiliary heads the network has on rollouts of its own, between
+    /// generations, in the order drawn from `rng`. Networks without any are returned as they
+    /// are.
+    fn train_auxiliary(&self, _device: &B::Device, _rng: &mut StdRng) -> Self {
+        self.clone()
+    }
+
+    /// A copy standardizing its input by statistics with the observations folded in. Networks
+    /// that take their input as it is are returned as they are.
+    fn with_observations(&self, _observations: &[Vec<f32>]) -> Self {
+        self.clone()
+    }
+
+    /// A copy fitted to imitate the recorded actions of a teacher, with the samples shuffled by
+    /// `rng`, `None` for networks that can't be trained this way.
+    fn imitate(
+        &self,
+        _samples: &[TeacherSample],
+        _config: &DistillationConfig,
+        _device: &B::Device,
+        _rng: &mut StdRng,
+    ) -> Option<Self> {
+        None
+    }
+
+    /// Whether each layer, in the order `layers` lists them, is kept out of mutation, pruning
+    /// and crossover. Nothing is frozen unless the network is wrapped in a `FrozenAI`.
+    fn frozen_layers(&self) -> Vec<bool> {
+        vec![false; self.layers().len()]
+    }
+
+    /// A copy with all weights and biases drawn anew from `rng` as the networks are initialized,
+    /// normally with `INIT_STD`, so fresh networks follow the run's seed on backends that cannot
+    /// be seeded.
+    fn redrawn(&self, rng: &mut StdRng) -> Self {
+        self.with_layers(
+            self.layers()
+                .iter()
+                .map(|(_, layer)| redraw_linear(layer, rng))
+                .collect(),
+        )
+    }
+
+    /// Like `jiggle`, but with the spread of `d` scaled per layer as `mutation` says. Frozen
+    /// layers are left as they are.
+    fn jiggle_layers(&self, d: &Distribution, mutation: &MutationConfig, rng: &mut StdRng) -> Self {
+        self.with_layers(
+            self.layers()
+                .iter()
+                .zip(self.frozen_layers())
+                .map(|((name, layer), frozen)| {
+                    if frozen {
+                        layer.clone()
+                    } else {
+                        jiggle_linear(layer, &mutation.distribution_for(name, d), rng)
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Every parameter in one vector, for optimizers that don't know the layer structure: layer
+    /// by layer as `layers` lists them, the `[inputs, outputs]` weights row by row, then the biases.
+    fn to_flat_vec(&self) -> Vec<f32> {
+        self.layers()
+            .into_iter()
+            .flat_map(|(_, layer)| {
+                let bias = layer.bias.map(|bias| bias.val().into_data());
+                std::iter::once(layer.weight.val().into_data()).chain(bias)
+            })
+            .flat_map(|data| {
+                data.convert::<f32>()
+                    .to_vec::<f32>()
+                    .expect("parameters are not f32")
+            })
+            .collect()
+    }
+
+    /// A copy with the parameters read back from a vector laid out like `to_flat_vec`.
+    #[allow(clippy::wrong_self_convention)]
+    fn from_flat_vec(&self, parameters: &[f32]) -> Self {
+        let mut rest = parameters;
+        let mut take = |count: usize| {
+            assert!(
+                rest.len() >= count,
+                "too few parameters for {}",
+                self.network_name()
+            );
+            let (taken, remaining) = rest.split_at(count);
+            rest = remaining;
+            taken.to_vec()
+        };
+        let layers = self
+            .layers()
+            .into_iter()
+            .map(|(_, layer)| {
+                let device = layer.weight.device();
+                let shape = layer.weight.dims();
+                let weight =
+                    Tensor::from_data(TensorData::new(take(shape[0] * shape[1]), shape), &device);
+                let bias = layer.bias.map(|bias| {
+                    let shape = bias.dims();
+                    Param::from_tensor(Tensor::from_data(
+                        TensorData::new(take(shape[0]), shape),
+                        &device,
+                    ))
+                });
+                Linear {
+                    weight: Param::from_tensor(weight),
+                    bias,
+                }
+            })
+            .collect();
+        assert!(
+            rest.is_empty(),
+            "too many parameters for {}",
+            self.network_name()
+        );
+        self.with_layers(layers)
+    }
+
+    /// Hash of the parameters rounded to `FINGERPRINT_RESOLUTION`, so copies that went through
+    /// lossy round trips, e.g. a save and load, still match.
+    fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for (name, layer) in self.layers() {
+            name.hash(&mut hasher);
+            let bias = layer.bias.as_ref().map(|bias| bias.val().into_data());
+            for data in std::iter::once(layer.weight.val().into_data()).chain(bias) {
+                for value in data
+                    .convert::<f32>()
+                    .to_vec::<f32>()
+                    .expect("parameters are not f32")
+                {
+                    ((value / FINGERPRINT_RESOLUTION).round() as i64).hash(&mut hasher);
+                }
+            }
+        }
+        hasher.finish()
+    }
+
+    /// A copy with each weight of the unfrozen layers zeroed with the given probability. Biases
+    /// are kept.
+    fn prune(&self, probability: f64, rng: &mut StdRng) -> Self {
+        self.with_layers(
+            self.layers()
+                .iter()
+                .zip(self.frozen_layers())
+                .map(|((_, layer), frozen)| {
+                    if frozen {
+                        layer.clone()
+                    } else {
+                        prune_linear(layer, probability, rng)
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Widths of the hidden layers evolution may grow and shrink. Hidden layer `i` is the output
+    /// of layer `i` and the input of layer `i + 1`, as `layers` lists them. Empty for networks
+    /// whose shape is fixed.
+    fn hidden_sizes(&self) -> Vec<usize> {
+        Vec::new()
+    }
+
+    /// A copy with one more unit in hidden layer `hidden`, its weights drawn from `d` so it
+    /// starts out close to silent. Units between frozen layers are left alone.
+    fn grow_hidden(&self, hidden: usize, d: &Distribution, rng: &mut StdRng) -> Self {
+        let frozen = self.frozen_layers();
+        if frozen[hidden] || frozen[hidden + 1] {
+            return self.clone();
+        }
+        let mut layers: Vec<Linear<B>> =
+            self.layers().into_iter().map(|(_, layer)| layer).collect();
+        let (into, out_of) = grow_unit(&layers[hidden], &layers[hidden + 1], d, rng);
+        layers[hidden] = into;
+        layers[hidden + 1] = out_of;
+        self.with_layers(layers)
+    }
+
+    /// A copy without the unit of hidden layer `hidden` whose outgoing weights are the smallest,
+    /// the one the network misses least. The last unit of a layer is kept.
+    fn shrink_hidden(&self, hidden: usize) -> Self {
+        let frozen = self.frozen_layers();
+        if frozen[hidden] || frozen[hidden + 1] || self.hidden_sizes()[hidden] <= 1 {
+            return self.clone();
+        }
+        let mut layers: Vec<Linear<B>> =
+            self.layers().into_iter().map(|(_, layer)| layer).collect();
+        let unit = weakest_unit(&layers[hidden + 1]);
+        let (into, out_of) = remove_unit(&layers[hidden], &layers[hidden + 1], unit);
+        layers[hidden] = into;
+        layers[hidden + 1] = out_of;
+        self.with_layers(layers)
+    }
+
+    /// `grow_hidden` on a random hidden layer.
+    fn grow(&self, d: &Distribution, rng: &mut StdRng) -> Self {
+        match self.hidden_sizes().len() {
+            0 => self.clone(),
+            count => self.grow_hidden(rng.random_range(0..count), d, rng),
+        }
+    }
+
+    /// `shrink_hidden` on a random hidden layer.
+    fn shrink(&self, rng: &mut StdRng) -> Self {
+        match self.hidden_sizes().len() {
+            0 => self.clone(),
+            count => self.shrink_hidden(rng.random_range(0..count)),
+        }
+    }
+
+    /// A copy with the weights of the unfrozen layers multiplied by `factor`. Biases are kept.
+    fn decay(&self, factor: f64) -> Self {
+        self.with_layers(
+            self.layers()
+                .iter()
+                .zip(self.frozen_layers())
+                .map(|((_, layer), frozen)| {
+                    if frozen {
+                        layer.clone()
+                    } else {
+                        decay_linear(layer, factor)
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+pub const FINGERPRINT_RESOLUTION: f32 = 1e-4;
+
+/// Spread of the normal distribution the weights and biases of new networks are drawn from.
+pub const INIT_STD: f64 = 1.;
+
+pub static SMALLEST_SD: f64 = 0.01;
+pub static INITIAL_SD: f64 = 0.15;
+/// Learning rate of the log-normal sigma mutation.
+pub static SIGMA_TAU: f64 = 0.2;
+
+/// Per-layer scaling of the mutation spread, keyed by the names `AI::layers` reports.
+/// Layers without an entry are perturbed with the unscaled distribution.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MutationConfig {
+    pub layer_scales: Vec<(&'static str, f64)>,
+    /// Hill climbing jiggles tried on every offspring, see `evolution::local_search`. Each costs
+    /// an evaluation; none are tried by default.
+    pub local_search_steps: usize,
+    /// Sigma of fresh random individuals.
+    pub initial_sigma: f64,
+    /// The floor inherited sigmas are kept above.
+    pub smallest_sigma: f64,
+    /// Learning rate of the log-normal sigma mutation.
+    pub sigma_tau: f64,
+    /// Sigma every offspring gets in place of the inherited one, set each generation when the
+    /// run anneals its sigma.
+    pub annealed_sigma: Option<f64>,
+}
+
+impl Default for MutationConfig {
+    fn default() -> Self {
+        Self {
+            layer_scales: Vec::new(),
+            local_search_steps: 0,
+            initial_sigma: INITIAL_SD,
+            smallest_sigma: SMALLEST_SD,
+            sigma_tau: SIGMA_TAU,
+            annealed_sigma: None,
+        }
+    }
 }
 
-fn jiggle_tensor<const N: usize, B: Backend>(t: &Tensor<B, N>, d: &Distribution) -> Tensor<B, N> {
-    let jiggle_with = t.random_like(*d);
+impl MutationConfig {
+    pub fn with_local_search(mut self, steps: usize) -> Self {
+        self.local_search_steps = steps;
+        self
+    }
+
+    pub fn with_annealed_sigma(mut self, sigma: Option<f64>) -> Self {
+        self.annealed_sigma = sigma;
+        self
+    }
+
+    pub fn with_layer_scale(mut self, layer: &'static str, scale: f64) -> Self {
+        self.layer_scales.retain(|(name, _)| *name != layer);
+        self.layer_scales.push((layer, scale));
+        self
+    }
+
+    pub fn scale_for(&self, layer: &str) -> f64 {
+        self.layer_scales
+            .iter()
+            .find(|(name, _)| *name == layer)
+            .map_or(1., |(_, scale)| *scale)
+    }
+
+    pub fn distribution_for(&self, layer: &str, d: &Distribution) -> Distribution {
+        scale_distribution(d, self.scale_for(layer))
+    }
+}
+
+pub fn scale_distribution(d: &Distribution, scale: f64) -> Distribution {
+    match *d {
+        Distribution::Normal(mean, std) => Distribution::Normal(mean * scale, std * scale),
+        Uniform(low, high) => Uniform(low * scale, high * scale),
+        other => other,
+    }
+}
+
+/// A tensor shaped like `t`, sampled on the host from `rng` so runs can be replayed from a seed.
+pub fn random_like<const N: usize, B: Backend>(
+    t: &Tensor<B, N>,
+    d: Distribution,
+    rng: &mut StdRng,
+) -> Tensor<B, N> {
+    let data = TensorData::random::<B::FloatElem, _, _>(t.dims(), d, rng);
+    Tensor::from_data(data, &t.device())
+}
+
+fn jiggle_tensor<const N: usize, B: Backend>(
+    t: &Tensor<B, N>,
+    d: &Distribution,
+    rng: &mut StdRng,
+) -> Tensor<B, N> {
+    let jiggle_with = random_like(t, *d, rng);
     t.clone().add(jiggle_with)
 }
-pub fn jiggle_linear<B: Backend>(ln: &Linear<B>, d: &Distribution) -> Linear<B> {
+pub fn jiggle_linear<B: Backend>(ln: &Linear<B>, d: &Distribution, rng: &mut StdRng) -> Linear<B> {
     Linear {
-        weight: Param::from_tensor(jiggle_tensor(&ln.weight, d)),
+        weight: Param::from_tensor(jiggle_tensor(&ln.weight, d, rng)),
         bias: ln
             .bias
             .as_ref()
-            .map(|p| Param::from_tensor(jiggle_tensor(p, d))),
+            .map(|p| Param::from_tensor(jiggle_tensor(p, d, rng))),
     }
 }
 
+fn redraw_tensor<const N: usize, B: Backend>(t: &Tensor<B, N>, rng: &mut StdRng) -> Tensor<B, N> {
+    random_like(t, Distribution::Normal(0., INIT_STD), rng)
+}
+
+pub fn redraw_linear<B: Backend>(ln: &Linear<B>, rng: &mut StdRng) -> Linear<B> {
+    Linear {
+        weight: Param::from_tensor(redraw_tensor(&ln.weight.val(), rng)),
+        bias: ln
+            .bias
+            .as_ref()
+            .map(|p| Param::from_tensor(redraw_tensor(&p.val(), rng))),
+    }
+}
+
+pub fn prune_linear<B: Backend>(ln: &Linear<B>, probability: f64, rng: &mut StdRng) -> Linear<B> {
+    let weight = ln.weight.val();
+    let pruned = random_like(&weight, Uniform(0., 1.), rng).lower_elem(probability);
+    Linear {
+        weight: Param::from_tensor(weight.clone().mask_where(pruned, weight.zeros_like())),
+        bias: ln.bias.clone(),
+    }
+}
+
+pub fn decay_linear<B: Backend>(ln: &Linear<B>, factor: f64) -> Linear<B> {
+    Linear {
+        weight: Param::from_tensor(ln.weight.val().mul_scalar(factor)),
+        bias: ln.bias.clone(),
+    }
+}
+
+/// Adds a unit between two consecutive layers: an output of `into` with a zero bias and an
+/// input of `out_of`, their weights drawn from `d`.
+pub fn grow_unit<B: Backend>(
+    into: &Linear<B>,
+    out_of: &Linear<B>,
+    d: &Distribution,
+    rng: &mut StdRng,
+) -> (Linear<B>, Linear<B>) {
+    let [d_in, _] = into.weight.dims();
+    let [_, d_out] = out_of.weight.dims();
+    let device = into.weight.device();
+    let column = random_like(&Tensor::<B, 2>::zeros([d_in, 1], &device), *d, rng);
+    let row = random_like(&Tensor::<B, 2>::zeros([1, d_out], &device), *d, rng);
+    let into = Linear {
+        weight: Param::from_tensor(Tensor::cat(vec![into.weight.val(), column], 1)),
+        bias: into.bias.as_ref().map(|bias| {
+            Param::from_tensor(Tensor::cat(
+                vec![bias.val(), Tensor::zeros([1], &device)],
+                0,
+            ))
+        }),
+    };
+    let out_of = Linear {
+        weight: Param::from_tensor(Tensor::cat(vec![out_of.weight.val(), row], 0)),
+        bias: out_of.bias.clone(),
+    };
+    (into, out_of)
+}
+
+/// Removes output `unit` of `into` and the matching input of `out_of`.
+pub fn remove_unit<B: Backend>(
+    into: &Linear<B>,
+    out_of: &Linear<B>,
+    unit: usize,
+) -> (Linear<B>, Linear<B>) {
+    let [_, units] = into.weight.dims();
+    let device = into.weight.device();
+    let kept: Vec<i64> = (0..units as i64).filter(|&i| i != unit as i64).collect();
+    let kept = Tensor::<B, 1, Int>::from_data(TensorData::new(kept, [units - 1]), &device);
+    let into = Linear {
+        weight: Param::from_tensor(into.weight.val().select(1, kept.clone())),
+        bias: into
+            .bias
+            .as_ref()
+            .map(|bias| Param::from_tensor(bias.val().select(0, kept.clone()))),
+    };
+    let out_of = Linear {
+        weight: Param::from_tensor(out_of.weight.val().select(0, kept)),
+        bias: out_of.bias.clone(),
+    };
+    (into, out_of)
+}
+
+/// The input of `layer` with the smallest summed weight magnitude.
+pub fn weakest_unit<B: Backend>(layer: &Linear<B>) -> usize {
+    let magnitudes: Vec<f32> = layer
+        .weight
+        .val()
+        .abs()
+        .sum_dim(1)
+        .into_data()
+        .convert::<f32>()
+        .to_vec()
+        .expect("weights are not f32");
+    magnitudes
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(unit, _)| unit)
+        .expect("layer has no inputs")
+}
+
 pub fn combine_bw_linear<B: Backend>(a: &Linear<B>, b: &Linear<B>) -> Linear<B> {
     Linear {
         weight: a.weight.clone(),
@@ -50,38 +521,90 @@ pub fn combine_bw_linear<B: Backend>(a: &Linear<B>, b: &Linear<B>) -> Linear<B>
     }
 }
 
-pub fn interleave_bw_linear<B: Backend>(a: &Linear<B>, b: &Linear<B>) -> Linear<B> {
+pub fn interleave_bw_linear<B: Backend>(
+    a: &Linear<B>,
+    b: &Linear<B>,
+    rng: &mut StdRng,
+) -> Linear<B> {
     Linear {
         weight: Param::from_tensor(interleave(
             a.weight.deref().clone(),
             b.weight.deref().clone(),
+            rng,
         )),
-        bias: b
-            .bias
-            .iter()
-            .flat_map(|bp| {
-                a.bias
-                    .as_ref()
-                    .map(|ap| interleave(ap.deref().clone(), bp.deref().clone()))
-            })
-            .map(Param::from_tensor)
-            .next(),
+        bias: match (&a.bias, &b.bias) {
+            (Some(ap), Some(bp)) => Some(Param::from_tensor(interleave(
+                ap.deref().clone(),
+                bp.deref().clone(),
+                rng,
+            ))),
+            _ => None,
+        },
     }
 }
 
-pub fn interleave<const N: usize, B: Backend>(a: Tensor<B, N>, b: Tensor<B, N>) -> Tensor<B, N> {
+pub fn interleave<const N: usize, B: Backend>(
+    a: Tensor<B, N>,
+    b: Tensor<B, N>,
+    rng: &mut StdRng,
+) -> Tensor<B, N> {
     let a_size = a.shape();
     let b_size = b.shape();
     assert_eq!(a_size, b_size);
 
-    let baseline = a.random_like(Uniform(0., 1.));
-    let a_mask = baseline.clone().lower_elem(0.5);
-    let b_mask = baseline.greater_equal_elem(0.5);
+    let from_a = random_like(&a, Uniform(0., 1.), rng).lower_elem(0.5);
+    interleave_masked(a, b, from_a)
+}
+
+/// Values of `a` where `from_a` is set and of `b` elsewhere. The mask broadcasts, so a `[1, n]`
+/// mask picks whole columns.
+pub fn interleave_masked<const N: usize, B: Backend>(
+    a: Tensor<B, N>,
+    b: Tensor<B, N>,
+    from_a: Tensor<B, N, Bool>,
+) -> Tensor<B, N> {
+    assert_eq!(a.shape(), b.shape());
+    let from_a = from_a.expand(a.shape());
+    b.mask_where(from_a, a)
+}
 
-    let a_vals = a.clone().mask_where(a_mask, a.zeros_like());
-    let b_vals = b.clone().mask_where(b_mask, b.zeros_like());
+/// For each of `units` neurons, whether it is inherited from the first parent, drawn from `rng`.
+pub fn unit_mask<B: Backend>(
+    units: usize,
+    device: &B::Device,
+    rng: &mut StdRng,
+) -> Tensor<B, 1, Bool> {
+    Tensor::<B, 1>::from_data(
+        TensorData::random::<B::FloatElem, _, _>([units], Uniform(0., 1.), rng),
+        device,
+    )
+    .lower_elem(0.5)
+}
 
-    a_vals + b_vals
+/// Each output neuron, its column of weights together with its bias, from a randomly chosen
+/// parent, so no neuron ends up with a mix of both parents' inputs.
+pub fn interleave_neurons_linear<B: Backend>(
+    a: &Linear<B>,
+    b: &Linear<B>,
+    rng: &mut StdRng,
+) -> Linear<B> {
+    let [_, units] = a.weight.dims();
+    let from_a = unit_mask::<B>(units, &a.weight.device(), rng);
+    Linear {
+        weight: Param::from_tensor(interleave_masked(
+            a.weight.val(),
+            b.weight.val(),
+            from_a.clone().unsqueeze(),
+        )),
+        bias: match (&a.bias, &b.bias) {
+            (Some(ap), Some(bp)) => Some(Param::from_tensor(interleave_masked(
+                ap.val(),
+                bp.val(),
+                from_a,
+            ))),
+            _ => None,
+        },
+    }
 }
 
 pub fn average<const N: usize, B: Backend>(a: Tensor<B, N>, b: Tensor<B, N>) -> Tensor<B, N> {
@@ -105,7 +628,7 @@ pub fn average_bw_linear<B: Backend>(a: &Linear<B>, b: &Linear<B>) -> Linear<B>
 }
 
 pub fn max_amp_for_tensor<const N: usize, B: Backend>(input: &Tensor<B, N>) -> f32 {
-    let data = input.clone().to_data();
+    let data = input.clone().to_data().convert::<f32>();
     let slice: &[f32] = data
         .as_slice()
         .expect("tensor is not representable as a slice");
@@ -129,58 +652,169 @@ pub fn max_amp_for_linear<B: Backend>(input: &Linear<B>) -> f32 {
 }
 
 pub trait ListableAI<B: Backend>: AI<B> {
-    fn list(&self) -> Vec<String>;
-}
-
-impl<B: Backend, A: AI<B>> ListableAI<B> for A {
     fn list(&self) -> Vec<String> {
-        let network_name = self.network_name();
-        let mut saved_files = Vec::new();
-
-        // Get the current directory (where model files are saved)
-        if let Ok(entries) = fs::read_dir(".") {
-            for entry in entries.flatten() {
-                if let Some(filename) = entry.file_name().to_str() {
-                    if let Some(seq) = extract_seq(filename, network_name) {
-                        saved_files.push((seq, filename.to_string()));
-                    }
-                }
-            }
-        }
+        self.list_in(Path::new("."))
+    }
 
-        saved_files.sort_by_key(|a| a.0);
+    /// The latest saved files of this network the directory's manifest lists, with the
+    /// directory prepended.
+    fn list_in(&self, directory: &Path) -> Vec<String>;
+}
 
-        saved_files
+impl<B: Backend, A: AI<B>> ListableAI<B> for A {
+    fn list_in(&self, directory: &Path) -> Vec<String> {
+        let manifest = Manifest::load(directory).unwrap_or_default();
+        manifest
+            .latest(self.network_name())
             .into_iter()
-            .rev()
             .take(30)
-            .map(|(_, filename)| filename)
+            .map(|entry| {
+                directory
+                    .join(&entry.file)
+                    .to_str()
+                    .expect("path is not unicode")
+                    .to_string()
+            })
             .collect()
     }
 }
 
-static REG: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"[A-Za-z]+_(?<net_name>[A-Z a-z]+)_(?<seq>[0-9]+)\.mpk").unwrap());
-
-pub fn extract_seq(filename: &str, network_name: &str) -> Option<usize> {
-    REG.captures(filename)
-        .filter(|c| &c["net_name"] == network_name)
-        .map(|c| c.name("seq").map(|m| m.as_str()))
-        .flatten()
-        .map(|seq_str| seq_str.parse().ok())
-        .flatten()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::small_ai::SmallAI;
     use burn::backend::candle::CandleDevice;
     use burn::backend::Candle;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_prune() {
+        type BE = Candle<f32, i64>;
+        let device = CandleDevice::Cpu;
+        let small_ai = SmallAI::<BE>::new(&device);
+
+        let zeros = |ai: &SmallAI<BE>| {
+            ai.layers()
+                .iter()
+                .flat_map(|(_, layer)| layer.weight.val().into_data().to_vec::<f32>().unwrap())
+                .filter(|w| *w == 0.)
+                .count()
+        };
+        let weight_count: usize = small_ai
+            .layers()
+            .iter()
+            .map(|(_, layer)| layer.weight.val().shape().num_elements())
+            .sum();
+        let mut rng = StdRng::seed_from_u64(7);
+        assert_eq!(zeros(&small_ai.prune(0., &mut rng)), 0);
+        assert_eq!(zeros(&small_ai.prune(1., &mut rng)), weight_count);
+        let half = zeros(&small_ai.prune(0.5, &mut rng)) as f32 / weight_count as f32;
+        assert!((0.4..0.6).contains(&half), "{half}");
+    }
+
+    #[test]
+    fn test_decay_shrinks_weights() {
+        type BE = Candle<f32, i64>;
+        let device = CandleDevice::Cpu;
+        let small_ai = SmallAI::<BE>::new(&device);
+        let decayed = small_ai.decay(0.5);
+        for ((_, before), (_, after)) in small_ai.layers().iter().zip(decayed.layers()) {
+            let halved: Vec<f32> = (before.weight.val() / 2.).to_data().to_vec().unwrap();
+            assert_eq!(
+                after.weight.val().to_data().to_vec::<f32>().unwrap(),
+                halved
+            );
+            assert_eq!(
+                after.bias.unwrap().val().to_data(),
+                before.bias.as_ref().unwrap().val().to_data()
+            );
+        }
+    }
 
     #[test]
-    fn test_extract_seq() {
-        assert_eq!(extract_seq("best_te st_1234.mpk", "te st"), Some(1234));
+    fn test_fingerprint() {
+        type BE = Candle<f32, i64>;
+        let device = CandleDevice::Cpu;
+        let small_ai = SmallAI::<BE>::new(&device);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        assert_eq!(small_ai.fingerprint(), small_ai.clone().fingerprint());
+        let jiggled = small_ai.jiggle(&Distribution::Normal(0., 0.1), &mut rng);
+        assert_ne!(small_ai.fingerprint(), jiggled.fingerprint());
+    }
+
+    #[test]
+    fn test_layer_scaled_jiggle() {
+        type BE = Candle<f32, i64>;
+        let device = CandleDevice::Cpu;
+        let small_ai = SmallAI::<BE>::new(&device);
+        let mutation = MutationConfig::default().with_layer_scale("output", 0.);
+
+        let jiggled = small_ai.jiggle_layers(
+            &Distribution::Normal(0., 0.1),
+            &mutation,
+            &mut StdRng::seed_from_u64(7),
+        );
+        for ((name, before), (_, after)) in small_ai.layers().iter().zip(jiggled.layers()) {
+            let unchanged = before.weight.val().to_data() == after.weight.val().to_data();
+            assert_eq!(unchanged, *name == "output", "{name}");
+        }
+    }
+
+    #[test]
+    fn test_flat_vec_round_trip() {
+        type BE = Candle<f32, i64>;
+        let device = CandleDevice::Cpu;
+        let small_ai = SmallAI::<BE>::new(&device);
+        let flat = small_ai.to_flat_vec();
+        let weight_count: usize = small_ai
+            .layers()
+            .iter()
+            .map(|(_, layer)| layer.weight.val().shape().num_elements())
+            .sum();
+        let bias_count: usize = small_ai
+            .layers()
+            .iter()
+            .map(|(_, layer)| layer.bias.as_ref().unwrap().dims()[0])
+            .sum();
+        assert_eq!(flat.len(), weight_count + bias_count);
+        assert_eq!(
+            small_ai.from_flat_vec(&flat).fingerprint(),
+            small_ai.fingerprint()
+        );
+
+        let shifted: Vec<f32> = flat.iter().map(|p| p + 1.).collect();
+        let moved = small_ai.from_flat_vec(&shifted);
+        assert_eq!(moved.to_flat_vec(), shifted);
+        assert_ne!(moved.fingerprint(), small_ai.fingerprint());
+    }
+
+    #[test]
+    fn test_grow_and_shrink_hidden() {
+        type BE = Candle<f32, i64>;
+        let device = CandleDevice::Cpu;
+        let mut rng = StdRng::seed_from_u64(3);
+        let small_ai = SmallAI::<BE>::new(&device);
+        assert_eq!(small_ai.hidden_sizes(), vec![128, 14]);
+        let observation = || Tensor::<BE, 1>::ones([crate::sim_for_ai::OBSERVATION_SIZE], &device);
+
+        // a silent unit changes nothing and is the first to go
+        let grown = small_ai.grow_hidden(1, &Distribution::Normal(0., 0.), &mut rng);
+        assert_eq!(grown.hidden_sizes(), vec![128, 15]);
+        assert_eq!(
+            grown.apply(observation()).to_data(),
+            small_ai.apply(observation()).to_data()
+        );
+        assert_eq!(grown.shrink_hidden(1).to_flat_vec(), small_ai.to_flat_vec());
+
+        let shrunk = small_ai
+            .shrink(&mut rng)
+            .grow(&Distribution::Normal(0., 0.01), &mut rng);
+        assert_eq!(shrunk.hidden_sizes().iter().sum::<usize>(), 142);
+        assert_eq!(
+            shrunk.apply(observation()).dims(),
+            [crate::sim_for_ai::ACTION_SIZE]
+        );
     }
 
     #[test]
@@ -0,0 +1,143 @@
+use crate::physics::world::{BasketConfig, PhysicsWorld, WorldConfig};
+use crate::sim_for_ai::{build_observation, EpisodeConfig, FrameStack, Task};
+
+/// Steps the ball has to stay in the basket for the episode to be won.
+pub const DEFAULT_HOLD_STEPS: usize = 25;
+/// Added to the reward of the step that wins the episode.
+pub const DEFAULT_BONUS: f32 = 1.;
+
+/// Putting the ball into a basket: every step is rewarded by how much of its starting distance
+/// to the basket the ball has been brought, and once it has stayed in the basket for
+/// `hold_steps` steps the episode is won, ending early with `bonus` on top of the reward.
+pub struct BasketTask {
+    pub basket: BasketConfig,
+    pub hold_steps: usize,
+    pub bonus: f32,
+    frames: FrameStack,
+    start_distance: f32,
+    steps_in_basket: usize,
+}
+
+impl BasketTask {
+    /// The task with the top of the basket floor at `(x, y)`.
+    pub fn at(x: f32, y: f32) -> Self {
+        Self {
+            basket: BasketConfig {
+                x,
+                y,
+                ..BasketConfig::default()
+            },
+            hold_steps: DEFAULT_HOLD_STEPS,
+            bonus: DEFAULT_BONUS,
+            frames: FrameStack::default(),
+            start_distance: 0.,
+            steps_in_basket: 0,
+        }
+    }
+
+    /// From the ball to the basket, in the units of `PhysicsWorld::normalize`.
+    fn distance(world: &PhysicsWorld) -> f32 {
+        let (ball, basket) = (
+            world.ball_position().expect("the task's world has a ball"),
+            world
+                .basket_position()
+                .expect("the task's world has a basket"),
+        );
+        let ((ball_x, ball_y), (basket_x, basket_y)) = (
+            world.normalize((ball.x, ball.y)),
+            world.normalize((basket.x, basket.y)),
+        );
+        ((ball_x - basket_x).powi(2) + (ball_y - basket_y).powi(2)).sqrt()
+    }
+
+    fn won(&self) -> bool {
+        self.steps_in_basket >= self.hold_steps
+    }
+}
+
+impl Default for BasketTask {
+    fn default() -> Self {
+        let basket = BasketConfig::default();
+        Self::at(basket.x, basket.y)
+    }
+}
+
+impl Task for BasketTask {
+    fn world_config(&self, seed: u64, episode: &EpisodeConfig) -> WorldConfig {
+        WorldConfig {
+            basket: Some(self.basket),
+            ..episode.world_config(seed)
+        }
+    }
+
+    fn reset(&mut self, world: &PhysicsWorld) {
+        self.frames.reset(world);
+        self.start_distance = Self::distance(world);
+        self.steps_in_basket = 0;
+    }
+
+    fn observe(&mut self, world: &PhysicsWorld, observation: &mut Vec<f32>) {
+        build_observation(observation, &mut self.frames, world);
+    }
+
+    fn reward(&mut self, world: &PhysicsWorld) -> f32 {
+        self.steps_in_basket = match world.is_ball_in_basket() {
+            true => self.steps_in_basket + 1,
+            false => 0,
+        };
+        let progress = match self.start_distance > 0. {
+            true => (1. - Self::distance(world) / self.start_distance).clamp(0., 1.),
+            false => 1.,
+        };
+        match self.won() {
+            true => progress + self.bonus,
+            false => progress,
+        }
+    }
+
+    fn terminated(&self, _world: &PhysicsWorld, _steps: usize) -> bool {
+        self.won()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fitness::FitnessKind;
+
+    #[test]
+    fn test_basket_task_rewards_the_ball_brought_in() {
+        let mut task = BasketTask::at(0.9, -1.6);
+        let world = PhysicsWorld::with_config(task.world_config(0, &EpisodeConfig::default()));
+        assert_eq!(
+            world.basket_position().map(|basket| (basket.x, basket.y)),
+            Some((0.9, -1.6))
+        );
+        assert!(!world.is_ball_in_basket());
+        task.reset(&world);
+        assert_eq!(task.reward(&world), 0.);
+        assert!(!task.terminated(&world, 1));
+
+        // as if the ball had been brought halfway
+        task.start_distance *= 2.;
+        assert!((task.reward(&world) - 0.5).abs() < 1e-6);
+        // a basket that need not hold the ball is won at once
+        task.hold_steps = 0;
+        assert!((task.reward(&world) - 0.5 - task.bonus).abs() < 1e-6);
+        assert!(task.terminated(&world, 1));
+
+        let mut observation = Vec::new();
+        task.observe(&world, &mut observation);
+        assert_eq!(observation.len(), crate::sim_for_ai::OBSERVATION_SIZE);
+
+        let picked = "ball_in_basket:0.9:-1.6"
+            .parse::<FitnessKind>()
+            .unwrap()
+            .task(Default::default());
+        assert!(
+            PhysicsWorld::with_config(picked.world_config(3, &EpisodeConfig::default()))
+                .basket_position()
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use crate::physics::world::PhysicsWorld;
+use serde::{Deserialize, Serialize};
+
+/// What a rollout did, as opposed to how well it scored, for novelty search and MAP-Elites
+/// grids. Positions are normalized like the observations.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct BehaviorDescriptor {
+    /// Index fingertip at the end of the rollout.
+    pub final_fingertip: (f32, f32),
+    /// Height of the index fingertip averaged over the steps.
+    pub mean_height: f32,
+    /// Arm contacts with the ground, wall or balls per step, so episodes of any length compare
+    /// and the count does not outweigh the normalized positions.
+    pub contact_rate: f32,
+}
+
+impl BehaviorDescriptor {
+    pub fn to_vec(&self) -> Vec<f32> {
+        vec![
+            self.final_fingertip.0,
+            self.final_fingertip.1,
+            self.mean_height,
+            self.contact_rate,
+        ]
+    }
+
+    /// Euclidean distance between the descriptor vectors.
+    pub fn distance(&self, other: &Self) -> f32 {
+        self.to_vec()
+            .iter()
+            .zip(other.to_vec())
+            .map(|(a, b)| (a - b).powi(2))
+            .sum::<f32>()
+            .sqrt()
+    }
+
+    /// The descriptor of several rollouts, each value averaged over them.
+    pub fn mean(descriptors: &[Self]) -> Self {
+        let n = descriptors.len().max(1) as f32;
+        let sum = |value: fn(&Self) -> f32| descriptors.iter().map(value).sum::<f32>() / n;
+        Self {
+            final_fingertip: (
+                sum(|descriptor| descriptor.final_fingertip.0),
+                sum(|descriptor| descriptor.final_fingertip.1),
+            ),
+            mean_height: sum(|descriptor| descriptor.mean_height),
+            contact_rate: sum(|descriptor| descriptor.contact_rate),
+        }
+    }
+}
+
+/// Collects a descriptor step by step while a rollout runs.
+#[derive(Clone, Debug, Default)]
+pub struct BehaviorRecorder {
+    steps: usize,
+    height_sum: f32,
+    contact_count: usize,
+}
+
+fn fingertip(world: &PhysicsWorld) -> (f32, f32) {
+    world.normalize(world.index_fingertip())
+}
+
+impl BehaviorRecorder {
+    /// Takes in the world after a step.
+    pub fn record(&mut self, world: &PhysicsWorld) {
+        self.steps += 1;
+        self.height_sum += fingertip(world).1;
+        self.contact_count += world.arm_contact_count();
+    }
+
+    pub fn finish(&self, world: &PhysicsWorld) -> BehaviorDescriptor {
+        BehaviorDescriptor {
+            final_fingertip: fingertip(world),
+            mean_height: self.height_sum / self.steps.max(1) as f32,
+            contact_rate: self.contact_count as f32 / self.steps.max(1) as f32,
+        }
+    }
+}
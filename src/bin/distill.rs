@@ -0,0 +1,45 @@
+use burn::backend::ndarray::NdArrayDevice;
+use burn::backend::{Autodiff, NdArray};
+use burn::module::AutodiffModule;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+use engine::ai::BigAI;
+use engine::base_ai::AI;
+use engine::distill::{collect_teacher_samples, distill, DistillationConfig};
+use engine::sim_for_ai::test_ai;
+use engine::small_ai::SmallAI;
+use engine::weights::load_saved;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+type BE = NdArray<f32>;
+
+/// Compresses a saved BigAI into a SmallAI, saved as `distilled_Small AI_0`.
+fn main() {
+    let device = NdArrayDevice::Cpu;
+    let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
+    let args = std::env::args().collect::<Vec<_>>();
+
+    let teacher = load_saved(BigAI::<BE>::new(&device), &args[1], &recorder);
+    let config = DistillationConfig::default();
+    let samples = collect_teacher_samples(&teacher, &config, &device);
+
+    let student = SmallAI::<Autodiff<BE>>::with_init_std(&device, 0.1);
+    let (student, losses) = distill(
+        student,
+        &samples,
+        &config,
+        &device,
+        &mut StdRng::seed_from_u64(0),
+    );
+    for (epoch, loss) in losses.iter().enumerate() {
+        println!("Epoch {epoch} loss: {loss}");
+    }
+
+    let student = student.valid();
+    println!("Teacher score: {}", test_ai(&teacher, &device));
+    println!("Student score: {}", test_ai(&student, &device));
+    student.save_file(
+        &format!("distilled_{}_0", student.network_name()),
+        &recorder,
+    );
+}
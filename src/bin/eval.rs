@@ -1,224 +1,1057 @@
-use burn::backend::candle::CandleDevice;
-use burn::backend::Candle;
-use burn::prelude::{Backend, Module};
-use burn::tensor::Distribution;
+use burn::prelude::Backend;
 
 use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
-use engine::base_ai::{extract_seq, ListableAI, AI};
-use engine::sim_for_ai::{test_ai, visual_ai};
-use engine::small_ai;
+use engine::ai::BigAI;
+use engine::attn_ai::AttnAI;
+use engine::aux_ai::AuxAI;
+use engine::backend::{BackendChoice, BackendTask, Precision};
+use engine::base_ai::ListableAI;
+use engine::checkpoint::{
+    generation_checkpoint, latest_checkpoint, remove_other_checkpoints, resolve_checkpoint,
+    Checkpoint,
+};
+use engine::cli::{Cli, CliError, Command};
+use engine::compare::{plot, table, RunCurve};
+use engine::evolution::{
+    init_island_population, island_crossing, local_search, make_new_generation, resume_island,
+    Genome, StopReason,
+};
+use engine::fitness_cache::FitnessCache;
+use engine::frozen_ai::FrozenAI;
+use engine::grip_ai::GripAI;
+use engine::hall_of_fame::HallOfFame;
+use engine::leaderboard::{Leaderboard, LeaderboardEntry};
+use engine::manifest::Manifest;
+use engine::map_elites::MapElites;
+use engine::medium_ai::MediumAI;
+use engine::metrics::{record_generation, record_timing, TensorBoardSink};
+use engine::noisy_ai::{ActionNoise, NoisyAI};
+use engine::novelty::NoveltyArchive;
+use engine::physics::trajectory::trajectory_name;
+use engine::population::Population;
+use engine::remote::{encode_network, serve, EvaluationRequest, WorkerPool};
+use engine::report::{GenerationReport, IslandReport};
+use engine::results_log::{GenerationRecord, ResultsLog};
+use engine::rnn_ai::RnnAI;
+use engine::schedule::{OperatorSchedule, OperatorStats, ScheduleConfig};
+use engine::scripted::ScriptedController;
+use engine::sim_for_ai::{
+    observe_ai_with_fitness, record_ai_with_fitness, test_ai_with_fitness, visual_ai,
+};
+use engine::small_ai::SmallAI;
+use engine::smoothed_ai::SmoothedAI;
+use engine::speciation::{parameter_descriptor, share_fitness, speciate, Descriptor};
+use engine::telemetry::{EvaluationTiming, TimingSummary};
+use engine::train_config::{RunMetadata, TrainConfig};
+use engine::weights::{load_saved, SaveFormat};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rayon::prelude::*;
-use std::time::SystemTime;
+use signal_hook::consts::SIGINT;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 
-static BEST_PROPORTION: f32 = 0.25;
-static ISLAND_POPULATION: usize = 100;
-static ALWAYS_RAND_COUNT: usize = 3;
-
-static SMALLEST_SD: f64 = 0.01;
-type BE = Candle<f32, i64>;
-
-fn ai_maker<BE: Backend>(d: &BE::Device) -> impl ListableAI<BE> {
-    // ai::BigAI::<BE>::new(d)
-    small_ai::SmallAI::<BE>::new(d)
+/// The action noise of an individual in an episode, the same whenever the run is replayed.
+fn noise_seed(run_seed: u64, episode: u64, fingerprint: u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (run_seed, episode, fingerprint).hash(&mut hasher);
+    hasher.finish()
 }
 
-fn init_island_population<BE: Backend, A: ListableAI<BE>>(
-    d: &BE::Device,
-    ai_maker: &impl Fn(&BE::Device) -> A,
-) -> Vec<A> {
-    (0..ISLAND_POPULATION).map(|_| ai_maker(d)).collect()
+/// The island evolution, run on the backend picked with `--device <name>` in the float
+/// precision picked with `--precision <name>`.
+struct Evolution {
+    cli: Cli,
 }
 
-fn main() {
-    let device = CandleDevice::Cpu;
-    let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
+impl BackendTask for Evolution {
+    type Output = ();
 
-    let sample_ai = ai_maker::<BE>(&device);
-    let args = std::env::args().collect::<Vec<_>>();
+    /// Evolves the network picked with `--network <name>`, the small one by default.
+    fn run<B: Backend>(self, device: B::Device) {
+        let network = self.cli.option("network").unwrap_or("small");
+        match network {
+            "small" => self.evolve_wrapped(device, SmallAI::<B>::new),
+            "medium" => self.evolve_wrapped(device, MediumAI::<B>::new),
+            "big" => self.evolve_wrapped(device, BigAI::<B>::new),
+            "rnn" => self.evolve_wrapped(device, RnnAI::<B>::new),
+            "attn" => self.evolve_wrapped(device, AttnAI::<B>::new),
+            "grip" => self.evolve_wrapped(device, GripAI::<B>::new),
+            "aux" => self.evolve_wrapped(device, AuxAI::<B>::new),
+            other => panic!("no network named {other}"),
+        }
+    }
+}
 
-    let (mut islands, mut number_of_bests, mut best_score): (Vec<Vec<_>>, usize, f32) =
-        if args.len() > 1 && args[1] == "resume" {
-            let islands = (0..5)
-                .map(|_| resume_island(&device, &|d| ai_maker::<BE>(d), BEST_PROPORTION, &recorder))
-                .collect::<Vec<_>>();
-            let best_score = test_ai(&islands[0][0], &device);
-            (
-                islands,
-                extract_seq(&sample_ai.list()[0], sample_ai.network_name()).unwrap(),
-                best_score,
-            )
-        } else {
-            (
-                (0..5)
-                    .map(|_| init_island_population(&device, &|d| ai_maker::<BE>(d)))
-                    .collect::<Vec<_>>(),
-                0,
-                0.0,
-            )
+impl Evolution {
+    /// Keeps the layers listed with `--freeze <name,...>` out of the evolution and low-pass
+    /// filters the forces with `--smoothing <0..1>`. By default nothing is frozen or filtered.
+    fn evolve_wrapped<B: Backend, A: ListableAI<B>>(
+        self,
+        device: B::Device,
+        ai_maker: impl Fn(&B::Device) -> A + Sync,
+    ) {
+        let frozen: Vec<String> = self.cli.option("freeze").map_or_else(Vec::new, |layers| {
+            layers.split(',').map(str::to_string).collect()
+        });
+        let smoothing = self.cli.parsed("smoothing").unwrap_or(1.);
+        let ai_maker = move |device: &B::Device| {
+            let frozen: Vec<&str> = frozen.iter().map(String::as_str).collect();
+            SmoothedAI::new(FrozenAI::new(ai_maker(device), &frozen), smoothing)
         };
-
-    for i in 0..100 {
-        for (j, island) in islands.iter_mut().enumerate() {
-            let before = SystemTime::now();
-            let inner_ais = island.clone();
-            let mut ai_w_scores = inner_ais
-                .into_par_iter()
-                .map(|ai| (test_ai(&ai, &device), ai))
-                .collect::<Vec<_>>();
-            ai_w_scores.sort_by(|a, b| {
-                b.0.partial_cmp(&a.0)
-                    .expect("ai score should be comparable")
-            });
-
-            let time_taken = before.elapsed().expect("elapsed calc failed").as_millis();
-            println!("{i},{j} Time taken: {} ms", time_taken);
-
-            let high_score = ai_w_scores
-                .iter()
-                .next()
-                .map(|(score, _)| *score)
-                .expect("high score not found");
-            if high_score > best_score {
-                best_score = high_score;
-                println!("{i},{j} New best score: {}", high_score);
-                let best_ai = &ai_w_scores[0].1;
-                visual_ai(best_ai, &device);
-                best_ai.save_file(&ai_naming(best_ai, number_of_bests), &recorder);
-                number_of_bests += 1;
-            }
-            println!("{i},{j} Best score: {}", high_score);
-            println!("{i},{j} Best mape: {}", (1.0 / high_score) - 1.);
-
-            *island = make_new_generation(ai_w_scores, &device, BEST_PROPORTION, &ai_maker);
+        match &self.cli.command {
+            Command::Evaluate(files) => self.evaluate(files, &device, ai_maker),
+            Command::Worker(address) => self.work(address, &device, ai_maker),
+            Command::Compare(_) => unreachable!("runs are compared without a backend"),
+            _ => self.evolve(device, ai_maker),
         }
+    }
 
-        if i % 100 == 0 {
-            island_crossing(&mut islands);
+    /// Ranks saved networks by their mean score over the episodes picked with `--episodes`,
+    /// the given files and every one listed in the manifest of the `--models` directory, and
+    /// writes the leaderboard into that directory.
+    fn evaluate<B: Backend, A: ListableAI<B>>(
+        &self,
+        files: &[String],
+        device: &B::Device,
+        ai_maker: impl Fn(&B::Device) -> A,
+    ) {
+        let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
+        let sample_ai = ai_maker(device);
+        let episodes: usize = self.cli.parsed("episodes").unwrap_or(1).max(1);
+        let TrainConfig {
+            shaping,
+            episode,
+            fitness,
+            ..
+        } = self.config();
+        let fitness = self.cli.parsed("fitness").unwrap_or(fitness);
+        let models = self.cli.option("models").map(PathBuf::from);
+        let trajectories = self.cli.option("trajectories").map(Path::new);
+        let mut files = files.to_vec();
+        if let Some(directory) = &models {
+            let manifest = Manifest::load(directory).expect("could not read the manifest");
+            files.extend(
+                manifest
+                    .latest(sample_ai.network_name())
+                    .iter()
+                    .map(|entry| {
+                        directory
+                            .join(&entry.file)
+                            .to_str()
+                            .expect("path is not unicode")
+                            .to_string()
+                    }),
+            );
+        }
+        let networks: Vec<(String, A)> = files
+            .into_iter()
+            .map(|file| {
+                (
+                    file.clone(),
+                    load_saved(sample_ai.clone(), &file, &recorder),
+                )
+            })
+            .collect();
+        // every episode of every network is scored at once
+        let rollouts: Vec<(&str, A, u64)> = networks
+            .iter()
+            .flat_map(|(file, ai)| {
+                let name = trajectory_name(file);
+                (0..episodes as u64).map(move |seed| (name, ai.clone(), seed))
+            })
+            .collect();
+        let scores: Vec<f32> = rollouts
+            .into_par_iter()
+            .map(|(name, ai, seed)| match trajectories {
+                Some(directory) => {
+                    record_ai_with_fitness(
+                        &ai, name, seed, fitness, shaping, &episode, directory, device,
+                    )
+                    .expect("could not record the trajectory")
+                    .0
+                }
+                None => test_ai_with_fitness(&ai, seed, fitness, shaping, &episode, device).0,
+            })
+            .collect();
+        let entries = networks
+            .into_iter()
+            .zip(scores.chunks(episodes))
+            .map(|((file, _), scores)| LeaderboardEntry::of(file, scores.to_vec()))
+            .collect();
+        let leaderboard = Leaderboard::new(episodes, entries);
+        print!("{leaderboard}");
+        if let Some(directory) = models {
+            leaderboard
+                .save(directory.join("leaderboard.json"))
+                .expect("could not write the leaderboard");
         }
     }
-}
-
-pub fn ai_naming<B: Backend, A: AI<B>>(best_ai: &A, i: usize) -> String {
-    format!("best_{}_{i}", best_ai.network_name())
-}
 
-pub fn island_crossing<B: Backend, A: AI<B>>(islands: &mut Vec<Vec<A>>) {
-    let fittest_start = ISLAND_POPULATION - (ISLAND_POPULATION as f32 * BEST_PROPORTION) as usize;
-    // Clone the best individuals instead of holding references
-    let best: Vec<Vec<A>> = islands
-        .iter()
-        .map(|island| island[fittest_start..].to_vec())
-        .collect();
+    /// The action noise picked with `--noise <spec>`, none by default.
+    fn noise(&self) -> Option<ActionNoise> {
+        self.cli
+            .option("noise")
+            .map(|noise| noise.parse().expect("invalid action noise"))
+    }
 
-    let island_count = islands.len();
-    let fittest_count = best[0].len();
+    /// The training configuration given with `--config`, for how the episodes are scored,
+    /// the default without.
+    fn config(&self) -> TrainConfig {
+        self.cli
+            .option("config")
+            .map_or_else(TrainConfig::default, |path| {
+                TrainConfig::load(path).expect("invalid training configuration")
+            })
+    }
 
-    // 10 crossings
-    for _ in 0..10 {
-        let (mothers_island, fathers_island) = make_distinct(island_count);
+    /// Scores networks for the run listening at `address`, over a connection per core. The
+    /// network options, the noise, the fitness and the configuration have to be the run's.
+    fn work<B: Backend, A: ListableAI<B>>(
+        &self,
+        address: &str,
+        device: &B::Device,
+        ai_maker: impl Fn(&B::Device) -> A + Sync,
+    ) {
+        let noise = self.noise();
+        let TrainConfig {
+            shaping,
+            episode,
+            fitness,
+            ..
+        } = self.config();
+        let fitness = self.cli.parsed("fitness").unwrap_or(fitness);
+        let connections = std::thread::available_parallelism().map_or(1, usize::from);
+        std::thread::scope(|scope| {
+            for _ in 0..connections {
+                scope.spawn(|| {
+                    let stream =
+                        TcpStream::connect(address).expect("could not connect to the coordinator");
+                    let served = serve(stream, &ai_maker(device), device, |ai, seed| match noise {
+                        Some(noise) => test_ai_with_fitness(
+                            &NoisyAI::new(ai.clone(), noise),
+                            seed,
+                            fitness,
+                            shaping,
+                            &episode,
+                            device,
+                        ),
+                        None => test_ai_with_fitness(ai, seed, fitness, shaping, &episode, device),
+                    })
+                    .expect("lost the coordinator");
+                    println!("Scored {served} networks");
+                });
+            }
+        });
+    }
 
-        let offspring = {
-            let mother = &best[mothers_island][rand::random_range(0..fittest_count)];
-            let father = &best[fathers_island][rand::random_range(0..fittest_count)];
-            make_offspring(mother, father, &Distribution::Normal(0.0, SMALLEST_SD))
+    fn evolve<B: Backend, A: ListableAI<B>>(
+        self,
+        device: B::Device,
+        ai_maker: impl Fn(&B::Device) -> A,
+    ) {
+        let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
+        let sample_ai = ai_maker(&device);
+        let noise = self.noise();
+        let cli = self.cli;
+        // the run's settings come from the TOML file given with `--config <path>`, overridden
+        // by the command line
+        let mut config = cli
+            .option("config")
+            .map_or_else(TrainConfig::default, |path| {
+                TrainConfig::load(path).expect("invalid training configuration")
+            });
+        config.generations = cli.parsed("generations").unwrap_or(config.generations);
+        config.islands = cli.parsed("islands").unwrap_or(config.islands);
+        config.island_population = cli
+            .parsed("island_population")
+            .unwrap_or(config.island_population);
+        config.model_dir = cli.parsed("model_dir").unwrap_or(config.model_dir);
+        config.results_log = cli.parsed("results_log").or(config.results_log);
+        config.metrics_dir = cli.parsed("metrics_dir").or(config.metrics_dir);
+        config.patience = cli.parsed("patience").or(config.patience);
+        config.target_score = cli.parsed("target_score").or(config.target_score);
+        config.max_hours = cli.parsed("max_hours").or(config.max_hours);
+        config.max_evaluations = cli.parsed("max_evaluations").or(config.max_evaluations);
+        config.confirmation_episodes = cli
+            .parsed("confirmation_episodes")
+            .or(config.confirmation_episodes);
+        config.episodes = cli.parsed("episodes").unwrap_or(config.episodes).max(1);
+        config.fitness = cli.parsed("fitness").unwrap_or(config.fitness);
+        config.validate().expect("invalid training configuration");
+        std::fs::create_dir_all(&config.model_dir).expect("could not create the model directory");
+        // every offspring is refined with `--local-search <steps>` extra evaluations
+        let local_search_steps = cli.parsed("local_search").unwrap_or(0);
+        let mutation = config.mutation().with_local_search(local_search_steps);
+        let save_format = if cli.switch("safetensors") {
+            SaveFormat::Safetensors
+        } else {
+            SaveFormat::Mpk
         };
-        islands[mothers_island][rand::random_range(0..ALWAYS_RAND_COUNT)] = offspring;
-    }
-}
+        // the run is replayed by passing the printed seed back with `--seed <n>`: the fresh
+        // networks, the operators, the migrations and the action noise all follow it
+        let seed = cli.parsed("seed").unwrap_or_else(rand::random);
+        println!("Seed: {seed}");
+        RunMetadata {
+            seed,
+            args: std::env::args().skip(1).collect(),
+            config: config.clone(),
+        }
+        .save(config.model_dir.join("run.json"))
+        .expect("could not write the run metadata");
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut schedule = OperatorSchedule::default();
+        if let Some(path) = cli.option("operators") {
+            let config = ScheduleConfig::load(path).expect("could not load the operator schedule");
+            schedule = schedule
+                .configured(&config)
+                .expect("invalid operator schedule");
+        }
+        let train_auxiliary = cli.switch("auxiliary");
+        let trajectories = cli.option("trajectories").map(Path::new);
+        // the noise only applies to scoring, the new bests are shown clean
+        let (fitness, shaping, episode_config) = (config.fitness, config.shaping, config.episode);
+        let score_in_episode = |ai: &A, episode: u64| match noise {
+            Some(noise) => {
+                let noisy = NoisyAI::seeded(
+                    ai.clone(),
+                    noise,
+                    noise_seed(seed, episode, ai.fingerprint()),
+                );
+                test_ai_with_fitness(&noisy, episode, fitness, shaping, &episode_config, &device)
+            }
+            None => test_ai_with_fitness(ai, episode, fitness, shaping, &episode_config, &device),
+        };
+        // an island's individuals are scored in the same episodes every generation, so a cached
+        // score is for the same episodes as a new one
+        let episode_seeds = |island: usize| config.episode_seeds(seed, island);
+        let score = |ai: &A, island: usize| {
+            let rollouts: Vec<_> = episode_seeds(island)
+                .map(|seed| score_in_episode(ai, seed))
+                .collect();
+            config.episode_aggregate.combine(&rollouts).0
+        };
+        // the mean over episodes none of the islands is scored in, all scored at once
+        let confirmed_score = |ai: &A, episodes: usize| {
+            let rollouts: Vec<(A, u64)> = config
+                .confirmation_seeds(episodes)
+                .map(|seed| (ai.clone(), seed))
+                .collect();
+            rollouts
+                .into_par_iter()
+                .map(|(ai, seed)| score_in_episode(&ai, seed).0)
+                .sum::<f32>()
+                / episodes as f32
+        };
+        if cli.switch("adaptive") && schedule.adaptation_rate().is_none() {
+            schedule = schedule.with_adaptation_rate(Some(0.1));
+        }
 
-fn make_new_generation<B: Backend, A: AI<B>>(
-    ais_w_score: Vec<(f32, A)>,
-    device: &B::Device,
-    best_proportion: f32,
-    ai_maker: &impl Fn(&B::Device) -> A,
-) -> Vec<A> {
-    let best_score = ais_w_score[0].0;
-    let std_deviation = ais_w_score[0].1.max_amp() as f64
-        * if best_score < 0.5 {
-            0.15
-        } else if best_score < 0.75 {
-            0.075
-        } else if best_score < 0.9 {
-            0.05
-        } else if best_score < 0.95 {
-            0.02
+        let next_seq = Manifest::load(&config.model_dir)
+            .expect("could not read the manifest")
+            .next_seq(sample_ai.network_name());
+        // the whole population is archived after every generation and picked up from there
+        let population = cli.option("population");
+        let mut first_generation = 0;
+        let mut best_score = 0_f32;
+        let mut last_scores = Vec::new();
+        // `resume` continues the latest checkpoint of the run, if it has been checkpointed
+        let resume_from = match cli.option("resume_from") {
+            Some(path) => {
+                Some(resolve_checkpoint(Path::new(path)).expect("no checkpoint to resume from"))
+            }
+            None if cli.command == Command::Resume => {
+                latest_checkpoint(&config.model_dir).expect("could not read the model directory")
+            }
+            None => None,
+        };
+        let resuming = resume_from.is_some();
+        // a fresh run starts with an empty hall of fame, a resumed one with that of its
+        // checkpoint, or without one with the members saved in the model directory
+        let mut hall_of_fame = if cli.command == Command::Resume && !resuming {
+            HallOfFame::resume(
+                config.hall_of_fame_size,
+                &config.model_dir,
+                &sample_ai,
+                next_seq,
+                &recorder,
+            )
+            .expect("could not read the hall of fame")
         } else {
-            SMALLEST_SD
+            HallOfFame::new(config.hall_of_fame_size, &config.model_dir, next_seq)
+        };
+        let mut islands: Vec<Vec<_>> = if let Some(path) = resume_from {
+            // a checkpointed run goes on with its islands, scores, generator, operator weights
+            // and hall of fame; the other archives, the patience and the budget start over
+            println!("Resuming from {}", path.display());
+            let checkpoint = Checkpoint::load(
+                path,
+                &sample_ai,
+                config.hall_of_fame_size,
+                &config.model_dir,
+                &recorder,
+                &device,
+            )
+            .expect("could not read the checkpoint");
+            first_generation = checkpoint.population.generation;
+            best_score = checkpoint.best_score;
+            last_scores = checkpoint.scores;
+            rng = StdRng::seed_from_u64(checkpoint.rng_seed);
+            schedule = schedule
+                .configured(&checkpoint.schedule)
+                .expect("invalid checkpointed schedule");
+            hall_of_fame = checkpoint.hall_of_fame;
+            checkpoint.population.islands
+        } else if let Some(path) =
+            population.filter(|path| Path::new(path).with_extension("mpk").exists())
+        {
+            let population = Population::load(path, &sample_ai, &recorder, &device)
+                .expect("could not read the population archive");
+            first_generation = population.generation;
+            population.islands
+        } else if cli.command == Command::Resume {
+            println!(
+                "No checkpoint in {}, starting from the best saved networks",
+                config.model_dir.display()
+            );
+            (0..config.islands)
+                .map(|_| {
+                    resume_island(
+                        &device,
+                        &ai_maker,
+                        &config,
+                        &mut schedule,
+                        &mutation,
+                        &recorder,
+                        &mut rng,
+                    )
+                })
+                .collect()
+        } else {
+            let mut islands: Vec<Vec<_>> = (0..config.islands)
+                .map(|_| {
+                    init_island_population(
+                        &device,
+                        config.island_size(0),
+                        &mutation,
+                        &ai_maker,
+                        &mut rng,
+                    )
+                })
+                .collect();
+            if let Some(warm_start) = &config.warm_start {
+                // the first of every island start out imitating the scripted controller, in the
+                // episodes of the run's task
+                let distillation = warm_start.distillation();
+                let samples = ScriptedController::default().collect_samples(
+                    &distillation,
+                    &mut *config.fitness.task(config.shaping),
+                    &config.episode,
+                );
+                let warmed = AtomicUsize::new(0);
+                for island in islands.iter_mut() {
+                    let fitted = warm_start.fitted_count(island.len());
+                    let seeds: Vec<u64> = (0..fitted).map(|_| rng.random()).collect();
+                    island[..fitted].par_iter_mut().zip(seeds).for_each(
+                        |(genome, seed): (&mut Genome<A>, u64)| {
+                            if let Some(ai) = genome.ai().imitate(
+                                &samples,
+                                &distillation,
+                                &device,
+                                &mut StdRng::seed_from_u64(seed),
+                            ) {
+                                *genome = genome.with_ai(ai);
+                                warmed.fetch_add(1, Ordering::Relaxed);
+                            }
+                        },
+                    );
+                }
+                match warmed.into_inner() {
+                    0 => println!(
+                        "{} networks cannot imitate, none warm started",
+                        sample_ai.network_name()
+                    ),
+                    warmed => println!("Warm started {warmed} networks"),
+                }
+            }
+            islands
         };
 
-    let distribution = Distribution::Normal(0.0, std_deviation);
+        last_scores.resize(islands.len(), Vec::new());
+        // the last scores with whom they belong to, which migrants are chosen by
+        let mut last_rankings: Vec<Vec<(f32, u64)>> = vec![Vec::new(); islands.len()];
 
-    // don't keep parents once they are combined.
-    let number_of_fittest = (best_proportion * ais_w_score.len() as f32) as usize;
-    let best_ones: Vec<_> = ais_w_score
-        .iter()
-        .take(number_of_fittest)
-        .map(|(_, ai)| ai.clone())
-        .collect();
-    let mut new_generation = Vec::new();
-    new_generation.extend((0..ALWAYS_RAND_COUNT).map(|_| ai_maker(device)));
+        let mut results_log = config
+            .results_log
+            .as_ref()
+            .map(|path| ResultsLog::open(path).expect("could not open the results log"));
+        let mut metrics = config.metrics_dir.as_ref().map(|directory| {
+            TensorBoardSink::create(directory).expect("could not create the metrics file")
+        });
+        let mut novelty = config.novelty.clone().map(NoveltyArchive::new);
+        // a fresh run starts its archive over, its first persist replaces the earlier index
+        let mut map_elites = config.map_elites.clone().map(|map_elites| {
+            let directory = config.model_dir.join("map_elites");
+            if !resuming {
+                return MapElites::new(map_elites, directory);
+            }
+            MapElites::resume(map_elites, directory, &sample_ai, &recorder)
+                .expect("could not read the MAP-Elites archive")
+        });
+        // noisy scores differ between evaluations, so there is nothing to cache
+        let mut fitness_cache = config
+            .fitness_cache
+            .as_ref()
+            .filter(|_| noise.is_none())
+            .map(FitnessCache::new);
+        let mut early_stopping = config.early_stopping();
+        let mut budget = config.budget();
+        // the first Ctrl-C lets the generation finish and checkpoints it, a second one exits
+        let interrupted = Arc::new(AtomicBool::new(false));
+        signal_hook::flag::register_conditional_shutdown(SIGINT, 130, Arc::clone(&interrupted))
+            .expect("could not handle Ctrl-C");
+        signal_hook::flag::register(SIGINT, Arc::clone(&interrupted))
+            .expect("could not handle Ctrl-C");
+        // the genomes are scored on the workers connecting to `--listen <address>`, and here
+        // while none are connected
+        let workers = cli.option("listen").map(|address| {
+            let workers = WorkerPool::listen(address, sample_ai.network_name())
+                .expect("could not listen for workers");
+            println!("Listening for workers on {}", workers.address());
+            workers
+        });
+        let mut operator_stats = OperatorStats::default();
+        for i in first_generation..first_generation + config.generations {
+            // with annealing every offspring bred in this generation gets the scheduled sigma
+            let mutation = mutation
+                .clone()
+                .with_annealed_sigma(config.annealed_sigma(i));
+            // all islands are evaluated in one pool, so the cores are kept busy to the end of
+            // the generation instead of idling at the end of every island
+            let before = SystemTime::now();
+            let genomes: Vec<(usize, Genome<A>)> = islands
+                .iter()
+                .enumerate()
+                .flat_map(|(j, island)| island.iter().map(move |genome| (j, genome.clone())))
+                .collect();
+            let cached: Vec<_> = genomes
+                .iter()
+                .map(|(j, genome)| {
+                    fitness_cache.as_mut().and_then(|cache| {
+                        cache.get(genome.fingerprint(), episode_seeds(*j).start, i)
+                    })
+                })
+                .collect();
+            let misses: Vec<bool> = cached.iter().map(Option::is_none).collect();
+            budget.spend(misses.iter().filter(|miss| **miss).count() * config.episodes);
+            // every episode of every individual to score is a request of its own
+            let mut remote = vec![Vec::new(); genomes.len()];
+            if let Some(workers) = workers.as_ref().filter(|workers| !workers.is_empty()) {
+                let pending: Vec<usize> = (0..genomes.len()).filter(|&k| misses[k]).collect();
+                let requests = pending
+                    .iter()
+                    .flat_map(|&k| {
+                        let network = encode_network(genomes[k].1.ai());
+                        episode_seeds(genomes[k].0).map(move |seed| EvaluationRequest {
+                            seed,
+                            network: network.clone(),
+                        })
+                    })
+                    .collect();
+                let mut results = workers.evaluate(requests).into_iter();
+                for k in pending {
+                    remote[k] = results.by_ref().take(config.episodes).collect();
+                }
+            }
+            let scored_all = genomes
+                .into_par_iter()
+                .zip(cached)
+                .zip(remote)
+                .map(|(((j, genome), cached), remote)| {
+                    // the episodes no worker scored are scored here, and timed
+                    let started = Instant::now();
+                    let mut local_episodes = 0;
+                    let (score, behavior) = cached.unwrap_or_else(|| {
+                        let rollouts: Vec<_> = episode_seeds(j)
+                            .zip(remote.into_iter().map(Some).chain(std::iter::repeat(None)))
+                            .map(|(seed, result)| match result.flatten() {
+                                Some(result) => (result.score, result.behavior),
+                                None => {
+                                    local_episodes += 1;
+                                    score_in_episode(genome.ai(), seed)
+                                }
+                            })
+                            .collect();
+                        config.episode_aggregate.combine(&rollouts)
+                    });
+                    let timing = (local_episodes > 0).then(|| EvaluationTiming {
+                        wall: started.elapsed(),
+                        physics_steps: local_episodes * config.episode.steps,
+                    });
+                    ((j, score, behavior, genome), timing)
+                })
+                .collect::<Vec<_>>();
+            let time_taken = before.elapsed().expect("elapsed calc failed");
+            println!("{i} Time taken: {} ms", time_taken.as_millis());
+            let (scored_all, timings): (Vec<_>, Vec<_>) = scored_all.into_iter().unzip();
+            let timed: Vec<(u64, EvaluationTiming)> = scored_all
+                .iter()
+                .zip(timings)
+                .filter_map(|((_, _, _, genome), timing)| {
+                    timing.map(|timing| (genome.fingerprint(), timing))
+                })
+                .collect();
+            let timing =
+                TimingSummary::of(&timed.iter().map(|(_, timing)| *timing).collect::<Vec<_>>());
+            if let Some(timing) = &timing {
+                let (slowest, _) = timed[timing.slowest];
+                println!("{i} Evaluation times: {timing}, slowest {slowest:016x}");
+                if let Some(metrics) = &mut metrics {
+                    record_timing(metrics, i, timing).expect("could not record the metrics");
+                }
+            }
+            if let Some(cache) = &mut fitness_cache {
+                for ((j, score, behavior, genome), _) in
+                    scored_all.iter().zip(misses).filter(|(_, miss)| *miss)
+                {
+                    cache.insert(
+                        genome.fingerprint(),
+                        episode_seeds(*j).start,
+                        i,
+                        (*score, *behavior),
+                    );
+                }
+            }
+            let evaluated = scored_all.len();
+            // every scored individual is offered to the MAP-Elites archive
+            if let Some(map_elites) = &mut map_elites {
+                let taken = scored_all
+                    .iter()
+                    .filter(|(_, score, behavior, genome)| {
+                        map_elites.insert(*score, *behavior, genome, i)
+                    })
+                    .count();
+                map_elites
+                    .persist(&recorder)
+                    .expect("could not save the MAP-Elites archive");
+                println!(
+                    "{i} MAP-Elites: {taken} cells taken, {:.0}% covered",
+                    map_elites.coverage() * 100.
+                );
+            }
+            let mut scored_islands: Vec<Vec<_>> = islands.iter().map(|_| Vec::new()).collect();
+            for (j, score, behavior, genome) in scored_all {
+                scored_islands[j].push((score, behavior, genome));
+            }
 
-    for _ in 0..(ais_w_score.len() - best_ones.len() - new_generation.len()) {
-        let (mother, father) = make_distinct(number_of_fittest);
-        let offspring = make_offspring(&best_ones[mother], &best_ones[father], &distribution);
-        new_generation.push(offspring);
-    }
+            let mut ranked_islands = Vec::new();
+            let mut island_reports = Vec::new();
+            for (j, mut scored) in scored_islands.into_iter().enumerate() {
+                // each island is accounted its share of the evaluation time
+                let time_taken = time_taken.mul_f64(scored.len() as f64 / evaluated as f64);
+                scored.sort_by(|a, b| {
+                    b.0.partial_cmp(&a.0)
+                        .expect("ai score should be comparable")
+                });
+                let (behaviors, ai_w_scores): (Vec<_>, Vec<_>) = scored
+                    .into_iter()
+                    .map(|(score, behavior, genome)| (behavior, (score, genome)))
+                    .unzip();
 
-    new_generation.extend(best_ones);
+                if let Some(results_log) = &mut results_log {
+                    results_log
+                        .append(&GenerationRecord::of(
+                            i,
+                            j,
+                            &ai_w_scores,
+                            time_taken,
+                            episode_seeds(j).start,
+                        ))
+                        .expect("could not log the results");
+                }
 
-    new_generation
-}
+                let high_score = ai_w_scores
+                    .first()
+                    .map(|(score, _)| *score)
+                    .expect("high score not found");
+                last_scores[j] = ai_w_scores.iter().map(|(score, _)| *score).collect();
+                last_rankings[j] = ai_w_scores
+                    .iter()
+                    .map(|(score, genome)| (*score, genome.fingerprint()))
+                    .collect();
+                for (score, genome) in ai_w_scores.iter().take(config.hall_of_fame_size) {
+                    // with confirmation the candidates are admitted with their mean score
+                    let score = match config.confirmation_episodes {
+                        Some(episodes) if hall_of_fame.admits(*score, genome) => {
+                            let confirmed = confirmed_score(genome.ai(), episodes);
+                            println!("{i},{j} Confirmed score: {confirmed} of {score}");
+                            confirmed
+                        }
+                        _ => *score,
+                    };
+                    if hall_of_fame.consider(score, genome, i, j, save_format) {
+                        println!("{i},{j} New best score: {}", score);
+                        let seed = episode_seeds(j).start;
+                        let best = hall_of_fame.best().expect("the new best is in");
+                        let name = trajectory_name(&best.file);
+                        visual_ai(
+                            genome.ai(),
+                            name,
+                            &config.episode,
+                            seed,
+                            trajectories,
+                            &device,
+                        )
+                        .expect("could not record the trajectory");
+                    }
+                }
+                best_score = match config.confirmation_episodes {
+                    Some(_) => best_score.max(hall_of_fame.best_score()),
+                    None => best_score.max(high_score),
+                };
+                hall_of_fame
+                    .persist(save_format, &recorder)
+                    .expect("could not save the hall of fame");
+                if let Some(retention) = &config.retention {
+                    let members: Vec<String> = hall_of_fame
+                        .entries()
+                        .iter()
+                        .map(|entry| entry.file.clone())
+                        .collect();
+                    retention
+                        .apply(&config.model_dir, sample_ai.network_name(), &members)
+                        .expect("could not prune the saved networks");
+                }
+                println!("{i},{j} Best score: {}", high_score);
+                println!("{i},{j} Best mape: {}", (1.0 / high_score) - 1.);
+                println!("{i},{j} Best sigma: {}", ai_w_scores[0].1.sigma);
+                println!("{i},{j} Best max amplitude: {}", ai_w_scores[0].1.max_amp());
 
-pub fn resume_island<B: Backend, A: ListableAI<B>>(
-    device: &B::Device,
-    ai_maker: &impl Fn(&B::Device) -> A,
-    best_proportion: f32,
-    recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
-) -> Vec<A> {
-    let mut initial = init_island_population::<B, A>(device, ai_maker);
-    let mut loaded_best = Vec::new();
-    let sample_specimen = initial[0].clone();
-    let ai_fnames = sample_specimen.list();
-
-    // TODO: make sure the list method receives the number of ais we want at most. i.e not 30
-    // TODO: instead of returning vec of string return vec of ais.
-    for fname in ai_fnames {
-        loaded_best.push(sample_specimen.clone().load_a_file(&fname, recorder));
-    }
+                let tallies =
+                    schedule.tally(&ai_w_scores, config.number_of_fittest(ai_w_scores.len()));
+                operator_stats.add(&tallies);
+                if let Some(metrics) = &mut metrics {
+                    record_generation(metrics, i, j, &last_scores[j], &tallies, time_taken)
+                        .expect("could not record the metrics");
+                }
+                island_reports.push(IslandReport::of(
+                    j,
+                    &ai_w_scores,
+                    &behaviors,
+                    &tallies,
+                    time_taken,
+                    episode_seeds(j),
+                ));
+                println!("{i},{j} Operators: {}", OperatorStats { tallies });
 
-    for i in 0..(best_proportion * ISLAND_POPULATION as f32) as usize {
-        initial[i] = loaded_best[i % loaded_best.len()].clone();
-    }
+                // with novelty search the parents are ranked by the scores blended with the novelty
+                let mut ai_w_scores = ai_w_scores;
+                if let Some(novelty) = &mut novelty {
+                    let scores: Vec<f32> = ai_w_scores.iter().map(|(score, _)| *score).collect();
+                    let behaviors: Vec<Vec<f32>> =
+                        behaviors.iter().map(|behavior| behavior.to_vec()).collect();
+                    for ((score, _), blended) in ai_w_scores
+                        .iter_mut()
+                        .zip(novelty.blend(&scores, &behaviors))
+                    {
+                        *score = blended;
+                    }
+                    println!("{i},{j} Novelty archive: {}", novelty.len());
+                }
+                // with speciation the parents are ranked by the fitness shared within their species
+                let mut ai_w_scores = match &config.speciation {
+                    Some(speciation) => {
+                        let descriptors: Vec<Vec<f32>> = match speciation.descriptor {
+                            Descriptor::Parameters => ai_w_scores
+                                .iter()
+                                .map(|(_, genome)| parameter_descriptor(genome.ai()))
+                                .collect(),
+                            Descriptor::Behavior => {
+                                behaviors.iter().map(|behavior| behavior.to_vec()).collect()
+                            }
+                        };
+                        let species = speciate(&descriptors, speciation.threshold);
+                        println!(
+                            "{i},{j} Species: {}",
+                            species.iter().max().map_or(0, |max| max + 1)
+                        );
+                        share_fitness(ai_w_scores, &species)
+                    }
+                    None => ai_w_scores,
+                };
+                ai_w_scores.sort_by(|a, b| {
+                    b.0.partial_cmp(&a.0)
+                        .expect("ai score should be comparable")
+                });
+                ranked_islands.push(ai_w_scores);
+            }
+            // with ALPS the islands are age layers the old individuals move up through
+            if let Some(alps) = &config.alps {
+                alps.promote(&mut ranked_islands, alps.reseeds_after(i));
+                let ages: Vec<usize> = ranked_islands
+                    .iter()
+                    .map(|ranked| {
+                        ranked
+                            .iter()
+                            .map(|(_, genome)| genome.age)
+                            .max()
+                            .unwrap_or(0)
+                    })
+                    .collect();
+                println!("{i} Oldest per layer: {ages:?}");
+            }
+            let size = config.island_size(i + 1);
+            for (island, ai_w_scores) in islands.iter_mut().zip(ranked_islands) {
+                *island = make_new_generation(
+                    ai_w_scores,
+                    size,
+                    &device,
+                    &config,
+                    &mut schedule,
+                    &mutation,
+                    &ai_maker,
+                    &mut rng,
+                );
+            }
 
-    let initial: Vec<(f32, A)> = initial.into_iter().map(|ai| (0., ai)).collect();
-    make_new_generation(initial, device, best_proportion, ai_maker)
-}
+            // the offspring and the fresh networks standardize their input by statistics with
+            // the elites' observations in the island's first episode folded in, gathered once per
+            // generation so a network never changes while it is scored
+            let elites: Vec<(usize, A)> = islands
+                .iter()
+                .enumerate()
+                .flat_map(|(j, island)| {
+                    island
+                        .iter()
+                        .filter(|genome| genome.survived())
+                        .map(move |genome| (j, genome.ai().clone()))
+                })
+                .collect();
+            let rollouts: Vec<(usize, Vec<Vec<f32>>)> = elites
+                .into_par_iter()
+                .map(|(j, ai)| {
+                    let seed = episode_seeds(j).start;
+                    let observed = observe_ai_with_fitness(
+                        &ai,
+                        seed,
+                        fitness,
+                        shaping,
+                        &episode_config,
+                        &device,
+                    );
+                    (j, observed)
+                })
+                .collect();
+            let mut observations = vec![Vec::new(); islands.len()];
+            for (j, observed) in rollouts {
+                observations[j].extend(observed);
+            }
+            for (island, observations) in islands.iter_mut().zip(&observations) {
+                island
+                    .par_iter_mut()
+                    .filter(|genome| !genome.survived())
+                    .for_each(|genome| {
+                        *genome = genome.with_ai(genome.ai().with_observations(observations));
+                    });
+            }
+
+            // the survivors' auxiliary heads learn from their own rollouts with `auxiliary`, and
+            // the offspring are refined with `local_search`, again with all islands in one pool.
+            // Training reaches the trunk, so a scored survivor is only replaced by its trained
+            // copy if the copy scores as well in the island's episodes
+            let last_score = |j: usize, genome: &Genome<A>| {
+                last_rankings[j]
+                    .iter()
+                    .find(|(_, fingerprint)| *fingerprint == genome.fingerprint())
+                    .map(|(score, _)| *score)
+            };
+            if train_auxiliary {
+                let scored = islands
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(j, island)| island.iter().map(move |genome| (j, genome)))
+                    .filter(|(j, genome)| {
+                        genome.operator.is_none() && last_score(*j, genome).is_some()
+                    })
+                    .count();
+                budget.spend(scored * config.episodes);
+            }
+            if mutation.local_search_steps > 0 {
+                let offspring = islands
+                    .iter()
+                    .flatten()
+                    .filter(|genome| genome.operator.is_some())
+                    .count();
+                budget.spend(offspring * (mutation.local_search_steps + 1) * config.episodes);
+            }
+            if train_auxiliary || mutation.local_search_steps > 0 {
+                let seeds: Vec<u64> = islands.iter().flatten().map(|_| rng.random()).collect();
+                let island_sizes: Vec<usize> = islands.iter().map(Vec::len).collect();
+                let mut refined = std::mem::take(&mut islands)
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(|(j, island)| island.into_iter().map(move |genome| (j, genome)))
+                    .collect::<Vec<_>>()
+                    .into_par_iter()
+                    .zip(seeds)
+                    .map(|((j, genome), seed)| match genome.operator {
+                        None if train_auxiliary => {
+                            let trained = genome.with_ai(
+                                genome
+                                    .ai()
+                                    .train_auxiliary(&device, &mut StdRng::seed_from_u64(seed)),
+                            );
+                            match last_score(j, &genome) {
+                                Some(kept) if score(trained.ai(), j) < kept => genome,
+                                _ => trained,
+                            }
+                        }
+                        Some(_) => local_search(
+                            genome,
+                            &mutation,
+                            |ai: &A| score(ai, j),
+                            &mut StdRng::seed_from_u64(seed),
+                        ),
+                        None => genome,
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter();
+                islands = island_sizes
+                    .iter()
+                    .map(|&size| refined.by_ref().take(size).collect())
+                    .collect();
+            }
 
-pub fn make_distinct(max: usize) -> (usize, usize) {
-    let specimen_one = rand::random_range(0..max);
+            println!("{i} Operators so far: {operator_stats}");
+            if let Some(cache) = &mut fitness_cache {
+                cache.evict_unused(i);
+                println!(
+                    "{i} Fitness cache: {} entries, {:.0}% hits",
+                    cache.len(),
+                    cache.hit_rate() * 100.
+                );
+            }
 
-    let specimen_two = loop {
-        let potential_specimen = rand::random_range(0..max);
-        if potential_specimen != specimen_one {
-            break potential_specimen;
+            if config.migrates_after(i) {
+                island_crossing(
+                    &mut islands,
+                    &last_rankings,
+                    &config,
+                    &schedule,
+                    &mutation,
+                    &mut rng,
+                );
+            }
+            if config.injects_after(i) {
+                hall_of_fame.inject(&mut islands, config.random_per_generation, &mut rng);
+            }
+            if let Some(map_elites) = map_elites.as_ref().filter(|_| config.alps.is_none()) {
+                map_elites.inject(&mut islands, config.random_per_generation, &mut rng);
+            }
+            if let Some(path) = population {
+                Population::new(islands.clone(), i + 1)
+                    .save(path, &recorder)
+                    .expect("could not save the population archive");
+            }
+            GenerationReport {
+                generation: i,
+                best_score,
+                evaluations: evaluated,
+                eval_ms: time_taken.as_millis(),
+                generation_ms: before.elapsed().expect("elapsed calc failed").as_millis(),
+                timing,
+                islands: island_reports,
+            }
+            .save(&config.model_dir)
+            .expect("could not write the generation report");
+            let stop = match interrupted.load(Ordering::Relaxed) {
+                true => Some(StopReason::Interrupted),
+                false => early_stopping
+                    .update(&last_scores.concat())
+                    .or_else(|| budget.exhausted()),
+            };
+            let last = stop.is_some() || i + 1 == first_generation + config.generations;
+            // the last generation is always checkpointed, so a stopped run can be continued;
+            // without `--checkpoint` it is named after its generation and replaces the one before
+            if last || (i + 1).is_multiple_of(config.checkpoint_interval) {
+                let path = match cli.option("checkpoint") {
+                    Some(path) => PathBuf::from(path),
+                    None => generation_checkpoint(&config.model_dir, i + 1),
+                };
+                // reseeded, so a run resumed from here draws the same numbers from now on
+                let rng_seed = rng.random();
+                rng = StdRng::seed_from_u64(rng_seed);
+                let checkpoint = Checkpoint {
+                    population: Population::new(islands.clone(), i + 1),
+                    scores: last_scores.clone(),
+                    best_score,
+                    rng_seed,
+                    schedule: schedule.config(),
+                    hall_of_fame: hall_of_fame.clone(),
+                };
+                checkpoint
+                    .save(&path, &recorder)
+                    .expect("could not save the checkpoint");
+                if cli.option("checkpoint").is_none() {
+                    remove_other_checkpoints(&config.model_dir, i + 1)
+                        .expect("could not remove the former checkpoints");
+                }
+                if stop == Some(StopReason::Interrupted) {
+                    println!(
+                        "{i} Checkpointed, continue with --resume {}",
+                        path.display()
+                    );
+                }
+            }
+            if let Some(reason) = stop {
+                println!("{i} Stopping: {reason}");
+                break;
+            }
+        }
+        println!(
+            "Done: {} evaluations in {:.2} hours, best score {best_score}",
+            budget.evaluations(),
+            budget.elapsed().as_secs_f64() / 3600.
+        );
+        if let Some(best) = hall_of_fame.best() {
+            println!(
+                "Best: {} from generation {}, island {}",
+                best.file, best.generation, best.island
+            );
         }
-    };
+    }
+}
 
-    (specimen_one, specimen_two)
+/// Prints the fitness curves of the runs side by side, as a table and a chart.
+fn compare(runs: &[String]) {
+    let curves: Vec<RunCurve> = runs
+        .iter()
+        .map(|run| {
+            RunCurve::load(Path::new(run)).unwrap_or_else(|e| panic!("could not read {run}: {e}"))
+        })
+        .collect();
+    println!("{}\n", table(&curves));
+    println!("{}", plot(&curves, 72, 20));
 }
 
-pub fn make_offspring<B: Backend, A: AI<B>>(
-    mother: &A,
-    father: &A,
-    distribution: &Distribution,
-) -> A {
-    match rand::random_range(0..15) {
-        0 | 1 | 2 | 3 | 4 => mother.offspring_iw(father, distribution),
-        5 | 6 | 7 | 8 => mother.offspring_aw(father, distribution),
-        9 => mother.offspring(father, distribution),
-        10 => mother.offspring_layers(father, distribution),
-        11 | 12 => mother.jiggle(distribution),
-        13 | 14 => father.jiggle(distribution),
-        _ => unreachable!(),
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let cli = Cli::parse(&args).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(if e == CliError::Help { 0 } else { 2 })
+    });
+    // comparing logs needs no backend
+    if let Command::Compare(runs) = &cli.command {
+        return compare(runs);
     }
+    let backend = BackendChoice::from_args(&cli.options).expect("invalid backend");
+    let precision = Precision::from_args(&cli.options).expect("invalid precision");
+    println!("Running on {backend} in {precision}");
+    backend
+        .run_in(precision, Evolution { cli })
+        .expect("unsupported precision");
 }
@@ -0,0 +1,103 @@
+use engine::sweep::{comparison_table, SweepConfig, SweepOutcome, SweepRun};
+use std::fs::File;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Trains the run with the eval binary next to this one, in a directory of its own where its
+/// configuration, operator schedule, output and results log are kept.
+fn train(
+    eval: &Path,
+    run: &SweepRun,
+    directory: &Path,
+    eval_args: &[String],
+) -> Option<SweepOutcome> {
+    std::fs::create_dir_all(directory).expect("could not create the run directory");
+    let results_log = directory.join("results.jsonl");
+    // a rerun sweep starts the run over
+    std::fs::remove_file(&results_log).ok();
+    let config = engine::train_config::TrainConfig {
+        model_dir: directory.to_path_buf(),
+        results_log: Some(results_log.clone()),
+        ..run.config.clone()
+    };
+    let config_path = directory.join("config.toml");
+    std::fs::write(
+        &config_path,
+        toml::to_string(&config).expect("could not write the configuration"),
+    )
+    .expect("could not write the configuration");
+    let schedule_path = directory.join("operators.json");
+    std::fs::write(
+        &schedule_path,
+        serde_json::to_string_pretty(&run.schedule).expect("could not write the schedule"),
+    )
+    .expect("could not write the schedule");
+
+    let output = File::create(directory.join("eval.log")).expect("could not create the run's log");
+    let status = Command::new(eval)
+        .arg("train")
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--operators")
+        .arg(&schedule_path)
+        .args(eval_args)
+        .stdout(output.try_clone().expect("could not share the run's log"))
+        .stderr(output)
+        .status()
+        .expect("could not start eval");
+    if !status.success() {
+        eprintln!("{}: eval failed with {status}", directory.display());
+    }
+    SweepOutcome::read(&results_log).ok().flatten()
+}
+
+/// `sweep <sweep.toml> [eval options]` trains every combination of the sweep, `parallel` at
+/// a time, and prints them ranked. The options, such as `--network` or `--seed`, are passed
+/// on to every run.
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let Some((sweep_path, eval_args)) = args.split_first() else {
+        eprintln!("usage: sweep <sweep.toml> [eval options]");
+        std::process::exit(2);
+    };
+    let sweep = SweepConfig::load(sweep_path).unwrap_or_else(|e| panic!("{e}"));
+    let eval = std::env::current_exe()
+        .expect("could not find the sweep binary")
+        .with_file_name(format!("eval{}", std::env::consts::EXE_SUFFIX));
+    let runs = sweep.runs();
+    println!("Sweeping {} runs, {} at a time", runs.len(), sweep.parallel);
+
+    let next = AtomicUsize::new(0);
+    let outcomes: Vec<(usize, Option<SweepOutcome>)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..sweep.parallel.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut outcomes = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(run) = runs.get(index) else {
+                            return outcomes;
+                        };
+                        let directory = sweep.directory.join(format!("run_{index}"));
+                        println!("run_{index}: {}", run.label);
+                        outcomes.push((index, train(&eval, run, &directory, eval_args)));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("a sweep worker panicked"))
+            .collect()
+    });
+
+    let mut ordered = vec![None; runs.len()];
+    for (index, outcome) in outcomes {
+        ordered[index] = outcome;
+    }
+    let table = comparison_table(&runs, &ordered);
+    println!("{table}");
+    std::fs::write(sweep.directory.join("comparison.txt"), table)
+        .expect("could not write the comparison");
+}
@@ -4,8 +4,15 @@ use burn::prelude::Backend;
 use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
 use engine::base_ai::ListableAI;
 use engine::base_ai::AI;
-use engine::sim_for_ai::visual_ai;
-use engine::{ai, small_ai};
+use engine::dot::export_dot;
+use engine::ensemble_ai::{Combine, EnsembleAI};
+use engine::map_elites::MapElitesIndex;
+use engine::physics::trajectory::trajectory_name;
+use engine::sim_for_ai::{visual_ai, EpisodeConfig};
+use engine::train_config::RunMetadata;
+use engine::weights::load_saved;
+use engine::{ai, attn_ai, aux_ai, grip_ai, medium_ai, small_ai};
+use std::path::Path;
 
 type BE = Candle<f32, i64>;
 
@@ -13,21 +20,128 @@ fn small_ai_maker<BE: Backend>(d: &BE::Device) -> impl ListableAI<BE> {
     small_ai::SmallAI::<BE>::new(d)
 }
 
+fn medium_ai_maker<BE: Backend>(d: &BE::Device) -> impl ListableAI<BE> {
+    medium_ai::MediumAI::<BE>::new(d)
+}
+
+fn attn_ai_maker<BE: Backend>(d: &BE::Device) -> impl ListableAI<BE> {
+    attn_ai::AttnAI::<BE>::new(d)
+}
+
+fn grip_ai_maker<BE: Backend>(d: &BE::Device) -> impl ListableAI<BE> {
+    grip_ai::GripAI::<BE>::new(d)
+}
+
+fn aux_ai_maker<BE: Backend>(d: &BE::Device) -> impl ListableAI<BE> {
+    aux_ai::AuxAI::<BE>::new(d)
+}
+
 fn big_ai_maker<BE: Backend>(d: &BE::Device) -> impl ListableAI<BE> {
     ai::BigAI::<BE>::new(d)
 }
 
+/// The episodes of the run the file was saved by, read from the `run.json` of the closest
+/// directory above it that has one, or the default episodes if none does.
+fn run_episode(mpk_name: &str) -> EpisodeConfig {
+    Path::new(mpk_name)
+        .ancestors()
+        .skip(1)
+        .map(|directory| directory.join("run.json"))
+        .find(|path| path.exists())
+        .map_or_else(EpisodeConfig::default, |path| {
+            RunMetadata::load(path)
+                .expect("could not read the run metadata")
+                .config
+                .episode
+        })
+}
+
+/// With `dot=<path>` among the arguments the network's structure is written there first, and
+/// with `trajectories=<dir>` the episode is recorded there. The episode is the one of
+/// `seed=<n>`, episode 0 without it.
 fn run_viz<B: Backend, A: AI<B>>(
     ai_maker: &impl Fn(&B::Device) -> A,
     mpk_name: &str,
+    dot: Option<&str>,
+    seed: u64,
+    trajectories: Option<&Path>,
     device: &B::Device,
 ) {
     let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
 
-    let sample_ai = ai_maker(&device);
+    let sample_ai = ai_maker(device);
 
-    let actual_ai = sample_ai.load_a_file(mpk_name, &recorder);
-    visual_ai(&actual_ai, &device);
+    let actual_ai = load_saved(sample_ai, mpk_name, &recorder);
+    if let Some(path) = dot {
+        export_dot(&actual_ai, path, true).expect("could not write the network structure");
+    }
+    visual_ai(
+        &actual_ai,
+        trajectory_name(mpk_name),
+        &run_episode(mpk_name),
+        seed,
+        trajectories,
+        device,
+    )
+    .expect("could not record the trajectory");
+}
+
+/// Several files are visualized as one ensemble averaging their forces.
+fn run_ensemble_viz<B: Backend, A: AI<B>>(
+    ai_maker: &impl Fn(&B::Device) -> A,
+    mpk_names: &[String],
+    seed: u64,
+    trajectories: Option<&Path>,
+    device: &B::Device,
+) {
+    let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
+
+    let members = mpk_names
+        .iter()
+        .map(|mpk_name| load_saved(ai_maker(device), mpk_name, &recorder))
+        .collect();
+    visual_ai(
+        &EnsembleAI::new(members, Combine::Mean),
+        "ensemble",
+        &run_episode(&mpk_names[0]),
+        seed,
+        trajectories,
+        device,
+    )
+    .expect("could not record the trajectory");
+}
+
+/// With `map=<dir>` the MAP-Elites archive there is shown as a grid of its cells, and with
+/// `cell=<x>,<y>` as well the elite of that cell is visualized.
+fn browse_map_elites(directory: &str, cell: Option<&str>) -> Option<String> {
+    let directory = Path::new(directory);
+    let index = MapElitesIndex::load(directory).expect("could not read the MAP-Elites archive");
+    let Some(cell) = cell else {
+        println!("{}", index.grid());
+        for cell in &index.cells {
+            println!(
+                "{},{}: {} from generation {} in {}",
+                cell.x, cell.y, cell.score, cell.generation, cell.file
+            );
+        }
+        return None;
+    };
+    let (x, y) = cell.split_once(',').expect("cell=<x>,<y>");
+    let (x, y) = (
+        x.parse().expect("invalid cell x"),
+        y.parse().expect("invalid cell y"),
+    );
+    let record = index
+        .cell(x, y)
+        .unwrap_or_else(|| panic!("cell {x},{y} is empty"));
+    println!("{x},{y}: {} {:?}", record.score, record.behavior);
+    Some(
+        directory
+            .join(&record.file)
+            .to_str()
+            .expect("path is not unicode")
+            .to_string(),
+    )
 }
 
 fn main() {
@@ -35,13 +149,133 @@ fn main() {
 
     let args = std::env::args().collect::<Vec<_>>();
 
-    let mpk_name = args[1].clone();
+    let dot = args.iter().find_map(|arg| arg.strip_prefix("dot="));
+    let map = args.iter().find_map(|arg| arg.strip_prefix("map="));
+    let cell = args.iter().find_map(|arg| arg.strip_prefix("cell="));
+    let seed = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("seed="))
+        .map_or(0, |seed| seed.parse().expect("invalid seed"));
+    let trajectories = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("trajectories="))
+        .map(Path::new);
+    let mut mpk_names: Vec<String> = args[1..]
+        .iter()
+        .filter(|arg| {
+            !["dot=", "map=", "cell=", "seed=", "trajectories="]
+                .iter()
+                .any(|prefix| arg.starts_with(prefix))
+        })
+        .cloned()
+        .collect();
+    if let Some(directory) = map {
+        match browse_map_elites(directory, cell) {
+            Some(file) => mpk_names = vec![file],
+            None => return,
+        }
+    }
+    let mpk_name = mpk_names[0].clone();
     let big = big_ai_maker::<BE>(&device);
+    let medium = medium_ai_maker::<BE>(&device);
     let small = small_ai_maker::<BE>(&device);
-    if mpk_name.contains(big.network_name()) {
-        run_viz(&big_ai_maker::<BE>, &mpk_name, &device);
+    let attn = attn_ai_maker::<BE>(&device);
+    let grip = grip_ai_maker::<BE>(&device);
+    let aux = aux_ai_maker::<BE>(&device);
+    if mpk_names.len() > 1 {
+        if mpk_name.contains(big.network_name()) {
+            run_ensemble_viz(&big_ai_maker::<BE>, &mpk_names, seed, trajectories, &device);
+        } else if mpk_name.contains(medium.network_name()) {
+            run_ensemble_viz(
+                &medium_ai_maker::<BE>,
+                &mpk_names,
+                seed,
+                trajectories,
+                &device,
+            );
+        } else if mpk_name.contains(attn.network_name()) {
+            run_ensemble_viz(
+                &attn_ai_maker::<BE>,
+                &mpk_names,
+                seed,
+                trajectories,
+                &device,
+            );
+        } else if mpk_name.contains(grip.network_name()) {
+            run_ensemble_viz(
+                &grip_ai_maker::<BE>,
+                &mpk_names,
+                seed,
+                trajectories,
+                &device,
+            );
+        } else if mpk_name.contains(aux.network_name()) {
+            run_ensemble_viz(&aux_ai_maker::<BE>, &mpk_names, seed, trajectories, &device);
+        } else if mpk_name.contains(small.network_name()) {
+            run_ensemble_viz(
+                &small_ai_maker::<BE>,
+                &mpk_names,
+                seed,
+                trajectories,
+                &device,
+            );
+        } else {
+            panic!("Invalid network name");
+        }
+    } else if mpk_name.contains(big.network_name()) {
+        run_viz(
+            &big_ai_maker::<BE>,
+            &mpk_name,
+            dot,
+            seed,
+            trajectories,
+            &device,
+        );
+    } else if mpk_name.contains(medium.network_name()) {
+        run_viz(
+            &medium_ai_maker::<BE>,
+            &mpk_name,
+            dot,
+            seed,
+            trajectories,
+            &device,
+        );
+    } else if mpk_name.contains(attn.network_name()) {
+        run_viz(
+            &attn_ai_maker::<BE>,
+            &mpk_name,
+            dot,
+            seed,
+            trajectories,
+            &device,
+        );
+    } else if mpk_name.contains(grip.network_name()) {
+        run_viz(
+            &grip_ai_maker::<BE>,
+            &mpk_name,
+            dot,
+            seed,
+            trajectories,
+            &device,
+        );
+    } else if mpk_name.contains(aux.network_name()) {
+        run_viz(
+            &aux_ai_maker::<BE>,
+            &mpk_name,
+            dot,
+            seed,
+            trajectories,
+            &device,
+        );
     } else if mpk_name.contains(small.network_name()) {
-        run_viz(&small_ai_maker::<BE>, &mpk_name, &device);
+        run_viz(
+            &small_ai_maker::<BE>,
+            &mpk_name,
+            dot,
+            seed,
+            trajectories,
+            &device,
+        );
     } else {
         panic!("Invalid network name");
     }
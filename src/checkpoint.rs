@@ -0,0 +1,345 @@
+use crate::base_ai::AI;
+use crate::hall_of_fame::HallOfFame;
+use crate::population::{Population, PopulationError};
+use crate::schedule::ScheduleConfig;
+use burn::prelude::Backend;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// Why a checkpoint could not be written or read.
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(std::io::Error),
+    Population(PopulationError),
+    State(serde_json::Error),
+}
+
+impl Display for CheckpointError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckpointError::Io(e) => write!(f, "could not access the checkpoint: {e}"),
+            CheckpointError::Population(e) => write!(f, "invalid checkpoint population: {e}"),
+            CheckpointError::State(e) => write!(f, "invalid checkpoint state: {e}"),
+        }
+    }
+}
+
+impl Error for CheckpointError {}
+
+impl From<std::io::Error> for CheckpointError {
+    fn from(e: std::io::Error) -> Self {
+        CheckpointError::Io(e)
+    }
+}
+
+impl From<PopulationError> for CheckpointError {
+    fn from(e: PopulationError) -> Self {
+        CheckpointError::Population(e)
+    }
+}
+
+impl From<serde_json::Error> for CheckpointError {
+    fn from(e: serde_json::Error) -> Self {
+        CheckpointError::State(e)
+    }
+}
+
+/// Where a run keeps the checkpoint it continues from at `generation`, unless it is given a
+/// checkpoint path.
+pub fn generation_checkpoint(run_directory: &Path, generation: usize) -> PathBuf {
+    run_directory.join(format!("checkpoint_gen_{generation:04}"))
+}
+
+/// The generation of a generation checkpoint's file, whatever its extension.
+fn checkpoint_generation(file_name: &str) -> Option<usize> {
+    let (stem, _) = file_name.strip_prefix("checkpoint_gen_")?.split_once('.')?;
+    stem.parse().ok()
+}
+
+/// Deletes the generation checkpoints of the run directory but the one of `generation`, so a
+/// run keeps only the one it wrote last, also when it was resumed from an earlier one.
+pub fn remove_other_checkpoints(run_directory: &Path, generation: usize) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(run_directory)? {
+        let entry = entry?;
+        let other = entry
+            .file_name()
+            .to_str()
+            .and_then(checkpoint_generation)
+            .is_some_and(|other| other != generation);
+        if other {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// The generation checkpoint of the run directory that goes furthest, if there is any.
+pub fn latest_checkpoint(run_directory: &Path) -> std::io::Result<Option<PathBuf>> {
+    let mut latest = None;
+    for entry in std::fs::read_dir(run_directory)? {
+        let name = entry?.file_name();
+        let generation = name.to_str().and_then(|name| {
+            name.strip_prefix("checkpoint_gen_")?
+                .strip_suffix(".json")?
+                .parse::<usize>()
+                .ok()
+        });
+        if let Some(generation) =
+            generation.filter(|generation| latest.is_none_or(|latest| *generation > latest))
+        {
+            latest = Some(generation);
+        }
+    }
+    Ok(latest.map(|generation| generation_checkpoint(run_directory, generation)))
+}
+
+/// The checkpoint `--resume <path>` continues from: the path itself, or for a run directory
+/// its latest generation checkpoint.
+pub fn resolve_checkpoint(path: &Path) -> std::io::Result<PathBuf> {
+    if !path.is_dir() {
+        return Ok(path.to_path_buf());
+    }
+    latest_checkpoint(path)?.ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no checkpoint in {}", path.display()),
+        )
+    })
+}
+
+/// A hall of fame member besides its network.
+#[derive(Debug, Deserialize, Serialize)]
+struct MemberState {
+    score: f32,
+    generation: usize,
+    island: usize,
+    file: String,
+}
+
+/// Everything in a checkpoint besides the networks.
+#[derive(Debug, Deserialize, Serialize)]
+struct CheckpointState {
+    scores: Vec<Vec<f32>>,
+    best_score: f32,
+    rng_seed: u64,
+    schedule: ScheduleConfig,
+    hall_of_fame: Vec<MemberState>,
+    next_seq: usize,
+}
+
+/// The state of a run between two generations. The networks of the islands and of the hall of
+/// fame go into one population archive, the rest into a JSON file next to it. The run reseeds
+/// its generator with `rng_seed` when it checkpoints, so a run continued from the checkpoint
+/// draws the same numbers. Not in it are the novelty archive, the MAP-Elites archive beyond what
+/// it persists itself, the early stopping patience, the time and evaluation budget and the
+/// fitness cache, which a continued run starts over with; with novelty search or MAP-Elites it
+/// may evolve other individuals than the run would have.
+#[derive(Clone, Debug)]
+pub struct Checkpoint<A> {
+    pub population: Population<A>,
+    /// The last scores of every island, best first.
+    pub scores: Vec<Vec<f32>>,
+    pub best_score: f32,
+    pub rng_seed: u64,
+    /// The operator weights, as adaptation left them.
+    pub schedule: ScheduleConfig,
+    pub hall_of_fame: HallOfFame<A>,
+}
+
+impl<A> Checkpoint<A> {
+    fn state_path(path: &Path) -> PathBuf {
+        path.with_extension("json")
+    }
+}
+
+impl<A: Clone> Checkpoint<A> {
+    /// Writes the checkpoint to `path` with the recorder's extension and to `path` as `.json`.
+    pub fn save<B: Backend>(
+        &self,
+        path: impl AsRef<Path>,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> Result<(), CheckpointError>
+    where
+        A: AI<B>,
+    {
+        let members = self.hall_of_fame.entries();
+        let mut islands = self.population.islands.clone();
+        islands.push(members.iter().map(|entry| entry.genome.clone()).collect());
+        Population::new(islands, self.population.generation).save(path.as_ref(), recorder)?;
+
+        let state = CheckpointState {
+            scores: self.scores.clone(),
+            best_score: self.best_score,
+            rng_seed: self.rng_seed,
+            schedule: self.schedule.clone(),
+            hall_of_fame: members
+                .iter()
+                .map(|entry| MemberState {
+                    score: entry.score,
+                    generation: entry.generation,
+                    island: entry.island,
+                    file: entry.file.clone(),
+                })
+                .collect(),
+            next_seq: self.hall_of_fame.next_seq(),
+        };
+        std::fs::write(
+            Self::state_path(path.as_ref()),
+            serde_json::to_string_pretty(&state)?,
+        )?;
+        Ok(())
+    }
+
+    /// Reads a checkpoint written by `save`, loading the networks into copies of `sample`. The
+    /// hall of fame keeps `capacity` members saved in `directory`.
+    pub fn load<B: Backend>(
+        path: impl AsRef<Path>,
+        sample: &A,
+        capacity: usize,
+        directory: impl Into<PathBuf>,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+        device: &B::Device,
+    ) -> Result<Self, CheckpointError>
+    where
+        A: AI<B>,
+    {
+        let state: CheckpointState =
+            serde_json::from_str(&std::fs::read_to_string(Self::state_path(path.as_ref()))?)?;
+        let mut population = Population::load(path.as_ref(), sample, recorder, device)?;
+        let members = population.islands.pop().unwrap_or_default();
+        if members.len() != state.hall_of_fame.len() {
+            return Err(PopulationError::Mismatch.into());
+        }
+        let members = members
+            .into_iter()
+            .zip(state.hall_of_fame)
+            .map(|(genome, member)| {
+                (
+                    genome,
+                    member.score,
+                    member.generation,
+                    member.island,
+                    member.file,
+                )
+            });
+        Ok(Self {
+            population,
+            scores: state.scores,
+            best_score: state.best_score,
+            rng_seed: state.rng_seed,
+            schedule: state.schedule,
+            hall_of_fame: HallOfFame::restored(capacity, directory, members, state.next_seq),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evolution::Genome;
+    use crate::schedule::OperatorSchedule;
+    use crate::small_ai::SmallAI;
+    use crate::weights::SaveFormat;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+    use std::env::temp_dir;
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
+        let directory = temp_dir().join("checkpoint_test");
+        let islands: Vec<Vec<_>> = (0..2)
+            .map(|_| {
+                (0..3)
+                    .map(|_| Genome::new(SmallAI::<BE>::new(&device)))
+                    .collect()
+            })
+            .collect();
+        let mut hall_of_fame = HallOfFame::new(3, &directory, 7);
+        hall_of_fame.consider(0.7, &islands[1][2], 4, 1, SaveFormat::Mpk);
+        let schedule = OperatorSchedule::<BE, SmallAI<BE>>::default()
+            .with_adaptation_rate(Some(0.1))
+            .config();
+        let checkpoint = Checkpoint {
+            population: Population::new(islands.clone(), 5),
+            scores: vec![vec![0.7, 0.2, 0.1], vec![0.6, 0.5, 0.4]],
+            best_score: 0.7,
+            rng_seed: 11,
+            schedule: schedule.clone(),
+            hall_of_fame,
+        };
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("run");
+        checkpoint.save(&path, &recorder).unwrap();
+
+        let loaded = Checkpoint::load(
+            &path,
+            &SmallAI::<BE>::new(&device),
+            3,
+            &directory,
+            &recorder,
+            &device,
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(loaded.population.generation, 5);
+        assert_eq!(loaded.population.islands.len(), 2);
+        for (island, loaded_island) in islands.iter().zip(&loaded.population.islands) {
+            let fingerprints = |island: &[Genome<SmallAI<BE>>]| {
+                island.iter().map(Genome::fingerprint).collect::<Vec<_>>()
+            };
+            assert_eq!(fingerprints(island), fingerprints(loaded_island));
+        }
+        assert_eq!(
+            (loaded.scores, loaded.best_score, loaded.rng_seed),
+            (checkpoint.scores, 0.7, 11)
+        );
+        assert_eq!(loaded.schedule, schedule);
+        assert_eq!(loaded.hall_of_fame.next_seq(), 8);
+        let best = loaded.hall_of_fame.best().unwrap();
+        assert_eq!((best.score, best.generation, best.island), (0.7, 4, 1));
+        assert_eq!(best.genome.fingerprint(), islands[1][2].fingerprint());
+    }
+
+    #[test]
+    fn test_latest_generation_checkpoint() {
+        let directory = temp_dir().join(format!("checkpoint_generations_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        assert_eq!(latest_checkpoint(&directory).unwrap(), None);
+        assert!(resolve_checkpoint(&directory).is_err());
+        for generation in [20, 420, 100] {
+            let path = generation_checkpoint(&directory, generation);
+            std::fs::write(path.with_extension("json"), "{}").unwrap();
+            std::fs::write(path.with_extension("mpk"), "").unwrap();
+        }
+        std::fs::write(directory.join("checkpoint_gen_x.json"), "{}").unwrap();
+
+        let latest = directory.join("checkpoint_gen_0420");
+        assert_eq!(latest_checkpoint(&directory).unwrap(), Some(latest.clone()));
+        assert_eq!(resolve_checkpoint(&directory).unwrap(), latest);
+        let named = directory.join("checkpoint_gen_0100");
+        assert_eq!(resolve_checkpoint(&named).unwrap(), named);
+
+        // only the one written last is kept, and files that are no checkpoints are left alone
+        remove_other_checkpoints(&directory, 100).unwrap();
+        let mut left: Vec<String> = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            vec![
+                "checkpoint_gen_0100.json",
+                "checkpoint_gen_0100.mpk",
+                "checkpoint_gen_x.json"
+            ]
+        );
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,324 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Options taking a value, as `--<flag> <value>`, `--<flag>=<value>` or `<key>=<value>`, with
+/// the key the rest of the binary reads them by.
+const VALUE_OPTIONS: [(&str, &str); 31] = [
+    ("network", "network"),
+    ("generations", "generations"),
+    ("islands", "islands"),
+    ("population", "island_population"),
+    ("model-dir", "model_dir"),
+    ("results-log", "results_log"),
+    ("metrics-dir", "metrics_dir"),
+    ("patience", "patience"),
+    ("target-score", "target_score"),
+    ("max-hours", "max_hours"),
+    ("max-evaluations", "max_evaluations"),
+    ("confirm", "confirmation_episodes"),
+    ("episodes", "episodes"),
+    ("checkpoint", "checkpoint"),
+    ("resume", "resume_from"),
+    ("resume-from", "resume_from"),
+    ("seed", "seed"),
+    ("device", "backend"),
+    ("precision", "precision"),
+    ("config", "config"),
+    ("archive", "population"),
+    ("operators", "operators"),
+    ("noise", "noise"),
+    ("fitness", "fitness"),
+    ("freeze", "freeze"),
+    ("smoothing", "smoothing"),
+    ("local-search", "local_search"),
+    ("dot", "dot"),
+    ("listen", "listen"),
+    ("models", "models"),
+    ("trajectories", "trajectories"),
+];
+
+/// Options that are either given or not.
+const SWITCHES: [&str; 3] = ["safetensors", "adaptive", "auxiliary"];
+
+pub const USAGE: &str = "\
+usage: eval [train | resume | evaluate [<file>...] | worker <address> | compare <run>...] [options]
+
+  train                   evolve from random networks (the default)
+  resume                  continue from the latest checkpoint in --model-dir, or without any
+                          evolve starting from the best saved networks
+  evaluate <file>...      rank saved networks by their mean score over --episodes
+  worker <address>        score networks for the run listening there, given its network options
+  compare <run>...        chart the best island scores of runs, given as results logs or run
+                          directories with generation reports, by generation with 95% intervals
+
+  --network <name>        small, medium, big, rnn, attn, grip or aux (small)
+  --generations <n>       generations to run
+  --islands <n>           number of islands
+  --population <n>        individuals per island, or with a file the archive like --archive
+  --model-dir <dir>       where networks are saved and resumed from (.)
+  --results-log <file>    log the scores of every generation as CSV, or JSON lines as *.jsonl
+  --metrics-dir <dir>     write TensorBoard event files there
+  --patience <n>          stop after n generations without the best or median score improving
+  --target-score <score>  stop once an individual scores this much
+  --max-hours <hours>     stop after this much wall-clock time
+  --max-evaluations <n>   stop after scoring this many individuals
+  --episodes <n>          score every individual over n episodes
+  --confirm <k>           average new bests over k episodes before saving them
+  --checkpoint <file>     checkpoint the whole run there every checkpoint_interval generations
+                          instead of to <model-dir>/checkpoint_gen_<generation>
+  --resume <file>         continue a checkpointed run exactly where it was, from the latest
+                          checkpoint when given a run directory, also as --resume-from
+                          Ctrl-C checkpoints and stops
+  --seed <n>              replay a run
+  --device <backend>      candle, ndarray, or wgpu and cuda when built with them (candle)
+  --precision <p>         f32, f16 or bf16 (f32)
+  --config <file>         TOML training configuration, the options above override it
+  --archive <file>        archive the population every generation and resume from it
+  --operators <file>      JSON operator schedule
+  --noise <spec>          score with action noise
+  --fitness <name>        pose_retention, reach, grasp_and_lift or ball_in_basket[:<x>:<y>]
+                          (pose_retention)
+  --freeze <layers>       comma separated layers kept out of the evolution
+  --smoothing <0..1>      low-pass filter the forces
+  --local-search <n>      hill climbing steps per offspring
+  --listen <address>      score the networks on the workers connecting there
+  --models <dir>          evaluate every network saved there, writing leaderboard.json
+  --trajectories <dir>    record every step of the evaluated episodes and of the new bests
+                          shown there as JSON lines, a file per network and episode
+  --safetensors           save networks as safetensors
+  --adaptive              adapt the operator weights
+  --auxiliary             train auxiliary heads between generations";
+
+/// Why the command line could not be understood.
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    UnknownOption(String),
+    MissingValue(String),
+    /// `evaluate` without any file or `--models` directory.
+    NothingToEvaluate,
+    /// `worker` without the coordinator's address.
+    NoCoordinator,
+    /// `compare` with fewer than two runs.
+    NothingToCompare,
+    /// `--help`, answered with the usage.
+    Help,
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::UnknownOption(option) => write!(f, "unknown option {option}\n\n{USAGE}"),
+            CliError::MissingValue(option) => write!(f, "{option} needs a value\n\n{USAGE}"),
+            CliError::NothingToEvaluate => write!(
+                f,
+                "evaluate needs the files or the --models directory to score\n\n{USAGE}"
+            ),
+            CliError::NoCoordinator => {
+                write!(f, "worker needs the address of the coordinator\n\n{USAGE}")
+            }
+            CliError::NothingToCompare => write!(f, "compare needs at least two runs\n\n{USAGE}"),
+            CliError::Help => write!(f, "{USAGE}"),
+        }
+    }
+}
+
+impl Error for CliError {}
+
+/// What the eval binary was asked to do.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Train,
+    Resume,
+    Evaluate(Vec<String>),
+    /// Scores networks for the coordinator at the address.
+    Worker(String),
+    /// Charts the results logs or run directories side by side.
+    Compare(Vec<String>),
+}
+
+/// The parsed command line: the command, and the options normalized to `key=value` and
+/// switch arguments, the way the code reading them always took them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cli {
+    pub command: Command,
+    pub options: Vec<String>,
+}
+
+impl Cli {
+    /// Parses the arguments after the program name.
+    pub fn parse(args: &[String]) -> Result<Self, CliError> {
+        let mut command = None;
+        let mut files = Vec::new();
+        let mut options = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--help" || arg == "-h" {
+                return Err(CliError::Help);
+            }
+            let flag = arg.strip_prefix("--");
+            let (name, value) = match flag.unwrap_or(arg).split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (flag.unwrap_or(arg), None),
+            };
+            // flags go by their own name, `key=value` arguments by the key
+            let value_option = VALUE_OPTIONS.iter().find(|(option, key)| match flag {
+                Some(_) => *option == name,
+                None => value.is_some() && *key == name,
+            });
+            if let Some((_, key)) = value_option {
+                let value = match value {
+                    Some(value) => value,
+                    None => args
+                        .next()
+                        .ok_or_else(|| CliError::MissingValue(arg.clone()))?
+                        .clone(),
+                };
+                // `population` named the archive before it was the island size, so a value
+                // that is no count still means the archive
+                let key = match name {
+                    "population" if value.parse::<usize>().is_err() => "population",
+                    "population" => "island_population",
+                    _ => key,
+                };
+                options.push(format!("{key}={value}"));
+            } else if SWITCHES.contains(&name) && value.is_none() {
+                options.push(name.to_string());
+            } else if flag.is_some() || value.is_some() {
+                return Err(CliError::UnknownOption(arg.clone()));
+            } else if command.is_none()
+                && ["train", "resume", "evaluate", "worker", "compare"].contains(&name)
+            {
+                command = Some(name);
+            } else if command == Some("evaluate")
+                || command == Some("compare")
+                || (command == Some("worker") && files.is_empty())
+            {
+                files.push(arg.clone());
+            } else {
+                return Err(CliError::UnknownOption(arg.clone()));
+            }
+        }
+        let command = match command {
+            None | Some("train") => Command::Train,
+            Some("resume") => Command::Resume,
+            Some("worker") => Command::Worker(files.pop().ok_or(CliError::NoCoordinator)?),
+            Some("compare") if files.len() < 2 => return Err(CliError::NothingToCompare),
+            Some("compare") => Command::Compare(files),
+            _ if files.is_empty()
+                && !options.iter().any(|option| option.starts_with("models=")) =>
+            {
+                return Err(CliError::NothingToEvaluate)
+            }
+            _ => Command::Evaluate(files),
+        };
+        Ok(Self { command, options })
+    }
+
+    /// The value of a `key=value` option.
+    pub fn option(&self, key: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find_map(|option| option.strip_prefix(key)?.strip_prefix('='))
+    }
+
+    /// A value option parsed, panicking with the option's name when it doesn't parse.
+    pub fn parsed<T: std::str::FromStr>(&self, key: &str) -> Option<T> {
+        self.option(key).map(|value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("{key} cannot be {value}"))
+        })
+    }
+
+    pub fn switch(&self, name: &str) -> bool {
+        self.options.iter().any(|option| option == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_command_line() {
+        let cli = Cli::parse(&args(
+            "resume --network big --seed=3 --device ndarray adaptive noise=0.1",
+        ))
+        .unwrap();
+        assert_eq!(cli.command, Command::Resume);
+        assert_eq!(cli.option("network"), Some("big"));
+        assert_eq!(cli.parsed::<u64>("seed"), Some(3));
+        assert_eq!(cli.option("backend"), Some("ndarray"));
+        assert_eq!(cli.option("noise"), Some("0.1"));
+        assert!(cli.switch("adaptive"));
+        assert!(!cli.switch("auxiliary"));
+        let cli = Cli::parse(&args("--resume run")).unwrap();
+        assert_eq!(
+            (&cli.command, cli.option("resume_from")),
+            (&Command::Train, Some("run"))
+        );
+        let cli = Cli::parse(&args("--resume-from runs/a/checkpoint_gen_0420")).unwrap();
+        assert_eq!(
+            cli.option("resume_from"),
+            Some("runs/a/checkpoint_gen_0420")
+        );
+
+        let cli = Cli::parse(&args(
+            "--population 50 --archive run evaluate best_a best_b",
+        ))
+        .unwrap();
+        assert_eq!(cli.command, Command::Evaluate(args("best_a best_b")));
+        assert_eq!(cli.parsed::<usize>("island_population"), Some(50));
+        assert_eq!(cli.option("population"), Some("run"));
+        for legacy in [
+            "population=runs/a/population",
+            "--population runs/a/population",
+        ] {
+            let cli = Cli::parse(&args(legacy)).unwrap();
+            assert_eq!(cli.option("population"), Some("runs/a/population"));
+            assert_eq!(cli.option("island_population"), None);
+        }
+        let cli = Cli::parse(&args("population=50")).unwrap();
+        assert_eq!(cli.parsed::<usize>("island_population"), Some(50));
+        assert_eq!(Cli::parse(&[]).unwrap().command, Command::Train);
+
+        assert_eq!(
+            Cli::parse(&args("--netwrok big")),
+            Err(CliError::UnknownOption("--netwrok".to_string()))
+        );
+        assert_eq!(
+            Cli::parse(&args("--seed")),
+            Err(CliError::MissingValue("--seed".to_string()))
+        );
+        assert_eq!(
+            Cli::parse(&args("evaluate")),
+            Err(CliError::NothingToEvaluate)
+        );
+        let cli = Cli::parse(&args("evaluate --models runs/a --episodes 8")).unwrap();
+        assert_eq!(
+            (&cli.command, cli.option("models")),
+            (&Command::Evaluate(Vec::new()), Some("runs/a"))
+        );
+        let cli = Cli::parse(&args("evaluate best_a --trajectories runs/a/trajectories")).unwrap();
+        assert_eq!(cli.option("trajectories"), Some("runs/a/trajectories"));
+        let cli = Cli::parse(&args("worker host:4000 --network big")).unwrap();
+        assert_eq!(cli.command, Command::Worker("host:4000".to_string()));
+        assert_eq!(Cli::parse(&args("worker")), Err(CliError::NoCoordinator));
+        assert!(Cli::parse(&args("worker host:4000 host:4001")).is_err());
+        assert!(Cli::parse(&args("train best_a")).is_err());
+        assert_eq!(Cli::parse(&args("train --help")), Err(CliError::Help));
+        let cli = Cli::parse(&args("compare runs/a runs/b/results.csv")).unwrap();
+        assert_eq!(
+            cli.command,
+            Command::Compare(args("runs/a runs/b/results.csv"))
+        );
+        assert_eq!(
+            Cli::parse(&args("compare runs/a")),
+            Err(CliError::NothingToCompare)
+        );
+    }
+}
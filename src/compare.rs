@@ -0,0 +1,258 @@
+use crate::report::GenerationReport;
+use crate::results_log::{read_results, GenerationRecord};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+/// The normal quantile of a two-sided 95% confidence interval.
+const Z_95: f32 = 1.96;
+/// Symbols the runs are drawn with, in the order they are given.
+const SYMBOLS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// The best scores of the islands in a generation, by their mean and its 95% confidence
+/// interval.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CurvePoint {
+    pub generation: usize,
+    pub mean: f32,
+    pub low: f32,
+    pub high: f32,
+}
+
+impl CurvePoint {
+    pub fn of(generation: usize, scores: &[f32]) -> Self {
+        let count = scores.len().max(1) as f32;
+        let mean = scores.iter().sum::<f32>() / count;
+        // the sample deviation, none for a single island
+        let variance = match scores.len() {
+            0 | 1 => 0.,
+            n => {
+                scores
+                    .iter()
+                    .map(|score| (score - mean).powi(2))
+                    .sum::<f32>()
+                    / (n - 1) as f32
+            }
+        };
+        let margin = Z_95 * (variance / count).sqrt();
+        Self {
+            generation,
+            mean,
+            low: mean - margin,
+            high: mean + margin,
+        }
+    }
+}
+
+/// The fitness curve of a run: a point per generation it logged.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunCurve {
+    pub name: String,
+    pub points: Vec<CurvePoint>,
+}
+
+impl RunCurve {
+    /// Groups the records by generation. A generation logged twice, as a resumed run repeating
+    /// it, is taken from its last records.
+    pub fn from_records(name: impl Into<String>, records: &[GenerationRecord]) -> Self {
+        let mut generations: BTreeMap<usize, BTreeMap<usize, f32>> = BTreeMap::new();
+        for record in records {
+            generations
+                .entry(record.generation)
+                .or_default()
+                .insert(record.island, record.best_score);
+        }
+        let points = generations
+            .into_iter()
+            .map(|(generation, islands)| {
+                CurvePoint::of(generation, &islands.into_values().collect::<Vec<_>>())
+            })
+            .collect();
+        Self {
+            name: name.into(),
+            points,
+        }
+    }
+
+    /// Reads a results log, or the generation reports of a run directory.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let name = path.display().to_string();
+        if !path.is_dir() {
+            return Ok(Self::from_records(name, &read_results(path)?));
+        }
+        let mut points = Vec::new();
+        for entry in std::fs::read_dir(path.join("reports"))? {
+            let text = std::fs::read_to_string(entry?.path())?;
+            let report: GenerationReport =
+                serde_json::from_str(&text).map_err(std::io::Error::other)?;
+            let scores: Vec<f32> = report
+                .islands
+                .iter()
+                .map(|island| island.best_score)
+                .collect();
+            points.push(CurvePoint::of(report.generation, &scores));
+        }
+        points.sort_by_key(|point| point.generation);
+        Ok(Self { name, points })
+    }
+
+    fn at(&self, generation: usize) -> Option<&CurvePoint> {
+        self.points
+            .iter()
+            .find(|point| point.generation == generation)
+    }
+}
+
+/// A row per generation any of the runs logged, with every run's mean and interval side by
+/// side, `-` where a run has no such generation.
+pub fn table(curves: &[RunCurve]) -> String {
+    let generations: Vec<usize> = curves
+        .iter()
+        .flat_map(|curve| curve.points.iter().map(|point| point.generation))
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let mut table = String::from("generation");
+    for (k, _) in curves.iter().enumerate() {
+        write!(
+            table,
+            "  {:<26}",
+            format!("{} mean [95% ci]", SYMBOLS[k % SYMBOLS.len()] as char)
+        )
+        .unwrap();
+    }
+    for generation in generations {
+        write!(table, "\n{generation:<10}").unwrap();
+        for curve in curves {
+            let cell = match curve.at(generation) {
+                Some(point) => format!("{:.4} [{:.4}, {:.4}]", point.mean, point.low, point.high),
+                None => "-".to_string(),
+            };
+            write!(table, "  {cell:<26}").unwrap();
+        }
+    }
+    table
+}
+
+/// The curves drawn in a `width` by `height` character chart, generations to the right and
+/// scores up. A run's means are drawn with its letter and its interval with `:`; where runs
+/// cross, the later one is drawn.
+pub fn plot(curves: &[RunCurve], width: usize, height: usize) -> String {
+    let points = || curves.iter().flat_map(|curve| &curve.points);
+    let (Some(first), Some(last)) = (
+        points().map(|p| p.generation).min(),
+        points().map(|p| p.generation).max(),
+    ) else {
+        return String::new();
+    };
+    let low = points()
+        .map(|point| point.low)
+        .fold(f32::INFINITY, f32::min);
+    let high = points()
+        .map(|point| point.high)
+        .fold(f32::NEG_INFINITY, f32::max);
+    let column = |generation: usize| (generation - first) * (width - 1) / (last - first).max(1);
+    let row = |score: f32| match high > low {
+        true => {
+            (((high - score) / (high - low) * (height - 1) as f32).round() as usize).min(height - 1)
+        }
+        false => height / 2,
+    };
+    let mut canvas = vec![vec![b' '; width]; height];
+    for (k, curve) in curves.iter().enumerate() {
+        for point in &curve.points {
+            let x = column(point.generation);
+            for line in canvas
+                .iter_mut()
+                .take(row(point.low) + 1)
+                .skip(row(point.high))
+            {
+                if line[x] == b' ' {
+                    line[x] = b':';
+                }
+            }
+            canvas[row(point.mean)][x] = SYMBOLS[k % SYMBOLS.len()];
+        }
+    }
+    let mut chart = String::new();
+    for (y, line) in canvas.iter().enumerate() {
+        let label = match y {
+            0 => format!("{high:>8.4}"),
+            _ if y == height - 1 => format!("{low:>8.4}"),
+            _ => " ".repeat(8),
+        };
+        writeln!(chart, "{label} |{}", String::from_utf8_lossy(line)).unwrap();
+    }
+    writeln!(chart, "{} +{}", " ".repeat(8), "-".repeat(width)).unwrap();
+    write!(
+        chart,
+        "{} {first:<w$}{last}",
+        " ".repeat(8),
+        w = width.saturating_sub(last.to_string().len())
+    )
+    .unwrap();
+    for (k, curve) in curves.iter().enumerate() {
+        write!(
+            chart,
+            "\n  {} {}",
+            SYMBOLS[k % SYMBOLS.len()] as char,
+            curve.name
+        )
+        .unwrap();
+    }
+    chart
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(generation: usize, island: usize, best_score: f32) -> GenerationRecord {
+        GenerationRecord {
+            generation,
+            island,
+            best_score,
+            median_score: best_score / 2.,
+            worst_score: 0.,
+            sigma: 0.1,
+            eval_ms: 10,
+            best_fingerprint: "0".to_string(),
+            episode_seed: 0,
+        }
+    }
+
+    #[test]
+    fn test_curves_are_aligned_by_generation() {
+        let point = CurvePoint::of(0, &[0.4, 0.6]);
+        assert!((point.mean - 0.5).abs() < 1e-6);
+        assert!((point.high - point.mean - Z_95 * 0.1).abs() < 1e-6);
+        assert_eq!(CurvePoint::of(0, &[0.3]).low, 0.3);
+
+        // the resumed run logged generation 1 again, over the first one
+        let a = RunCurve::from_records(
+            "a",
+            &[
+                record(0, 0, 0.2),
+                record(0, 1, 0.4),
+                record(1, 0, 0.1),
+                record(1, 0, 0.5),
+            ],
+        );
+        assert_eq!(a.points.len(), 2);
+        assert_eq!(a.points[1].mean, 0.5);
+        let b = RunCurve::from_records("b", &[record(1, 0, 0.7), record(2, 0, 0.8)]);
+
+        let table = table(&[a.clone(), b.clone()]);
+        let rows: Vec<&str> = table.lines().collect();
+        assert_eq!(rows.len(), 4);
+        assert!(rows[1].starts_with("0 ") && rows[1].trim_end().ends_with('-'));
+        assert!(rows[2].contains("0.5000") && rows[2].contains("0.7000"));
+
+        let chart = plot(&[a, b], 20, 5);
+        let lines: Vec<&str> = chart.lines().collect();
+        assert_eq!(lines.len(), 9);
+        assert!(lines[0].starts_with("  0.8000 |") && lines[0].ends_with('B'));
+        assert!(lines[6].trim().starts_with('0') && lines[6].ends_with('2'));
+        assert_eq!(lines[7], "  A a");
+    }
+}
@@ -0,0 +1,175 @@
+use crate::base_ai::{
+    average_bw_linear, combine_bw_linear, interleave_bw_linear, interleave_neurons_linear, AI,
+};
+use burn::nn::Linear;
+use burn::prelude::Backend;
+use rand::rngs::StdRng;
+use std::fmt::Debug;
+
+/// A way of recombining two parents into one offspring. Strategies work on the layers every
+/// `AI` exposes, so a new scheme does not need to touch the network structs. Mutation is applied
+/// separately, after the crossover.
+pub trait CrossoverStrategy<B: Backend, A: AI<B>>: Debug {
+    fn cross(&self, mother: &A, father: &A, rng: &mut StdRng) -> A;
+}
+
+/// Combines the parents layer by layer, keeping the mother's frozen layers.
+fn layerwise<B: Backend, A: AI<B>>(
+    mother: &A,
+    father: &A,
+    mut combine: impl FnMut(usize, &Linear<B>, &Linear<B>) -> Linear<B>,
+) -> A {
+    let layers = mother
+        .layers()
+        .iter()
+        .zip(father.layers())
+        .zip(mother.frozen_layers())
+        .enumerate()
+        .map(|(i, (((_, m), (_, f)), frozen))| if frozen { m.clone() } else { combine(i, m, &f) })
+        .collect();
+    mother.with_layers(layers)
+}
+
+/// Weights of the mother with the biases of the father.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CombineWeightsAndBiases;
+
+impl<B: Backend, A: AI<B>> CrossoverStrategy<B, A> for CombineWeightsAndBiases {
+    fn cross(&self, mother: &A, father: &A, _rng: &mut StdRng) -> A {
+        layerwise(mother, father, |_, m, f| combine_bw_linear(m, f))
+    }
+}
+
+/// Every parameter taken from a randomly chosen parent.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Interleave;
+
+impl<B: Backend, A: AI<B>> CrossoverStrategy<B, A> for Interleave {
+    fn cross(&self, mother: &A, father: &A, rng: &mut StdRng) -> A {
+        layerwise(mother, father, |_, m, f| interleave_bw_linear(m, f, rng))
+    }
+}
+
+/// Every neuron, with its incoming weights and its bias, taken from a randomly chosen parent.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct InterleaveNeurons;
+
+impl<B: Backend, A: AI<B>> CrossoverStrategy<B, A> for InterleaveNeurons {
+    fn cross(&self, mother: &A, father: &A, rng: &mut StdRng) -> A {
+        layerwise(mother, father, |_, m, f| {
+            interleave_neurons_linear(m, f, rng)
+        })
+    }
+}
+
+/// The mean of both parents.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Average;
+
+impl<B: Backend, A: AI<B>> CrossoverStrategy<B, A> for Average {
+    fn cross(&self, mother: &A, father: &A, _rng: &mut StdRng) -> A {
+        layerwise(mother, father, |_, m, f| average_bw_linear(m, f))
+    }
+}
+
+/// Whole layers taken alternately from the mother and the father.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AlternateLayers;
+
+impl<B: Backend, A: AI<B>> CrossoverStrategy<B, A> for AlternateLayers {
+    fn cross(&self, mother: &A, father: &A, _rng: &mut StdRng) -> A {
+        layerwise(mother, father, |i, m, f| {
+            if i % 2 == 0 {
+                m.clone()
+            } else {
+                f.clone()
+            }
+        })
+    }
+}
+
+/// One of the parents unchanged, so the offspring is only mutated.
+#[derive(Copy, Clone, Debug)]
+pub enum KeepParent {
+    Mother,
+    Father,
+}
+
+impl<B: Backend, A: AI<B>> CrossoverStrategy<B, A> for KeepParent {
+    fn cross(&self, mother: &A, father: &A, _rng: &mut StdRng) -> A {
+        match self {
+            KeepParent::Mother => mother.clone(),
+            KeepParent::Father => father.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_alternate_layers() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let mother = SmallAI::<BE>::new(&device);
+        let father = SmallAI::<BE>::new(&device);
+
+        let child = AlternateLayers.cross(&mother, &father, &mut StdRng::seed_from_u64(7));
+        let weights = |ai: &SmallAI<BE>| {
+            ai.layers()
+                .into_iter()
+                .map(|(_, layer)| layer.weight.val().to_data())
+                .collect::<Vec<_>>()
+        };
+        let (child, mother, father) = (weights(&child), weights(&mother), weights(&father));
+        assert_eq!(child[0], mother[0]);
+        assert_eq!(child[1], father[1]);
+        assert_eq!(child[2], mother[2]);
+    }
+
+    #[test]
+    fn test_interleave_neurons() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let mother = SmallAI::<BE>::new(&device);
+        let father = SmallAI::<BE>::new(&device);
+
+        let child = InterleaveNeurons.cross(&mother, &father, &mut StdRng::seed_from_u64(7));
+        let again = InterleaveNeurons.cross(&mother, &father, &mut StdRng::seed_from_u64(7));
+        assert_eq!(child.to_flat_vec(), again.to_flat_vec());
+
+        let columns = |layer: &Linear<BE>| -> Vec<(Vec<f32>, f32)> {
+            let [d_in, d_out] = layer.weight.dims();
+            let weight: Vec<f32> = layer.weight.val().to_data().to_vec().unwrap();
+            let bias: Vec<f32> = layer
+                .bias
+                .as_ref()
+                .unwrap()
+                .val()
+                .to_data()
+                .to_vec()
+                .unwrap();
+            (0..d_out)
+                .map(|j| ((0..d_in).map(|i| weight[i * d_out + j]).collect(), bias[j]))
+                .collect()
+        };
+        let mut from_mother = 0;
+        for (((_, c), (_, m)), (_, f)) in child
+            .layers()
+            .iter()
+            .zip(mother.layers())
+            .zip(father.layers())
+        {
+            for ((c, m), f) in columns(c).into_iter().zip(columns(&m)).zip(columns(&f)) {
+                assert!(c == m || c == f);
+                from_mother += usize::from(c == m);
+            }
+        }
+        assert!(from_mother > 0 && from_mother < 128 + 14 + 8);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::base_ai::AI;
+use crate::sim_for_ai::{apply_forces_and_step, build_observation, prepare_simulation, split_grip};
+use burn::module::{AutodiffModule, Module};
+use burn::nn::loss::{MseLoss, Reduction};
+use burn::optim::{AdamConfig, GradientsParams, Optimizer};
+use burn::prelude::Backend;
+use burn::record::{BinBytesRecorder, FullPrecisionSettings, Recorder};
+use burn::tensor::backend::AutodiffBackend;
+use burn::tensor::{ElementConversion, Tensor};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+/// Settings for fitting a student network to a teacher's actions.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DistillationConfig {
+    pub episodes: usize,
+    pub steps_per_episode: usize,
+    pub epochs: usize,
+    pub batch_size: usize,
+    pub learning_rate: f64,
+}
+
+impl Default for DistillationConfig {
+    fn default() -> Self {
+        Self {
+            episodes: 4,
+            steps_per_episode: 500,
+            epochs: 20,
+            batch_size: 64,
+            learning_rate: 1e-3,
+        }
+    }
+}
+
+/// One observation the teacher saw and the forces it answered with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TeacherSample {
+    pub observation: Vec<f32>,
+    pub action: Vec<f32>,
+}
+
+/// Lets the teacher drive fresh worlds and records what it does, so the student learns on the
+/// states a good controller actually visits.
+pub fn collect_teacher_samples<B: Backend, T: AI<B>>(
+    teacher: &T,
+    config: &DistillationConfig,
+    device: &B::Device,
+) -> Vec<TeacherSample> {
+    let mut samples = Vec::with_capacity(config.episodes * config.steps_per_episode);
+    for _ in 0..config.episodes {
+        let (mut world, mut frames, mut tensor_input) = prepare_simulation();
+        teacher.reset_state();
+        for _ in 0..config.steps_per_episode {
+            build_observation(&mut tensor_input, &mut frames, &world);
+            let observation = Tensor::<B, 1>::from_floats(tensor_input.as_slice(), device);
+            let action: Vec<f32> = teacher
+                .apply(observation)
+                .to_data()
+                .convert::<f32>()
+                .to_vec()
+                .expect("teacher forces not available");
+            let (forces, grip) = split_grip(teacher, &action);
+            apply_forces_and_step(&mut world, forces, grip);
+            samples.push(TeacherSample {
+                observation: tensor_input.clone(),
+                action,
+            });
+        }
+    }
+    samples
+}
+
+/// Fits `student` to the recorded samples with an MSE loss and returns it together with the
+/// mean loss of every epoch. The student is used as a feed-forward network, one sample at a time,
+/// and the samples are shuffled by `rng` every epoch.
+pub fn distill<B, S>(
+    mut student: S,
+    samples: &[TeacherSample],
+    config: &DistillationConfig,
+    device: &B::Device,
+    rng: &mut StdRng,
+) -> (S, Vec<f32>)
+where
+    B: AutodiffBackend,
+    S: AI<B> + AutodiffModule<B>,
+{
+    let mut optimizer = AdamConfig::new().init::<B, S>();
+    let mut order: Vec<usize> = (0..samples.len()).collect();
+    let mut epoch_losses = Vec::with_capacity(config.epochs);
+
+    for _ in 0..config.epochs {
+        order.shuffle(rng);
+        let mut total_loss = 0_f32;
+        let mut batches = 0;
+        for batch in order.chunks(config.batch_size) {
+            let predictions = batch
+                .iter()
+                .map(|&i| {
+                    student.apply(Tensor::from_floats(
+                        samples[i].observation.as_slice(),
+                        device,
+                    ))
+                })
+                .collect();
+            let targets = batch
+                .iter()
+                .map(|&i| Tensor::from_floats(samples[i].action.as_slice(), device))
+                .collect();
+            let loss = MseLoss::new().forward(
+                Tensor::<B, 1>::stack::<2>(predictions, 0),
+                Tensor::<B, 1>::stack::<2>(targets, 0),
+                Reduction::Mean,
+            );
+            total_loss += loss.clone().into_scalar().elem::<f32>();
+            batches += 1;
+
+            let grads = GradientsParams::from_grads(loss.backward(), &student);
+            student = optimizer.step(config.learning_rate, student, grads);
+        }
+        epoch_losses.push(total_loss / batches as f32);
+    }
+    (student, epoch_losses)
+}
+
+/// Fits a copy of `network` to the samples on the autodiff backend, carrying the record over
+/// through bytes into `student`, whose own weights are all replaced.
+pub fn distill_copy<B, S>(
+    network: &S::InnerModule,
+    student: S,
+    samples: &[TeacherSample],
+    config: &DistillationConfig,
+    device: &B::Device,
+    rng: &mut StdRng,
+) -> S::InnerModule
+where
+    B: AutodiffBackend,
+    S: AI<B> + AutodiffModule<B>,
+{
+    let recorder = BinBytesRecorder::<FullPrecisionSettings>::default();
+    let bytes = recorder
+        .record(network.clone().into_record(), ())
+        .expect("could not record the network");
+    let student = student.load_record(
+        recorder
+            .load(bytes, device)
+            .expect("could not load the network"),
+    );
+    distill(student, samples, config, device, rng).0.valid()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::{Autodiff, NdArray};
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_student_learns_the_teacher() {
+        type BE = Autodiff<NdArray<f32>>;
+        let device = NdArrayDevice::Cpu;
+        let config = DistillationConfig {
+            episodes: 1,
+            steps_per_episode: 20,
+            epochs: 30,
+            batch_size: 10,
+            ..DistillationConfig::default()
+        };
+        let teacher = SmallAI::<NdArray<f32>>::with_init_std(&device, 0.1);
+        let samples = collect_teacher_samples(&teacher, &config, &device);
+        assert_eq!(samples.len(), 20);
+
+        let student = SmallAI::<BE>::with_init_std(&device, 0.1);
+        let (_, losses) = distill(
+            student,
+            &samples,
+            &config,
+            &device,
+            &mut StdRng::seed_from_u64(0),
+        );
+        assert_eq!(losses.len(), 30);
+        assert!(losses[29] < losses[0], "{losses:?}");
+    }
+}
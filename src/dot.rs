@@ -0,0 +1,118 @@
+use crate::base_ai::{max_amp_for_linear, AI};
+use burn::prelude::Backend;
+use burn::tensor::ElementConversion;
+use std::fmt::Write;
+use std::path::Path;
+
+/// The network's structure as a Graphviz DOT graph: the observation, one node per layer with
+/// its size and activation, and the forces, wired as `AI::layer_inputs` and `AI::force_layers`
+/// say, so heads, gates and ensemble members show as parallel branches. With `magnitudes` every
+/// layer also shows its largest and mean absolute weight. Render with e.g.
+/// `dot -Tsvg network.dot -o network.svg`.
+pub fn to_dot<B: Backend, A: AI<B>>(network: &A, magnitudes: bool) -> String {
+    let mut dot = String::new();
+    writeln!(dot, "digraph \"{}\" {{", network.network_name()).expect("writing to a string");
+    writeln!(dot, "    rankdir=LR;\n    node [shape=box];").expect("writing to a string");
+    writeln!(
+        dot,
+        "    observation [shape=ellipse, label=\"observation\\n{}\"];",
+        network.input_size()
+    )
+    .expect("writing to a string");
+
+    let node =
+        |source: Option<usize>| source.map_or("observation".to_string(), |i| format!("layer_{i}"));
+    let frozen = network.frozen_layers();
+    for (i, (((name, layer), activation), inputs)) in network
+        .layers()
+        .into_iter()
+        .zip(network.activations())
+        .zip(network.layer_inputs())
+        .enumerate()
+    {
+        let [d_in, d_out] = layer.weight.dims();
+        let mut label = format!("{name}\\n{d_in} → {d_out}\\n{activation}");
+        if magnitudes {
+            let mean = layer.weight.val().abs().mean().into_scalar().elem::<f32>();
+            write!(
+                label,
+                "\\nmax |w| {:.3}, mean |w| {mean:.3}",
+                max_amp_for_linear(&layer)
+            )
+            .expect("writing to a string");
+        }
+        let style = if frozen[i] {
+            ", style=filled, fillcolor=lightgrey"
+        } else {
+            ""
+        };
+        writeln!(dot, "    layer_{i} [label=\"{label}\"{style}];").expect("writing to a string");
+        for source in inputs {
+            writeln!(dot, "    {} -> layer_{i};", node(source)).expect("writing to a string");
+        }
+    }
+
+    writeln!(
+        dot,
+        "    forces [shape=ellipse, label=\"forces\\n{}\"];",
+        network.output_size()
+    )
+    .expect("writing to a string");
+    for layer in network.force_layers() {
+        writeln!(dot, "    layer_{layer} -> forces;").expect("writing to a string");
+    }
+    writeln!(dot, "}}").expect("writing to a string");
+    dot
+}
+
+/// Writes `to_dot` to a file.
+pub fn export_dot<B: Backend, A: AI<B>>(
+    network: &A,
+    path: impl AsRef<Path>,
+    magnitudes: bool,
+) -> std::io::Result<()> {
+    std::fs::write(path, to_dot(network, magnitudes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble_ai::{Combine, EnsembleAI};
+    use crate::frozen_ai::FrozenAI;
+    use crate::grip_ai::GripAI;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+
+    #[test]
+    fn test_dot_lists_the_layers() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let network = FrozenAI::new(SmallAI::<BE>::new(&device), &["input"]);
+        let dot = to_dot(&network, false);
+        assert!(dot.starts_with("digraph \"Small AI\" {"));
+        assert!(dot.contains("layer_0 [label=\"input\\n"));
+        assert!(dot.contains("hidden\\n128 → 14\\nrelu\"]"));
+        assert!(dot.contains("fillcolor=lightgrey"));
+        assert!(dot.contains("observation -> layer_0;\n    layer_1"));
+        assert!(dot.trim_end().ends_with("layer_2 -> forces;\n}"));
+        assert!(!dot.contains("|w|"));
+        assert!(to_dot(&network, true).contains("max |w| "));
+    }
+
+    #[test]
+    fn test_dot_draws_branches_in_parallel() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let grip = to_dot(&GripAI::<BE>::new(&device), false);
+        assert!(grip.contains("layer_1 -> layer_2;\n") && grip.contains("layer_1 -> layer_3;\n"));
+        assert!(grip.contains("layer_2 -> forces;\n    layer_3 -> forces;\n"));
+
+        let members = vec![SmallAI::<BE>::new(&device), SmallAI::<BE>::new(&device)];
+        let ensemble = to_dot(&EnsembleAI::new(members, Combine::Mean), false);
+        assert!(ensemble.contains("observation -> layer_0;\n"));
+        assert!(ensemble.contains("observation -> layer_3;\n"));
+        assert!(!ensemble.contains("layer_2 -> layer_3;"));
+        assert!(ensemble.contains("layer_2 -> forces;\n    layer_5 -> forces;\n"));
+    }
+}
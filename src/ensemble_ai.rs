@@ -0,0 +1,329 @@
+use crate::base_ai::AI;
+use crate::distill::{DistillationConfig, TeacherSample};
+use crate::weights::{load_versioned, save_versioned};
+use burn::module::{Ignored, Module};
+use burn::nn::Linear;
+use burn::prelude::Backend;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+use burn::tensor::{Distribution, Tensor};
+use rand::rngs::StdRng;
+use std::marker::PhantomData;
+
+/// How the members' forces are merged.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Combine {
+    #[default]
+    Mean,
+    /// Per channel median, a vote that ignores a single member going wild.
+    Median,
+}
+
+/// Several networks of the same kind acting as one: every member sees the observation and the
+/// forces are merged. Smooths out noisy evolved policies, e.g. the top of a hall of fame.
+#[derive(Module, Debug)]
+pub struct EnsembleAI<B: Backend, A: Module<B>> {
+    members: Vec<A>,
+    combine: Ignored<Combine>,
+    backend: PhantomData<B>,
+}
+
+impl<B: Backend, A: AI<B>> EnsembleAI<B, A> {
+    pub fn new(members: Vec<A>, combine: Combine) -> Self {
+        assert!(!members.is_empty(), "an ensemble needs members");
+        Self {
+            members,
+            combine: Ignored(combine),
+            backend: PhantomData,
+        }
+    }
+
+    pub fn members(&self) -> &[A] {
+        &self.members
+    }
+}
+
+impl<B: Backend, A: AI<B>> AI<B> for EnsembleAI<B, A> {
+    fn jiggle(&self, d: &Distribution, rng: &mut StdRng) -> Self {
+        let members = self
+            .members
+            .iter()
+            .map(|member| member.jiggle(d, rng))
+            .collect();
+        Self::new(members, *self.combine)
+    }
+
+    fn apply(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
+        let outputs = self
+            .members
+            .iter()
+            .map(|member| member.apply(input.clone()))
+            .collect();
+        let outputs = Tensor::<B, 1>::stack::<2>(outputs, 0);
+        match *self.combine {
+            Combine::Mean => outputs.mean_dim(0).squeeze(0),
+            Combine::Median => {
+                let count = self.members.len();
+                let sorted = outputs.sort(0);
+                let upper = sorted.clone().narrow(0, count / 2, 1);
+                let lower = sorted.narrow(0, (count - 1) / 2, 1);
+                ((upper + lower) / 2.).squeeze(0)
+            }
+        }
+    }
+
+    fn reset_state(&self) {
+        for member in &self.members {
+            member.reset_state();
+        }
+    }
+
+    fn max_amp(&self) -> f32 {
+        self.members
+            .iter()
+            .map(|member| member.max_amp())
+            .max_by(|a, b| {
+                a.partial_cmp(b)
+                    .expect("max amplitude comparison failed across members")
+            })
+            .expect("ensemble has no members")
+    }
+
+    fn with_observations(&self, observations: &[Vec<f32>]) -> Self {
+        let members = self
+            .members
+            .iter()
+            .map(|member| member.with_observations(observations))
+            .collect();
+        Self::new(members, *self.combine)
+    }
+
+    fn train_auxiliary(&self, device: &B::Device, rng: &mut StdRng) -> Self {
+        let members = self
+            .members
+            .iter()
+            .map(|member| member.train_auxiliary(device, rng))
+            .collect();
+        Self::new(members, *self.combine)
+    }
+
+    fn imitate(
+        &self,
+        samples: &[TeacherSample],
+        config: &DistillationConfig,
+        device: &B::Device,
+        rng: &mut StdRng,
+    ) -> Option<Self> {
+        let members = self
+            .members
+            .iter()
+            .map(|member| member.imitate(samples, config, device, rng))
+            .collect::<Option<_>>()?;
+        Some(Self::new(members, *self.combine))
+    }
+
+    fn save_file(&self, filename: &str, recorder: &NamedMpkFileRecorder<FullPrecisionSettings>) {
+        save_versioned(self, filename, recorder).expect("save failed");
+    }
+
+    fn load_a_file(
+        self,
+        filename: &str,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> Self {
+        load_versioned(self, filename, recorder).expect("load failed")
+    }
+
+    fn network_name(&self) -> &'static str {
+        "Ensemble"
+    }
+
+    /// The layers of every member, one member after the other.
+    fn layers(&self) -> Vec<(&'static str, Linear<B>)> {
+        self.members
+            .iter()
+            .flat_map(|member| member.layers())
+            .collect()
+    }
+
+    fn activations(&self) -> Vec<&'static str> {
+        self.members
+            .iter()
+            .flat_map(|member| member.activations())
+            .collect()
+    }
+
+    /// The members side by side, each reading the observation.
+    fn layer_inputs(&self) -> Vec<Vec<Option<usize>>> {
+        let mut offset = 0;
+        let mut inputs = Vec::new();
+        for member in self.members.iter() {
+            inputs.extend(member.layer_inputs().into_iter().map(|sources| {
+                sources
+                    .into_iter()
+                    .map(|source| source.map(|layer| layer + offset))
+                    .collect()
+            }));
+            offset += member.layers().len();
+        }
+        inputs
+    }
+
+    /// The force layers of every member, which the forces are combined from.
+    fn force_layers(&self) -> Vec<usize> {
+        let mut offset = 0;
+        let mut layers = Vec::new();
+        for member in self.members.iter() {
+            layers.extend(
+                member
+                    .force_layers()
+                    .into_iter()
+                    .map(|layer| layer + offset),
+            );
+            offset += member.layers().len();
+        }
+        layers
+    }
+
+    fn input_size(&self) -> usize {
+        self.members[0].input_size()
+    }
+
+    fn output_size(&self) -> usize {
+        self.members[0].output_size()
+    }
+
+    fn grips(&self) -> bool {
+        self.members[0].grips()
+    }
+
+    fn frozen_layers(&self) -> Vec<bool> {
+        self.members
+            .iter()
+            .flat_map(|member| member.frozen_layers())
+            .collect()
+    }
+
+    fn with_layers(&self, layers: Vec<Linear<B>>) -> Self {
+        let mut layers = layers.into_iter();
+        let members = self
+            .members
+            .iter()
+            .map(|member| {
+                let count = member.layers().len();
+                member.with_layers(layers.by_ref().take(count).collect())
+            })
+            .collect();
+        Self::new(members, *self.combine)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim_for_ai::{ACTION_SIZE, CONTACT_SLOTS, FRAME_OBJECT_SLOTS, OBSERVATION_SIZE};
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+    use burn::module::Param;
+    use std::env::temp_dir;
+
+    #[test]
+    fn test_ensemble_combines_members() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let members: Vec<_> = (0..3)
+            .map(|_| SmallAI::<BE>::with_init_std(&device, 0.1))
+            .collect();
+        let observation = Tensor::<BE, 1>::ones([OBSERVATION_SIZE], &device);
+        let outputs: Vec<Vec<f32>> = members
+            .iter()
+            .map(|member| {
+                member
+                    .apply(observation.clone())
+                    .to_data()
+                    .to_vec()
+                    .unwrap()
+            })
+            .collect();
+
+        let mean: Vec<f32> = EnsembleAI::new(members.clone(), Combine::Mean)
+            .apply(observation.clone())
+            .to_data()
+            .to_vec()
+            .unwrap();
+        let median: Vec<f32> = EnsembleAI::new(members.clone(), Combine::Median)
+            .apply(observation)
+            .to_data()
+            .to_vec()
+            .unwrap();
+        for channel in 0..mean.len() {
+            let mut values: Vec<f32> = outputs.iter().map(|output| output[channel]).collect();
+            assert!((mean[channel] - values.iter().sum::<f32>() / 3.).abs() < 1e-6);
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            assert!((median[channel] - values[1]).abs() < 1e-6);
+        }
+
+        let ensemble = EnsembleAI::new(members, Combine::Mean);
+        let layers = ensemble
+            .layers()
+            .into_iter()
+            .map(|(_, layer)| layer)
+            .collect();
+        assert_eq!(
+            ensemble.with_layers(layers).fingerprint(),
+            ensemble.fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_ensembles_load_and_migrate_member_by_member() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
+        let members: Vec<_> = (0..2)
+            .map(|_| SmallAI::<BE>::with_init_std(&device, 0.1))
+            .collect();
+        let ensemble = EnsembleAI::new(members.clone(), Combine::Mean);
+        let template = || EnsembleAI::new(vec![SmallAI::<BE>::new(&device); 2], Combine::Mean);
+        let path = temp_dir().join(format!("ensemble_test_{}", std::process::id()));
+        let filename = path.to_str().unwrap();
+        AI::save_file(&ensemble, filename, &recorder);
+        let loaded = template().load_a_file(filename, &recorder);
+        assert_eq!(loaded.to_flat_vec(), ensemble.to_flat_vec());
+
+        // members saved before the observation grew are widened one by one
+        let frames = 2 * (4 * ACTION_SIZE + FRAME_OBJECT_SLOTS);
+        let saved_in = OBSERVATION_SIZE - 2 - ACTION_SIZE - CONTACT_SLOTS;
+        let narrowed: Vec<_> = members
+            .iter()
+            .map(|member| {
+                let mut layers: Vec<Linear<BE>> = member
+                    .layers()
+                    .into_iter()
+                    .map(|(_, layer)| layer)
+                    .collect();
+                layers[0] = Linear {
+                    weight: Param::from_tensor(layers[0].weight.val().narrow(0, 0, saved_in)),
+                    bias: layers[0].bias.clone(),
+                };
+                member.with_layers(layers)
+            })
+            .collect();
+        Module::save_file(EnsembleAI::new(narrowed, Combine::Mean), &path, &recorder).unwrap();
+        let migrated = template().load_a_file(filename, &recorder);
+        std::fs::remove_file(path.with_extension("mpk")).unwrap();
+        for (migrated, member) in migrated.members().iter().zip(&members) {
+            let (_, input) = migrated.layers().into_iter().next().unwrap();
+            let (_, original) = member.layers().into_iter().next().unwrap();
+            assert_eq!(input.weight.dims(), [OBSERVATION_SIZE, 128]);
+            assert_eq!(
+                input.weight.val().narrow(0, 0, frames).to_data(),
+                original.weight.val().narrow(0, 0, frames).to_data()
+            );
+            assert_eq!(
+                migrated.layers()[2].1.weight.val().to_data(),
+                member.layers()[2].1.weight.val().to_data()
+            );
+        }
+    }
+}
@@ -0,0 +1,1150 @@
+use crate::base_ai::{ListableAI, MutationConfig, AI, INITIAL_SD};
+use crate::schedule::{Operator, OperatorSchedule};
+use crate::train_config::TrainConfig;
+use crate::weights::load_saved;
+use burn::prelude::Backend;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+use burn::tensor::Distribution;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// A network together with its own mutation strength, evolution strategies style. The strength
+/// is inherited, mutated log-normally, and then used to jiggle the offspring, so individuals
+/// that mutate at a good rate spread their rate along with their weights.
+#[derive(Clone, Debug)]
+pub struct Genome<A> {
+    /// Private so the values cached from it cannot go stale; `with_ai` makes a changed genome.
+    ai: A,
+    pub sigma: f64,
+    /// Index of the scheduled operator that produced this offspring, `None` for fresh and
+    /// carried over individuals.
+    pub operator: Option<usize>,
+    /// Generations the oldest of its genetic material has been evolved for: 0 for fresh random
+    /// individuals, one more than the older parent for offspring, one more for every generation
+    /// survived.
+    pub age: usize,
+    pub(crate) cache: GenomeCache,
+}
+
+/// Values derived from the weights, computed once per individual since every read copies all
+/// parameters to the host. A changed network is always a new genome, so nothing goes stale.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GenomeCache {
+    fingerprint: OnceLock<u64>,
+    max_amp: OnceLock<f32>,
+}
+
+impl<A> Genome<A> {
+    pub fn new(ai: A) -> Self {
+        Self {
+            ai,
+            sigma: INITIAL_SD,
+            operator: None,
+            age: 0,
+            cache: GenomeCache::default(),
+        }
+    }
+
+    /// A fresh random individual, starting from the configured sigma.
+    pub fn fresh(ai: A, mutation: &MutationConfig) -> Self {
+        Self {
+            sigma: mutation.initial_sigma,
+            ..Self::new(ai)
+        }
+    }
+
+    /// This genome's sigma, operator and age with a changed network, whose values are derived
+    /// anew.
+    pub fn with_ai(&self, ai: A) -> Self {
+        Self {
+            sigma: self.sigma,
+            operator: self.operator,
+            age: self.age,
+            ..Self::new(ai)
+        }
+    }
+
+    pub fn ai(&self) -> &A {
+        &self.ai
+    }
+
+    /// Whether the genome is an elite carried over from the previous generation as it was,
+    /// rather than bred or drawn in this one.
+    pub fn survived(&self) -> bool {
+        self.operator.is_none() && self.age > 0
+    }
+
+    pub fn fingerprint<B: Backend>(&self) -> u64
+    where
+        A: AI<B>,
+    {
+        *self.cache.fingerprint.get_or_init(|| self.ai.fingerprint())
+    }
+
+    pub fn max_amp<B: Backend>(&self) -> f32
+    where
+        A: AI<B>,
+    {
+        *self.cache.max_amp.get_or_init(|| self.ai.max_amp())
+    }
+
+    /// The sigma an offspring of the two parents starts from, the annealed one if the run
+    /// anneals.
+    pub fn inherited_sigma(
+        mother: &Self,
+        father: &Self,
+        mutation: &MutationConfig,
+        rng: &mut StdRng,
+    ) -> f64 {
+        if let Some(sigma) = mutation.annealed_sigma {
+            return sigma;
+        }
+        let n: f64 = rng.sample(StandardNormal);
+        ((mother.sigma * father.sigma).sqrt() * (mutation.sigma_tau * n).exp())
+            .max(mutation.smallest_sigma)
+    }
+}
+
+/// Fresh random individuals, their weights drawn from `rng`.
+pub fn init_island_population<B: Backend, A: AI<B>>(
+    d: &B::Device,
+    size: usize,
+    mutation: &MutationConfig,
+    ai_maker: &impl Fn(&B::Device) -> A,
+    rng: &mut StdRng,
+) -> Vec<Genome<A>> {
+    (0..size)
+        .map(|_| Genome::fresh(ai_maker(d).redrawn(rng), mutation))
+        .collect()
+}
+
+pub fn ai_naming<B: Backend, A: AI<B>>(best_ai: &A, i: usize) -> String {
+    format!("best_{}_{i}", best_ai.network_name())
+}
+
+/// How the islands exchange individuals.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Migration {
+    /// An offspring of one of the fittest of an island and one of another joins the first.
+    #[default]
+    Crossover,
+    /// One of the fittest of an island joins another as it is.
+    Copy,
+}
+
+/// A sigma decaying over the generations that every offspring is jiggled with, instead of the
+/// self-adapted one inherited from its parents.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Annealing {
+    /// The initial sigma multiplied by `rate` every generation, down to the smallest one.
+    Exponential { rate: f64 },
+    /// From the initial sigma to the smallest one along half a cosine over `generations`.
+    Cosine { generations: usize },
+}
+
+impl Annealing {
+    pub fn sigma(&self, generation: usize, mutation: &MutationConfig) -> f64 {
+        let (initial, smallest) = (mutation.initial_sigma, mutation.smallest_sigma);
+        match *self {
+            Annealing::Exponential { rate } => {
+                (initial * rate.powi(generation as i32)).max(smallest)
+            }
+            Annealing::Cosine { generations } => {
+                let progress = (generation as f64 / generations.max(1) as f64).min(1.);
+                smallest
+                    + (initial - smallest) * (1. + (std::f64::consts::PI * progress).cos()) / 2.
+            }
+        }
+    }
+}
+
+/// Whose place a migrant takes on its new island.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrantSlot {
+    /// One of the fresh random individuals.
+    #[default]
+    Fresh,
+    /// One of the offspring, leaving the fresh ones alone.
+    Offspring,
+}
+
+/// How the migrant coming from another island is chosen.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrantChoice {
+    /// Any of the fittest of a random island.
+    #[default]
+    Random,
+    /// The better of two of the fittest of any other island, compared by their scores
+    /// standardized within their island, so an island with easier rollouts is not favored.
+    Standardized,
+}
+
+/// The scores as standard deviations from their mean, all 0 when they are alike.
+pub fn standardize(scores: &[f32]) -> Vec<f32> {
+    let mean = scores.iter().sum::<f32>() / scores.len().max(1) as f32;
+    let std = (scores
+        .iter()
+        .map(|score| (score - mean).powi(2))
+        .sum::<f32>()
+        / scores.len().max(1) as f32)
+        .sqrt();
+    scores
+        .iter()
+        .map(|score| if std > 0. { (score - mean) / std } else { 0. })
+        .collect()
+}
+
+/// Exchanges `config.island_crossings` migrants between the islands of a new generation.
+/// `rankings` are the last scores of every island, each with the fingerprint of the individual
+/// that got it, which the fittest are ranked by when migrants are chosen by standardized score.
+pub fn island_crossing<B: Backend, A: AI<B>>(
+    islands: &mut [Vec<Genome<A>>],
+    rankings: &[Vec<(f32, u64)>],
+    config: &TrainConfig,
+    schedule: &OperatorSchedule<B, A>,
+    mutation: &MutationConfig,
+    rng: &mut StdRng,
+) {
+    // each of the best carries its own score standardized within its island, found by its
+    // fingerprint since the elites are no longer in the order they were ranked in
+    let standings: Vec<HashMap<u64, f32>> = rankings
+        .iter()
+        .map(|ranking| {
+            let scores: Vec<f32> = ranking.iter().map(|(score, _)| *score).collect();
+            ranking
+                .iter()
+                .map(|(_, fingerprint)| *fingerprint)
+                .zip(standardize(&scores))
+                .collect()
+        })
+        .collect();
+    // Clone the best individuals instead of holding references
+    let best: Vec<Vec<(Genome<A>, f32)>> = islands
+        .iter()
+        .enumerate()
+        .map(|(j, island)| {
+            island[island.len() - config.elite_count(island.len())..]
+                .iter()
+                .map(|genome| {
+                    let standing = standings
+                        .get(j)
+                        .and_then(|standings| standings.get(&genome.fingerprint()))
+                        .copied()
+                        .unwrap_or(0.);
+                    (genome.clone(), standing)
+                })
+                .collect()
+        })
+        .collect();
+
+    let island_count = islands.len();
+    let fittest_count = best[0].len();
+    // the elites at the back are never replaced
+    let slots = match config.migrant_slot {
+        MigrantSlot::Fresh => 0..config.random_per_generation,
+        MigrantSlot::Offspring => config.random_per_generation..islands[0].len() - fittest_count,
+    };
+    if island_count < 2 || fittest_count == 0 || slots.is_empty() {
+        return;
+    }
+
+    // the one coming over from `fathers_island`, or with standardized scores the better of it
+    // and one from any other island but the mother's
+    let pick_father = |mothers_island: usize, fathers_island: usize, rng: &mut StdRng| {
+        let father = (fathers_island, rng.random_range(0..fittest_count));
+        if config.migrant_choice == MigrantChoice::Random {
+            return father;
+        }
+        let rival = loop {
+            let island = rng.random_range(0..island_count);
+            if island != mothers_island {
+                break (island, rng.random_range(0..fittest_count));
+            }
+        };
+        if best[rival.0][rival.1].1 > best[father.0][father.1].1 {
+            rival
+        } else {
+            father
+        }
+    };
+
+    for _ in 0..config.island_crossings {
+        let (mothers_island, fathers_island) = make_distinct(island_count, rng);
+
+        let migrant = match config.migration {
+            Migration::Crossover => {
+                let mother = &best[mothers_island][rng.random_range(0..fittest_count)].0;
+                // a migrant crossed with its own copy would add nothing, so another father is
+                // looked for, up to once for every one coming into question
+                let father = (0..fittest_count * island_count)
+                    .map(|_| pick_father(mothers_island, fathers_island, rng))
+                    .map(|(fathers_island, father)| &best[fathers_island][father].0)
+                    .find(|father| father.fingerprint() != mother.fingerprint());
+                let Some(father) = father else {
+                    continue;
+                };
+                make_offspring(mother, father, schedule, mutation, rng)
+            }
+            Migration::Copy => {
+                let (fathers_island, father) = pick_father(mothers_island, fathers_island, rng);
+                best[fathers_island][father].0.clone()
+            }
+        };
+        islands[mothers_island][rng.random_range(slots.clone())] = migrant;
+    }
+}
+
+/// Builds the next generation of `size` from individuals sorted best first: a few fresh random
+/// ones, offspring of parents picked by the configured selection, and the elites themselves.
+/// The generation may be larger or smaller than the one it is bred from. Without anyone to breed
+/// from, e.g. an ALPS layer that all moved up, the generation starts over with fresh ones.
+#[allow(clippy::too_many_arguments)]
+pub fn make_new_generation<B: Backend, A: AI<B>>(
+    ais_w_score: Vec<(f32, Genome<A>)>,
+    size: usize,
+    device: &B::Device,
+    config: &TrainConfig,
+    schedule: &mut OperatorSchedule<B, A>,
+    mutation: &MutationConfig,
+    ai_maker: &impl Fn(&B::Device) -> A,
+    rng: &mut StdRng,
+) -> Vec<Genome<A>> {
+    if ais_w_score.is_empty() {
+        return init_island_population(device, size, mutation, ai_maker, rng);
+    }
+    // don't keep parents once they are combined.
+    let number_of_fittest = config.number_of_fittest(ais_w_score.len());
+    schedule.adapt(&ais_w_score, number_of_fittest);
+    // identical individuals are kept once, so no one is evaluated twice or crossed with itself
+    let mut distinct = HashSet::new();
+    let parents: Vec<(f32, Genome<A>)> = ais_w_score
+        .iter()
+        .filter(|(_, genome)| distinct.insert(genome.fingerprint()))
+        .map(|(score, genome)| {
+            (
+                *score,
+                Genome {
+                    operator: None,
+                    ..genome.clone()
+                },
+            )
+        })
+        .collect();
+    let scores: Vec<f32> = parents.iter().map(|(score, _)| *score).collect();
+    let fingerprints: Vec<u64> = parents
+        .iter()
+        .map(|(_, genome)| genome.fingerprint())
+        .collect();
+    let mut seen = HashSet::new();
+    let fittest_count = ais_w_score
+        .iter()
+        .take(number_of_fittest)
+        .filter(|(_, genome)| seen.insert(genome.fingerprint()))
+        .count();
+    // the elites are the best distinct ones, so the first of the parents
+    let elite_count = config.elite_count(size).min(parents.len());
+    let random_count = config.random_per_generation.min(size);
+    let mut new_generation = init_island_population(device, random_count, mutation, ai_maker, rng);
+    seen.extend(new_generation.iter().map(|genome| genome.fingerprint()));
+
+    let selection = config.selection.strategy(config.selection_pressure);
+    let offspring_count = size - elite_count - random_count;
+    let mut attempts = 0;
+    while new_generation.len() < random_count + offspring_count {
+        let (mother, father) = selection.select_pair(&scores, &fingerprints, fittest_count, rng);
+        let offspring = make_offspring(
+            &parents[mother].1,
+            &parents[father].1,
+            schedule,
+            mutation,
+            rng,
+        );
+        attempts += 1;
+        if seen.insert(offspring.fingerprint())
+            || attempts > offspring_count * config.duplicate_retries
+        {
+            new_generation.push(offspring);
+        }
+    }
+
+    new_generation.extend(
+        parents
+            .into_iter()
+            .take(elite_count)
+            .map(|(_, genome)| Genome {
+                age: genome.age + 1,
+                ..genome
+            }),
+    );
+
+    new_generation
+}
+
+/// Lamarckian refinement of an offspring: `mutation.local_search_steps` jiggles at the genome's
+/// own sigma, each kept only if it scores better than the best so far, so the improvements are
+/// inherited by its own offspring.
+pub fn local_search<B: Backend, A: AI<B>>(
+    genome: Genome<A>,
+    mutation: &MutationConfig,
+    score: impl Fn(&A) -> f32,
+    rng: &mut StdRng,
+) -> Genome<A> {
+    if mutation.local_search_steps == 0 {
+        return genome;
+    }
+    let d = Distribution::Normal(0.0, genome.sigma);
+    let mut best_score = score(genome.ai());
+    let mut best = None;
+    for _ in 0..mutation.local_search_steps {
+        let candidate = best
+            .as_ref()
+            .unwrap_or(genome.ai())
+            .jiggle_layers(&d, mutation, rng);
+        let candidate_score = score(&candidate);
+        if candidate_score > best_score {
+            best_score = candidate_score;
+            best = Some(candidate);
+        }
+    }
+    match best {
+        Some(ai) => genome.with_ai(ai),
+        None => genome,
+    }
+}
+
+pub fn resume_island<B: Backend, A: ListableAI<B>>(
+    device: &B::Device,
+    ai_maker: &impl Fn(&B::Device) -> A,
+    config: &TrainConfig,
+    schedule: &mut OperatorSchedule<B, A>,
+    mutation: &MutationConfig,
+    recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    rng: &mut StdRng,
+) -> Vec<Genome<A>> {
+    let size = config.island_size(0);
+    let mut initial = init_island_population::<B, A>(device, size, mutation, ai_maker, rng);
+    let mut loaded_best = Vec::new();
+    let sample_specimen = initial[0].ai().clone();
+    let ai_fnames = sample_specimen.list_in(&config.model_dir);
+
+    // TODO: make sure the list method receives the number of ais we want at most. i.e not 30
+    // TODO: instead of returning vec of string return vec of ais.
+    for fname in ai_fnames {
+        loaded_best.push(load_saved(sample_specimen.clone(), &fname, recorder));
+    }
+
+    // saved files carry no sigma, resumed individuals start from the initial one. Without any
+    // listed in the manifest the island starts out random
+    let fittest = config.number_of_fittest(size);
+    for (genome, ai) in initial
+        .iter_mut()
+        .take(fittest)
+        .zip(loaded_best.iter().cycle())
+    {
+        *genome = Genome::fresh(ai.clone(), mutation);
+    }
+
+    let initial: Vec<(f32, Genome<A>)> = initial.into_iter().map(|genome| (0., genome)).collect();
+    make_new_generation(
+        initial, size, device, config, schedule, mutation, ai_maker, rng,
+    )
+}
+
+pub fn make_distinct(max: usize, rng: &mut StdRng) -> (usize, usize) {
+    let specimen_one = rng.random_range(0..max);
+
+    let specimen_two = loop {
+        let potential_specimen = rng.random_range(0..max);
+        if potential_specimen != specimen_one {
+            break potential_specimen;
+        }
+    };
+
+    (specimen_one, specimen_two)
+}
+
+/// Two individuals that are not clones of each other by their fingerprints, `None` when all
+/// are, as the copies of one elite filling an island.
+pub fn make_distinct_by(fingerprints: &[u64], rng: &mut StdRng) -> Option<(usize, usize)> {
+    if fingerprints.is_empty() {
+        return None;
+    }
+    let specimen_one = rng.random_range(0..fingerprints.len());
+    let others: Vec<usize> = (0..fingerprints.len())
+        .filter(|&k| fingerprints[k] != fingerprints[specimen_one])
+        .collect();
+    match others.is_empty() {
+        true => None,
+        false => Some((specimen_one, others[rng.random_range(0..others.len())])),
+    }
+}
+
+/// One offspring from an operator picked by the schedule, carrying the inherited sigma. Parents
+/// that are clones of each other are not crossed, as that would only jiggle the mother while
+/// crediting the crossover; one of the other operators is picked for them, if any is weighted.
+pub fn make_offspring<B: Backend, A: AI<B>>(
+    mother: &Genome<A>,
+    father: &Genome<A>,
+    schedule: &OperatorSchedule<B, A>,
+    mutation: &MutationConfig,
+    rng: &mut StdRng,
+) -> Genome<A> {
+    let sigma = Genome::inherited_sigma(mother, father, mutation, rng);
+    let mut operator = schedule.pick(rng);
+    if matches!(schedule.operator(operator), Operator::Crossover(_))
+        && mother.fingerprint() == father.fingerprint()
+    {
+        operator = schedule.pick_mutation(rng).unwrap_or(operator);
+    }
+    let ai = match schedule.operator(operator) {
+        Operator::Prune(probability) => mother.ai().prune(*probability, rng),
+        Operator::Decay(factor) => mother.ai().decay(*factor),
+        Operator::Grow(std) => mother.ai().grow(&Distribution::Normal(0.0, *std), rng),
+        Operator::Shrink => mother.ai().shrink(rng),
+        Operator::Crossover(strategy) => {
+            // parents grown apart can't be recombined layer by layer
+            let father = if father.ai().hidden_sizes() == mother.ai().hidden_sizes() {
+                father
+            } else {
+                mother
+            };
+            strategy.cross(mother.ai(), father.ai(), rng).jiggle_layers(
+                &Distribution::Normal(0.0, sigma),
+                mutation,
+                rng,
+            )
+        }
+    };
+    Genome {
+        sigma,
+        operator: Some(operator),
+        age: mother.age.max(father.age) + 1,
+        ..Genome::new(ai)
+    }
+}
+
+/// Why a run stopped before its last generation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopReason {
+    /// Neither the best nor the median score improved for this many generations.
+    Stagnated(usize),
+    TargetReached(f32),
+    /// The run took this long, more than it was given.
+    OutOfTime(Duration),
+    /// The run scored this many individuals, as many as it was given.
+    OutOfEvaluations(usize),
+    /// Ctrl-C was pressed.
+    Interrupted,
+}
+
+impl Display for StopReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopReason::Stagnated(generations) => {
+                write!(f, "no improvement in {generations} generations")
+            }
+            StopReason::TargetReached(score) => write!(f, "target reached with a score of {score}"),
+            StopReason::OutOfTime(elapsed) => write!(
+                f,
+                "time budget spent after {:.2} hours",
+                elapsed.as_secs_f64() / 3600.
+            ),
+            StopReason::OutOfEvaluations(evaluations) => {
+                write!(f, "evaluation budget spent after {evaluations} evaluations")
+            }
+            StopReason::Interrupted => write!(f, "interrupted"),
+        }
+    }
+}
+
+/// Follows the best and median score over the generations, so unattended runs stop once they
+/// no longer improve or are good enough.
+#[derive(Clone, Debug)]
+pub struct EarlyStopping {
+    patience: Option<usize>,
+    target_score: Option<f32>,
+    best: f32,
+    median: f32,
+    flat_generations: usize,
+}
+
+impl EarlyStopping {
+    pub fn new(patience: Option<usize>, target_score: Option<f32>) -> Self {
+        Self {
+            patience,
+            target_score,
+            best: f32::NEG_INFINITY,
+            median: f32::NEG_INFINITY,
+            flat_generations: 0,
+        }
+    }
+
+    /// Takes the scores of a generation, of all islands, and tells whether to stop after it.
+    pub fn update(&mut self, scores: &[f32]) -> Option<StopReason> {
+        let mut sorted = scores.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).expect("ai score should be comparable"));
+        let best = *sorted.last()?;
+        let median = sorted[sorted.len() / 2];
+        if best > self.best || median > self.median {
+            self.best = self.best.max(best);
+            self.median = self.median.max(median);
+            self.flat_generations = 0;
+        } else {
+            self.flat_generations += 1;
+        }
+
+        if self.target_score.is_some_and(|target| best >= target) {
+            Some(StopReason::TargetReached(best))
+        } else if self
+            .patience
+            .is_some_and(|patience| self.flat_generations >= patience)
+        {
+            Some(StopReason::Stagnated(self.flat_generations))
+        } else {
+            None
+        }
+    }
+}
+
+/// The wall-clock time and the evaluations a run may spend, counted from its start.
+#[derive(Clone, Debug)]
+pub struct Budget {
+    max_time: Option<Duration>,
+    max_evaluations: Option<usize>,
+    started: Instant,
+    evaluations: usize,
+}
+
+impl Budget {
+    pub fn new(max_time: Option<Duration>, max_evaluations: Option<usize>) -> Self {
+        Self {
+            max_time,
+            max_evaluations,
+            started: Instant::now(),
+            evaluations: 0,
+        }
+    }
+
+    /// Counts individuals scored.
+    pub fn spend(&mut self, evaluations: usize) {
+        self.evaluations += evaluations;
+    }
+
+    pub fn evaluations(&self) -> usize {
+        self.evaluations
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Tells whether to stop after the current generation.
+    pub fn exhausted(&self) -> Option<StopReason> {
+        let elapsed = self.elapsed();
+        if self.max_time.is_some_and(|max_time| elapsed >= max_time) {
+            Some(StopReason::OutOfTime(elapsed))
+        } else if self
+            .max_evaluations
+            .is_some_and(|max_evaluations| self.evaluations >= max_evaluations)
+        {
+            Some(StopReason::OutOfEvaluations(self.evaluations))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_ai::SMALLEST_SD;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_fresh_populations_follow_the_seed() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let mutation = MutationConfig::default();
+        let population = |seed: u64| -> Vec<u64> {
+            init_island_population(
+                &device,
+                3,
+                &mutation,
+                &SmallAI::<BE>::new,
+                &mut StdRng::seed_from_u64(seed),
+            )
+            .iter()
+            .map(Genome::fingerprint)
+            .collect()
+        };
+        assert_eq!(population(7), population(7));
+        assert_ne!(population(7), population(8));
+    }
+
+    #[test]
+    fn test_offspring_inherit_a_mutated_sigma() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let mother = Genome {
+            sigma: 0.04,
+            ..Genome::new(SmallAI::<BE>::new(&device))
+        };
+        let father = Genome {
+            sigma: 0.01,
+            ..Genome::new(SmallAI::<BE>::new(&device))
+        };
+        let schedule = OperatorSchedule::default();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let sigmas: Vec<f64> = (0..20)
+            .map(|_| {
+                make_offspring(
+                    &mother,
+                    &father,
+                    &schedule,
+                    &MutationConfig::default(),
+                    &mut rng,
+                )
+                .sigma
+            })
+            .collect();
+        assert!(sigmas.iter().all(|sigma| *sigma >= SMALLEST_SD));
+        assert!(sigmas.iter().any(|sigma| (sigma - 0.02).abs() > 1e-9));
+        // log-normal steps around the geometric mean of the parents
+        let mean_log = sigmas.iter().map(|sigma| sigma.ln()).sum::<f64>() / sigmas.len() as f64;
+        assert!((mean_log - 0.02f64.ln()).abs() < 0.3, "{mean_log}");
+    }
+
+    #[test]
+    fn test_annealed_sigma() {
+        let mutation = MutationConfig {
+            initial_sigma: 0.1,
+            smallest_sigma: 0.01,
+            ..MutationConfig::default()
+        };
+        let exponential = Annealing::Exponential { rate: 0.5 };
+        let sigmas: Vec<f64> = [0, 1, 2, 10]
+            .map(|generation| exponential.sigma(generation, &mutation))
+            .to_vec();
+        assert_eq!(sigmas, vec![0.1, 0.05, 0.025, 0.01]);
+        let cosine = Annealing::Cosine { generations: 10 };
+        assert_eq!(cosine.sigma(0, &mutation), 0.1);
+        assert!((cosine.sigma(5, &mutation) - 0.055).abs() < 1e-12);
+        assert_eq!(cosine.sigma(20, &mutation), 0.01);
+
+        let parent = Genome::new(SmallAI::<NdArray<f32>>::new(&NdArrayDevice::Cpu));
+        let annealed = mutation.with_annealed_sigma(Some(0.03));
+        let sigma =
+            Genome::inherited_sigma(&parent, &parent, &annealed, &mut StdRng::seed_from_u64(7));
+        assert_eq!(sigma, 0.03);
+    }
+
+    #[test]
+    fn test_same_seed_same_offspring() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let mother = Genome::new(SmallAI::<BE>::new(&device));
+        let father = Genome::new(SmallAI::<BE>::new(&device));
+        let schedule = OperatorSchedule::default();
+
+        let offspring = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..5)
+                .map(|_| {
+                    let child = make_offspring(
+                        &mother,
+                        &father,
+                        &schedule,
+                        &MutationConfig::default(),
+                        &mut rng,
+                    );
+                    let weights: Vec<_> = child
+                        .ai()
+                        .layers()
+                        .iter()
+                        .map(|(_, l)| l.weight.val().to_data())
+                        .collect();
+                    (child.sigma, child.operator, weights)
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(offspring(3), offspring(3));
+        assert_ne!(offspring(3), offspring(4));
+    }
+
+    #[test]
+    fn test_clones_are_not_crossed() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let mut rng = StdRng::seed_from_u64(5);
+        assert_eq!(make_distinct_by(&[4, 4, 4], &mut rng), None);
+        for _ in 0..20 {
+            let (one, two) = make_distinct_by(&[4, 4, 9], &mut rng).unwrap();
+            assert!(one == 2 || two == 2);
+        }
+
+        let genome = Genome::new(SmallAI::<BE>::new(&device));
+        let schedule = OperatorSchedule::default();
+        for _ in 0..20 {
+            let child = make_offspring(
+                &genome,
+                &genome.clone(),
+                &schedule,
+                &MutationConfig::default(),
+                &mut rng,
+            );
+            let operator = schedule.operator(child.operator.unwrap());
+            assert!(!matches!(operator, Operator::Crossover(_)), "{operator:?}");
+        }
+    }
+
+    #[test]
+    fn test_new_generation_has_no_duplicates() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let clone = Genome::new(SmallAI::<BE>::new(&device));
+        let ranked = vec![(0.5, clone); 8];
+
+        let generation = make_new_generation(
+            ranked,
+            8,
+            &device,
+            &TrainConfig {
+                best_proportion: 0.5,
+                ..TrainConfig::default()
+            },
+            &mut OperatorSchedule::default(),
+            &MutationConfig::default(),
+            &|d| SmallAI::<BE>::new(d),
+            &mut StdRng::seed_from_u64(7),
+        );
+        assert_eq!(generation.len(), 8);
+        let fingerprints: HashSet<u64> = generation
+            .iter()
+            .map(|genome| genome.ai().fingerprint())
+            .collect();
+        assert_eq!(fingerprints.len(), 8);
+    }
+
+    #[test]
+    fn test_new_generation_grows_and_shrinks() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let config = TrainConfig {
+            elites: Some(3),
+            random_per_generation: 1,
+            ..TrainConfig::default()
+        };
+        let ranked: Vec<_> = (0..8)
+            .map(|i| (1. - i as f32 / 8., Genome::new(SmallAI::<BE>::new(&device))))
+            .collect();
+        let best: Vec<u64> = ranked[..3]
+            .iter()
+            .map(|(_, genome)| genome.fingerprint())
+            .collect();
+        let mut rng = StdRng::seed_from_u64(7);
+        let ai_maker = |d: &NdArrayDevice| SmallAI::<BE>::new(d);
+
+        for size in [12, 5, 3, 2] {
+            let generation = make_new_generation(
+                ranked.clone(),
+                size,
+                &device,
+                &config,
+                &mut OperatorSchedule::default(),
+                &MutationConfig::default(),
+                &ai_maker,
+                &mut rng,
+            );
+            assert_eq!(generation.len(), size);
+            // the elites that fit stay at the back, best first
+            let elites = config.elite_count(size);
+            let survivors: Vec<u64> = generation[size - elites..]
+                .iter()
+                .map(Genome::fingerprint)
+                .collect();
+            assert_eq!(survivors, best[..elites]);
+        }
+    }
+
+    #[test]
+    fn test_copied_migrants_replace_offspring() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let config = TrainConfig {
+            island_crossings: 3,
+            random_per_generation: 1,
+            best_proportion: 0.25,
+            migration: Migration::Copy,
+            migrant_slot: MigrantSlot::Offspring,
+            ..TrainConfig::default()
+        };
+        let mut islands: Vec<Vec<_>> = (0..2)
+            .map(|_| {
+                (0..8)
+                    .map(|_| Genome::new(SmallAI::<BE>::new(&device)))
+                    .collect()
+            })
+            .collect();
+        let before: Vec<Vec<u64>> = islands
+            .iter()
+            .map(|island| island.iter().map(Genome::fingerprint).collect())
+            .collect();
+
+        let schedule = OperatorSchedule::default();
+        island_crossing(
+            &mut islands,
+            &[],
+            &config,
+            &schedule,
+            &MutationConfig::default(),
+            &mut StdRng::seed_from_u64(7),
+        );
+        for (i, island) in islands.iter().enumerate() {
+            let after: Vec<u64> = island.iter().map(Genome::fingerprint).collect();
+            // the fresh one and the two fittest stay, replaced offspring are the other's fittest
+            assert_eq!(after[0], before[i][0]);
+            assert_eq!(after[6..], before[i][6..]);
+            for (slot, fingerprint) in after.iter().enumerate().take(6).skip(1) {
+                assert!(
+                    *fingerprint == before[i][slot] || before[1 - i][6..].contains(fingerprint)
+                );
+            }
+        }
+        assert_ne!(
+            islands
+                .iter()
+                .map(|island| island.iter().map(Genome::fingerprint).collect())
+                .collect::<Vec<Vec<u64>>>(),
+            before
+        );
+    }
+
+    #[test]
+    fn test_migrants_chosen_by_standardized_score() {
+        assert_eq!(standardize(&[3., 1.]), vec![1., -1.]);
+        assert_eq!(standardize(&[0.5, 0.5]), vec![0., 0.]);
+        // the same spread, far apart: standardized the islands rank alike
+        assert_eq!(
+            standardize(&[10.9, 10.5, 10.1]),
+            standardize(&[0.9, 0.5, 0.1])
+        );
+
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let config = TrainConfig {
+            island_crossings: 20,
+            random_per_generation: 1,
+            best_proportion: 0.05,
+            migration: Migration::Copy,
+            migrant_slot: MigrantSlot::Offspring,
+            migrant_choice: MigrantChoice::Standardized,
+            ..TrainConfig::default()
+        };
+        let mut islands: Vec<Vec<_>> = (0..2)
+            .map(|_| {
+                (0..40)
+                    .map(|_| Genome::new(SmallAI::<BE>::new(&device)))
+                    .collect()
+            })
+            .collect();
+        // the two fittest are at the back, the best of them last, so their order differs from the
+        // ranking's and each has to be known by its own score
+        let fittest: Vec<[u64; 2]> = islands
+            .iter()
+            .map(|island| [island[39].fingerprint(), island[38].fingerprint()])
+            .collect();
+        let rankings: Vec<Vec<(f32, u64)>> = islands
+            .iter()
+            .zip([100., 1.])
+            .map(|(island, top)| {
+                (0..40)
+                    .map(|k| {
+                        let score = top - k as f32 * top / 40.;
+                        (score, island[39 - k].fingerprint())
+                    })
+                    .collect()
+            })
+            .collect();
+        island_crossing(
+            &mut islands,
+            &rankings,
+            &config,
+            &OperatorSchedule::default(),
+            &MutationConfig::default(),
+            &mut StdRng::seed_from_u64(1),
+        );
+        // migrants are mostly the best of their island, whichever island scores higher
+        let arrived = |rank: usize| {
+            (0..2)
+                .map(|i| {
+                    islands[i][1..38]
+                        .iter()
+                        .filter(|genome| genome.fingerprint() == fittest[1 - i][rank])
+                        .count()
+                })
+                .sum::<usize>()
+        };
+        assert!(arrived(0) > arrived(1), "{} {}", arrived(0), arrived(1));
+    }
+
+    #[test]
+    fn test_elites_survive_unmodified_and_stay_put() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let config = TrainConfig {
+            elites: Some(3),
+            random_per_generation: 1,
+            island_crossings: 20,
+            migration: Migration::Copy,
+            migrant_slot: MigrantSlot::Offspring,
+            ..TrainConfig::default()
+        };
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut islands = Vec::new();
+        let mut elites = Vec::new();
+        for _ in 0..2 {
+            let ranked: Vec<_> = (0..8)
+                .map(|i| (1. - i as f32 / 8., Genome::new(SmallAI::<BE>::new(&device))))
+                .collect();
+            elites.push(
+                ranked[..3]
+                    .iter()
+                    .map(|(_, genome)| genome.fingerprint())
+                    .collect::<Vec<u64>>(),
+            );
+            let schedule = &mut OperatorSchedule::default();
+            let ai_maker = |d: &NdArrayDevice| SmallAI::<BE>::new(d);
+            islands.push(make_new_generation(
+                ranked,
+                8,
+                &device,
+                &config,
+                schedule,
+                &MutationConfig::default(),
+                &ai_maker,
+                &mut rng,
+            ));
+        }
+        island_crossing(
+            &mut islands,
+            &[],
+            &config,
+            &OperatorSchedule::default(),
+            &MutationConfig::default(),
+            &mut rng,
+        );
+        for (island, elites) in islands.iter().zip(elites) {
+            assert_eq!(island.len(), 8);
+            let survivors: Vec<u64> = island[5..].iter().map(Genome::fingerprint).collect();
+            assert_eq!(survivors, elites);
+            assert!(island[5..].iter().all(|genome| genome.operator.is_none()));
+        }
+    }
+
+    #[test]
+    fn test_local_search_keeps_only_improvements() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let genome = Genome {
+            operator: Some(0),
+            ..Genome::new(SmallAI::<BE>::new(&device))
+        };
+        let mutation = MutationConfig::default().with_local_search(5);
+        // a score that prefers smaller weights, so some jiggles improve and some don't
+        let score = |ai: &SmallAI<BE>| -ai.to_flat_vec().iter().map(|p| p.abs()).sum::<f32>();
+
+        let refined = local_search(
+            genome.clone(),
+            &mutation,
+            score,
+            &mut StdRng::seed_from_u64(7),
+        );
+        assert!(score(refined.ai()) >= score(genome.ai()));
+        assert_eq!(
+            (refined.sigma, refined.operator),
+            (genome.sigma, genome.operator)
+        );
+
+        let never_better = local_search(
+            genome.clone(),
+            &mutation,
+            |_| 0.,
+            &mut StdRng::seed_from_u64(7),
+        );
+        assert_eq!(never_better.fingerprint(), genome.fingerprint());
+        let disabled = local_search(
+            genome.clone(),
+            &MutationConfig::default(),
+            score,
+            &mut StdRng::seed_from_u64(7),
+        );
+        assert_eq!(disabled.fingerprint(), genome.fingerprint());
+    }
+
+    #[test]
+    fn test_cached_values_survive_cloning() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let genome = Genome::new(SmallAI::<BE>::new(&device));
+        assert!(genome.cache.max_amp.get().is_none());
+
+        assert_eq!(genome.max_amp(), genome.ai().max_amp());
+        assert_eq!(genome.fingerprint(), genome.ai().fingerprint());
+        let elite = Genome {
+            operator: None,
+            ..genome.clone()
+        };
+        assert_eq!(elite.cache.max_amp.get(), Some(&genome.ai().max_amp()));
+        assert_eq!(
+            elite.cache.fingerprint.get(),
+            Some(&genome.ai().fingerprint())
+        );
+    }
+
+    #[test]
+    fn test_early_stopping() {
+        let mut patient = EarlyStopping::new(Some(2), None);
+        assert_eq!(patient.update(&[0.1, 0.2, 0.3]), None);
+        // a better median alone is progress
+        assert_eq!(patient.update(&[0.25, 0.25, 0.3]), None);
+        assert_eq!(patient.update(&[0.1, 0.2, 0.3]), None);
+        assert_eq!(
+            patient.update(&[0.2, 0.2, 0.3]),
+            Some(StopReason::Stagnated(2))
+        );
+
+        let mut targeted = EarlyStopping::new(None, Some(0.9));
+        assert_eq!(targeted.update(&[0.5]), None);
+        assert_eq!(
+            targeted.update(&[0.95, 0.1]),
+            Some(StopReason::TargetReached(0.95))
+        );
+        assert_eq!(EarlyStopping::new(None, None).update(&[]), None);
+
+        let mut budget = Budget::new(None, Some(100));
+        budget.spend(60);
+        assert_eq!(budget.exhausted(), None);
+        budget.spend(60);
+        assert_eq!(budget.exhausted(), Some(StopReason::OutOfEvaluations(120)));
+        let timed = Budget::new(Some(Duration::ZERO), None);
+        assert!(matches!(timed.exhausted(), Some(StopReason::OutOfTime(_))));
+        assert_eq!(
+            Budget::new(Some(Duration::from_secs(3600)), None).exhausted(),
+            None
+        );
+    }
+}
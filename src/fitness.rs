@@ -0,0 +1,377 @@
+use crate::basket_task::{BasketTask, DEFAULT_BONUS, DEFAULT_HOLD_STEPS};
+use crate::physics::world::{PhysicsWorld, WorldConfig};
+use crate::sim_for_ai::{mape, save_world_state, EpisodeConfig, PoseRetentionTask, Task};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Distance at which the reaching scores are halved.
+const REACH_SCALE: f32 = 0.1;
+/// Height above its start the ball has to be raised for the full lift score.
+const LIFT_HEIGHT: f32 = 0.2;
+/// Where the ball is brought by default when scoring `ball_in_basket`: above the ground,
+/// on the far side of the ball from the wall.
+const DEFAULT_BASKET: (f32, f32) = (0.6, -1.6);
+
+/// Terms added to the reward of every step on top of the task's, given as a `[shaping]` table
+/// of the training configuration. All weights are 0, no shaping, by default.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShapingConfig {
+    /// Weight of the normalized distance from the pinch point to the ball, taken off every
+    /// step to draw the hand to the ball where keeping the pose would leave it idle.
+    pub reach: f32,
+    /// Weight of the sum of the absolute forces of the step, taken off every step for the
+    /// effort.
+    pub energy: f32,
+    /// Weight of the sum of the absolute changes of the forces from the previous step, taken
+    /// off every step against violent oscillation.
+    pub jerk: f32,
+}
+
+/// What an episode is scored by, step by step. A rollout's score is made of the step scores.
+pub trait Fitness {
+    /// Takes in the world the episode starts in.
+    fn reset(&mut self, world: &PhysicsWorld);
+    /// The score of the step the world just took, between 0 and 1.
+    fn score_step(&mut self, world: &PhysicsWorld) -> f32;
+}
+
+/// The objectives, as picked with `fitness` in the training configuration or `--fitness`.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FitnessKind {
+    #[default]
+    PoseRetention,
+    Reach,
+    GraspAndLift,
+    /// The ball is brought into a basket with the top of its floor at `(x, y)`, and the episode
+    /// is won with `bonus` once it stayed in for `hold_steps`.
+    BallInBasket {
+        x: f32,
+        y: f32,
+        #[serde(default = "default_hold_steps")]
+        hold_steps: usize,
+        #[serde(default = "default_bonus")]
+        bonus: f32,
+    },
+}
+
+fn default_hold_steps() -> usize {
+    DEFAULT_HOLD_STEPS
+}
+
+fn default_bonus() -> f32 {
+    DEFAULT_BONUS
+}
+
+impl FromStr for FitnessKind {
+    type Err = String;
+
+    /// Reads `pose_retention`, `reach`, `grasp_and_lift`, or `ball_in_basket`, optionally with
+    /// the basket as `ball_in_basket:<x>:<y>`, and the hold steps and the bonus as
+    /// `ball_in_basket:<x>:<y>:<hold_steps>:<bonus>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let number = |part: &str| {
+            part.parse::<f32>()
+                .map_err(|e| format!("invalid basket coordinate {part}: {e}"))
+        };
+        match parts.as_slice() {
+            ["pose_retention"] => Ok(FitnessKind::PoseRetention),
+            ["reach"] => Ok(FitnessKind::Reach),
+            ["grasp_and_lift"] => Ok(FitnessKind::GraspAndLift),
+            ["ball_in_basket"] => Ok(FitnessKind::BallInBasket {
+                x: DEFAULT_BASKET.0,
+                y: DEFAULT_BASKET.1,
+                hold_steps: DEFAULT_HOLD_STEPS,
+                bonus: DEFAULT_BONUS,
+            }),
+            ["ball_in_basket", x, y] => Ok(FitnessKind::BallInBasket {
+                x: number(x)?,
+                y: number(y)?,
+                hold_steps: DEFAULT_HOLD_STEPS,
+                bonus: DEFAULT_BONUS,
+            }),
+            ["ball_in_basket", x, y, hold_steps, bonus] => Ok(FitnessKind::BallInBasket {
+                x: number(x)?,
+                y: number(y)?,
+                hold_steps: hold_steps
+                    .parse()
+                    .map_err(|e| format!("invalid hold steps {hold_steps}: {e}"))?,
+                bonus: bonus
+                    .parse()
+                    .map_err(|e| format!("invalid bonus {bonus}: {e}"))?,
+            }),
+            _ => Err(format!("unknown fitness {s}")),
+        }
+    }
+}
+
+impl FitnessKind {
+    /// The task the objective is evolved in, with the shaping terms: a basket is put into the
+    /// world for `ball_in_basket`, the others score the steps of the pose retention task.
+    pub fn task(self, shaping: ShapingConfig) -> Box<dyn Task> {
+        let fitness: Box<dyn Fitness> = match self {
+            FitnessKind::PoseRetention => Box::new(PoseRetention::default()),
+            FitnessKind::Reach => Box::new(Reach),
+            FitnessKind::GraspAndLift => Box::new(GraspAndLift::default()),
+            FitnessKind::BallInBasket {
+                x,
+                y,
+                hold_steps,
+                bonus,
+            } => {
+                let mut basket = BasketTask::at(x, y);
+                basket.hold_steps = hold_steps;
+                basket.bonus = bonus;
+                return Box::new(Shaped::new(basket, shaping));
+            }
+        };
+        Box::new(Shaped::new(PoseRetentionTask::scored_by(fitness), shaping))
+    }
+}
+
+/// How little the arm moved, from its initial pose and from the previous step, by the mean
+/// absolute percentage error of the segment corners.
+#[derive(Clone, Debug, Default)]
+pub struct PoseRetention {
+    init_state: Vec<f32>,
+    previous_state: Vec<f32>,
+    end_state: Vec<f32>,
+}
+
+impl Fitness for PoseRetention {
+    fn reset(&mut self, world: &PhysicsWorld) {
+        self.init_state.clear();
+        save_world_state(world, &mut self.init_state);
+        self.previous_state.clone_from(&self.init_state);
+    }
+
+    fn score_step(&mut self, world: &PhysicsWorld) -> f32 {
+        self.end_state.clear();
+        save_world_state(world, &mut self.end_state);
+
+        let mape_init = mape(&self.init_state, &self.end_state);
+        let mape_prev = mape(&self.previous_state, &self.end_state);
+        std::mem::swap(&mut self.previous_state, &mut self.end_state);
+
+        ((1. / (mape_init + 1.)) + (1. / (mape_prev + 1.))) / 2.
+    }
+}
+
+/// Between the index fingertip and the thumb tip, where the ball is pinched.
+fn pinch_point(world: &PhysicsWorld) -> (f32, f32) {
+    let (first, second) = world.upper_thumb_farthest_corners();
+    let (index, thumb) = (
+        world.index_fingertip(),
+        ((first.0 + second.0) / 2., (first.1 + second.1) / 2.),
+    );
+    ((index.0 + thumb.0) / 2., (index.1 + thumb.1) / 2.)
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// 1 at no distance, falling off with the distance in units of `REACH_SCALE`.
+fn closeness(distance: f32) -> f32 {
+    1. / (1. + distance / REACH_SCALE)
+}
+
+fn ball(world: &PhysicsWorld) -> (f32, f32) {
+    let position = world.ball_position().expect("the task's world has a ball");
+    (position.x, position.y)
+}
+
+/// How close the index fingertip is to the ball.
+#[derive(Clone, Debug, Default)]
+pub struct Reach;
+
+impl Fitness for Reach {
+    fn reset(&mut self, _world: &PhysicsWorld) {}
+
+    fn score_step(&mut self, world: &PhysicsWorld) -> f32 {
+        closeness(distance(world.index_fingertip(), ball(world)))
+    }
+}
+
+/// Reaching the ball, and lifting it off where it lay.
+#[derive(Clone, Debug, Default)]
+pub struct GraspAndLift {
+    start_height: f32,
+}
+
+impl Fitness for GraspAndLift {
+    fn reset(&mut self, world: &PhysicsWorld) {
+        self.start_height = ball(world).1;
+    }
+
+    fn score_step(&mut self, world: &PhysicsWorld) -> f32 {
+        let lift = ((ball(world).1 - self.start_height) / LIFT_HEIGHT).clamp(0., 1.);
+        (Reach.score_step(world) + lift) / 2.
+    }
+}
+
+/// A task with the shaping terms added to its rewards.
+pub struct Shaped<T> {
+    task: T,
+    shaping: ShapingConfig,
+    previous_forces: Vec<f32>,
+}
+
+impl<T: Task> Shaped<T> {
+    pub fn new(task: T, shaping: ShapingConfig) -> Self {
+        Self {
+            task,
+            shaping,
+            previous_forces: Vec::new(),
+        }
+    }
+
+    /// The shaping terms of the step the world just took, 0 when all weights are.
+    fn shaping_terms(&mut self, world: &PhysicsWorld) -> f32 {
+        let mut terms = 0.;
+        if self.shaping.reach != 0. && world.ball_count() > 0 {
+            terms -= self.shaping.reach
+                * distance(
+                    world.normalize(pinch_point(world)),
+                    world.normalize(ball(world)),
+                );
+        }
+        if self.shaping.energy != 0. || self.shaping.jerk != 0. {
+            let forces = world.joint_applied_forces();
+            terms -= self.shaping.energy * forces.iter().map(|force| force.abs()).sum::<f32>();
+            terms -= self.shaping.jerk
+                * forces
+                    .iter()
+                    .zip(&self.previous_forces)
+                    .map(|(force, previous)| (force - previous).abs())
+                    .sum::<f32>();
+            self.previous_forces = forces;
+        }
+        terms
+    }
+}
+
+impl<T: Task> Task for Shaped<T> {
+    fn world_config(&self, seed: u64, episode: &EpisodeConfig) -> WorldConfig {
+        self.task.world_config(seed, episode)
+    }
+
+    fn reset(&mut self, world: &PhysicsWorld) {
+        self.task.reset(world);
+        self.previous_forces = world.joint_applied_forces();
+    }
+
+    fn observe(&mut self, world: &PhysicsWorld, observation: &mut Vec<f32>) {
+        self.task.observe(world, observation);
+    }
+
+    fn reward(&mut self, world: &PhysicsWorld) -> f32 {
+        self.task.reward(world) + self.shaping_terms(world)
+    }
+
+    fn terminated(&self, world: &PhysicsWorld, steps: usize) -> bool {
+        self.task.terminated(world, steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fitness() {
+        assert_eq!("reach".parse(), Ok(FitnessKind::Reach));
+        assert_eq!(
+            "ball_in_basket:0.5:-1".parse(),
+            Ok(FitnessKind::BallInBasket {
+                x: 0.5,
+                y: -1.,
+                hold_steps: DEFAULT_HOLD_STEPS,
+                bonus: DEFAULT_BONUS
+            })
+        );
+        assert_eq!(
+            "ball_in_basket:0.5:-1:10:2".parse(),
+            Ok(FitnessKind::BallInBasket {
+                x: 0.5,
+                y: -1.,
+                hold_steps: 10,
+                bonus: 2.
+            })
+        );
+        assert!("ball_in_basket:0.5".parse::<FitnessKind>().is_err());
+        assert!("speed".parse::<FitnessKind>().is_err());
+    }
+
+    #[test]
+    fn test_fitnesses_score_a_still_world() {
+        for kind in [
+            FitnessKind::PoseRetention,
+            FitnessKind::Reach,
+            FitnessKind::GraspAndLift,
+            "ball_in_basket".parse().unwrap(),
+        ] {
+            let mut task = kind.task(ShapingConfig::default());
+            let world = PhysicsWorld::with_config(task.world_config(0, &EpisodeConfig::default()));
+            task.reset(&world);
+            let score = task.reward(&world);
+            assert!((0. ..=1.).contains(&score), "{kind:?} {score}");
+        }
+        let mut world = PhysicsWorld::new();
+        // an untouched pose is kept perfectly
+        let mut pose = PoseRetention::default();
+        pose.reset(&world);
+        assert_eq!(pose.score_step(&world), 1.);
+        world.step();
+        assert!(pose.score_step(&world) <= 1.);
+
+        // the fingertip brought to the ball reaches it better than the resting arm
+        let resting = Reach.score_step(&world);
+        world.set_arm_pose(&[-0.9, -0.9, -0.3, -0.6, 0., 0., 0., 0.]);
+        assert!(Reach.score_step(&world) > resting);
+    }
+
+    #[test]
+    fn test_shaping_draws_the_hand_to_the_ball() {
+        let mut world = PhysicsWorld::new();
+        let mut plain = FitnessKind::PoseRetention.task(ShapingConfig::default());
+        let mut shaped = FitnessKind::PoseRetention.task(ShapingConfig {
+            reach: 0.5,
+            ..ShapingConfig::default()
+        });
+        let mut penalty = |world: &PhysicsWorld| {
+            plain.reset(world);
+            shaped.reset(world);
+            plain.reward(world) - shaped.reward(world)
+        };
+        let resting = penalty(&world);
+        assert!(resting > 0.);
+        world.set_arm_pose(&[-0.9, -0.9, -0.3, -0.6, 0., 0., 0., 0.]);
+        assert!(penalty(&world) < resting);
+    }
+
+    #[test]
+    fn test_effort_and_jerk_are_penalized() {
+        let mut world = PhysicsWorld::new();
+        let mut task = FitnessKind::Reach.task(ShapingConfig {
+            energy: 0.1,
+            jerk: 0.2,
+            ..ShapingConfig::default()
+        });
+        task.reset(&world);
+        let idle = Reach.score_step(&world);
+        assert_eq!(task.reward(&world), idle);
+
+        let forces = [0.5, -0.5, 0., 0., 0., 0., 0., 0.];
+        crate::sim_for_ai::apply_forces_and_step(&mut world, &forces, None);
+        let moved = Reach.score_step(&world);
+        // the first push costs its effort and its change from rest
+        assert!((task.reward(&world) - (moved - 0.1 - 0.2)).abs() < 1e-6);
+        crate::sim_for_ai::apply_forces_and_step(&mut world, &forces, None);
+        let held = Reach.score_step(&world);
+        // holding the same forces only costs their effort
+        assert!((task.reward(&world) - (held - 0.1)).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Settings of the fitness cache, given as a `[fitness_cache]` table of the training
+/// configuration.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FitnessCacheConfig {
+    /// Generations a cached score is trusted for, forever when left out.
+    pub max_age: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+struct CachedFitness<V> {
+    value: V,
+    evaluated: usize,
+    last_used: usize,
+}
+
+/// Scores of individuals already evaluated, keyed by their fingerprint and the seed of the
+/// episode they were scored on, so elites carried over unchanged are not evaluated again.
+///
+/// The running observation statistics of a network keep moving with every evaluation, so a
+/// cached score is only as exact as they are settled; `max_age` has old scores taken again.
+/// Individuals gone from the islands rarely come back, so entries not used in a generation are
+/// dropped by `evict_unused`, which keeps the cache at about the population's size.
+#[derive(Clone, Debug)]
+pub struct FitnessCache<V> {
+    max_age: Option<usize>,
+    entries: HashMap<(u64, u64), CachedFitness<V>>,
+    hits: usize,
+    misses: usize,
+}
+
+impl<V: Clone> FitnessCache<V> {
+    pub fn new(config: &FitnessCacheConfig) -> Self {
+        Self {
+            max_age: config.max_age,
+            entries: HashMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// The cached value, unless there is none or it is too old by the given generation.
+    pub fn get(&mut self, fingerprint: u64, seed: u64, generation: usize) -> Option<V> {
+        let max_age = self.max_age;
+        let fresh = self
+            .entries
+            .get_mut(&(fingerprint, seed))
+            .filter(|cached| max_age.is_none_or(|max_age| generation - cached.evaluated < max_age));
+        match fresh {
+            Some(cached) => {
+                self.hits += 1;
+                cached.last_used = generation;
+                Some(cached.value.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, fingerprint: u64, seed: u64, generation: usize, value: V) {
+        self.entries.insert(
+            (fingerprint, seed),
+            CachedFitness {
+                value,
+                evaluated: generation,
+                last_used: generation,
+            },
+        );
+    }
+
+    /// Drops the entries not used or added in the given generation.
+    pub fn evict_unused(&mut self, generation: usize) {
+        self.entries
+            .retain(|_, cached| cached.last_used >= generation);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Share of the lookups answered from the cache.
+    pub fn hit_rate(&self) -> f32 {
+        self.hits as f32 / (self.hits + self.misses).max(1) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_scores_expire() {
+        let mut cache = FitnessCache::new(&FitnessCacheConfig { max_age: Some(2) });
+        assert_eq!(cache.get(7, 0, 0), None);
+        cache.insert(7, 0, 0, 0.5);
+        cache.insert(8, 0, 0, 0.1);
+        assert_eq!(cache.get(7, 0, 1), Some(0.5));
+        // another episode is another score
+        assert_eq!(cache.get(7, 1, 1), None);
+        assert_eq!(cache.get(7, 0, 2), None);
+        assert_eq!(cache.hit_rate(), 0.25);
+
+        cache.evict_unused(1);
+        assert_eq!(cache.len(), 1);
+        let mut forever = FitnessCache::new(&FitnessCacheConfig::default());
+        forever.insert(7, 0, 0, 0.5);
+        assert_eq!(forever.get(7, 0, 100), Some(0.5));
+    }
+}
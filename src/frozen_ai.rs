@@ -0,0 +1,206 @@
+use crate::base_ai::AI;
+use crate::distill::{DistillationConfig, TeacherSample};
+use burn::module::{Ignored, Module};
+use burn::nn::Linear;
+use burn::prelude::Backend;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+use burn::tensor::{Distribution, Tensor};
+use rand::rngs::StdRng;
+use std::marker::PhantomData;
+
+/// Wraps a network and marks some of its layers frozen, so mutation, pruning and crossover only
+/// evolve the rest, e.g. a new output head on top of a pretrained input encoder. Replacing the
+/// layers directly, as importing weights does, still reaches the frozen ones.
+/// Saving and loading only touch the inner network.
+#[derive(Module, Debug)]
+pub struct FrozenAI<B: Backend, A: Module<B>> {
+    inner: A,
+    frozen: Ignored<Vec<bool>>,
+    backend: PhantomData<B>,
+}
+
+impl<B: Backend, A: AI<B>> FrozenAI<B, A> {
+    /// Freezes the layers with the given names, as `AI::layers` reports them.
+    pub fn new(inner: A, frozen: &[&str]) -> Self {
+        let layers = inner.layers();
+        for name in frozen {
+            assert!(
+                layers.iter().any(|(layer, _)| layer == name),
+                "{} has no layer named {name}",
+                inner.network_name()
+            );
+        }
+        let mask = layers
+            .iter()
+            .map(|(name, _)| frozen.contains(name))
+            .collect();
+        Self::with_mask(inner, mask)
+    }
+
+    fn with_mask(inner: A, frozen: Vec<bool>) -> Self {
+        Self {
+            inner,
+            frozen: Ignored(frozen),
+            backend: PhantomData,
+        }
+    }
+
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+}
+
+impl<B: Backend, A: AI<B>> AI<B> for FrozenAI<B, A> {
+    fn jiggle(&self, d: &Distribution, rng: &mut StdRng) -> Self {
+        let jiggled = self.inner.jiggle(d, rng);
+        let layers = self
+            .inner
+            .layers()
+            .into_iter()
+            .zip(jiggled.layers())
+            .zip(self.frozen.iter())
+            .map(|(((_, kept), (_, jiggled)), frozen)| if *frozen { kept } else { jiggled })
+            .collect();
+        Self::with_mask(jiggled.with_layers(layers), self.frozen.to_vec())
+    }
+
+    fn apply(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
+        self.inner.apply(input)
+    }
+
+    fn reset_state(&self) {
+        self.inner.reset_state();
+    }
+
+    fn max_amp(&self) -> f32 {
+        self.inner.max_amp()
+    }
+
+    fn with_observations(&self, observations: &[Vec<f32>]) -> Self {
+        Self::with_mask(
+            self.inner.with_observations(observations),
+            self.frozen.to_vec(),
+        )
+    }
+
+    fn train_auxiliary(&self, device: &B::Device, rng: &mut StdRng) -> Self {
+        Self::with_mask(
+            self.inner.train_auxiliary(device, rng),
+            self.frozen.to_vec(),
+        )
+    }
+
+    /// The frozen layers are put back as they were once the network is fitted.
+    fn imitate(
+        &self,
+        samples: &[TeacherSample],
+        config: &DistillationConfig,
+        device: &B::Device,
+        rng: &mut StdRng,
+    ) -> Option<Self> {
+        let fitted = self.inner.imitate(samples, config, device, rng)?;
+        let layers = fitted
+            .layers()
+            .into_iter()
+            .zip(self.inner.layers())
+            .zip(self.frozen.iter())
+            .map(|(((_, fitted), (_, kept)), frozen)| if *frozen { kept } else { fitted })
+            .collect();
+        Some(Self::with_mask(
+            fitted.with_layers(layers),
+            self.frozen.to_vec(),
+        ))
+    }
+
+    fn save_file(&self, filename: &str, recorder: &NamedMpkFileRecorder<FullPrecisionSettings>) {
+        AI::save_file(&self.inner, filename, recorder);
+    }
+
+    fn load_a_file(
+        self,
+        filename: &str,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> Self {
+        let frozen = self.frozen.to_vec();
+        Self::with_mask(self.inner.load_a_file(filename, recorder), frozen)
+    }
+
+    fn network_name(&self) -> &'static str {
+        self.inner.network_name()
+    }
+
+    fn layers(&self) -> Vec<(&'static str, Linear<B>)> {
+        self.inner.layers()
+    }
+
+    fn activations(&self) -> Vec<&'static str> {
+        self.inner.activations()
+    }
+
+    fn layer_inputs(&self) -> Vec<Vec<Option<usize>>> {
+        self.inner.layer_inputs()
+    }
+
+    fn force_layers(&self) -> Vec<usize> {
+        self.inner.force_layers()
+    }
+
+    fn input_size(&self) -> usize {
+        self.inner.input_size()
+    }
+
+    fn output_size(&self) -> usize {
+        self.inner.output_size()
+    }
+
+    fn grips(&self) -> bool {
+        self.inner.grips()
+    }
+
+    fn hidden_sizes(&self) -> Vec<usize> {
+        self.inner.hidden_sizes()
+    }
+
+    fn frozen_layers(&self) -> Vec<bool> {
+        self.frozen.to_vec()
+    }
+
+    fn with_layers(&self, layers: Vec<Linear<B>>) -> Self {
+        Self::with_mask(self.inner.with_layers(layers), self.frozen.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_ai::MutationConfig;
+    use crate::crossover::{CrossoverStrategy, Interleave};
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_frozen_layers_do_not_evolve() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let mut rng = StdRng::seed_from_u64(7);
+        let mother = FrozenAI::new(SmallAI::<BE>::new(&device), &["input"]);
+        let father = FrozenAI::new(SmallAI::<BE>::new(&device), &["input"]);
+        assert_eq!(mother.frozen_layers(), vec![true, false, false]);
+
+        let d = Distribution::Normal(0., 0.1);
+        let offspring = [
+            mother.jiggle(&d, &mut rng),
+            mother.jiggle_layers(&d, &MutationConfig::default(), &mut rng),
+            mother.prune(0.5, &mut rng),
+            Interleave.cross(&mother, &father, &mut rng),
+        ];
+        for child in offspring {
+            for ((name, before), (_, after)) in mother.layers().iter().zip(child.layers()) {
+                let unchanged = before.weight.val().to_data() == after.weight.val().to_data();
+                assert_eq!(unchanged, *name == "input", "{name}");
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::base_ai::{jiggle_linear, max_amp_for_linear, AI, INIT_STD};
+use crate::running_norm::RunningNorm;
+use crate::sim_for_ai::{ACTION_SIZE, OBSERVATION_SIZE};
+use crate::weights::{load_versioned, save_versioned};
+use burn::module::Module;
+use burn::nn::{Initializer, Linear, LinearConfig};
+use burn::prelude::Backend;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+use burn::tensor::activation::{relu, tanh};
+use burn::tensor::{Distribution, Tensor};
+use rand::rngs::StdRng;
+
+/// The joint forces followed by the grip intent.
+pub const GRIP_ACTION_SIZE: usize = ACTION_SIZE + 1;
+
+/// A shared trunk with two heads: one force per joint, and a single grip intent the
+/// environment turns into the finger and thumb closing together. Grasping then needs one output
+/// instead of four coordinated ones.
+#[derive(Module, Debug)]
+pub struct GripAI<B: Backend> {
+    input: Linear<B>,
+    hidden: Linear<B>,
+    joints: Linear<B>,
+    grip: Linear<B>,
+    norm: RunningNorm<B>,
+}
+
+impl<B: Backend> AI<B> for GripAI<B> {
+    fn jiggle(&self, d: &Distribution, rng: &mut StdRng) -> Self {
+        Self {
+            input: jiggle_linear(&self.input, d, rng),
+            hidden: jiggle_linear(&self.hidden, d, rng),
+            joints: jiggle_linear(&self.joints, d, rng),
+            grip: jiggle_linear(&self.grip, d, rng),
+            norm: self.norm.detached(),
+        }
+    }
+
+    fn apply(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
+        let x = relu(self.input.forward(self.norm.standardize(input)));
+        let x = relu(self.hidden.forward(x));
+        tanh(Tensor::cat(
+            vec![self.joints.forward(x.clone()), self.grip.forward(x)],
+            0,
+        ))
+    }
+
+    fn with_observations(&self, observations: &[Vec<f32>]) -> Self {
+        Self {
+            norm: self.norm.updated(observations),
+            ..self.clone()
+        }
+    }
+
+    fn max_amp(&self) -> f32 {
+        let all_maximums = [
+            max_amp_for_linear(&self.input),
+            max_amp_for_linear(&self.hidden),
+            max_amp_for_linear(&self.joints),
+            max_amp_for_linear(&self.grip),
+        ];
+        all_maximums
+            .iter()
+            .max_by(|a, b| {
+                a.partial_cmp(b)
+                    .expect("max amplitude comparison failed across all layers")
+            })
+            .copied()
+            .expect("no max amplitude found across all layers")
+    }
+
+    fn save_file(&self, filename: &str, recorder: &NamedMpkFileRecorder<FullPrecisionSettings>) {
+        save_versioned(self, filename, recorder).expect("save failed");
+    }
+
+    fn load_a_file(
+        self,
+        filename: &str,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> Self {
+        load_versioned(self, filename, recorder).expect("load failed")
+    }
+
+    fn network_name(&self) -> &'static str {
+        "GripAI"
+    }
+
+    fn layers(&self) -> Vec<(&'static str, Linear<B>)> {
+        vec![
+            ("input", self.input.clone()),
+            ("hidden", self.hidden.clone()),
+            ("joints", self.joints.clone()),
+            ("grip", self.grip.clone()),
+        ]
+    }
+
+    fn activations(&self) -> Vec<&'static str> {
+        vec!["relu", "relu", "tanh", "tanh"]
+    }
+
+    /// The joint forces and the grip are two heads on the hidden layer.
+    fn layer_inputs(&self) -> Vec<Vec<Option<usize>>> {
+        vec![vec![None], vec![Some(0)], vec![Some(1)], vec![Some(1)]]
+    }
+
+    fn force_layers(&self) -> Vec<usize> {
+        vec![2, 3]
+    }
+
+    fn output_size(&self) -> usize {
+        self.joints.weight.dims()[1] + self.grip.weight.dims()[1]
+    }
+
+    fn grips(&self) -> bool {
+        true
+    }
+
+    fn with_layers(&self, layers: Vec<Linear<B>>) -> Self {
+        let [input, hidden, joints, grip] =
+            <[Linear<B>; 4]>::try_from(layers).expect("GripAI has four layers");
+        Self {
+            input,
+            hidden,
+            joints,
+            grip,
+            norm: self.norm.detached(),
+        }
+    }
+}
+
+impl<B: Backend> GripAI<B> {
+    pub fn new(device: &B::Device) -> Self {
+        Self::with_init_std(device, INIT_STD)
+    }
+
+    pub fn with_init_std(device: &B::Device, std: f64) -> Self {
+        let config = |d_input, d_output| {
+            LinearConfig::new(d_input, d_output)
+                .with_bias(true)
+                .with_initializer(Initializer::Normal { mean: 0., std })
+        };
+
+        Self {
+            input: config(OBSERVATION_SIZE, 128).init(device),
+            hidden: config(128, 14).init(device),
+            joints: config(14, ACTION_SIZE).init(device),
+            grip: config(14, 1).init(device),
+            norm: RunningNorm::new(OBSERVATION_SIZE, device),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim_for_ai::{apply_forces_and_step, prepare_simulation};
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+
+    #[test]
+    fn test_grip_intent_closes_finger_and_thumb() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let forces = GripAI::<BE>::new(&device).apply(Tensor::ones([OBSERVATION_SIZE], &device));
+        assert_eq!(forces.dims(), [GRIP_ACTION_SIZE]);
+
+        let (mut world, _, _) = prepare_simulation();
+        let mut forces = vec![0.; ACTION_SIZE];
+        apply_forces_and_step(&mut world, &forces, Some(0.5));
+        assert_eq!(
+            world.joint_applied_forces(),
+            vec![0., 0., 0., 0., -0.5, -0.5, 0.5, 0.5]
+        );
+
+        // the grip adds to the joint forces, within the force range
+        forces[4] = -1.;
+        forces[6] = 0.25;
+        apply_forces_and_step(&mut world, &forces, Some(0.5));
+        assert_eq!(world.joint_applied_forces()[4..], [-1., -0.5, 0.75, 0.5]);
+    }
+}
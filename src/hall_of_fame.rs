@@ -0,0 +1,330 @@
+use crate::base_ai::AI;
+use crate::evolution::{ai_naming, Genome};
+use crate::manifest::{Manifest, ManifestEntry};
+use crate::retention::ModelMetadata;
+use crate::weights::{load_saved, save_as, SaveFormat};
+use burn::prelude::Backend;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// One of the best individuals ever seen, with where and when it was found.
+#[derive(Clone, Debug)]
+pub struct HallOfFameEntry<A> {
+    pub genome: Genome<A>,
+    pub score: f32,
+    pub generation: usize,
+    pub island: usize,
+    /// File the network is saved in, relative to the hall of fame directory.
+    pub file: String,
+    /// Sequence number of a member not saved yet.
+    pending: Option<usize>,
+}
+
+/// What is written to the metadata file next to the saved networks.
+#[derive(Debug, Deserialize, Serialize)]
+struct EntryRecord {
+    file: String,
+    score: f32,
+    generation: usize,
+    island: usize,
+    sigma: f64,
+}
+
+/// The top individuals of a whole run, best first. Members are saved under the usual
+/// `best_<network>_<seq>` names, listed in the directory's manifest, with their scores in
+/// `hall_of_fame_<network>.json`, so a resumed run continues with them, and copies are injected back into the islands.
+#[derive(Clone, Debug)]
+pub struct HallOfFame<A> {
+    capacity: usize,
+    directory: PathBuf,
+    entries: Vec<HallOfFameEntry<A>>,
+    next_seq: usize,
+}
+
+impl<A> HallOfFame<A> {
+    pub fn new(capacity: usize, directory: impl Into<PathBuf>, next_seq: usize) -> Self {
+        Self {
+            capacity,
+            directory: directory.into(),
+            entries: Vec::new(),
+            next_seq,
+        }
+    }
+
+    /// A hall of fame as it was, with members already saved under their files.
+    pub(crate) fn restored(
+        capacity: usize,
+        directory: impl Into<PathBuf>,
+        entries: impl IntoIterator<Item = (Genome<A>, f32, usize, usize, String)>,
+        next_seq: usize,
+    ) -> Self {
+        let entries = entries
+            .into_iter()
+            .map(
+                |(genome, score, generation, island, file)| HallOfFameEntry {
+                    genome,
+                    score,
+                    generation,
+                    island,
+                    file,
+                    pending: None,
+                },
+            )
+            .collect();
+        Self {
+            capacity,
+            directory: directory.into(),
+            entries,
+            next_seq,
+        }
+    }
+
+    pub fn entries(&self) -> &[HallOfFameEntry<A>] {
+        &self.entries
+    }
+
+    /// The sequence number the next saved member gets.
+    pub fn next_seq(&self) -> usize {
+        self.next_seq
+    }
+
+    pub fn best(&self) -> Option<&HallOfFameEntry<A>> {
+        self.entries.first()
+    }
+
+    /// The score an individual has to beat to become the new best, 0 while empty.
+    pub fn best_score(&self) -> f32 {
+        self.best().map_or(0., |entry| entry.score)
+    }
+
+    fn metadata_path(&self, network_name: &str) -> PathBuf {
+        self.directory
+            .join(format!("hall_of_fame_{network_name}.json"))
+    }
+}
+
+impl<A: Clone> HallOfFame<A> {
+    /// Whether the individual beats the worst member and is not already in.
+    pub fn admits<B: Backend>(&self, score: f32, genome: &Genome<A>) -> bool
+    where
+        A: AI<B>,
+    {
+        let full = self.entries.len() >= self.capacity;
+        let beaten = !full || self.entries.last().is_some_and(|worst| worst.score < score);
+        beaten
+            && !self
+                .entries
+                .iter()
+                .any(|entry| entry.genome.fingerprint() == genome.fingerprint())
+    }
+
+    /// Admits the individual if it beats the worst member and is not already in. Returns
+    /// whether it became the new best.
+    pub fn consider<B: Backend>(
+        &mut self,
+        score: f32,
+        genome: &Genome<A>,
+        generation: usize,
+        island: usize,
+        format: SaveFormat,
+    ) -> bool
+    where
+        A: AI<B>,
+    {
+        if !self.admits(score, genome) {
+            return false;
+        }
+
+        let seq = self.next_seq;
+        let file = format!("{}.{}", ai_naming(genome.ai(), seq), format.extension());
+        self.next_seq += 1;
+        let rank = self.entries.partition_point(|entry| entry.score >= score);
+        let mut genome = genome.clone();
+        genome.operator = None;
+        self.entries.insert(
+            rank,
+            HallOfFameEntry {
+                genome,
+                score,
+                generation,
+                island,
+                file,
+                pending: Some(seq),
+            },
+        );
+        self.entries.truncate(self.capacity);
+        rank == 0
+    }
+
+    /// Saves the members not saved yet, each with its sidecar, lists them in the manifest and
+    /// rewrites the metadata file.
+    pub fn persist<B: Backend>(
+        &mut self,
+        format: SaveFormat,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> std::io::Result<()>
+    where
+        A: AI<B>,
+    {
+        let Some(best) = self.best() else {
+            return Ok(());
+        };
+        let network = best.genome.ai().network_name();
+        let metadata_path = self.metadata_path(network);
+        let mut manifest = Manifest::load(&self.directory)?;
+        for entry in self.entries.iter_mut() {
+            let Some(seq) = entry.pending else {
+                continue;
+            };
+            let path = self.directory.join(&entry.file);
+            let stem = path.with_extension("");
+            save_as(
+                entry.genome.ai(),
+                stem.to_str().expect("path is not unicode"),
+                format,
+                recorder,
+            );
+            ModelMetadata {
+                score: entry.score,
+                generation: entry.generation,
+                island: entry.island,
+                sigma: entry.genome.sigma,
+            }
+            .save(&path)?;
+            manifest.add(ManifestEntry {
+                file: entry.file.clone(),
+                network: network.to_string(),
+                score: entry.score,
+                seq,
+            });
+            entry.pending = None;
+        }
+        manifest.save(&self.directory)?;
+
+        let records: Vec<EntryRecord> = self
+            .entries
+            .iter()
+            .map(|entry| EntryRecord {
+                file: entry.file.clone(),
+                score: entry.score,
+                generation: entry.generation,
+                island: entry.island,
+                sigma: entry.genome.sigma,
+            })
+            .collect();
+        let json = serde_json::to_string_pretty(&records).map_err(std::io::Error::other)?;
+        std::fs::write(metadata_path, json)
+    }
+
+    /// Reads back a persisted hall of fame, loading the networks into copies of `sample`.
+    /// Returns an empty one when nothing was persisted in `directory`.
+    pub fn resume<B: Backend>(
+        capacity: usize,
+        directory: impl AsRef<Path>,
+        sample: &A,
+        next_seq: usize,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> std::io::Result<Self>
+    where
+        A: AI<B>,
+    {
+        let mut hall_of_fame = Self::new(capacity, directory.as_ref(), next_seq);
+        let metadata_path = hall_of_fame.metadata_path(sample.network_name());
+        if !metadata_path.exists() {
+            return Ok(hall_of_fame);
+        }
+        let records: Vec<EntryRecord> =
+            serde_json::from_str(&std::fs::read_to_string(metadata_path)?)
+                .map_err(std::io::Error::other)?;
+        for record in records.into_iter().take(capacity) {
+            let path = hall_of_fame.directory.join(&record.file);
+            let ai = load_saved(
+                sample.clone(),
+                path.to_str().expect("path is not unicode"),
+                recorder,
+            );
+            let mut genome = Genome::new(ai);
+            genome.sigma = record.sigma;
+            hall_of_fame.entries.push(HallOfFameEntry {
+                genome,
+                score: record.score,
+                generation: record.generation,
+                island: record.island,
+                file: record.file,
+                pending: None,
+            });
+        }
+        Ok(hall_of_fame)
+    }
+
+    /// Puts a random member into one of the first `random_slots`, where the fresh random
+    /// individuals are, of every island.
+    pub fn inject(&self, islands: &mut [Vec<Genome<A>>], random_slots: usize, rng: &mut StdRng) {
+        if self.entries.is_empty() || random_slots == 0 {
+            return;
+        }
+        for island in islands {
+            let entry = &self.entries[rng.random_range(0..self.entries.len())];
+            island[rng.random_range(0..random_slots)] = entry.genome.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+    use std::env::temp_dir;
+
+    type BE = NdArray<f32>;
+
+    #[test]
+    fn test_hall_of_fame_keeps_the_best_and_resumes() {
+        let device = NdArrayDevice::Cpu;
+        let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
+        let directory = temp_dir().join(format!("hall_of_fame_test_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let genomes: Vec<_> = (0..4)
+            .map(|_| Genome::new(SmallAI::<BE>::new(&device)))
+            .collect();
+
+        let mut hall_of_fame = HallOfFame::new(2, &directory, 0);
+        assert!(hall_of_fame.consider(0.5, &genomes[0], 0, 0, SaveFormat::Safetensors));
+        assert!(!hall_of_fame.consider(0.4, &genomes[1], 0, 1, SaveFormat::Safetensors));
+        // already in
+        assert!(!hall_of_fame.consider(0.9, &genomes[0], 1, 0, SaveFormat::Safetensors));
+        assert!(!hall_of_fame.consider(0.3, &genomes[2], 1, 0, SaveFormat::Safetensors));
+        assert!(hall_of_fame.admits(0.8, &genomes[3]) && !hall_of_fame.admits(0.9, &genomes[0]));
+        assert!(hall_of_fame.consider(0.8, &genomes[3], 2, 4, SaveFormat::Safetensors));
+        let scores: Vec<f32> = hall_of_fame
+            .entries()
+            .iter()
+            .map(|entry| entry.score)
+            .collect();
+        assert_eq!(scores, vec![0.8, 0.5]);
+        hall_of_fame
+            .persist(SaveFormat::Safetensors, &recorder)
+            .unwrap();
+
+        let resumed = HallOfFame::resume(2, &directory, genomes[0].ai(), 5, &recorder).unwrap();
+        let manifest = Manifest::load(&directory).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        let seqs: Vec<usize> = manifest
+            .latest("Small AI")
+            .iter()
+            .map(|entry| entry.seq)
+            .collect();
+        assert_eq!(seqs, vec![2, 0]);
+        assert_eq!(resumed.best_score(), 0.8);
+        assert_eq!(resumed.best().unwrap().island, 4);
+        assert_eq!(
+            resumed.best().unwrap().genome.fingerprint(),
+            genomes[3].fingerprint()
+        );
+    }
+}
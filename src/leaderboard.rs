@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// How a saved network scored over the episodes.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LeaderboardEntry {
+    pub file: String,
+    pub mean: f32,
+    /// Population standard deviation of the episode scores.
+    pub std: f32,
+    /// The score of every episode, by episode seed.
+    pub scores: Vec<f32>,
+}
+
+impl LeaderboardEntry {
+    pub fn of(file: impl Into<String>, scores: Vec<f32>) -> Self {
+        let count = scores.len().max(1) as f32;
+        let mean = scores.iter().sum::<f32>() / count;
+        let std = (scores
+            .iter()
+            .map(|score| (score - mean).powi(2))
+            .sum::<f32>()
+            / count)
+            .sqrt();
+        Self {
+            file: file.into(),
+            mean,
+            std,
+            scores,
+        }
+    }
+}
+
+/// Saved networks ranked by their mean score over the same seeded episodes.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Leaderboard {
+    pub episodes: usize,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    /// Ranks the entries best first.
+    pub fn new(episodes: usize, mut entries: Vec<LeaderboardEntry>) -> Self {
+        entries.sort_by(|a, b| b.mean.total_cmp(&a.mean));
+        Self { episodes, entries }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(
+            path,
+            serde_json::to_string_pretty(self).map_err(std::io::Error::other)?,
+        )
+    }
+}
+
+impl Display for Leaderboard {
+    /// A row per network: rank, mean ± std, file.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "rank  mean      std       file ({} episodes)",
+            self.episodes
+        )?;
+        for (rank, entry) in self.entries.iter().enumerate() {
+            writeln!(
+                f,
+                "{:<5} {:<9.6} {:<9.6} {}",
+                rank + 1,
+                entry.mean,
+                entry.std,
+                entry.file
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leaderboard_ranks_by_mean() {
+        let leaderboard = Leaderboard::new(
+            2,
+            vec![
+                LeaderboardEntry::of("a.mpk", vec![0.2, 0.4]),
+                LeaderboardEntry::of("b.mpk", vec![0.5, 0.5]),
+            ],
+        );
+        let files: Vec<&str> = leaderboard
+            .entries
+            .iter()
+            .map(|entry| entry.file.as_str())
+            .collect();
+        assert_eq!(files, vec!["b.mpk", "a.mpk"]);
+        assert_eq!(leaderboard.entries[0].std, 0.);
+        assert!((leaderboard.entries[1].mean - 0.3).abs() < 1e-6);
+        assert!((leaderboard.entries[1].std - 0.1).abs() < 1e-6);
+        let table = leaderboard.to_string();
+        assert_eq!(table.lines().count(), 3);
+        assert!(table.lines().nth(1).unwrap().starts_with("1     0.500000"));
+
+        let path = std::env::temp_dir().join(format!("leaderboard_{}.json", std::process::id()));
+        leaderboard.save(&path).unwrap();
+        let loaded: Leaderboard =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded, leaderboard);
+    }
+}
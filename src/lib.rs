@@ -1,5 +1,49 @@
 pub mod ai;
+pub mod alps;
+pub mod attn_ai;
+pub mod aux_ai;
+pub mod backend;
 pub mod base_ai;
-pub mod small_ai;
+pub mod basket_task;
+pub mod behavior;
+pub mod checkpoint;
+pub mod cli;
+pub mod compare;
+pub mod crossover;
+pub mod distill;
+pub mod dot;
+pub mod ensemble_ai;
+pub mod evolution;
+pub mod fitness;
+pub mod fitness_cache;
+pub mod frozen_ai;
+pub mod grip_ai;
+pub mod hall_of_fame;
+pub mod leaderboard;
+pub mod manifest;
+pub mod map_elites;
+pub mod medium_ai;
+pub mod metrics;
+pub mod mirrored_ai;
+pub mod noisy_ai;
+pub mod novelty;
 pub mod physics;
+pub mod population;
+pub mod remote;
+pub mod report;
+pub mod results_log;
+pub mod retention;
+pub mod rl;
+pub mod rnn_ai;
+pub mod running_norm;
+pub mod schedule;
+pub mod scripted;
+pub mod selection;
 pub mod sim_for_ai;
+pub mod small_ai;
+pub mod smoothed_ai;
+pub mod speciation;
+pub mod sweep;
+pub mod telemetry;
+pub mod train_config;
+pub mod weights;
@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// One saved network as the manifest lists it.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ManifestEntry {
+    /// File the network is saved in, relative to the model directory.
+    pub file: String,
+    /// `AI::network_name` of the saved network.
+    pub network: String,
+    pub score: f32,
+    /// Order the networks of one kind were saved in.
+    pub seq: usize,
+}
+
+/// The score of the sidecar `retention::ModelMetadata` writes next to a saved network.
+#[derive(Deserialize)]
+struct Sidecar {
+    score: f32,
+}
+
+/// The saved networks of a model directory, kept in its `manifest.json`, so they are found
+/// by what they are rather than by parsing their file names.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn path(directory: &Path) -> PathBuf {
+        directory.join("manifest.json")
+    }
+
+    /// The manifest of the directory. One saved into before the networks were listed has none
+    /// yet; its networks are listed from their file names then, keeping their sequence numbers
+    /// so the next ones saved don't overwrite them. Empty for a directory nothing was saved in.
+    pub fn load(directory: &Path) -> std::io::Result<Self> {
+        let path = Self::path(directory);
+        if !path.exists() {
+            return Self::from_file_names(directory);
+        }
+        serde_json::from_str(&std::fs::read_to_string(path)?).map_err(std::io::Error::other)
+    }
+
+    /// Lists the `best_<network>_<seq>.mpk` and `.safetensors` files of the directory, with the
+    /// score of their sidecar if they have one, 0 otherwise.
+    fn from_file_names(directory: &Path) -> std::io::Result<Self> {
+        if !directory.is_dir() {
+            return Ok(Self::default());
+        }
+        let mut entries = Vec::new();
+        for file in std::fs::read_dir(directory)? {
+            let path = file?.path();
+            let extension = path.extension().and_then(|extension| extension.to_str());
+            if !matches!(extension, Some("mpk" | "safetensors")) {
+                continue;
+            }
+            let Some((network, seq)) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.strip_prefix("best_"))
+                .and_then(|name| name.rsplit_once('_'))
+            else {
+                continue;
+            };
+            let Ok(seq) = seq.parse() else {
+                continue;
+            };
+            let score = std::fs::read_to_string(path.with_extension("json"))
+                .ok()
+                .and_then(|sidecar| serde_json::from_str::<Sidecar>(&sidecar).ok())
+                .map_or(0., |sidecar| sidecar.score);
+            entries.push(ManifestEntry {
+                file: path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .expect("file name is unicode")
+                    .to_string(),
+                network: network.to_string(),
+                score,
+                seq,
+            });
+        }
+        entries.sort_by(|a, b| (&a.network, a.seq).cmp(&(&b.network, b.seq)));
+        Ok(Self { entries })
+    }
+
+    /// Writes a temporary file first and renames it over the manifest, so an interrupted
+    /// run never leaves a truncated one behind.
+    pub fn save(&self, directory: &Path) -> std::io::Result<()> {
+        let path = Self::path(directory);
+        let temporary = path.with_extension("json.tmp");
+        std::fs::write(
+            &temporary,
+            serde_json::to_string_pretty(self).map_err(std::io::Error::other)?,
+        )?;
+        std::fs::rename(temporary, path)
+    }
+
+    /// Lists a saved network, replacing whatever was listed under the same file.
+    pub fn add(&mut self, entry: ManifestEntry) {
+        self.remove(&entry.file);
+        self.entries.push(entry);
+    }
+
+    pub fn remove(&mut self, file: &str) {
+        self.entries.retain(|entry| entry.file != file);
+    }
+
+    /// The saved networks of `network`, the most recent first.
+    pub fn latest(&self, network: &str) -> Vec<&ManifestEntry> {
+        let mut entries: Vec<&ManifestEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.network == network)
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.seq));
+        entries
+    }
+
+    /// The sequence number the next saved network of `network` gets.
+    pub fn next_seq(&self, network: &str) -> usize {
+        self.latest(network)
+            .first()
+            .map_or(0, |entry| entry.seq + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let directory = temp_dir().join(format!("manifest_test_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        assert_eq!(Manifest::load(&directory).unwrap(), Manifest::default());
+
+        let entry = |file: &str, network: &str, seq| ManifestEntry {
+            file: file.to_string(),
+            network: network.to_string(),
+            score: 0.5,
+            seq,
+        };
+        let mut manifest = Manifest::default();
+        // names with digits and underscores that the old file name parsing tripped over
+        manifest.add(entry("best_Small AI_2.mpk", "Small AI", 2));
+        manifest.add(entry("best_Net_2x_10.mpk", "Net_2x", 10));
+        manifest.add(entry("best_Small AI_7.mpk", "Small AI", 7));
+        manifest.add(entry("best_Small AI_2.mpk", "Small AI", 2));
+        manifest.save(&directory).unwrap();
+
+        let loaded = Manifest::load(&directory).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(loaded.entries.len(), 3);
+        let files: Vec<&str> = loaded
+            .latest("Small AI")
+            .iter()
+            .map(|entry| entry.file.as_str())
+            .collect();
+        assert_eq!(files, vec!["best_Small AI_7.mpk", "best_Small AI_2.mpk"]);
+        assert_eq!(loaded.next_seq("Net_2x"), 11);
+        assert_eq!(loaded.next_seq("Medium AI"), 0);
+    }
+
+    #[test]
+    fn test_directories_without_a_manifest_are_listed_from_their_files() {
+        let directory = temp_dir().join(format!("manifest_legacy_test_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for file in [
+            "best_Small AI_3.mpk",
+            "best_Small AI_12.safetensors",
+            "best_Net_2x_4.mpk",
+            "best_Small AI_x.mpk",
+            "population.mpk",
+        ] {
+            std::fs::write(directory.join(file), []).unwrap();
+        }
+        std::fs::write(directory.join("best_Small AI_3.json"), r#"{"score": 0.75}"#).unwrap();
+
+        let manifest = Manifest::load(&directory).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        let listed: Vec<(&str, &str, f32, usize)> = manifest
+            .entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.file.as_str(),
+                    entry.network.as_str(),
+                    entry.score,
+                    entry.seq,
+                )
+            })
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("best_Net_2x_4.mpk", "Net_2x", 0., 4),
+                ("best_Small AI_3.mpk", "Small AI", 0.75, 3),
+                ("best_Small AI_12.safetensors", "Small AI", 0., 12),
+            ]
+        );
+        assert_eq!(manifest.next_seq("Small AI"), 13);
+    }
+}
@@ -0,0 +1,368 @@
+use crate::base_ai::AI;
+use crate::behavior::BehaviorDescriptor;
+use crate::evolution::Genome;
+use crate::weights::{load_saved, save_as, SaveFormat};
+use burn::prelude::Backend;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Settings of the MAP-Elites archive, given as a `[map_elites]` table of the training
+/// configuration.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MapElitesConfig {
+    /// Cells along each axis of the final fingertip position, at least 1.
+    pub bins: usize,
+    /// Elites put back into the fresh slots of every island each generation.
+    pub injections: usize,
+}
+
+impl Default for MapElitesConfig {
+    fn default() -> Self {
+        Self {
+            bins: 10,
+            injections: 1,
+        }
+    }
+}
+
+/// An elite as the index lists it.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CellRecord {
+    pub x: usize,
+    pub y: usize,
+    pub score: f32,
+    pub behavior: BehaviorDescriptor,
+    pub sigma: f64,
+    /// Generation the elite took the cell in.
+    pub generation: usize,
+    /// File the network is saved in, relative to the archive directory.
+    pub file: String,
+}
+
+/// The `map_elites.json` of an archive directory, readable without loading any network.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct MapElitesIndex {
+    pub bins: usize,
+    pub cells: Vec<CellRecord>,
+}
+
+impl MapElitesIndex {
+    pub fn path(directory: &Path) -> PathBuf {
+        directory.join("map_elites.json")
+    }
+
+    pub fn load(directory: &Path) -> std::io::Result<Self> {
+        serde_json::from_str(&std::fs::read_to_string(Self::path(directory))?)
+            .map_err(std::io::Error::other)
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> Option<&CellRecord> {
+        self.cells.iter().find(|cell| cell.x == x && cell.y == y)
+    }
+
+    /// The grid with the top row highest, a cell shown by the tenths of its score, 9 for
+    /// anything from 0.9 up, and `.` while empty.
+    pub fn grid(&self) -> String {
+        let mut rows = Vec::with_capacity(self.bins);
+        for y in (0..self.bins).rev() {
+            let row: String = (0..self.bins)
+                .map(|x| match self.cell(x, y) {
+                    Some(cell) => char::from_digit((cell.score.clamp(0., 0.99) * 10.) as u32, 10)
+                        .unwrap_or('?'),
+                    None => '.',
+                })
+                .collect();
+            rows.push(row);
+        }
+        rows.join("\n")
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Elite<A> {
+    genome: Genome<A>,
+    record: CellRecord,
+    saved: bool,
+}
+
+/// The quality-diversity archive: the behavior space of the final fingertip position is
+/// divided into a grid, and every cell keeps the best individual that ended up there. An
+/// offspring takes a cell only by beating its incumbent, so the archive collects good
+/// solutions of every kind rather than many copies of the single best.
+#[derive(Clone, Debug)]
+pub struct MapElites<A> {
+    config: MapElitesConfig,
+    directory: PathBuf,
+    cells: BTreeMap<(usize, usize), Elite<A>>,
+}
+
+impl<A> MapElites<A> {
+    pub fn new(config: MapElitesConfig, directory: impl Into<PathBuf>) -> Self {
+        Self {
+            config,
+            directory: directory.into(),
+            cells: BTreeMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Share of the cells taken.
+    pub fn coverage(&self) -> f32 {
+        self.cells.len() as f32 / (self.config.bins * self.config.bins).max(1) as f32
+    }
+
+    /// The cell of a behavior, by its normalized final fingertip position.
+    pub fn cell(&self, behavior: &BehaviorDescriptor) -> (usize, usize) {
+        let bin = |value: f32| {
+            ((value.clamp(0., 1.) * self.config.bins as f32) as usize).min(self.config.bins - 1)
+        };
+        (
+            bin(behavior.final_fingertip.0),
+            bin(behavior.final_fingertip.1),
+        )
+    }
+
+    pub fn best_score(&self) -> Option<f32> {
+        self.cells
+            .values()
+            .map(|elite| elite.record.score)
+            .reduce(f32::max)
+    }
+
+    pub fn index(&self) -> MapElitesIndex {
+        MapElitesIndex {
+            bins: self.config.bins,
+            cells: self
+                .cells
+                .values()
+                .map(|elite| elite.record.clone())
+                .collect(),
+        }
+    }
+}
+
+impl<A: Clone> MapElites<A> {
+    /// Puts the individual into its cell if the cell is empty or it scores better than the
+    /// incumbent. Returns whether it did.
+    pub fn insert<B: Backend>(
+        &mut self,
+        score: f32,
+        behavior: BehaviorDescriptor,
+        genome: &Genome<A>,
+        generation: usize,
+    ) -> bool
+    where
+        A: AI<B>,
+    {
+        let (x, y) = self.cell(&behavior);
+        if self
+            .cells
+            .get(&(x, y))
+            .is_some_and(|incumbent| incumbent.record.score >= score)
+        {
+            return false;
+        }
+        let file = format!(
+            "{}_cell_{x}_{y}.{}",
+            genome.ai().network_name(),
+            SaveFormat::Mpk.extension()
+        );
+        let record = CellRecord {
+            x,
+            y,
+            score,
+            behavior,
+            sigma: genome.sigma,
+            generation,
+            file,
+        };
+        let mut genome = genome.clone();
+        genome.operator = None;
+        self.cells.insert(
+            (x, y),
+            Elite {
+                genome,
+                record,
+                saved: false,
+            },
+        );
+        true
+    }
+
+    /// Puts `config.injections` random elites into the first `random_slots`, where the fresh
+    /// random individuals are, of every island.
+    pub fn inject(&self, islands: &mut [Vec<Genome<A>>], random_slots: usize, rng: &mut StdRng) {
+        if self.cells.is_empty() || random_slots == 0 {
+            return;
+        }
+        let elites: Vec<&Elite<A>> = self.cells.values().collect();
+        for island in islands {
+            for _ in 0..self.config.injections {
+                island[rng.random_range(0..random_slots)] =
+                    elites[rng.random_range(0..elites.len())].genome.clone();
+            }
+        }
+    }
+
+    /// Saves the elites that took their cell since the last save and rewrites the index.
+    pub fn persist<B: Backend>(
+        &mut self,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> std::io::Result<()>
+    where
+        A: AI<B>,
+    {
+        std::fs::create_dir_all(&self.directory)?;
+        for elite in self.cells.values_mut().filter(|elite| !elite.saved) {
+            let stem = self.directory.join(&elite.record.file).with_extension("");
+            save_as(
+                elite.genome.ai(),
+                stem.to_str().expect("path is not unicode"),
+                SaveFormat::Mpk,
+                recorder,
+            );
+            elite.saved = true;
+        }
+        let json = serde_json::to_string_pretty(&self.index()).map_err(std::io::Error::other)?;
+        std::fs::write(MapElitesIndex::path(&self.directory), json)
+    }
+
+    /// Reads back a persisted archive, loading the networks into copies of `sample`. Returns
+    /// an empty one when nothing was persisted in `directory`, and an error when the archive
+    /// was persisted with another number of bins, as its cells would not line up.
+    pub fn resume<B: Backend>(
+        config: MapElitesConfig,
+        directory: impl Into<PathBuf>,
+        sample: &A,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> std::io::Result<Self>
+    where
+        A: AI<B>,
+    {
+        let mut archive = Self::new(config, directory);
+        if !MapElitesIndex::path(&archive.directory).exists() {
+            return Ok(archive);
+        }
+        let index = MapElitesIndex::load(&archive.directory)?;
+        if index.bins != archive.config.bins {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "the archive has {} bins, the configuration {}",
+                    index.bins, archive.config.bins
+                ),
+            ));
+        }
+        for record in index.cells {
+            let path = archive.directory.join(&record.file);
+            let ai = load_saved(
+                sample.clone(),
+                path.to_str().expect("path is not unicode"),
+                recorder,
+            );
+            let mut genome = Genome::new(ai);
+            genome.sigma = record.sigma;
+            archive.cells.insert(
+                (record.x, record.y),
+                Elite {
+                    genome,
+                    record,
+                    saved: true,
+                },
+            );
+        }
+        Ok(archive)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+    use rand::SeedableRng;
+
+    type BE = NdArray<f32>;
+
+    fn ending_at(x: f32, y: f32) -> BehaviorDescriptor {
+        BehaviorDescriptor {
+            final_fingertip: (x, y),
+            ..BehaviorDescriptor::default()
+        }
+    }
+
+    #[test]
+    fn test_cells_keep_their_best_and_persist() {
+        let device = NdArrayDevice::Cpu;
+        let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
+        let directory =
+            std::env::temp_dir().join(format!("map_elites_test_{}", std::process::id()));
+        let config = MapElitesConfig {
+            bins: 4,
+            injections: 2,
+        };
+        let genomes: Vec<_> = (0..3)
+            .map(|_| Genome::new(SmallAI::<BE>::new(&device)))
+            .collect();
+
+        let mut archive = MapElites::new(config.clone(), &directory);
+        assert_eq!(archive.cell(&ending_at(0.3, 1.)), (1, 3));
+        assert!(archive.insert(0.5, ending_at(0.3, 0.9), &genomes[0], 0));
+        assert!(!archive.insert(0.4, ending_at(0.26, 0.8), &genomes[1], 1));
+        assert!(archive.insert(0.6, ending_at(0.26, 0.8), &genomes[1], 1));
+        assert!(archive.insert(0.95, ending_at(0.9, 0.1), &genomes[2], 1));
+        assert_eq!(
+            (archive.len(), archive.coverage(), archive.best_score()),
+            (2, 2. / 16., Some(0.95))
+        );
+        assert_eq!(
+            archive.index().grid(),
+            "\
+.6..
+....
+....
+...9"
+        );
+
+        let mut islands = vec![(0..5)
+            .map(|_| Genome::new(SmallAI::<BE>::new(&device)))
+            .collect::<Vec<_>>()];
+        archive.inject(&mut islands, 1, &mut StdRng::seed_from_u64(7));
+        let elites = [genomes[1].fingerprint(), genomes[2].fingerprint()];
+        assert!(elites.contains(&islands[0][0].fingerprint()));
+
+        archive.persist(&recorder).unwrap();
+        let regridded = MapElitesConfig {
+            bins: 5,
+            ..config.clone()
+        };
+        assert!(MapElites::resume(
+            regridded,
+            &directory,
+            &SmallAI::<BE>::new(&device),
+            &recorder
+        )
+        .is_err());
+        let resumed =
+            MapElites::resume(config, &directory, &SmallAI::<BE>::new(&device), &recorder).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(resumed.index(), archive.index());
+        assert_eq!(
+            resumed.cells[&(1, 3)].genome.fingerprint(),
+            genomes[1].fingerprint()
+        );
+    }
+}
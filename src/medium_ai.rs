@@ -0,0 +1,198 @@
+use crate::base_ai::{jiggle_linear, max_amp_for_linear, AI, INIT_STD};
+use crate::distill::{distill_copy, DistillationConfig, TeacherSample};
+use crate::running_norm::RunningNorm;
+use crate::sim_for_ai::{ACTION_SIZE, OBSERVATION_SIZE};
+use crate::weights::{load_versioned, save_versioned};
+use burn::backend::Autodiff;
+use burn::module::Module;
+use burn::nn::{Initializer, Linear, LinearConfig};
+use burn::prelude::Backend;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+use burn::tensor::activation::{relu, tanh};
+use burn::tensor::{Distribution, Tensor};
+use rand::rngs::StdRng;
+
+const HIDDEN_SIZE: usize = 64;
+
+/// Between the small and the big network: one encoding layer and two hidden layers that each
+/// add to their input instead of replacing it, so a mutation deep in the net can't wipe out
+/// what the encoding carries.
+#[derive(Module, Debug)]
+pub struct MediumAI<B: Backend> {
+    input: Linear<B>,
+    hidden_1: Linear<B>,
+    hidden_2: Linear<B>,
+    output: Linear<B>,
+    norm: RunningNorm<B>,
+}
+
+impl<B: Backend> AI<B> for MediumAI<B> {
+    fn jiggle(&self, d: &Distribution, rng: &mut StdRng) -> Self {
+        Self {
+            input: jiggle_linear(&self.input, d, rng),
+            hidden_1: jiggle_linear(&self.hidden_1, d, rng),
+            hidden_2: jiggle_linear(&self.hidden_2, d, rng),
+            output: jiggle_linear(&self.output, d, rng),
+            norm: self.norm.detached(),
+        }
+    }
+
+    fn apply(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
+        let x = relu(self.input.forward(self.norm.standardize(input)));
+        let x = x.clone() + relu(self.hidden_1.forward(x));
+        let x = x.clone() + relu(self.hidden_2.forward(x));
+        tanh(self.output.forward(x))
+    }
+
+    fn with_observations(&self, observations: &[Vec<f32>]) -> Self {
+        Self {
+            norm: self.norm.updated(observations),
+            ..self.clone()
+        }
+    }
+
+    fn max_amp(&self) -> f32 {
+        let all_maximums = [
+            max_amp_for_linear(&self.input),
+            max_amp_for_linear(&self.hidden_1),
+            max_amp_for_linear(&self.hidden_2),
+            max_amp_for_linear(&self.output),
+        ];
+        all_maximums
+            .iter()
+            .max_by(|a, b| {
+                a.partial_cmp(b)
+                    .expect("max amplitude comparison failed across all layers")
+            })
+            .copied()
+            .expect("no max amplitude found across all layers")
+    }
+
+    fn save_file(&self, filename: &str, recorder: &NamedMpkFileRecorder<FullPrecisionSettings>) {
+        save_versioned(self, filename, recorder).expect("save failed");
+    }
+
+    fn load_a_file(
+        self,
+        filename: &str,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> Self {
+        load_versioned(self, filename, recorder).expect("load failed")
+    }
+
+    fn network_name(&self) -> &'static str {
+        "MediumAI"
+    }
+
+    fn imitate(
+        &self,
+        samples: &[TeacherSample],
+        config: &DistillationConfig,
+        device: &B::Device,
+        rng: &mut StdRng,
+    ) -> Option<Self> {
+        Some(distill_copy(
+            self,
+            MediumAI::<Autodiff<B>>::new(device),
+            samples,
+            config,
+            device,
+            rng,
+        ))
+    }
+
+    fn layers(&self) -> Vec<(&'static str, Linear<B>)> {
+        vec![
+            ("input", self.input.clone()),
+            ("hidden_1", self.hidden_1.clone()),
+            ("hidden_2", self.hidden_2.clone()),
+            ("output", self.output.clone()),
+        ]
+    }
+
+    fn activations(&self) -> Vec<&'static str> {
+        vec!["relu", "relu", "relu", "tanh"]
+    }
+
+    /// The hidden layers are residual, each adds to what it reads.
+    fn layer_inputs(&self) -> Vec<Vec<Option<usize>>> {
+        vec![
+            vec![None],
+            vec![Some(0)],
+            vec![Some(0), Some(1)],
+            vec![Some(0), Some(1), Some(2)],
+        ]
+    }
+
+    fn with_layers(&self, layers: Vec<Linear<B>>) -> Self {
+        let [input, hidden_1, hidden_2, output] =
+            <[Linear<B>; 4]>::try_from(layers).expect("MediumAI has four layers");
+        Self {
+            input,
+            hidden_1,
+            hidden_2,
+            output,
+            norm: self.norm.detached(),
+        }
+    }
+}
+
+impl<B: Backend> MediumAI<B> {
+    pub fn new(device: &B::Device) -> Self {
+        Self::with_init_std(device, INIT_STD)
+    }
+
+    pub fn with_init_std(device: &B::Device, std: f64) -> Self {
+        let config = |d_input, d_output| {
+            LinearConfig::new(d_input, d_output)
+                .with_bias(true)
+                .with_initializer(Initializer::Normal { mean: 0., std })
+        };
+
+        Self {
+            input: config(OBSERVATION_SIZE, HIDDEN_SIZE).init(device),
+            hidden_1: config(HIDDEN_SIZE, HIDDEN_SIZE).init(device),
+            hidden_2: config(HIDDEN_SIZE, HIDDEN_SIZE).init(device),
+            output: config(HIDDEN_SIZE, ACTION_SIZE).init(device),
+            norm: RunningNorm::new(OBSERVATION_SIZE, device),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+
+    #[test]
+    fn test_silent_hidden_layers_pass_the_encoding_through() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let medium = MediumAI::<BE>::with_init_std(&device, 0.1);
+        let silent = |layer: &Linear<BE>| Linear {
+            weight: layer.weight.clone().map(|w| w.zeros_like()),
+            bias: layer.bias.clone().map(|b| b.map(|b| b.zeros_like())),
+        };
+        let layers = medium.layers();
+        let silenced = medium.with_layers(vec![
+            layers[0].1.clone(),
+            silent(&layers[1].1),
+            silent(&layers[2].1),
+            layers[3].1.clone(),
+        ]);
+
+        // a fresh running norm passes the observation through as it is
+        let observation = Tensor::<BE, 1>::ones([OBSERVATION_SIZE], &device);
+        let expected = tanh(
+            layers[3]
+                .1
+                .forward(relu(layers[0].1.forward(observation.clone()))),
+        );
+        let forces: Vec<f32> = silenced.apply(observation).to_data().to_vec().unwrap();
+        let expected: Vec<f32> = expected.to_data().to_vec().unwrap();
+        for (force, expected) in forces.iter().zip(expected) {
+            assert!((force - expected).abs() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use crate::schedule::OperatorTally;
+use crate::telemetry::TimingSummary;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const HISTOGRAM_BUCKETS: usize = 30;
+
+/// Somewhere the metrics of a run are sent to while it goes on.
+pub trait MetricsSink {
+    fn scalar(&mut self, tag: &str, step: usize, value: f64) -> std::io::Result<()>;
+
+    /// The distribution of the values, e.g. the scores of an island.
+    fn histogram(&mut self, tag: &str, step: usize, values: &[f64]) -> std::io::Result<()>;
+}
+
+/// Records the island's score distribution, best and median score, evaluation throughput and
+/// the success of the operators that produced it, at the generation as step.
+pub fn record_generation(
+    sink: &mut impl MetricsSink,
+    generation: usize,
+    island: usize,
+    scores: &[f32],
+    tallies: &[OperatorTally],
+    eval_time: Duration,
+) -> std::io::Result<()> {
+    let mut sorted: Vec<f64> = scores.iter().map(|&score| score as f64).collect();
+    sorted.sort_by(|a, b| b.partial_cmp(a).expect("ai score should be comparable"));
+    sink.histogram(&format!("island_{island}/scores"), generation, &sorted)?;
+    sink.scalar(
+        &format!("island_{island}/best_score"),
+        generation,
+        sorted[0],
+    )?;
+    sink.scalar(
+        &format!("island_{island}/median_score"),
+        generation,
+        sorted[sorted.len() / 2],
+    )?;
+    sink.scalar(
+        &format!("island_{island}/evaluations_per_second"),
+        generation,
+        scores.len() as f64 / eval_time.as_secs_f64().max(f64::EPSILON),
+    )?;
+    for tally in tallies {
+        if let Some(rate) = tally.success_rate() {
+            sink.scalar(
+                &format!("island_{island}/operators/{}", tally.name),
+                generation,
+                rate as f64,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Records how long the evaluations of the generation took, to chart the stragglers.
+pub fn record_timing(
+    sink: &mut impl MetricsSink,
+    generation: usize,
+    timing: &TimingSummary,
+) -> std::io::Result<()> {
+    sink.scalar("timing/median_ms", generation, timing.median_ms)?;
+    sink.scalar("timing/p95_ms", generation, timing.p95_ms)?;
+    sink.scalar("timing/max_ms", generation, timing.max_ms)?;
+    sink.scalar(
+        "timing/steps_per_second",
+        generation,
+        timing.steps_per_second,
+    )?;
+    sink.scalar("timing/stragglers", generation, timing.stragglers as f64)
+}
+
+/// Writes TensorBoard event files, so `tensorboard --logdir <dir>` charts the run live.
+pub struct TensorBoardSink {
+    writer: BufWriter<File>,
+}
+
+impl TensorBoardSink {
+    /// Starts a new event file in the directory, which is created if needed.
+    pub fn create(directory: impl AsRef<Path>) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&directory)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock before 1970");
+        let name = format!(
+            "events.out.tfevents.{}.{}",
+            now.as_secs(),
+            std::process::id()
+        );
+        let mut sink = Self {
+            writer: BufWriter::new(File::create(directory.as_ref().join(name))?),
+        };
+        let mut event = event_header(0);
+        string_field(&mut event, 3, "brain.Event:2");
+        sink.write_record(&event)?;
+        Ok(sink)
+    }
+
+    fn write_record(&mut self, data: &[u8]) -> std::io::Result<()> {
+        let length = (data.len() as u64).to_le_bytes();
+        self.writer.write_all(&length)?;
+        self.writer
+            .write_all(&masked_crc32c(&length).to_le_bytes())?;
+        self.writer.write_all(data)?;
+        self.writer.write_all(&masked_crc32c(data).to_le_bytes())?;
+        self.writer.flush()
+    }
+
+    fn write_value(&mut self, step: usize, value: &[u8]) -> std::io::Result<()> {
+        let mut summary = Vec::new();
+        bytes_field(&mut summary, 1, value);
+        let mut event = event_header(step);
+        bytes_field(&mut event, 5, &summary);
+        self.write_record(&event)
+    }
+}
+
+impl MetricsSink for TensorBoardSink {
+    fn scalar(&mut self, tag: &str, step: usize, value: f64) -> std::io::Result<()> {
+        let mut summary_value = Vec::new();
+        string_field(&mut summary_value, 1, tag);
+        key(&mut summary_value, 2, 5);
+        summary_value.extend((value as f32).to_le_bytes());
+        self.write_value(step, &summary_value)
+    }
+
+    fn histogram(&mut self, tag: &str, step: usize, values: &[f64]) -> std::io::Result<()> {
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let width = (max - min) / HISTOGRAM_BUCKETS as f64;
+        let mut counts = [0_f64; HISTOGRAM_BUCKETS];
+        for value in values {
+            let bucket = if width > 0. {
+                ((value - min) / width) as usize
+            } else {
+                0
+            };
+            counts[bucket.min(HISTOGRAM_BUCKETS - 1)] += 1.;
+        }
+        let limits: Vec<f64> = (1..=HISTOGRAM_BUCKETS)
+            .map(|i| min + width * i as f64)
+            .collect();
+
+        let mut histogram = Vec::new();
+        double_field(&mut histogram, 1, min);
+        double_field(&mut histogram, 2, max);
+        double_field(&mut histogram, 3, values.len() as f64);
+        double_field(&mut histogram, 4, values.iter().sum());
+        double_field(
+            &mut histogram,
+            5,
+            values.iter().map(|value| value * value).sum(),
+        );
+        bytes_field(
+            &mut histogram,
+            6,
+            &limits
+                .iter()
+                .flat_map(|limit| limit.to_le_bytes())
+                .collect::<Vec<_>>(),
+        );
+        bytes_field(
+            &mut histogram,
+            7,
+            &counts
+                .iter()
+                .flat_map(|count| count.to_le_bytes())
+                .collect::<Vec<_>>(),
+        );
+        let mut summary_value = Vec::new();
+        string_field(&mut summary_value, 1, tag);
+        bytes_field(&mut summary_value, 5, &histogram);
+        self.write_value(step, &summary_value)
+    }
+}
+
+// The few protobuf encodings the event messages need.
+
+fn varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn key(buffer: &mut Vec<u8>, field: u64, wire_type: u64) {
+    varint(buffer, field << 3 | wire_type);
+}
+
+fn double_field(buffer: &mut Vec<u8>, field: u64, value: f64) {
+    key(buffer, field, 1);
+    buffer.extend(value.to_le_bytes());
+}
+
+fn bytes_field(buffer: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    key(buffer, field, 2);
+    varint(buffer, bytes.len() as u64);
+    buffer.extend(bytes);
+}
+
+fn string_field(buffer: &mut Vec<u8>, field: u64, text: &str) {
+    bytes_field(buffer, field, text.as_bytes());
+}
+
+/// An event's wall time and step.
+fn event_header(step: usize) -> Vec<u8> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock before 1970");
+    let mut event = Vec::new();
+    double_field(&mut event, 1, now.as_secs_f64());
+    key(&mut event, 2, 0);
+    varint(&mut event, step as u64);
+    event
+}
+
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ 0x82f6_3b78
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn masked_crc32c(data: &[u8]) -> u32 {
+    crc32c(data).rotate_right(15).wrapping_add(0xa282_ead8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tensorboard_events_are_framed() {
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+
+        let directory = std::env::temp_dir().join(format!("metrics_{}", std::process::id()));
+        let mut sink = TensorBoardSink::create(&directory).unwrap();
+        let tallies = [OperatorTally {
+            name: "jiggle",
+            produced: 4,
+            selected: 1,
+        }];
+        record_generation(
+            &mut sink,
+            7,
+            0,
+            &[0.9, 0.4, 0.2],
+            &tallies,
+            Duration::from_millis(300),
+        )
+        .unwrap();
+        drop(sink);
+
+        let file = std::fs::read_dir(&directory)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        assert!(file
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("events.out.tfevents."));
+        let bytes = std::fs::read(&file).unwrap();
+        let mut records = Vec::new();
+        let mut rest = bytes.as_slice();
+        while !rest.is_empty() {
+            let length = u64::from_le_bytes(rest[..8].try_into().unwrap()) as usize;
+            assert_eq!(
+                u32::from_le_bytes(rest[8..12].try_into().unwrap()),
+                masked_crc32c(&rest[..8])
+            );
+            let data = &rest[12..12 + length];
+            assert_eq!(
+                u32::from_le_bytes(rest[12 + length..16 + length].try_into().unwrap()),
+                masked_crc32c(data)
+            );
+            records.push(data.to_vec());
+            rest = &rest[16 + length..];
+        }
+        // the version, the histogram, three scalars and the operator
+        assert_eq!(records.len(), 6);
+        let contains = |record: &[u8], text: &str| {
+            record
+                .windows(text.len())
+                .any(|window| window == text.as_bytes())
+        };
+        assert!(contains(&records[0], "brain.Event:2"));
+        assert!(contains(&records[1], "island_0/scores"));
+        assert!(contains(&records[5], "island_0/operators/jiggle"));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,278 @@
+use crate::base_ai::AI;
+use crate::distill::{DistillationConfig, TeacherSample};
+use crate::sim_for_ai::{
+    observation_size, ACTION_SIZE, FRAME_OBJECT_SLOTS, OBSERVATION_FRAMES, OBSERVATION_SIZE,
+};
+use burn::module::Module;
+use burn::nn::Linear;
+use burn::prelude::Backend;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+use burn::tensor::{Distribution, Tensor};
+use rand::rngs::StdRng;
+use std::marker::PhantomData;
+
+/// Where the ball and basket slots start in a single arm observation.
+const SLOTS_START: usize = ACTION_SIZE * 4 * OBSERVATION_FRAMES;
+/// Where the ball velocity starts, after the ball and basket slots of every frame.
+const VELOCITY_START: usize = SLOTS_START + FRAME_OBJECT_SLOTS * OBSERVATION_FRAMES;
+/// Where the joint velocities start, followed by the fingertip contacts.
+const JOINT_VELOCITIES_START: usize = VELOCITY_START + 2;
+/// Where the action feedback starts, if there is any.
+const FEEDBACK_START: usize = observation_size(ACTION_SIZE, OBSERVATION_FRAMES);
+
+/// Reflects a single arm observation across the vertical axis: normalized x coordinates become
+/// `1 - x`, and the x distances to the basket, the ball's x velocity, the joint velocities and
+/// the fed back forces change sign.
+pub fn mirror_observation(observation: &[f32]) -> Vec<f32> {
+    observation
+        .iter()
+        .enumerate()
+        .map(|(i, value)| match i {
+            i if i < SLOTS_START && i % 2 == 0 => 1. - value,
+            // ball x, in every frame
+            i if (SLOTS_START..VELOCITY_START).contains(&i)
+                && (i - SLOTS_START).is_multiple_of(FRAME_OBJECT_SLOTS) =>
+            {
+                1. - value
+            }
+            // distance to basket x, in every frame
+            i if (SLOTS_START..VELOCITY_START).contains(&i)
+                && (i - SLOTS_START) % FRAME_OBJECT_SLOTS == 2 =>
+            {
+                -value
+            }
+            // ball velocity x
+            i if i == VELOCITY_START => -value,
+            i if (JOINT_VELOCITIES_START..JOINT_VELOCITIES_START + ACTION_SIZE).contains(&i) => {
+                -value
+            }
+            i if i >= FEEDBACK_START => -value,
+            _ => *value,
+        })
+        .collect()
+}
+
+/// Reflects a single arm's forces: every joint turns the other way, while a grip intent, if the
+/// network sends one last, closes the hand the same on both sides.
+fn mirror_action(action: &[f32], grips: bool) -> Vec<f32> {
+    let forces = action.len() - usize::from(grips);
+    action
+        .iter()
+        .enumerate()
+        .map(|(i, value)| if i < forces { -value } else { *value })
+        .collect()
+}
+
+/// Drives two mirror image arms with one network: the input is the observation of the first
+/// arm followed by that of the second, the output the forces of the first followed by the
+/// second's. The second arm sees its observation mirrored and its forces are mirrored back,
+/// turning the other way, so a bimanual task evolves only one arm's parameters.
+///
+/// There is no dual-arm world yet; this is the controller side for when there is one.
+#[derive(Module, Debug)]
+pub struct MirroredAI<B: Backend, A: Module<B>> {
+    inner: A,
+    backend: PhantomData<B>,
+}
+
+impl<B: Backend, A: AI<B>> MirroredAI<B, A> {
+    pub fn new(inner: A) -> Self {
+        Self {
+            inner,
+            backend: PhantomData,
+        }
+    }
+
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+}
+
+impl<B: Backend, A: AI<B>> AI<B> for MirroredAI<B, A> {
+    fn jiggle(&self, d: &Distribution, rng: &mut StdRng) -> Self {
+        Self::new(self.inner.jiggle(d, rng))
+    }
+
+    fn apply(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
+        let device = input.device();
+        let first = input.clone().narrow(0, 0, OBSERVATION_SIZE);
+        let second: Vec<f32> = input
+            .narrow(0, OBSERVATION_SIZE, OBSERVATION_SIZE)
+            .to_data()
+            .convert::<f32>()
+            .to_vec()
+            .expect("second arm observation not available");
+        let second = Tensor::<B, 1>::from_floats(mirror_observation(&second).as_slice(), &device);
+
+        let forces = self.inner.apply(first);
+        let mirrored_forces = self.inner.apply(second);
+        let mirrored_forces = if self.inner.grips() {
+            let joints = mirrored_forces.dims()[0] - 1;
+            let grip = mirrored_forces.clone().narrow(0, joints, 1);
+            Tensor::cat(vec![mirrored_forces.narrow(0, 0, joints).neg(), grip], 0)
+        } else {
+            mirrored_forces.neg()
+        };
+        Tensor::cat(vec![forces, mirrored_forces], 0)
+    }
+
+    fn reset_state(&self) {
+        self.inner.reset_state();
+    }
+
+    fn max_amp(&self) -> f32 {
+        self.inner.max_amp()
+    }
+
+    /// The inner network sees both arms, the second one mirrored, so it learns from both.
+    fn with_observations(&self, observations: &[Vec<f32>]) -> Self {
+        let halves: Vec<Vec<f32>> = observations
+            .iter()
+            .flat_map(|observation| {
+                let (first, second) = observation.split_at(OBSERVATION_SIZE);
+                [first.to_vec(), mirror_observation(second)]
+            })
+            .collect();
+        Self::new(self.inner.with_observations(&halves))
+    }
+
+    fn train_auxiliary(&self, device: &B::Device, rng: &mut StdRng) -> Self {
+        Self::new(self.inner.train_auxiliary(device, rng))
+    }
+
+    /// The teacher drives a single arm, so the inner network imitates it together with its
+    /// mirror image.
+    fn imitate(
+        &self,
+        samples: &[TeacherSample],
+        config: &DistillationConfig,
+        device: &B::Device,
+        rng: &mut StdRng,
+    ) -> Option<Self> {
+        let grips = self.inner.grips();
+        let samples: Vec<TeacherSample> = samples
+            .iter()
+            .flat_map(|sample| {
+                [
+                    sample.clone(),
+                    TeacherSample {
+                        observation: mirror_observation(&sample.observation),
+                        action: mirror_action(&sample.action, grips),
+                    },
+                ]
+            })
+            .collect();
+        Some(Self::new(
+            self.inner.imitate(&samples, config, device, rng)?,
+        ))
+    }
+
+    fn save_file(&self, filename: &str, recorder: &NamedMpkFileRecorder<FullPrecisionSettings>) {
+        AI::save_file(&self.inner, filename, recorder);
+    }
+
+    fn load_a_file(
+        self,
+        filename: &str,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> Self {
+        Self::new(self.inner.load_a_file(filename, recorder))
+    }
+
+    fn network_name(&self) -> &'static str {
+        self.inner.network_name()
+    }
+
+    fn layers(&self) -> Vec<(&'static str, Linear<B>)> {
+        self.inner.layers()
+    }
+
+    fn activations(&self) -> Vec<&'static str> {
+        self.inner.activations()
+    }
+
+    fn layer_inputs(&self) -> Vec<Vec<Option<usize>>> {
+        self.inner.layer_inputs()
+    }
+
+    fn force_layers(&self) -> Vec<usize> {
+        self.inner.force_layers()
+    }
+
+    /// Both arms' observations, each as wide as the inner network reads.
+    fn input_size(&self) -> usize {
+        2 * self.inner.input_size()
+    }
+
+    /// Both arms' forces, each as many as the inner network sends.
+    fn output_size(&self) -> usize {
+        2 * self.inner.output_size()
+    }
+
+    /// Each arm's grip intent comes last of its forces; `split_grip` sees the second arm's.
+    fn grips(&self) -> bool {
+        self.inner.grips()
+    }
+
+    fn hidden_sizes(&self) -> Vec<usize> {
+        self.inner.hidden_sizes()
+    }
+
+    fn frozen_layers(&self) -> Vec<bool> {
+        self.inner.frozen_layers()
+    }
+
+    fn with_layers(&self, layers: Vec<Linear<B>>) -> Self {
+        Self::new(self.inner.with_layers(layers))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grip_ai::GripAI;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+
+    #[test]
+    fn test_mirrored_arm_gets_mirrored_forces() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let observation: Vec<f32> = (0..OBSERVATION_SIZE)
+            .map(|i| i as f32 / OBSERVATION_SIZE as f32)
+            .collect();
+        for (twice, once) in mirror_observation(&mirror_observation(&observation))
+            .iter()
+            .zip(&observation)
+        {
+            assert!((twice - once).abs() < 1e-6);
+        }
+
+        let mirrored = MirroredAI::new(SmallAI::<BE>::with_init_std(&device, 0.1));
+        let both = [observation.clone(), mirror_observation(&observation)].concat();
+        let forces: Vec<f32> = mirrored
+            .apply(Tensor::from_floats(both.as_slice(), &device))
+            .to_data()
+            .to_vec()
+            .unwrap();
+        assert_eq!(forces.len(), 2 * ACTION_SIZE);
+        for (first, second) in forces[..ACTION_SIZE].iter().zip(&forces[ACTION_SIZE..]) {
+            assert!((first + second).abs() < 1e-6, "{forces:?}");
+        }
+
+        // both hands close alike
+        let gripping = MirroredAI::new(GripAI::<BE>::with_init_std(&device, 0.1));
+        assert!(gripping.grips());
+        let forces: Vec<f32> = gripping
+            .apply(Tensor::from_floats(both.as_slice(), &device))
+            .to_data()
+            .to_vec()
+            .unwrap();
+        assert_eq!(forces.len(), 2 * (ACTION_SIZE + 1));
+        let (first, second) = forces.split_at(ACTION_SIZE + 1);
+        for (second, mirrored) in second.iter().zip(mirror_action(first, true)) {
+            assert!((second - mirrored).abs() < 1e-6, "{forces:?}");
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use crate::base_ai::AI;
+use crate::distill::{DistillationConfig, TeacherSample};
+use burn::module::{Ignored, Module};
+use burn::nn::Linear;
+use burn::prelude::Backend;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+use burn::tensor::{Distribution, Tensor};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rand_distr::{Distribution as _, StandardNormal};
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard};
+
+/// Actuator noise added to the forces.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ActionNoise {
+    /// Independent noise every step.
+    Gaussian { std: f32 },
+    /// Ornstein-Uhlenbeck process: noise drifting back to zero at rate `theta`, so consecutive
+    /// steps are correlated like a slowly misbehaving motor.
+    OrnsteinUhlenbeck { theta: f32, sigma: f32 },
+}
+
+impl FromStr for ActionNoise {
+    type Err = String;
+
+    /// Reads `gaussian:<std>` or `ou:<theta>:<sigma>`, with none of the parameters negative.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let number = |part: &str| {
+            let value = part
+                .parse::<f32>()
+                .map_err(|e| format!("invalid noise parameter {part}: {e}"))?;
+            match value.is_finite() && value >= 0. {
+                true => Ok(value),
+                false => Err(format!(
+                    "noise parameter {part} is not a finite, non-negative number"
+                )),
+            }
+        };
+        match parts.as_slice() {
+            ["gaussian", std] => Ok(ActionNoise::Gaussian { std: number(std)? }),
+            ["ou", theta, sigma] => Ok(ActionNoise::OrnsteinUhlenbeck {
+                theta: number(theta)?,
+                sigma: number(sigma)?,
+            }),
+            _ => Err(format!("unknown action noise {s}")),
+        }
+    }
+}
+
+/// The current noise of an Ornstein-Uhlenbeck process and the generator drawing it. Like the
+/// recurrent hidden state, clones start from zero.
+#[derive(Debug, Default)]
+pub struct NoiseState {
+    current: Mutex<Vec<f32>>,
+    rng: Mutex<Option<StdRng>>,
+}
+
+impl Clone for NoiseState {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl NoiseState {
+    fn current(&self) -> MutexGuard<'_, Vec<f32>> {
+        self.current.lock().expect("noise state poisoned")
+    }
+
+    fn rng(&self) -> MutexGuard<'_, Option<StdRng>> {
+        self.rng.lock().expect("noise state poisoned")
+    }
+}
+
+/// Wraps a network and perturbs its forces, so evolution favours controllers that stay robust
+/// under actuator noise. Evaluate through the wrapper and visualize the inner network.
+/// Saving and loading only touch the inner network.
+#[derive(Module, Debug)]
+pub struct NoisyAI<B: Backend, A: Module<B>> {
+    inner: A,
+    noise: Ignored<ActionNoise>,
+    /// Seeds the noise of every episode alike, drawn from the system when not given.
+    seed: Ignored<Option<u64>>,
+    state: Ignored<NoiseState>,
+    backend: PhantomData<B>,
+}
+
+impl<B: Backend, A: AI<B>> NoisyAI<B, A> {
+    pub fn new(inner: A, noise: ActionNoise) -> Self {
+        Self {
+            inner,
+            noise: Ignored(noise),
+            seed: Ignored(None),
+            state: Ignored(NoiseState::default()),
+            backend: PhantomData,
+        }
+    }
+
+    /// Noise replayed from the seed in every episode.
+    pub fn seeded(inner: A, noise: ActionNoise, seed: u64) -> Self {
+        Self {
+            seed: Ignored(Some(seed)),
+            ..Self::new(inner, noise)
+        }
+    }
+
+    /// The same noise around another network.
+    fn wrapping(&self, inner: A) -> Self {
+        Self {
+            seed: Ignored(*self.seed),
+            ..Self::new(inner, *self.noise)
+        }
+    }
+
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    fn sample(&self, size: usize) -> Vec<f32> {
+        let mut rng = self.state.rng();
+        let rng = rng.get_or_insert_with(|| match *self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_rng(&mut rand::rng()),
+        });
+        let mut normal = || -> f32 { StandardNormal.sample(rng) };
+        match *self.noise {
+            ActionNoise::Gaussian { std } => (0..size).map(|_| std * normal()).collect(),
+            ActionNoise::OrnsteinUhlenbeck { theta, sigma } => {
+                let mut current = self.state.current();
+                current.resize(size, 0.);
+                for x in current.iter_mut() {
+                    *x += -theta * *x + sigma * normal();
+                }
+                current.clone()
+            }
+        }
+    }
+}
+
+impl<B: Backend, A: AI<B>> AI<B> for NoisyAI<B, A> {
+    fn jiggle(&self, d: &Distribution, rng: &mut StdRng) -> Self {
+        self.wrapping(self.inner.jiggle(d, rng))
+    }
+
+    /// The inner forces plus noise, kept within the tanh range of the clean forces.
+    fn apply(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
+        let forces = self.inner.apply(input);
+        let [size] = forces.dims();
+        let noise = Tensor::<B, 1>::from_floats(self.sample(size).as_slice(), &forces.device());
+        (forces + noise).clamp(-1., 1.)
+    }
+
+    fn reset_state(&self) {
+        self.inner.reset_state();
+        self.state.current().clear();
+        self.state.rng().take();
+    }
+
+    fn max_amp(&self) -> f32 {
+        self.inner.max_amp()
+    }
+
+    fn with_observations(&self, observations: &[Vec<f32>]) -> Self {
+        self.wrapping(self.inner.with_observations(observations))
+    }
+
+    fn train_auxiliary(&self, device: &B::Device, rng: &mut StdRng) -> Self {
+        self.wrapping(self.inner.train_auxiliary(device, rng))
+    }
+
+    fn imitate(
+        &self,
+        samples: &[TeacherSample],
+        config: &DistillationConfig,
+        device: &B::Device,
+        rng: &mut StdRng,
+    ) -> Option<Self> {
+        Some(self.wrapping(self.inner.imitate(samples, config, device, rng)?))
+    }
+
+    fn save_file(&self, filename: &str, recorder: &NamedMpkFileRecorder<FullPrecisionSettings>) {
+        AI::save_file(&self.inner, filename, recorder);
+    }
+
+    fn load_a_file(
+        self,
+        filename: &str,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> Self {
+        let (noise, seed) = (*self.noise, *self.seed);
+        Self {
+            seed: Ignored(seed),
+            ..Self::new(self.inner.load_a_file(filename, recorder), noise)
+        }
+    }
+
+    fn network_name(&self) -> &'static str {
+        self.inner.network_name()
+    }
+
+    fn layers(&self) -> Vec<(&'static str, Linear<B>)> {
+        self.inner.layers()
+    }
+
+    fn activations(&self) -> Vec<&'static str> {
+        self.inner.activations()
+    }
+
+    fn layer_inputs(&self) -> Vec<Vec<Option<usize>>> {
+        self.inner.layer_inputs()
+    }
+
+    fn force_layers(&self) -> Vec<usize> {
+        self.inner.force_layers()
+    }
+
+    fn input_size(&self) -> usize {
+        self.inner.input_size()
+    }
+
+    fn output_size(&self) -> usize {
+        self.inner.output_size()
+    }
+
+    fn grips(&self) -> bool {
+        self.inner.grips()
+    }
+
+    fn hidden_sizes(&self) -> Vec<usize> {
+        self.inner.hidden_sizes()
+    }
+
+    fn frozen_layers(&self) -> Vec<bool> {
+        self.inner.frozen_layers()
+    }
+
+    fn with_layers(&self, layers: Vec<Linear<B>>) -> Self {
+        self.wrapping(self.inner.with_layers(layers))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim_for_ai::OBSERVATION_SIZE;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+
+    #[test]
+    fn test_noise_perturbs_the_forces() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let ai = SmallAI::<BE>::with_init_std(&device, 0.1);
+        let observation = Tensor::<BE, 1>::ones([OBSERVATION_SIZE], &device);
+        let clean: Vec<f32> = ai.apply(observation.clone()).to_data().to_vec().unwrap();
+
+        let silent = NoisyAI::new(ai.clone(), "gaussian:0".parse().unwrap());
+        let forces: Vec<f32> = silent
+            .apply(observation.clone())
+            .to_data()
+            .to_vec()
+            .unwrap();
+        assert_eq!(forces, clean);
+
+        let noisy = NoisyAI::new(ai, "ou:0.15:0.5".parse().unwrap());
+        let forces: Vec<f32> = noisy.apply(observation).to_data().to_vec().unwrap();
+        assert_ne!(forces, clean);
+        assert!(forces.iter().all(|force| force.abs() <= 1.));
+        assert_eq!(noisy.state.current().len(), forces.len());
+        noisy.reset_state();
+        assert!(noisy.state.current().is_empty());
+        assert!("uniform:1".parse::<ActionNoise>().is_err());
+        assert!("gaussian:-0.3".parse::<ActionNoise>().is_err());
+        assert!("ou:0.15:NaN".parse::<ActionNoise>().is_err());
+
+        let seeded = NoisyAI::seeded(
+            SmallAI::<BE>::with_init_std(&device, 0.1),
+            "gaussian:0.3".parse().unwrap(),
+            7,
+        );
+        let episode = |ai: &NoisyAI<BE, SmallAI<BE>>| {
+            ai.reset_state();
+            let observation = Tensor::<BE, 1>::ones([OBSERVATION_SIZE], &device);
+            (0..3)
+                .map(|_| {
+                    ai.apply(observation.clone())
+                        .to_data()
+                        .to_vec::<f32>()
+                        .unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(episode(&seeded), episode(&seeded));
+        assert_eq!(
+            episode(
+                &seeded.with_layers(
+                    seeded
+                        .layers()
+                        .into_iter()
+                        .map(|(_, layer)| layer)
+                        .collect()
+                )
+            ),
+            episode(&seeded)
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Settings of novelty search, given as a `[novelty]` table of the training configuration.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NoveltyConfig {
+    /// How many nearest behaviors the novelty is the mean distance to.
+    pub neighbours: usize,
+    /// Share of the novelty in the ranking, the rest being the score. 1 is pure novelty search.
+    pub weight: f32,
+    /// Novelty a behavior needs to be archived.
+    pub archive_threshold: f32,
+    /// Behaviors kept at most, the oldest are dropped first.
+    pub archive_size: usize,
+}
+
+impl Default for NoveltyConfig {
+    fn default() -> Self {
+        Self {
+            neighbours: 15,
+            weight: 0.5,
+            archive_threshold: 0.1,
+            archive_size: 1000,
+        }
+    }
+}
+
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// Where each value ranks among the others, from 0 for the lowest to 1 for the highest, ties
+/// sharing their mean rank.
+fn ranks(values: &[f32]) -> Vec<f32> {
+    let top = values.len().saturating_sub(1).max(1) as f32;
+    values
+        .iter()
+        .map(|value| {
+            let below = values.iter().filter(|other| *other < value).count();
+            let tied = values.iter().filter(|other| *other == value).count();
+            (below as f32 + (tied - 1) as f32 / 2.) / top
+        })
+        .collect()
+}
+
+/// Behaviors seen so far. Ranking by how far a behavior is from them, instead of by the score
+/// alone, rewards doing something new and so leads away from the local optimum of holding
+/// still that the scoring rewards early on.
+#[derive(Clone, Debug)]
+pub struct NoveltyArchive {
+    config: NoveltyConfig,
+    behaviors: VecDeque<Vec<f32>>,
+}
+
+impl NoveltyArchive {
+    pub fn new(config: NoveltyConfig) -> Self {
+        Self {
+            config,
+            behaviors: VecDeque::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.behaviors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.behaviors.is_empty()
+    }
+
+    /// Every behavior's mean distance to its nearest neighbours among the other behaviors of
+    /// the generation and the archived ones.
+    pub fn novelty(&self, behaviors: &[Vec<f32>]) -> Vec<f32> {
+        behaviors
+            .iter()
+            .enumerate()
+            .map(|(i, behavior)| {
+                let mut distances: Vec<f32> = behaviors
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, other)| other)
+                    .chain(&self.behaviors)
+                    .map(|other| distance(behavior, other))
+                    .collect();
+                distances.sort_by(|a, b| a.partial_cmp(b).expect("distances should be comparable"));
+                let nearest = &distances[..self.config.neighbours.min(distances.len())];
+                nearest.iter().sum::<f32>() / nearest.len().max(1) as f32
+            })
+            .collect()
+    }
+
+    /// The scores blended with the novelty of the behaviors, in the same order, archiving the
+    /// behaviors novel enough. Both are blended by their rank in the generation, so each weighs
+    /// in as configured whatever the spread of the scores and the distances.
+    pub fn blend(&mut self, scores: &[f32], behaviors: &[Vec<f32>]) -> Vec<f32> {
+        let novelty = self.novelty(behaviors);
+        for (behavior, novelty) in behaviors.iter().zip(&novelty) {
+            if *novelty > self.config.archive_threshold {
+                self.behaviors.push_back(behavior.clone());
+            }
+        }
+        while self.behaviors.len() > self.config.archive_size {
+            self.behaviors.pop_front();
+        }
+
+        let weight = self.config.weight;
+        ranks(scores)
+            .into_iter()
+            .zip(ranks(&novelty))
+            .map(|(score, novelty)| (1. - weight) * score + weight * novelty)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_novel_behaviors_rank_higher() {
+        let config = NoveltyConfig {
+            neighbours: 2,
+            weight: 1.,
+            archive_threshold: 0.5,
+            archive_size: 2,
+        };
+        let mut archive = NoveltyArchive::new(config);
+        let behaviors = vec![vec![0., 0.], vec![0.1, 0.], vec![0., 0.1], vec![2., 0.]];
+        let novelty = archive.novelty(&behaviors);
+        assert!(novelty[3] > 1.5 && novelty[0] < 0.2, "{novelty:?}");
+
+        let blended = archive.blend(&[0.9, 0.8, 0.7, 0.1], &behaviors);
+        assert_eq!(blended[3], 1.);
+        assert!(blended[0] < 0.1);
+        // only the outlier was novel enough to be archived
+        assert_eq!(archive.len(), 1);
+        let again = archive.novelty(&[vec![2., 0.], vec![0., 0.]]);
+        assert!(again[0] < again[1], "{again:?}");
+
+        let mut half = NoveltyArchive::new(NoveltyConfig {
+            weight: 0.5,
+            archive_threshold: f32::INFINITY,
+            ..NoveltyConfig::default()
+        });
+        let behaviors = [vec![0.], vec![1.], vec![3.]];
+        let blended = half.blend(&[0.9, 0.5, 0.1], &behaviors);
+        assert_eq!(blended, vec![0.75, 0.25, 0.5]);
+        // neither the spread of the scores nor of the distances changes the blend
+        assert_eq!(half.blend(&[900., 500., 100.], &behaviors), blended);
+        let spread = behaviors.map(|behavior| vec![behavior[0] * 10.]);
+        assert_eq!(half.blend(&[0.9, 0.5, 0.1], &spread), blended);
+        assert_eq!(ranks(&[0.4, 0.4]), vec![0.5, 0.5]);
+        assert_eq!(ranks(&[0.4]), vec![0.]);
+    }
+}
@@ -1,5 +1,7 @@
-pub(crate) mod modelbody;
 pub(crate) mod arm;
+pub(crate) mod modelbody;
+pub mod rope;
+pub mod trajectory;
 pub mod world;
 
-pub type Corners=((f32, f32), (f32, f32));
+pub type Corners = ((f32, f32), (f32, f32));
@@ -1,9 +1,11 @@
-use std::sync::OnceLock;
-use rapier2d::dynamics::{RigidBodySet};
-use rapier2d::na::Point2;
+use rapier2d::dynamics::{ImpulseJointSet, RigidBodyHandle, RigidBodySet};
+use rapier2d::na::{point, Point2, Vector3};
+use rapier2d::prelude::nalgebra;
 use crate::physics::modelbody::{ModelBody, WorldSets};
 use crate::physics::{Corners};
 use crate::physics::modelbody::JoinType::{HorizontalJoin, VerticalJoin};
+use serde::Deserialize;
+use std::path::Path;
 
 // Arm dimensions (half-extents!)
 pub(super) const TRICEP_HALF_WIDTH: f32 = 0.155;
@@ -12,38 +14,130 @@ pub(super) const TRICEP_HALF_HEIGHT: f32 = 0.0375;
 const FOREARM_HALF_WIDTH: f32 = 0.125;
 const FOREARM_HALF_HEIGHT: f32 = 0.03;
 
+const WRIST_HALF_WIDTH: f32 = 0.01;
+const WRIST_HALF_HEIGHT: f32 = 0.02;
+
 const PALM_HALF_WIDTH: f32 = 0.05;
 const PALM_HALF_HEIGHT: f32 = 0.01;
 
 const FINGER_HALF_WIDTH: f32 = 0.0175;
 const FINGER_HALF_HEIGHT: f32 = 0.008;
 
+const KNUCKLE_HALF_WIDTH: f32 = 0.004;
+
 const THUMB_HALF_WIDTH: f32 = FINGER_HALF_HEIGHT;
 const THUMB_HALF_HEIGHT: f32 = FINGER_HALF_WIDTH;
 
 pub(super) const TRICEP_MAX_FORCE:f32 = 0.05;
 
+/// Maps world coordinates into the square the fully stretched arm can sweep, so observations
+/// don't depend on where, or at what scale, the arm was built.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(super) struct Normalization {
+    min_x: f32,
+    x_range: f32,
+    min_y: f32,
+    y_range: f32,
+}
+
+impl Normalization {
+    pub fn normalize(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        ((x - self.min_x) / self.x_range, (y - self.min_y) / self.y_range)
+    }
+
+    /// A difference of points, like a velocity, in the units of `normalize`.
+    pub fn scale(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (x / self.x_range, y / self.y_range)
+    }
+}
+
+/// Actuated joints of an arm without the optional ones.
+pub const BASE_JOINT_COUNT: usize = 8;
+
+/// Morphology options for the arm.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ArmConfig {
+    /// Fraction by which the palm narrows towards the fingers. Zero keeps the capsule palm.
+    pub palm_taper: f32,
+    /// Adds a short knuckle at the index finger root that can swing sideways by at most this
+    /// many radians either way, with its own force channel. `None` keeps the finger rigid there.
+    pub finger_abduction: Option<f32>,
+}
+
+impl ArmConfig {
+    /// Actuated joints of an arm built from the config, one force channel each.
+    pub fn joint_count(&self) -> usize {
+        BASE_JOINT_COUNT + usize::from(self.finger_abduction.is_some())
+    }
+
+    fn palm_outline(&self, scale: f32) -> [Point2<f32>; 4] {
+        let (half_width, half_height) = (PALM_HALF_WIDTH * scale, PALM_HALF_HEIGHT * scale);
+        let far_half_height = half_height * (1. - self.palm_taper.clamp(0., 0.9));
+        [
+            point![-half_width, -half_height],
+            point![half_width, -far_half_height],
+            point![half_width, far_half_height],
+            point![-half_width, half_height],
+        ]
+    }
+}
+
+/// Maps network outputs in [-1, 1] onto the force each channel drives its joint with, given as
+/// multiples of the joint's built-in maximum force, e.g. `{"ranges": [[-0.5, 0.5], [0, 1]]}`.
+/// Channels without a range keep [-1, 1].
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct ActionScaler {
+    #[serde(default)]
+    pub ranges: Vec<(f32, f32)>,
+}
+
+impl ActionScaler {
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        serde_json::from_str(&std::fs::read_to_string(path)?).map_err(std::io::Error::other)
+    }
+
+    /// Sets the range of one channel, leaving the channels before it at [-1, 1] if unset.
+    pub fn with_range(mut self, channel: usize, min: f32, max: f32) -> Self {
+        if self.ranges.len() <= channel {
+            self.ranges.resize(channel + 1, (-1., 1.));
+        }
+        self.ranges[channel] = (min, max);
+        self
+    }
 
-pub(super) static X_RANGE:OnceLock<f32> = OnceLock::new();
-pub(super) static Y_RANGE:OnceLock<f32> = OnceLock::new();
-pub(super) static MIN_X:OnceLock<f32> = OnceLock::new();
-pub(super) static MIN_Y:OnceLock<f32> = OnceLock::new();
+    pub fn scale(&self, channel: usize, output: f32) -> f32 {
+        let (min, max) = self.ranges.get(channel).copied().unwrap_or((-1., 1.));
+        min + (output + 1.) / 2. * (max - min)
+    }
+}
 
+#[derive(Clone)]
 pub(super) struct Arm {
+    shoulder_mb: ModelBody,
     tricep_mb: ModelBody,
     forearm_mb: ModelBody,
+    wrist_mb: ModelBody,
     palm_mb: ModelBody,
+    knuckle_mb: Option<ModelBody>,
     lower_index_finger_mb: ModelBody,
     upper_index_finger_mb: ModelBody,
     lower_thumb_mb: ModelBody,
     upper_thumb_mb: ModelBody,
+    normalization: Normalization,
+    motor_torques: Vec<f32>,
+    applied_forces: Vec<f32>,
 }
 
 impl Arm {
+    /// Builds the arm off the shoulder with every length multiplied by `scale`. Forces grow with
+    /// the cube of the scale: segment masses grow with their area, and gravity with the scale.
     pub fn new(
         world_sets: &mut WorldSets,
         shoulder_body: &ModelBody,
+        config: &ArmConfig,
+        scale: f32,
     ) -> Self {
+        let max_force = TRICEP_MAX_FORCE * scale.powi(3);
         let shoulder_far_side_centre = shoulder_body.get_far_side_centre(&world_sets.rigid_body_set);
 
         // Calculate positions based on wall position and component dimensions
@@ -51,75 +145,221 @@ impl Arm {
         let shoulder_middle_y = shoulder_far_side_centre.y;
         // Tricep
         let tricep_mb = world_sets.create_joined_body_and_collider(
-            &shoulder_body,
+            shoulder_body,
             HorizontalJoin,
-            TRICEP_HALF_WIDTH,
-            TRICEP_HALF_HEIGHT,
-            TRICEP_MAX_FORCE,
+            TRICEP_HALF_WIDTH * scale,
+            TRICEP_HALF_HEIGHT * scale,
+            max_force,
         );
 
         // Forearm
         let forearm_mb = world_sets.create_joined_body_and_collider(&tricep_mb,
                                                                     HorizontalJoin,
-                                                                    FOREARM_HALF_WIDTH,
-                                                                    FOREARM_HALF_HEIGHT,
-                                                                    TRICEP_MAX_FORCE/2.
+                                                                    FOREARM_HALF_WIDTH * scale,
+                                                                    FOREARM_HALF_HEIGHT * scale,
+                                                                    max_force/2.
         );
 
-        // Palm
-        let palm_mb = world_sets.create_joined_body_and_collider(&forearm_mb,
-                                                                 HorizontalJoin,
-                                                                 PALM_HALF_WIDTH,
-                                                                 PALM_HALF_HEIGHT,
-                                                                 TRICEP_MAX_FORCE/25.
+        // Wrist, so the palm can turn independently of the elbow
+        let wrist_mb = world_sets.create_joined_body_and_collider(&forearm_mb,
+                                                                  HorizontalJoin,
+                                                                  WRIST_HALF_WIDTH * scale,
+                                                                  WRIST_HALF_HEIGHT * scale,
+                                                                  max_force/25.
         );
 
+        // Palm
+        let palm_mb = if config.palm_taper > 0. {
+            world_sets.create_joined_convex_polygon(&wrist_mb,
+                                                    HorizontalJoin,
+                                                    &config.palm_outline(scale),
+                                                    max_force/25.
+            ).expect("palm outline is convex")
+        } else {
+            world_sets.create_joined_body_and_collider(&wrist_mb,
+                                                       HorizontalJoin,
+                                                       PALM_HALF_WIDTH * scale,
+                                                       PALM_HALF_HEIGHT * scale,
+                                                       max_force/25.
+            )
+        };
+
+        // Knuckle for the optional sideways finger motion
+        let knuckle_mb = config.finger_abduction.map(|max_angle| {
+            let knuckle_mb = world_sets.create_joined_body_and_collider(&palm_mb,
+                                                                        HorizontalJoin,
+                                                                        KNUCKLE_HALF_WIDTH * scale,
+                                                                        FINGER_HALF_HEIGHT * scale,
+                                                                        max_force/40.
+            );
+            knuckle_mb.limit_joint(&mut world_sets.impulse_joint_set, max_angle.abs());
+            knuckle_mb
+        });
+
         // Lower index finger
-        let lower_index_finger_mb = world_sets.create_joined_body_and_collider(&palm_mb,
+        let lower_index_finger_mb = world_sets.create_joined_body_and_collider(knuckle_mb.as_ref().unwrap_or(&palm_mb),
                                                                                HorizontalJoin,
-                                                                               FINGER_HALF_WIDTH,
-                                                                               FINGER_HALF_HEIGHT,
-                                                                               TRICEP_MAX_FORCE/40.
+                                                                               FINGER_HALF_WIDTH * scale,
+                                                                               FINGER_HALF_HEIGHT * scale,
+                                                                               max_force/40.
         );
 
         // Upper index finger
         let upper_index_finger_mb = world_sets.create_joined_body_and_collider(&lower_index_finger_mb,
                                                                                HorizontalJoin,
-                                                                               FINGER_HALF_WIDTH,
-                                                                               FINGER_HALF_HEIGHT,
-                                                                               TRICEP_MAX_FORCE/50.
+                                                                               FINGER_HALF_WIDTH * scale,
+                                                                               FINGER_HALF_HEIGHT * scale,
+                                                                               max_force/50.
         );
         let farthest_point = upper_index_finger_mb.long_axis_farthest_corner(&world_sets.rigid_body_set);
-        let _set_results = MIN_X.set(shoulder_right_edge - farthest_point.0.0)
-            .and_then(|_| X_RANGE.set(farthest_point.0.0*2.))
-            .and_then(|_| MIN_Y.set(shoulder_middle_y - farthest_point.0.0))
-            .and_then(|_|Y_RANGE.set(farthest_point.0.0*2.));
+        let normalization = Normalization {
+            min_x: shoulder_right_edge - farthest_point.0.0,
+            x_range: farthest_point.0.0*2.,
+            min_y: shoulder_middle_y - farthest_point.0.0,
+            y_range: farthest_point.0.0*2.,
+        };
 
 
         // Lower thumb
         let lower_thumb_mb = world_sets.create_joined_body_and_collider(&palm_mb,
                                                                         VerticalJoin,
-                                                                        THUMB_HALF_WIDTH,
-                                                                        THUMB_HALF_HEIGHT,
-                                                                        TRICEP_MAX_FORCE/40.
+                                                                        THUMB_HALF_WIDTH * scale,
+                                                                        THUMB_HALF_HEIGHT * scale,
+                                                                        max_force/40.
         );
 
         // Upper thumb
         let upper_thumb_mb = world_sets.create_joined_body_and_collider(&lower_thumb_mb,
                                                                         VerticalJoin,
-                                                                        THUMB_HALF_WIDTH,
-                                                                        THUMB_HALF_HEIGHT,
-                                                                        TRICEP_MAX_FORCE/50.
+                                                                        THUMB_HALF_WIDTH * scale,
+                                                                        THUMB_HALF_HEIGHT * scale,
+                                                                        max_force/50.
         );
 
-        Self {
+        let mut arm = Self {
+            shoulder_mb: *shoulder_body,
             tricep_mb,
             forearm_mb,
+            wrist_mb,
             palm_mb,
+            knuckle_mb,
             lower_index_finger_mb,
             upper_index_finger_mb,
             lower_thumb_mb,
             upper_thumb_mb,
+            normalization,
+            motor_torques: Vec::new(),
+            applied_forces: Vec::new(),
+        };
+        arm.motor_torques = vec![0.; arm.joint_count()];
+        arm.applied_forces = vec![0.; arm.joint_count()];
+        arm
+    }
+
+    pub fn normalization(&self) -> &Normalization {
+        &self.normalization
+    }
+
+    /// Every actuated segment paired with the body it hangs off, parents before children,
+    /// in the same order as the force channels.
+    /// The knuckle, when present, comes right after the palm.
+    fn joints(&self) -> Vec<(ModelBody, ModelBody)> {
+        let mut joints = vec![
+            (self.shoulder_mb, self.tricep_mb),
+            (self.tricep_mb, self.forearm_mb),
+            (self.forearm_mb, self.wrist_mb),
+            (self.wrist_mb, self.palm_mb),
+        ];
+        match self.knuckle_mb {
+            Some(knuckle_mb) => {
+                joints.push((self.palm_mb, knuckle_mb));
+                joints.push((knuckle_mb, self.lower_index_finger_mb));
+            }
+            None => joints.push((self.palm_mb, self.lower_index_finger_mb)),
+        }
+        joints.extend([
+            (self.lower_index_finger_mb, self.upper_index_finger_mb),
+            (self.palm_mb, self.lower_thumb_mb),
+            (self.lower_thumb_mb, self.upper_thumb_mb),
+        ]);
+        joints
+    }
+
+    /// The shoulder and every segment hanging off it.
+    pub fn body_handles(&self) -> Vec<RigidBodyHandle> {
+        let mut handles = vec![self.shoulder_mb.body_handle()];
+        handles.extend(self.joints().iter().map(|(_, segment)| segment.body_handle()));
+        handles
+    }
+
+    /// Per force channel, the direction that closes the hand: the index finger bends down
+    /// towards the thumb, the thumb up towards the finger, the rest of the arm is left alone.
+    pub fn grip_synergy(&self) -> Vec<f32> {
+        let index_finger = [Some(self.lower_index_finger_mb), Some(self.upper_index_finger_mb), self.knuckle_mb];
+        let thumb = [self.lower_thumb_mb, self.upper_thumb_mb];
+        self.joints()
+            .iter()
+            .map(|(_, segment)| {
+                if index_finger.iter().flatten().any(|finger| finger.is_same_body(segment)) {
+                    -1.
+                } else if thumb.iter().any(|thumb| thumb.is_same_body(segment)) {
+                    1.
+                } else {
+                    0.
+                }
+            })
+            .collect()
+    }
+
+    pub fn joint_count(&self) -> usize {
+        self.joints().len()
+    }
+
+    /// Places every segment at the given joint angles (radians, relative to the parent segment,
+    /// zero being the fully extended starting pose) and zeroes their velocities.
+    pub fn set_pose(&mut self, rigid_body_set: &mut RigidBodySet, joint_angles: &[f32]) {
+        let joints = self.joints();
+        assert_eq!(joint_angles.len(), joints.len(), "one angle is needed per arm joint");
+        for ((parent, segment), angle) in joints.iter().zip(joint_angles) {
+            segment.place_at_joint_angle(parent, *angle, rigid_body_set);
+        }
+        self.motor_torques.fill(0.);
+        self.applied_forces.fill(0.);
+    }
+
+    pub fn joint_angles(&self, rigid_body_set: &RigidBodySet) -> Vec<f32> {
+        self.joints()
+            .iter()
+            .map(|(parent, segment)| segment.joint_angle(parent, rigid_body_set))
+            .collect()
+    }
+
+    pub fn joint_angular_velocities(&self, rigid_body_set: &RigidBodySet) -> Vec<f32> {
+        self.joints()
+            .iter()
+            .map(|(parent, segment)| segment.joint_angular_velocity(parent, rigid_body_set))
+            .collect()
+    }
+
+    /// The bodies of the index fingertip and the thumb tip, in that order.
+    pub fn fingertip_handles(&self) -> [RigidBodyHandle; 2] {
+        [self.upper_index_finger_mb.body_handle(), self.upper_thumb_mb.body_handle()]
+    }
+
+    pub fn joint_impulses(&self, impulse_joint_set: &ImpulseJointSet) -> Vec<Vector3<f32>> {
+        self.joints()
+            .iter()
+            .map(|(_, segment)| segment.joint_impulse(impulse_joint_set))
+            .collect()
+    }
+
+    /// Turns CCD on or off for the fingertips and for the rest of the segments separately.
+    pub fn set_ccd_enabled(&self, rigid_body_set: &mut RigidBodySet, fingertips: bool, others: bool) {
+        for (_, segment) in self.joints() {
+            segment.set_ccd_enabled(rigid_body_set, others);
+        }
+        for fingertip in [self.upper_index_finger_mb, self.upper_thumb_mb] {
+            fingertip.set_ccd_enabled(rigid_body_set, fingertips);
         }
     }
 
@@ -127,20 +367,21 @@ impl Arm {
         &self,
         rigid_body_set: &RigidBodySet,
     ) -> Vec<[Point2<f32>; 4]> {
-        [
-            self.tricep_mb,
-            self.forearm_mb,
-            self.palm_mb,
-            self.lower_index_finger_mb,
-            self.upper_index_finger_mb,
-            self.lower_thumb_mb,
-            self.upper_thumb_mb,
-        ]
+        self.joints()
             .iter()
+            .map(|(_, segment)| segment)
             .map(|&rb_handle| rb_handle.get_bounding_box(rigid_body_set))
             .collect()
     }
 
+    /// Far corners of every actuated segment, in force channel order.
+    pub fn joint_farthest_corners(&self, rigid_body_set: &RigidBodySet) -> Vec<Corners> {
+        self.joints()
+            .iter()
+            .map(|(_, segment)| segment.long_axis_farthest_corner(rigid_body_set))
+            .collect()
+    }
+
     pub fn tricep_farthest_corners(
         &self,
         rigid_body_set: &RigidBodySet,
@@ -155,6 +396,13 @@ impl Arm {
         self.forearm_mb.long_axis_farthest_corner(rigid_body_set)
     }
 
+    pub fn wrist_farthest_corners(
+        &self,
+        rigid_body_set: &RigidBodySet,
+    ) -> Corners {
+        self.wrist_mb.long_axis_farthest_corner(rigid_body_set)
+    }
+
     pub fn palm_farthest_corners(
         &self,
         rigid_body_set: &RigidBodySet,
@@ -190,83 +438,108 @@ impl Arm {
         self.upper_thumb_mb.long_axis_farthest_corner(rigid_body_set)
     }
 
+    /// Applies the force of the given channel, numbered like `joints`.
+    pub fn apply_joint_force(&mut self, channel: usize, scaling_factor: f32, rigid_body_set: &mut RigidBodySet) {
+        let (parent, segment) = self.joints()[channel];
+        self.drive(parent, segment, rigid_body_set, scaling_factor);
+    }
+
+    /// Pulls `segment` around its joint with `parent` and books the torque on its channel.
+    fn drive(&mut self, parent: ModelBody, segment: ModelBody, rigid_body_set: &mut RigidBodySet, scaling_factor: f32) {
+        let torque = ModelBody::apply_force_between(&parent, &segment, rigid_body_set, scaling_factor);
+        let channel = self.joints()
+            .iter()
+            .position(|(_, driven)| driven.is_same_body(&segment))
+            .expect("only arm segments are driven");
+        self.motor_torques[channel] += torque;
+        self.applied_forces[channel] = scaling_factor;
+    }
+
+    /// Torque each force channel exerts around its joint. Rapier keeps user forces applied until
+    /// they are reset, so this is the sum of everything applied since the arm was last posed.
+    pub fn motor_torques(&self) -> &[f32] {
+        &self.motor_torques
+    }
+
+    /// The scaling factor last applied on each force channel.
+    pub fn applied_forces(&self) -> &[f32] {
+        &self.applied_forces
+    }
+
     pub fn apply_tricep_force(
-        &self,
+        &mut self,
         shoulder: &ModelBody,
         scaling_factor: f32,
         rigid_body_set: &mut RigidBodySet,
     ) {
-        ModelBody::apply_force_between(shoulder, &self.tricep_mb, rigid_body_set, scaling_factor);
+        self.drive(*shoulder, self.tricep_mb, rigid_body_set, scaling_factor);
     }
 
     pub fn apply_forearm_force(
-        &self,
+        &mut self,
         scaling_factor: f32,
         rigid_body_set: &mut RigidBodySet,
     ) {
-        ModelBody::apply_force_between(&self.tricep_mb, &self.forearm_mb, rigid_body_set, scaling_factor);
+        self.drive(self.tricep_mb, self.forearm_mb, rigid_body_set, scaling_factor);
+    }
+
+    pub fn apply_wrist_force(&mut self, scaling_factor: f32, rigid_body_set: &mut RigidBodySet) {
+        self.drive(self.forearm_mb, self.wrist_mb, rigid_body_set, scaling_factor);
     }
 
-    pub fn apply_palm_force(&self, scaling_factor: f32, rigid_body_set: &mut RigidBodySet) {
-        ModelBody::apply_force_between(&self.forearm_mb, &self.palm_mb, rigid_body_set, scaling_factor);
+    pub fn apply_palm_force(&mut self, scaling_factor: f32, rigid_body_set: &mut RigidBodySet) {
+        self.drive(self.wrist_mb, self.palm_mb, rigid_body_set, scaling_factor);
     }
 
     pub fn apply_lower_index_finger_force(
-        &self,
+        &mut self,
         scaling_factor: f32,
         rigid_body_set: &mut RigidBodySet,
     ) {
-        ModelBody::apply_force_between(&self.palm_mb, &self.lower_index_finger_mb, rigid_body_set, scaling_factor);
+        self.drive(self.palm_mb, self.lower_index_finger_mb, rigid_body_set, scaling_factor);
     }
 
     pub fn apply_upper_index_finger_force(
-        &self,
+        &mut self,
         scaling_factor: f32,
         rigid_body_set: &mut RigidBodySet,
     ) {
-        ModelBody::apply_force_between(&self.lower_index_finger_mb, &self.upper_index_finger_mb, rigid_body_set, scaling_factor);
+        self.drive(self.lower_index_finger_mb, self.upper_index_finger_mb, rigid_body_set, scaling_factor);
     }
 
     pub fn apply_lower_thumb_force(
-        &self,
+        &mut self,
         scaling_factor: f32,
         rigid_body_set: &mut RigidBodySet,
     ) {
-        ModelBody::apply_force_between(&self.palm_mb, &self.lower_thumb_mb, rigid_body_set, scaling_factor);
+        self.drive(self.palm_mb, self.lower_thumb_mb, rigid_body_set, scaling_factor);
     }
 
     pub fn apply_upper_thumb_force(
-        &self,
+        &mut self,
         scaling_factor: f32,
         rigid_body_set: &mut RigidBodySet,
     ) {
-        ModelBody::apply_force_between(&self.lower_thumb_mb, &self.upper_thumb_mb, rigid_body_set, scaling_factor);
+        self.drive(self.lower_thumb_mb, self.upper_thumb_mb, rigid_body_set, scaling_factor);
     }
 }
 
-pub fn normalize_x(x_value: f32) -> f32 {
-    (x_value - MIN_X.get().unwrap()) / X_RANGE.get().unwrap()
-}
-
-pub fn normalize_y(y_value: f32) -> f32 {
-    (y_value - MIN_Y.get().unwrap()) / Y_RANGE.get().unwrap()
-}
-
 #[cfg(test)]
 mod tests {
-    use rapier2d::na::distance;
-    use crate::physics::world::Hangman;
+    use rapier2d::na::{center, distance};
+    use crate::physics::world::{GroundShape, Hangman};
     use super::*;
 
     #[test]
     pub fn test_arm() {
         let mut world = WorldSets::default();
-        let hangman = Hangman::new(&mut world);
-        let arm = Arm::new(&mut world, &hangman.shoulder);
+        let hangman = Hangman::new(&mut world, &GroundShape::Flat, 1.);
+        let arm = Arm::new(&mut world, &hangman.shoulder, &ArmConfig::default(), 1.);
         let corners = arm.all_corners(&world.rigid_body_set);
         let expectations = [
             (0,1,TRICEP_HALF_WIDTH*2.),
             (0,1, FOREARM_HALF_WIDTH*2.),
+            (0,1, WRIST_HALF_WIDTH*2.),
             (0,1,PALM_HALF_WIDTH*2.),
             (0,1, FINGER_HALF_WIDTH*2.),
             (0,1, FINGER_HALF_WIDTH*2.),
@@ -277,4 +550,52 @@ mod tests {
             assert!(distance(&corner[expectation.0], &corner[expectation.1])-expectation.2<0.0001);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn test_tapered_palm() {
+        let mut world = WorldSets::default();
+        let hangman = Hangman::new(&mut world, &GroundShape::Flat, 1.);
+        let config = ArmConfig { palm_taper: 0.5, ..ArmConfig::default() };
+        let arm = Arm::new(&mut world, &hangman.shoulder, &config, 1.);
+        let corners = arm.all_corners(&world.rigid_body_set);
+        let palm = corners[3];
+        assert!((distance(&palm[0], &palm[1]) - PALM_HALF_WIDTH * 2.).abs() < 0.0001);
+        assert!((distance(&palm[1], &palm[2]) - PALM_HALF_HEIGHT * 2.).abs() < 0.0001);
+        // the palm still sits flush between the wrist and the index finger
+        assert!((palm[0].x - corners[2][1].x).abs() < 0.0001);
+        assert!((corners[4][0].x - palm[1].x).abs() < 0.0001);
+    }
+
+    #[test]
+    pub fn test_set_pose() {
+        let mut world = WorldSets::default();
+        let hangman = Hangman::new(&mut world, &GroundShape::Flat, 1.);
+        let mut arm = Arm::new(&mut world, &hangman.shoulder, &ArmConfig::default(), 1.);
+        let pose = [-0.6, 0.9, 0.2, 0.3, -0.2, 0.4, 0.1, -0.3];
+        arm.set_pose(&mut world.rigid_body_set, &pose);
+        for (actual, expected) in arm.joint_angles(&world.rigid_body_set).iter().zip(pose) {
+            assert!((actual - expected).abs() < 0.0001);
+        }
+        // the elbow still connects the tricep to the forearm
+        let tricep = arm.tricep_mb.get_bounding_box(&world.rigid_body_set);
+        let forearm = arm.forearm_mb.get_bounding_box(&world.rigid_body_set);
+        let tricep_end = center(&tricep[1], &tricep[2]);
+        let forearm_start = center(&forearm[0], &forearm[3]);
+        assert!(distance(&tricep_end, &forearm_start) < 0.0001);
+    }
+
+    #[test]
+    pub fn test_finger_abduction() {
+        let mut world = WorldSets::default();
+        let hangman = Hangman::new(&mut world, &GroundShape::Flat, 1.);
+        let config = ArmConfig { finger_abduction: Some(0.2), ..ArmConfig::default() };
+        let arm = Arm::new(&mut world, &hangman.shoulder, &config, 1.);
+        assert_eq!(arm.joint_count(), 9);
+        assert_eq!(config.joint_count(), 9);
+        let corners = arm.all_corners(&world.rigid_body_set);
+        // the knuckle sits flush between the palm and the index finger
+        assert!((corners[4][0].x - corners[3][1].x).abs() < 0.0001);
+        assert!((corners[5][0].x - corners[4][1].x).abs() < 0.0001);
+        assert!((distance(&corners[4][0], &corners[4][1]) - KNUCKLE_HALF_WIDTH * 2.).abs() < 0.0001);
+    }
+}
@@ -1,13 +1,14 @@
 use std::ops::{Deref, Index};
-use rapier2d::dynamics::{ImpulseJointSet, MultibodyJointSet, RevoluteJointBuilder, RigidBodyBuilder, RigidBodyHandle, RigidBodySet};
-use rapier2d::geometry::{ColliderBuilder, ColliderSet};
-use rapier2d::na::{distance, point, vector, Isometry2, Point2, Vector2};
+use rapier2d::dynamics::{ImpulseJointHandle, ImpulseJointSet, JointAxis, MultibodyJointSet, RevoluteJointBuilder, RigidBodyBuilder, RigidBodyHandle, RigidBodySet};
+use rapier2d::geometry::{ColliderBuilder, ColliderHandle, ColliderSet, Shape};
+use rapier2d::parry::query::contact;
+use rapier2d::na::{distance, point, vector, Isometry2, Point2, UnitComplex, Vector2, Vector3};
 use rapier2d::prelude::ActiveEvents;
 use rapier2d::prelude::nalgebra;
 use crate::physics::Corners;
 use crate::physics::modelbody::JoinType::*;
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub(super) struct WorldSets {
     pub(super) rigid_body_set: RigidBodySet,
     pub(super) collider_set: ColliderSet,
@@ -15,24 +16,30 @@ pub(super) struct WorldSets {
     pub(super) multibody_joint_set: MultibodyJointSet,
 }
 
-#[derive(Debug)]
-pub(super) struct BodyStateSnapshot {
-    rb: RigidBodyHandle,
-    position: Isometry2<f32>,
-    linear_velocity: Vector2<f32>,
-    angular_velocity: f32,
-}
+/// Contacts deeper than this count as spawning one object inside another; resting contact doesn't.
+const PENETRATION_TOLERANCE: f32 = 1e-4;
 
-impl BodyStateSnapshot {
-    pub(super) fn load(self, body_set: &mut RigidBodySet) {
-        let body = &mut body_set[self.rb];
-        body.set_position(self.position, true);
-        body.set_linvel(self.linear_velocity, true);
-        body.set_angvel(self.angular_velocity, true);
+impl WorldSets {
+    /// The first solid collider that `shape` placed at `position` would sink into, ignoring
+    /// the colliders attached to the `ignored` bodies.
+    pub(super) fn penetrated_collider(&self,
+                                      shape: &dyn Shape,
+                                      position: &Isometry2<f32>,
+                                      ignored: &[ModelBody],
+    ) -> Option<ColliderHandle> {
+        self.collider_set
+            .iter()
+            .filter(|(_, collider)| !collider.is_sensor())
+            .filter(|(_, collider)| collider.parent().is_none_or(|parent| ignored.iter().all(|mb| mb.rb != parent)))
+            .find(|(_, collider)| {
+                contact(position, shape, collider.position(), collider.shape(), 0.)
+                    .ok()
+                    .flatten()
+                    .is_some_and(|c| c.dist < -PENETRATION_TOLERANCE)
+            })
+            .map(|(handle, _)| handle)
     }
-}
 
-impl WorldSets {
     pub(super) fn create_joined_body_and_collider(&mut self,
                                        root: &ModelBody,
                                        join: JoinType,
@@ -51,6 +58,22 @@ impl WorldSets {
         )
     }
 
+    pub(super) fn create_joined_convex_polygon(&mut self,
+                                               root: &ModelBody,
+                                               join: JoinType,
+                                               points: &[Point2<f32>],
+                                               max_force_scale: f32,
+    ) -> Option<ModelBody> {
+        root.create_joined_convex_polygon(
+            join,
+            &mut self.rigid_body_set,
+            &mut self.collider_set,
+            points,
+            &mut self.impulse_joint_set,
+            max_force_scale
+        )
+    }
+
     pub(super) fn create_dynamic_with_cb(&mut self,
                                          centre_x: f32,
                                          centre_y: f32,
@@ -66,6 +89,7 @@ impl WorldSets {
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn create_body_with_builders(&mut self,
                                  centre_x: f32,
                                  centre_y: f32,
@@ -109,12 +133,12 @@ impl SingleForcePoint {
     pub fn transform(&self, tr:&Isometry2<f32>) -> Self {
         Self {
             on_body: self.tr_on_body(tr),
-            around_joint: tr * &self.around_joint
+            around_joint: tr * self.around_joint
         }
     }
 
     pub fn tr_on_body(&self, tr:&Isometry2<f32>) -> Point2<f32> {
-        tr * &self.on_body
+        tr * self.on_body
     }
 }
 
@@ -223,6 +247,28 @@ fn target_point(from: Point2<f32>, towards: Point2<f32>) -> Point2<f32> {
 
 impl BoundingBox {
 
+    /// The axis aligned box around an arbitrary outline, in the usual corner order.
+    fn enclosing(points: &[Point2<f32>]) -> Self {
+        let min_x = points.iter().map(|p| p.x).fold(f32::INFINITY, f32::min);
+        let max_x = points.iter().map(|p| p.x).fold(f32::NEG_INFINITY, f32::max);
+        let min_y = points.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+        let max_y = points.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max);
+        [
+            point!(min_x, max_y),
+            point!(max_x, max_y),
+            point!(max_x, min_y),
+            point!(min_x, min_y),
+        ].into()
+    }
+
+    fn long_axis_join(&self) -> JoinType {
+        if distance(&self[0], &self[1]) >= distance(&self[1], &self[2]) {
+            HorizontalJoin
+        } else {
+            VerticalJoin
+        }
+    }
+
     fn new_force_point(&self, mid_start: i8, mid_end: i8, targ_start: i8, targ_end: i8) -> SingleForcePoint {
         SingleForcePoint {
             on_body: fractional_point_on_line(self[mid_start], self[mid_end], 0.2),
@@ -294,15 +340,52 @@ pub struct ModelBody {
     bounding_box: BoundingBox,
     force_points: ForcePoints,
     join_type: Option<JoinType>,
+    joint: Option<Joint>,
     max_force_scale: f32,
 }
 
+/// The revolute joint pinning a body to the body before it, with the anchors in the local
+/// frames of both bodies.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Joint {
+    handle: ImpulseJointHandle,
+    on_parent: Point2<f32>,
+    on_self: Point2<f32>,
+}
+
 impl ModelBody {
 
     pub fn  current_centre(&self, rigid_body_set: &RigidBodySet) -> Point2<f32> {
         rigid_body_set[self.rb].position().translation.vector.into()
     }
 
+    /// The first collider outside `ignored` that this body is sunk into.
+    pub(super) fn penetrated_collider(&self, world_sets: &WorldSets, ignored: &[ModelBody]) -> Option<ColliderHandle> {
+        let mut ignored = ignored.to_vec();
+        ignored.push(*self);
+        world_sets.rigid_body_set[self.rb]
+            .colliders()
+            .iter()
+            .map(|handle| &world_sets.collider_set[*handle])
+            .find_map(|collider| world_sets.penetrated_collider(collider.shape(), collider.position(), &ignored))
+    }
+
+    pub fn current_pose(&self, rigid_body_set: &RigidBodySet) -> Isometry2<f32> {
+        *rigid_body_set[self.rb].position()
+    }
+
+    pub fn set_ccd_enabled(&self, rigid_body_set: &mut RigidBodySet, enabled: bool) {
+        rigid_body_set[self.rb].enable_ccd(enabled);
+    }
+
+    pub fn set_linear_velocity(&self, rigid_body_set: &mut RigidBodySet, velocity: Vector2<f32>) {
+        rigid_body_set[self.rb].set_linvel(velocity, true);
+    }
+
+    pub fn linear_velocity(&self, rigid_body_set: &RigidBodySet) -> Vector2<f32> {
+        *rigid_body_set[self.rb].linvel()
+    }
+
     pub fn get_bounding_box(&self, rigid_body_set: &RigidBodySet) -> [Point2<f32>; 4] {
         let body_transform = &rigid_body_set[self.rb].position();
         self.bounding_box.0.iter().map(|p| *body_transform * *p).collect::<Vec<_>>().try_into().unwrap()
@@ -313,6 +396,7 @@ impl ModelBody {
         body_transform*point!(self.bounding_box[1].x, (self.bounding_box[1].y+self.bounding_box[2].y)/2.)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn create_body_with_builders(body_set: &mut RigidBodySet,
                                  centre_x: f32,
                                  centre_y: f32,
@@ -322,6 +406,25 @@ impl ModelBody {
                                  height: f32,
                                  cb: ColliderBuilder,
                                  max_force_scale: f32,
+    ) -> Self {
+        let bounding_box:BoundingBox = [
+            point!(-width, height),
+            point!(width, height),
+            point!(width, -height),
+            point!(-width, -height),
+        ].into();
+        Self::create_body_with_bounding_box(body_set, centre_x, centre_y, rbb, collider_set, bounding_box, cb, max_force_scale)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_body_with_bounding_box(body_set: &mut RigidBodySet,
+                                     centre_x: f32,
+                                     centre_y: f32,
+                                     rbb: RigidBodyBuilder,
+                                     collider_set: &mut ColliderSet,
+                                     bounding_box: BoundingBox,
+                                     cb: ColliderBuilder,
+                                     max_force_scale: f32,
     ) -> Self {
         let body_handle =body_set.insert(rbb.translation(vector![centre_x, centre_y]).angular_damping(2.).build());
         let collider_handle = cb
@@ -330,22 +433,24 @@ impl ModelBody {
             .active_events(ActiveEvents::COLLISION_EVENTS)
             .build();
         collider_set.insert_with_parent(collider_handle, body_handle, body_set);
-        let bounding_box:BoundingBox = [
-            point!(-width, height),
-            point!(width, height),
-            point!(width, -height),
-            point!(-width, -height),
-        ].into();
         Self {
             rb: body_handle,
             force_points: bounding_box.force_points(),
             starting_centre: point![centre_x, centre_y],
             join_type: None,
+            joint: None,
             bounding_box,
             max_force_scale
         }
     }
 
+    fn dynamic_builder() -> RigidBodyBuilder {
+        RigidBodyBuilder::dynamic()
+            .can_sleep(false)
+            .ccd_enabled(true)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn create_dynamic_and_collider(
         body_set: &mut RigidBodySet,
         centre_x: f32,
@@ -356,9 +461,7 @@ impl ModelBody {
         cb: ColliderBuilder,
         max_force_scale: f32,
     ) -> Self {
-        Self::create_body_with_builders(body_set, centre_x, centre_y, RigidBodyBuilder::dynamic()
-            .can_sleep(false)
-            .ccd_enabled(true), collider_set, width, height, cb, max_force_scale)
+        Self::create_body_with_builders(body_set, centre_x, centre_y, Self::dynamic_builder(), collider_set, width, height, cb, max_force_scale)
     }
 
     fn create_body_and_collider(
@@ -381,6 +484,7 @@ impl ModelBody {
         result
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn create_joined_body_and_collider(
         &self,
         join: JoinType,
@@ -397,34 +501,137 @@ impl ModelBody {
         } else {
             (own_centre.x, own_bb[2].y-height)
         };
-        let follower = Self::create_body_and_collider(body_set, centre_x, centre_y, collider_set, width, height, max_force_scale);
-        if join == HorizontalJoin {
+        let mut follower = Self::create_body_and_collider(body_set, centre_x, centre_y, collider_set, width, height, max_force_scale);
+        follower.joint = Some(if join == HorizontalJoin {
             self.join_horizontal_rigid_bodies(&follower, impulse_joint_set)
         } else {
             self.join_vertical_rigid_bodies(&follower, impulse_joint_set)
-        }
+        });
         follower
     }
 
+    /// Creates a dynamic body with the convex hull of `points` (relative to its centre) as
+    /// collider. Force points are derived from the axis aligned box enclosing the hull.
+    fn create_convex_polygon(
+        body_set: &mut RigidBodySet,
+        centre_x: f32,
+        centre_y: f32,
+        collider_set: &mut ColliderSet,
+        points: &[Point2<f32>],
+        max_force_scale: f32,
+    ) -> Option<Self> {
+        if points.len() < 3 {
+            return None;
+        }
+        let cb = ColliderBuilder::convex_hull(points)?;
+        let bounding_box = BoundingBox::enclosing(points);
+        let mut result = Self::create_body_with_bounding_box(
+            body_set, centre_x, centre_y, Self::dynamic_builder(), collider_set, bounding_box, cb, max_force_scale
+        );
+        result.join_type = Some(bounding_box.long_axis_join());
+        Some(result)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_joined_convex_polygon(
+        &self,
+        join: JoinType,
+        body_set: &mut RigidBodySet,
+        collider_set: &mut ColliderSet,
+        points: &[Point2<f32>],
+        impulse_joint_set: &mut ImpulseJointSet,
+        max_force_scale: f32,
+    ) -> Option<Self> {
+        let own_bb = self.get_bounding_box(body_set);
+        let own_centre = self.current_centre(body_set);
+        let follower_bb = BoundingBox::enclosing(points);
+        let (centre_x, centre_y) = if join == HorizontalJoin {
+            (own_bb[1].x - follower_bb[0].x, own_centre.y)
+        } else {
+            (own_centre.x, own_bb[2].y - follower_bb[1].y)
+        };
+        let mut follower = Self::create_convex_polygon(body_set, centre_x, centre_y, collider_set, points, max_force_scale)?;
+        follower.joint = Some(if join == HorizontalJoin {
+            self.join_horizontal_rigid_bodies(&follower, impulse_joint_set)
+        } else {
+            self.join_vertical_rigid_bodies(&follower, impulse_joint_set)
+        });
+        Some(follower)
+    }
+
     fn join_horizontal_rigid_bodies(
         &self,
         other: &Self,
         joint_set: &mut ImpulseJointSet,
-    ) {
+    ) -> Joint {
         self.join_with_anchors(other, joint_set, point![self.bounding_box[1].x, 0.0], point![other.bounding_box[0].x, 0.0])
     }
 
-    fn join_vertical_rigid_bodies(&self, other: &Self, joint_set:&mut ImpulseJointSet) {
+    fn join_vertical_rigid_bodies(&self, other: &Self, joint_set:&mut ImpulseJointSet) -> Joint {
         self.join_with_anchors(other, joint_set, point![0.0, self.bounding_box[2].y], point![0.0, other.bounding_box[1].y])
     }
 
-    fn join_with_anchors(&self, other:&Self, joint_set: &mut ImpulseJointSet, self_anchor:Point2<f32>, other_anchor:Point2<f32>) {
+    fn join_with_anchors(&self, other:&Self, joint_set: &mut ImpulseJointSet, self_anchor:Point2<f32>, other_anchor:Point2<f32>) -> Joint {
         let joint = RevoluteJointBuilder::new()
             .local_anchor1(self_anchor)
             .local_anchor2(other_anchor)
             .build();
 
-        joint_set.insert(self.rb, other.rb, joint, true);
+        let handle = joint_set.insert(self.rb, other.rb, joint, true);
+        Joint {
+            handle,
+            on_parent: self_anchor,
+            on_self: other_anchor,
+        }
+    }
+
+    /// Restricts the joint to this body's parent to turn at most `max_angle` radians either way.
+    pub(super) fn limit_joint(&self, impulse_joint_set: &mut ImpulseJointSet, max_angle: f32) {
+        let joint = self.joint.expect("only joined bodies can be limited");
+        impulse_joint_set
+            .get_mut(joint.handle, true)
+            .expect("joint was removed from the world")
+            .data
+            .set_limits(JointAxis::AngX, [-max_angle, max_angle]);
+    }
+
+    /// Teleports this joined body so that it is rotated by `angle` relative to `parent` with
+    /// the joint anchors coinciding, and brings it to rest.
+    pub(super) fn place_at_joint_angle(&self, parent: &Self, angle: f32, rigid_body_set: &mut RigidBodySet) {
+        let joint = self.joint.expect("only joined bodies can be posed");
+        let parent_pose = parent.current_pose(rigid_body_set);
+        let rotation = parent_pose.rotation * UnitComplex::new(angle);
+        let joint_location = parent_pose * joint.on_parent;
+        let translation = joint_location.coords - rotation * joint.on_self.coords;
+        let body = &mut rigid_body_set[self.rb];
+        body.set_position(Isometry2::from_parts(translation.into(), rotation), true);
+        body.set_linvel(Vector2::zeros(), true);
+        body.set_angvel(0., true);
+        body.reset_forces(true);
+        body.reset_torques(true);
+    }
+
+    /// Impulse the solver applied through the joint to the parent in the last step, in the
+    /// joint frame: linear x and y, then angular.
+    pub(super) fn joint_impulse(&self, impulse_joint_set: &ImpulseJointSet) -> Vector3<f32> {
+        let joint = self.joint.expect("only joined bodies have joint impulses");
+        impulse_joint_set
+            .get(joint.handle)
+            .map(|joint| joint.impulses)
+            .expect("joint was removed from the world")
+    }
+
+    /// Rotation of this body relative to `parent`, in the same sense as `place_at_joint_angle`.
+    pub(super) fn joint_angle(&self, parent: &Self, rigid_body_set: &RigidBodySet) -> f32 {
+        let parent_rotation = parent.current_pose(rigid_body_set).rotation;
+        let own_rotation = self.current_pose(rigid_body_set).rotation;
+        parent_rotation.angle_to(&own_rotation)
+    }
+
+    /// How fast this body turns relative to `parent`, in radians per second, in the sense of
+    /// `joint_angle`.
+    pub(super) fn joint_angular_velocity(&self, parent: &Self, rigid_body_set: &RigidBodySet) -> f32 {
+        rigid_body_set[self.rb].angvel() - rigid_body_set[parent.rb].angvel()
     }
 
     pub(super) fn long_axis_farthest_corner(&self, rigid_body_set: &RigidBodySet) -> Corners {
@@ -440,7 +647,8 @@ impl ModelBody {
         body_rel.transform(rigid_body_set[self.rb].position())
     }
 
-    fn apply_force<T,B>(&self, rigid_body_set: &mut RigidBodySet, force: AdjustedForce, top_provider: T, bottom_provider: B)
+    /// Returns the applied force and the world point it acts on.
+    fn apply_force<T,B>(&self, rigid_body_set: &mut RigidBodySet, force: AdjustedForce, top_provider: T, bottom_provider: B) -> (Vector2<f32>, Point2<f32>)
     where T: Fn(&Self) -> SingleForcePoint,
           B: Fn(&Self) -> SingleForcePoint,
     {
@@ -453,37 +661,44 @@ impl ModelBody {
         let force_vector = force_point.scaled_force_vector(force);
         // println!("force vector: {:?}", force_vector);
         rigid_body_set[self.rb].add_force_at_point(force_vector, force_point.on_body, true);
+        (force_vector, force_point.on_body)
     }
 
 
-    fn apply_forward_force(&self, rigid_body_set: &mut RigidBodySet, force: AdjustedForce) {
-        self.apply_force(rigid_body_set, force, |s| s.force_points.top_forward, |s| s.force_points.bottom_forward);
+    fn apply_forward_force(&self, rigid_body_set: &mut RigidBodySet, force: AdjustedForce) -> (Vector2<f32>, Point2<f32>) {
+        self.apply_force(rigid_body_set, force, |s| s.force_points.top_forward, |s| s.force_points.bottom_forward)
     }
 
-    fn apply_backward_force(&self, rigid_body_set: &mut RigidBodySet, force: AdjustedForce) {
-        self.apply_force(rigid_body_set, force, |s| s.force_points.top_backward, |s| s.force_points.bottom_backward);
+    fn apply_backward_force(&self, rigid_body_set: &mut RigidBodySet, force: AdjustedForce) -> (Vector2<f32>, Point2<f32>) {
+        self.apply_force(rigid_body_set, force, |s| s.force_points.top_backward, |s| s.force_points.bottom_backward)
     }
 
 
-    pub(super) fn apply_force_between(forward:&Self, backward:&Self, rigid_body_set: &mut RigidBodySet, scale: f32) {
+    /// Returns the torque the pull exerts on `backward` around its joint, counter-clockwise positive.
+    pub(super) fn apply_force_between(forward:&Self, backward:&Self, rigid_body_set: &mut RigidBodySet, scale: f32) -> f32 {
         let force_scale = ForceScale::between(forward, backward, scale, rigid_body_set);
         // println!("force scale: {:?}", force_scale);
         forward.apply_forward_force(rigid_body_set, force_scale);
-        backward.apply_backward_force(rigid_body_set, force_scale);
-    }
-
-    pub fn snapshot(&self, rigid_body_set: &RigidBodySet) -> BodyStateSnapshot {
-        let body = &rigid_body_set[self.rb];
-        let position = body.position().clone();
-        let linear_velocity = body.linvel().clone();
-        let angular_velocity = body.angvel();
-        BodyStateSnapshot {
-            position,
-            linear_velocity,
-            angular_velocity,
-            rb: self.rb,
+        let (force, point) = backward.apply_backward_force(rigid_body_set, force_scale);
+        let lever = point - backward.joint_location(rigid_body_set);
+        lever.perp(&force)
+    }
+
+    /// Where this body is pinned to its parent, or its centre if it is not joined.
+    fn joint_location(&self, rigid_body_set: &RigidBodySet) -> Point2<f32> {
+        match self.joint {
+            Some(joint) => self.current_pose(rigid_body_set) * joint.on_self,
+            None => self.current_centre(rigid_body_set),
         }
     }
+
+    pub(super) fn body_handle(&self) -> RigidBodyHandle {
+        self.rb
+    }
+
+    pub(super) fn is_same_body(&self, other: &Self) -> bool {
+        self.rb == other.rb
+    }
 }
 
 
@@ -491,13 +706,13 @@ impl ModelBody {
 mod test {
     use rapier2d::dynamics::{CCDSolver, ImpulseJointSet, IntegrationParameters, IslandManager, MultibodyJointSet, RevoluteJointBuilder, RigidBodyBuilder, RigidBodySet};
     use rapier2d::geometry::{ColliderBuilder, ColliderSet, DefaultBroadPhase, NarrowPhase};
-    use rapier2d::na::{distance, point, vector, Complex, Isometry2, Unit, UnitComplex};
+    use rapier2d::na::{distance, point, vector, Complex, Isometry2, Unit};
     use rapier2d::pipeline::{ActiveEvents, PhysicsPipeline};
-    use crate::physics::modelbody::{BodyStateSnapshot, BoundingBox, ForcePoints, ModelBody, SingleForcePoint, WorldSets};
+    use crate::physics::modelbody::{BoundingBox, ForcePoints, ModelBody, SingleForcePoint, WorldSets};
     use rapier2d::prelude::nalgebra;
     use crate::physics::arm::{TRICEP_HALF_HEIGHT, TRICEP_HALF_WIDTH, TRICEP_MAX_FORCE};
-    use crate::physics::modelbody::JoinType::{HorizontalJoin, VerticalJoin};
-    use crate::physics::world::{Hangman, PhysicsContext, GROUND_HALF_HEIGHT, WALL_HALF_HEIGHT, WALL_HALF_WIDTH};
+    use crate::physics::modelbody::JoinType::HorizontalJoin;
+    use crate::physics::world::{GroundShape, Hangman, PhysicsContext};
 
     #[test]
     fn test_bounding_box_horizontal() {
@@ -604,9 +819,11 @@ mod test {
         );
 
         let mut physics_pipeline = PhysicsPipeline::new();
-        let mut integration_params = IntegrationParameters::default();
-        integration_params.dt = 1.0 / 240.0;
-        integration_params.max_ccd_substeps = 4;
+        let integration_params = IntegrationParameters {
+            dt: 1.0 / 240.0,
+            max_ccd_substeps: 4,
+            ..IntegrationParameters::default()
+        };
         let mut island_manager = IslandManager::new();
         let mut broad_phase = DefaultBroadPhase::new();
         let mut narrow_phase = NarrowPhase::new();
@@ -640,7 +857,7 @@ mod test {
     #[test]
     fn test_crossing_wall() {
         let mut world_sets = WorldSets::default();
-        let hangman = Hangman::new(&mut world_sets);
+        let hangman = Hangman::new(&mut world_sets, &GroundShape::Flat, 1.);
         let body_mb = world_sets.create_joined_body_and_collider(&hangman.shoulder,
             HorizontalJoin,
             TRICEP_HALF_WIDTH,
@@ -649,7 +866,11 @@ mod test {
         );
         let mut context = PhysicsContext::new();
         let mut prev_pos = Vec::new();
-        let mut prev_status = body_mb.snapshot(&world_sets.rigid_body_set);
+        let status = |body_mb: &ModelBody, rigid_body_set: &RigidBodySet| {
+            let body = &rigid_body_set[body_mb.rb];
+            (*body.position(), *body.linvel(), body.angvel())
+        };
+        let mut prev_status = status(&body_mb, &world_sets.rigid_body_set);
         let wall_dims = hangman.wall.get_bounding_box(&world_sets.rigid_body_set);
         let mut iters = 0;
 
@@ -658,7 +879,7 @@ mod test {
             ModelBody::apply_force_between(&hangman.shoulder, &body_mb, &mut world_sets.rigid_body_set, force);
             context.step(&mut world_sets);
             let curr_pos = vec![hangman.wall.get_bounding_box(&world_sets.rigid_body_set), hangman.shoulder.get_bounding_box(&world_sets.rigid_body_set), body_mb.get_bounding_box(&world_sets.rigid_body_set)];
-            let curr_status = body_mb.snapshot(&world_sets.rigid_body_set);
+            let curr_status = status(&body_mb, &world_sets.rigid_body_set);
             let pos = world_sets.rigid_body_set[body_mb.rb].position();
             let up_right = pos * point![body_mb.bounding_box[1].x-TRICEP_HALF_HEIGHT, body_mb.bounding_box[1].y];
             let down_right = pos * point![body_mb.bounding_box[1].x-TRICEP_HALF_HEIGHT, body_mb.bounding_box[2].y];
@@ -677,7 +898,7 @@ mod test {
     #[test]
     fn test_dropping_arm() {
         let mut world_sets = WorldSets::default();
-        let hangman = Hangman::new(&mut world_sets);
+        let hangman = Hangman::new(&mut world_sets, &GroundShape::Flat, 1.);
         let body_mb = world_sets.create_joined_body_and_collider(&hangman.shoulder,
                                                                  HorizontalJoin,
                                                                  TRICEP_HALF_WIDTH,
@@ -758,9 +979,11 @@ mod test {
             body_handle
         };
 
-        let mut integration_parameters = IntegrationParameters::default();
-        integration_parameters.dt = 1.0 / 240.0;
-        integration_parameters.max_ccd_substeps = 4;
+        let integration_parameters = IntegrationParameters {
+            dt: 1.0 / 240.0,
+            max_ccd_substeps: 4,
+            ..IntegrationParameters::default()
+        };
         let mut physics_pipeline = PhysicsPipeline::new();
         let mut island_manager = IslandManager::new();
         let mut broad_phase = DefaultBroadPhase::new();
@@ -778,4 +1001,21 @@ mod test {
         let attachment_top_right = attachment_translation * point![half_width-ball_radius, ball_radius];
         assert!(attachment_top_right.x > wall_far_side_centre.x);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_convex_polygon_bounding_box() {
+        let mut world_sets = WorldSets::default();
+        let hangman = Hangman::new(&mut world_sets, &GroundShape::Flat, 1.);
+        let trapezoid = [point![-0.05, -0.02], point![0.05, -0.01], point![0.05, 0.01], point![-0.05, 0.02]];
+        let pad = world_sets.create_joined_convex_polygon(&hangman.shoulder, HorizontalJoin, &trapezoid, 1.)
+            .expect("trapezoid is convex");
+        let expected: BoundingBox = [point![-0.05, 0.02], point![0.05, 0.02], point![0.05, -0.02], point![-0.05, -0.02]].into();
+        assert_eq!(pad.bounding_box.0, expected.0);
+        assert_eq!(pad.force_points, expected.force_points());
+        assert_eq!(pad.join_type, Some(HorizontalJoin));
+        let shoulder_side = hangman.shoulder.get_far_side_centre(&world_sets.rigid_body_set);
+        let pad_box = pad.get_bounding_box(&world_sets.rigid_body_set);
+        assert!((pad_box[0].x - shoulder_side.x).abs() < 0.0001);
+        assert!(world_sets.create_joined_convex_polygon(&hangman.shoulder, HorizontalJoin, &[point![0., 0.]], 1.).is_none());
+    }
+}
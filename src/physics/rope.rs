@@ -0,0 +1,91 @@
+use crate::physics::modelbody::JoinType::VerticalJoin;
+use crate::physics::modelbody::{ModelBody, WorldSets};
+use rapier2d::dynamics::{RigidBodyBuilder, RigidBodySet};
+use rapier2d::geometry::{ColliderBuilder, ColliderHandle};
+use rapier2d::na::{Isometry2, Point2};
+
+const ANCHOR_RADIUS: f32 = 0.01;
+
+/// A rope hanging from a fixed anchor, made of capsules joined by revolute joints.
+#[derive(Clone, Debug)]
+pub struct RopeConfig {
+    pub anchor_x: f32,
+    pub anchor_y: f32,
+    pub segment_count: usize,
+    pub segment_half_length: f32,
+    pub segment_radius: f32,
+}
+
+impl Default for RopeConfig {
+    fn default() -> Self {
+        Self {
+            anchor_x: 0.45,
+            anchor_y: -0.9,
+            segment_count: 6,
+            segment_half_length: 0.025,
+            segment_radius: 0.006,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct Rope {
+    segments: Vec<ModelBody>,
+}
+
+impl Rope {
+    pub fn new(world_sets: &mut WorldSets, config: &RopeConfig, scale: f32) -> Self {
+        let anchor_radius = ANCHOR_RADIUS * scale;
+        let anchor = world_sets.create_body_with_builders(
+            config.anchor_x * scale,
+            config.anchor_y * scale,
+            RigidBodyBuilder::fixed(),
+            anchor_radius,
+            anchor_radius,
+            ColliderBuilder::ball(anchor_radius).sensor(true),
+            0.,
+        );
+
+        let mut segments: Vec<ModelBody> = Vec::with_capacity(config.segment_count);
+        for _ in 0..config.segment_count {
+            let previous = segments.last().unwrap_or(&anchor);
+            let segment = world_sets.create_joined_body_and_collider(
+                previous,
+                VerticalJoin,
+                config.segment_radius * scale,
+                config.segment_half_length * scale,
+                0.,
+            );
+            segments.push(segment);
+        }
+
+        Self { segments }
+    }
+
+    /// The first collider outside the rope that one of its segments was spawned into.
+    pub fn penetrated_collider(&self, world_sets: &WorldSets) -> Option<ColliderHandle> {
+        self.segments
+            .iter()
+            .find_map(|segment| segment.penetrated_collider(world_sets, &self.segments))
+    }
+
+    pub fn set_ccd_enabled(&self, rigid_body_set: &mut RigidBodySet, enabled: bool) {
+        for segment in &self.segments {
+            segment.set_ccd_enabled(rigid_body_set, enabled);
+        }
+    }
+
+    pub fn segment_poses(&self, rigid_body_set: &RigidBodySet) -> Vec<Isometry2<f32>> {
+        self.segments
+            .iter()
+            .map(|segment| segment.current_pose(rigid_body_set))
+            .collect()
+    }
+
+    pub fn all_corners(&self, rigid_body_set: &RigidBodySet) -> Vec<[Point2<f32>; 4]> {
+        self.segments
+            .iter()
+            .map(|segment| segment.get_bounding_box(rigid_body_set))
+            .collect()
+    }
+}
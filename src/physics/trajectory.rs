@@ -0,0 +1,116 @@
+use crate::physics::world::PhysicsWorld;
+use rapier2d::na::Isometry2;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Where a body is: its centre and its rotation in radians.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Pose {
+    pub x: f32,
+    pub y: f32,
+    pub angle: f32,
+}
+
+impl From<&Isometry2<f32>> for Pose {
+    fn from(isometry: &Isometry2<f32>) -> Self {
+        Self {
+            x: isometry.translation.x,
+            y: isometry.translation.y,
+            angle: isometry.rotation.angle(),
+        }
+    }
+}
+
+/// A physics step of an episode: what the network saw and sent, and where the bodies ended up,
+/// in the order of `PhysicsWorld::body_poses`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TrajectoryStep {
+    pub step: usize,
+    pub observation: Vec<f32>,
+    pub action: Vec<f32>,
+    pub poses: Vec<Pose>,
+}
+
+/// Writes the steps of an episode as JSON lines into a file of its own, for replaying or
+/// plotting a rollout outside the simulation.
+pub struct TrajectoryRecorder {
+    writer: BufWriter<File>,
+    /// The first write that failed, kept for `finish` so recording never stops an episode.
+    error: Option<std::io::Error>,
+}
+
+impl TrajectoryRecorder {
+    /// The file of the episode of the seed played by the named network, named like the file
+    /// the network is saved in so the two are found together, and with the network's
+    /// fingerprint so different networks saved under the same name keep their own.
+    pub fn path(directory: &Path, network: &str, fingerprint: u64, seed: u64) -> PathBuf {
+        directory.join(format!(
+            "trajectory_{network}_{fingerprint:016x}_seed_{seed}.jsonl"
+        ))
+    }
+
+    /// Starts the file of the episode over, creating the directory if needed.
+    pub fn create(
+        directory: &Path,
+        network: &str,
+        fingerprint: u64,
+        seed: u64,
+    ) -> std::io::Result<Self> {
+        std::fs::create_dir_all(directory)?;
+        let file = File::create(Self::path(directory, network, fingerprint, seed))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            error: None,
+        })
+    }
+
+    pub fn record(
+        &mut self,
+        step: usize,
+        observation: &[f32],
+        action: &[f32],
+        world: &PhysicsWorld,
+    ) {
+        if self.error.is_some() {
+            return;
+        }
+        let line = TrajectoryStep {
+            step,
+            observation: observation.to_vec(),
+            action: action.to_vec(),
+            poses: world.body_poses(),
+        };
+        let written = serde_json::to_writer(&mut self.writer, &line)
+            .map_err(std::io::Error::other)
+            .and_then(|_| writeln!(self.writer));
+        self.error = written.err();
+    }
+
+    /// Flushes the file, or returns the error recording it first ran into.
+    pub fn finish(mut self) -> std::io::Result<()> {
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => self.writer.flush(),
+        }
+    }
+}
+
+/// The name the trajectories of the network saved in the file are recorded under, the stem of
+/// the file.
+pub fn trajectory_name(file: &str) -> &str {
+    Path::new(file)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(file)
+}
+
+/// Reads back the steps a `TrajectoryRecorder` wrote.
+pub fn read_trajectory(path: impl AsRef<Path>) -> std::io::Result<Vec<TrajectoryStep>> {
+    let text = std::fs::read_to_string(path)?;
+    text.lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_str(line).map_err(std::io::Error::other))
+        .collect()
+}
@@ -1,14 +1,20 @@
-use rapier2d::dynamics::{CCDSolver, IntegrationParameters, IslandManager, RigidBodyBuilder};
-use rapier2d::geometry::{ColliderBuilder, DefaultBroadPhase, NarrowPhase};
-use rapier2d::na::{vector, Point2, Vector2};
+use rapier2d::dynamics::{CCDSolver, IntegrationParameters, IslandManager, RigidBodyBuilder, RigidBodySet};
+use std::error::Error;
+use std::num::NonZeroUsize;
+use std::fmt::{Display, Formatter};
+use rapier2d::geometry::{Ball, ColliderBuilder, DefaultBroadPhase, NarrowPhase, SharedShape};
+use rapier2d::na::{distance, point, vector, Isometry2, Point2, Vector2, Vector3};
 use rapier2d::pipeline::PhysicsPipeline;
 use rapier2d::prelude::nalgebra;
 use crate::physics::{Corners};
 use crate::physics::arm::{Arm, TRICEP_HALF_HEIGHT, TRICEP_MAX_FORCE};
+pub use crate::physics::arm::{ActionScaler, ArmConfig, BASE_JOINT_COUNT};
 use crate::physics::modelbody::{ModelBody, WorldSets};
+use crate::physics::rope::{Rope, RopeConfig};
+use crate::physics::trajectory::Pose;
 
 // Ground dimensions
-pub(super) const GROUND_HALF_WIDTH: f32 = 10.0;
+pub const GROUND_HALF_WIDTH: f32 = 10.0;
 pub(super) const GROUND_HALF_HEIGHT: f32 = 0.1;
 
 const GROUND_MIDDLE_Y: f32 = -2.0;
@@ -17,41 +23,373 @@ const GROUND_MIDDLE_Y: f32 = -2.0;
 pub(super) const WALL_HALF_WIDTH: f32 = 0.3;
 pub(super) const WALL_HALF_HEIGHT: f32 = 0.6;
 
+/// Shape of the walkable ground surface, relative to the top of the flat ground slab.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum GroundShape {
+    /// The original flat cuboid.
+    #[default]
+    Flat,
+    /// Evenly spaced height offsets spanning the full ground width, from left to right.
+    Heightfield(Vec<f32>),
+    /// A polyline through `(x, height offset)` points, sorted by x.
+    Polyline(Vec<(f32, f32)>),
+}
+
+/// Why a ground shape has no surface.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GroundError {
+    /// A heightfield of fewer than two heights, or a polyline without points.
+    TooFewPoints,
+    /// A polyline whose points are not sorted by x.
+    Unsorted,
+    /// A height or an x that is infinite or NaN.
+    NotFinite,
+}
+
+impl Display for GroundError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroundError::TooFewPoints => write!(f, "the ground has too few points for a surface"),
+            GroundError::Unsorted => write!(f, "the ground polyline is not sorted by x"),
+            GroundError::NotFinite => write!(f, "the ground has a point that is not finite"),
+        }
+    }
+}
+
+impl Error for GroundError {}
+
+impl GroundShape {
+    /// Whether the shape describes a surface, which the other methods expect.
+    pub fn validate(&self) -> Result<(), GroundError> {
+        let points: Vec<(f32, f32)> = match self {
+            GroundShape::Flat => return Ok(()),
+            GroundShape::Heightfield(heights) if heights.len() < 2 => {
+                return Err(GroundError::TooFewPoints)
+            }
+            GroundShape::Heightfield(heights) => heights.iter().map(|h| (0., *h)).collect(),
+            GroundShape::Polyline(points) if points.is_empty() => {
+                return Err(GroundError::TooFewPoints)
+            }
+            GroundShape::Polyline(points) => points.clone(),
+        };
+        if points.iter().any(|(x, y)| !x.is_finite() || !y.is_finite()) {
+            return Err(GroundError::NotFinite);
+        }
+        if points.windows(2).any(|w| w[1].0 < w[0].0) {
+            return Err(GroundError::Unsorted);
+        }
+        Ok(())
+    }
+
+    /// Height offset of the surface above the flat ground top at the given x.
+    pub fn height_at(&self, x: f32) -> f32 {
+        let points = self.surface_points(1.);
+        let (first, last) = (points[0], points[points.len() - 1]);
+        if x <= first.0 {
+            return first.1;
+        }
+        if x >= last.0 {
+            return last.1;
+        }
+        points
+            .windows(2)
+            .find(|w| x <= w[1].0)
+            .map(|w| {
+                let frac = (x - w[0].0) / (w[1].0 - w[0].0);
+                w[0].1 + (w[1].1 - w[0].1) * frac
+            })
+            .expect("x within the ground surface range")
+    }
+
+    /// Surface outline from the left to the right edge of the ground, in world units.
+    fn surface_points(&self, scale: f32) -> Vec<(f32, f32)> {
+        let unscaled = match self {
+            GroundShape::Flat => vec![(-GROUND_HALF_WIDTH, 0.), (GROUND_HALF_WIDTH, 0.)],
+            GroundShape::Heightfield(heights) => {
+                let step = GROUND_HALF_WIDTH * 2. / (heights.len() - 1) as f32;
+                heights
+                    .iter()
+                    .enumerate()
+                    .map(|(i, h)| (-GROUND_HALF_WIDTH + i as f32 * step, *h))
+                    .collect()
+            }
+            GroundShape::Polyline(points) => {
+                let mut extended = Vec::with_capacity(points.len() + 2);
+                extended.push((-GROUND_HALF_WIDTH, points[0].1));
+                extended.extend(points.iter().copied());
+                extended.push((GROUND_HALF_WIDTH, points[points.len() - 1].1));
+                extended
+            }
+        };
+        unscaled.into_iter().map(|(x, y)| (x * scale, y * scale)).collect()
+    }
+
+    /// Uneven grounds are built as a solid strip of convex columns under the surface,
+    /// so bodies pushed into the terrain are ejected upwards rather than falling through.
+    fn collider(&self, scale: f32) -> ColliderBuilder {
+        let half_height = GROUND_HALF_HEIGHT * scale;
+        if let GroundShape::Flat = self {
+            return ColliderBuilder::cuboid(GROUND_HALF_WIDTH * scale, half_height);
+        }
+        let points = self.surface_points(scale);
+        let lowest = points.iter().map(|p| p.1).fold(0., f32::min);
+        let bottom = lowest - half_height * 2.;
+        let columns = points
+            .windows(2)
+            .filter(|w| w[1].0 > w[0].0)
+            .filter_map(|w| {
+                SharedShape::convex_polyline(vec![
+                    point![w[0].0, bottom],
+                    point![w[1].0, bottom],
+                    point![w[1].0, w[1].1],
+                    point![w[0].0, w[0].1],
+                ])
+            })
+            .map(|column| (Isometry2::translation(0., half_height), column))
+            .collect();
+        ColliderBuilder::compound(columns)
+    }
+}
+
+/// A "water" layer below `surface_y` where dynamic bodies are slowed down in proportion
+/// to their velocity. Damping coefficients are per unit mass, in 1/s.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DragRegion {
+    pub surface_y: f32,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+}
+
+impl Default for DragRegion {
+    fn default() -> Self {
+        Self {
+            surface_y: -1.6,
+            linear_damping: 4.,
+            angular_damping: 4.,
+        }
+    }
+}
+
+impl DragRegion {
+    fn apply(&self, rigid_body_set: &mut RigidBodySet, dt: f32) {
+        for (_, body) in rigid_body_set.iter_mut() {
+            if !body.is_dynamic() || body.translation().y > self.surface_y {
+                continue;
+            }
+            let drag = -*body.linvel() * self.linear_damping * body.mass() * dt;
+            let angular_drag = -body.angvel() * self.angular_damping * body.mass_properties().effective_angular_inertia() * dt;
+            body.apply_impulse(drag, false);
+            body.apply_torque_impulse(angular_drag, false);
+        }
+    }
+}
+
+/// A pinchable ball put on the ground at `x`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BallConfig {
+    pub x: f32,
+    pub radius: f32,
+    /// Of the ball as it is put down, at rest by default.
+    pub velocity: (f32, f32),
+}
+
+impl Default for BallConfig {
+    fn default() -> Self {
+        Self {
+            x: TRICEP_HALF_HEIGHT * 2., // Position it away from the wall
+            radius: 0.03, // Small ball that can be pinched
+            velocity: (0., 0.),
+        }
+    }
+}
+
+/// Half the thickness of the floor and the side walls of a basket.
+const BASKET_WALL_HALF_THICKNESS: f32 = 0.005;
+
+/// An open-topped basket fixed in place, the top of its floor centred at `(x, y)`, which the
+/// ball is to be put into.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BasketConfig {
+    pub x: f32,
+    pub y: f32,
+    /// Between the side walls.
+    pub inner_width: f32,
+    /// Of the side walls above the floor.
+    pub wall_height: f32,
+}
+
+impl Default for BasketConfig {
+    fn default() -> Self {
+        Self {
+            x: 0.6, // On the far side of the ball from the wall
+            y: -1.6,
+            inner_width: 0.1,
+            wall_height: 0.06,
+        }
+    }
+}
+
+impl BasketConfig {
+    /// The floor and the two side walls, relative to the top of the floor.
+    fn collider(&self, scale: f32) -> ColliderBuilder {
+        let t = BASKET_WALL_HALF_THICKNESS * scale;
+        let (half_width, wall_half_height) = (self.inner_width * scale / 2., self.wall_height * scale / 2.);
+        let wall = |x: f32| (Isometry2::translation(x, wall_half_height), SharedShape::cuboid(t, wall_half_height));
+        ColliderBuilder::compound(vec![
+            (Isometry2::translation(0., -t), SharedShape::cuboid(half_width + 2. * t, t)),
+            wall(-half_width - t),
+            wall(half_width + t),
+        ])
+    }
+}
+
+/// Why an object could not be placed in the world.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpawnError {
+    /// The object would have been spawned inside another body.
+    Overlap { x: f32, y: f32 },
+    /// No free spot was found on the ground in the relocation range.
+    NoFreeSpot,
+    /// The ground could not be built.
+    Ground(GroundError),
+}
+
+impl Display for SpawnError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpawnError::Overlap { x, y } => write!(f, "object at ({x}, {y}) overlaps another body"),
+            SpawnError::NoFreeSpot => write!(f, "no free spot found on the ground"),
+            SpawnError::Ground(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for SpawnError {}
+
+#[derive(Clone, Debug)]
+pub struct WorldConfig {
+    /// Multiplies every length in the world, so thin bodies can be simulated at a size the
+    /// solver handles better. Everything else in the config is given at unit scale.
+    pub scale: f32,
+    pub ground: GroundShape,
+    pub arm: ArmConfig,
+    /// How the network outputs are turned into joint forces.
+    pub actions: ActionScaler,
+    pub balls: Vec<BallConfig>,
+    pub basket: Option<BasketConfig>,
+    pub rope: Option<RopeConfig>,
+    pub drag_region: Option<DragRegion>,
+    pub physics: PhysicsContextConfig,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            scale: 1.,
+            ground: GroundShape::default(),
+            arm: ArmConfig::default(),
+            actions: ActionScaler::default(),
+            balls: vec![BallConfig::default()],
+            basket: None,
+            rope: None,
+            drag_region: None,
+            physics: PhysicsContextConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub(super) struct Hangman {
     pub(super) ground: ModelBody,
+    /// Only the tests look the wall up again, to check bodies against it.
+    #[cfg(test)]
     pub(super) wall: ModelBody,
     pub(super) shoulder: ModelBody,
+    scale: f32,
 }
 
 impl Hangman {
-    pub fn new(world_sets: &mut WorldSets) -> Self {
-        let ground_y = GROUND_MIDDLE_Y;
-        let ground_top = ground_y + GROUND_HALF_HEIGHT;
+    pub fn new(world_sets: &mut WorldSets, ground_shape: &GroundShape, scale: f32) -> Self {
+        let (ground_half_width, ground_half_height) = (GROUND_HALF_WIDTH * scale, GROUND_HALF_HEIGHT * scale);
+        let ground_y = GROUND_MIDDLE_Y * scale;
+        let ground_top = ground_y + ground_half_height;
         let ground = world_sets.create_body_with_builders(
             0.0, ground_y, RigidBodyBuilder::fixed(),
-            GROUND_HALF_WIDTH, GROUND_HALF_HEIGHT, ColliderBuilder::cuboid(GROUND_HALF_WIDTH, GROUND_HALF_HEIGHT), 0.
+            ground_half_width, ground_half_height, ground_shape.collider(scale), 0.
         );
 
         // Create the wall sitting on top of the ground without overlap
-        let wall_y = ground_top + WALL_HALF_HEIGHT;
+        let (wall_half_width, wall_half_height) = (WALL_HALF_WIDTH * scale, WALL_HALF_HEIGHT * scale);
+        let wall_y = ground_top + wall_half_height;
         let wall = world_sets.create_body_with_builders(
             0.0, wall_y, RigidBodyBuilder::fixed(),
-            WALL_HALF_WIDTH, WALL_HALF_HEIGHT, ColliderBuilder::cuboid(WALL_HALF_WIDTH, WALL_HALF_HEIGHT), 0.
+            wall_half_width, wall_half_height, ColliderBuilder::cuboid(wall_half_width, wall_half_height), 0.
         );
 
         let wall_far_side_centre = wall.get_far_side_centre(&world_sets.rigid_body_set);
 
+        let shoulder_radius = TRICEP_HALF_HEIGHT * scale;
         let shoulder = world_sets.create_body_with_builders(
             wall_far_side_centre.x, wall_far_side_centre.y, RigidBodyBuilder::fixed(),
-            TRICEP_HALF_HEIGHT, TRICEP_HALF_HEIGHT, ColliderBuilder::ball(TRICEP_HALF_HEIGHT), TRICEP_MAX_FORCE
+            shoulder_radius, shoulder_radius, ColliderBuilder::ball(shoulder_radius), TRICEP_MAX_FORCE * scale.powi(3)
         );
 
         Self {
             ground,
+            #[cfg(test)]
             wall,
             shoulder,
+            scale,
         }
     }
+
+    /// Top of the flat ground slab, which uneven surfaces are measured from.
+    pub(super) fn ground_top(&self, rigid_body_set: &RigidBodySet) -> f32 {
+        self.ground.current_centre(rigid_body_set).y + GROUND_HALF_HEIGHT * self.scale
+    }
+}
+
+/// Solver settings for stepping a world. CCD is chosen per body class: only the small, fast
+/// fingertips and balls are likely to tunnel, the other arm segments and the rope rarely do.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PhysicsContextConfig {
+    pub dt: f32,
+    pub solver_iterations: usize,
+    pub max_ccd_substeps: usize,
+    pub fingertip_ccd: bool,
+    pub ball_ccd: bool,
+    /// CCD for every other dynamic body: the remaining arm segments and the rope.
+    pub body_ccd: bool,
+}
+
+impl PhysicsContextConfig {
+    /// CCD on every dynamic body with plenty of substeps; the original behaviour.
+    pub fn accuracy() -> Self {
+        Self {
+            dt: 1.0 / 250.0,
+            solver_iterations: 4,
+            max_ccd_substeps: 16,
+            fingertip_ccd: true,
+            ball_ccd: true,
+            body_ccd: true,
+        }
+    }
+
+    /// CCD only where tunnelling is likely, with a single CCD substep and fewer solver iterations.
+    pub fn fast() -> Self {
+        Self {
+            solver_iterations: 2,
+            max_ccd_substeps: 1,
+            body_ccd: false,
+            ..Self::accuracy()
+        }
+    }
+}
+
+impl Default for PhysicsContextConfig {
+    fn default() -> Self {
+        Self::accuracy()
+    }
 }
 
 pub struct PhysicsContext {
@@ -62,13 +400,21 @@ pub struct PhysicsContext {
     ccd_solver: CCDSolver,
     integration_parameters: IntegrationParameters,
     gravity: Vector2<f32>,
+    drag_region: Option<DragRegion>,
 }
 
 impl PhysicsContext {
     pub fn new() -> Self {
-        let mut integration_parameters = IntegrationParameters::default();
-        integration_parameters.dt = 1.0 / 250.0;
-        integration_parameters.max_ccd_substeps = 16;
+        Self::with_config(&PhysicsContextConfig::default())
+    }
+
+    pub fn with_config(config: &PhysicsContextConfig) -> Self {
+        let integration_parameters = IntegrationParameters {
+            dt: config.dt,
+            num_solver_iterations: NonZeroUsize::new(config.solver_iterations).expect("at least one solver iteration"),
+            max_ccd_substeps: config.max_ccd_substeps,
+            ..IntegrationParameters::default()
+        };
         Self {
             physics_pipeline: PhysicsPipeline::new(),
             island_manager: IslandManager::new(),
@@ -77,13 +423,34 @@ impl PhysicsContext {
             ccd_solver: CCDSolver::new(),
             integration_parameters,
             gravity: vector![0.0, -9.81],
+            drag_region: None,
+        }
+    }
+
+    pub fn with_drag_region(mut self, drag_region: Option<DragRegion>) -> Self {
+        self.drag_region = drag_region;
+        self
+    }
+
+    /// Scales gravity along with the world, so a scaled arm moves on the same time scale, and
+    /// tells the solver the typical object size so its tolerances follow.
+    pub fn with_length_scale(mut self, scale: f32) -> Self {
+        self.gravity *= scale;
+        self.integration_parameters.length_unit = scale;
+        if let Some(drag_region) = &mut self.drag_region {
+            drag_region.surface_y *= scale;
         }
+        self
     }
 
     pub(super) fn step(&mut self, world_sets: &mut WorldSets) {
         let physics_hooks = ();
         let event_handler = ();
 
+        if let Some(drag_region) = &self.drag_region {
+            drag_region.apply(&mut world_sets.rigid_body_set, self.integration_parameters.dt);
+        }
+
         self.physics_pipeline.step(
             &self.gravity,
             &self.integration_parameters,
@@ -101,43 +468,213 @@ impl PhysicsContext {
     }
 }
 
+/// The copy starts with a fresh pipeline, which only holds scratch buffers between steps.
+impl Clone for PhysicsContext {
+    fn clone(&self) -> Self {
+        Self {
+            physics_pipeline: PhysicsPipeline::new(),
+            island_manager: self.island_manager.clone(),
+            broad_phase: self.broad_phase.clone(),
+            narrow_phase: self.narrow_phase.clone(),
+            ccd_solver: self.ccd_solver.clone(),
+            integration_parameters: self.integration_parameters,
+            gravity: self.gravity,
+            drag_region: self.drag_region,
+        }
+    }
+}
+
+impl Default for PhysicsContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone)]
 pub struct PhysicsWorld {
     context: PhysicsContext,
     world_sets: WorldSets,
     arm: Arm,
     hangman: Hangman,
-    ball: ModelBody,
+    balls: Vec<ModelBody>,
+    basket: Option<ModelBody>,
+    rope: Option<Rope>,
+    config: WorldConfig,
+}
+
+impl Default for PhysicsWorld {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PhysicsWorld {
     pub fn new() -> Self {
+        Self::with_config(WorldConfig::default())
+    }
+
+    pub fn with_config(config: WorldConfig) -> Self {
+        Self::try_with_config(config).expect("the configured world could not be built")
+    }
+
+    /// Builds the world, or tells which configured object could not be placed in it.
+    pub fn try_with_config(config: WorldConfig) -> Result<Self, SpawnError> {
+        config.ground.validate().map_err(SpawnError::Ground)?;
         let mut world_sets = WorldSets::default();
 
-        let hangman = Hangman::new(&mut world_sets);
+        let hangman = Hangman::new(&mut world_sets, &config.ground, config.scale);
 
         // Create the arm attached to the wall
         let arm = Arm::new(
             &mut world_sets,
             &hangman.shoulder,
+            &config.arm,
+            config.scale,
         );
-        let ground_top = hangman.ground.get_far_side_centre(&world_sets.rigid_body_set).y;
-
-        // Create a pinchable ball positioned on the ground, about tricep length away from the wall
-        let ball_radius = 0.03; // Small ball that can be pinched
-        let ball_x = TRICEP_HALF_HEIGHT * 2.; // Position it away from the wall
-        let ball_y = ground_top + ball_radius; // On the ground surface
-
-        let ball = world_sets.create_dynamic_with_cb(
-            ball_x, ball_y,ball_radius, ball_radius, ColliderBuilder::ball(ball_radius), 0.
-        );
+        let rope = config.rope.as_ref().map(|rope_config| Rope::new(&mut world_sets, rope_config, config.scale));
+        if let (Some(rope), Some(rope_config)) = (&rope, &config.rope) {
+            if rope.penetrated_collider(&world_sets).is_some() {
+                return Err(SpawnError::Overlap { x: rope_config.anchor_x, y: rope_config.anchor_y });
+            }
+            rope.set_ccd_enabled(&mut world_sets.rigid_body_set, config.physics.body_ccd);
+        }
+        arm.set_ccd_enabled(&mut world_sets.rigid_body_set, config.physics.fingertip_ccd, config.physics.body_ccd);
+        let basket = config.basket.map(|basket| {
+            let (t, scale) = (BASKET_WALL_HALF_THICKNESS, config.scale);
+            let basket_mb = world_sets.create_body_with_builders(
+                basket.x * scale, basket.y * scale, RigidBodyBuilder::fixed(),
+                (basket.inner_width / 2. + 2. * t) * scale, basket.wall_height * scale, basket.collider(scale), 0.
+            );
+            match basket_mb.penetrated_collider(&world_sets, &[]) {
+                None => Ok(basket_mb),
+                Some(_) => Err(SpawnError::Overlap { x: basket.x, y: basket.y }),
+            }
+        }).transpose()?;
 
-        Self {
-            context: PhysicsContext::new(),
+        let mut world = Self {
+            context: PhysicsContext::with_config(&config.physics)
+                .with_drag_region(config.drag_region)
+                .with_length_scale(config.scale),
             arm,
             hangman,
-            ball,
+            balls: Vec::new(),
+            basket,
+            rope,
             world_sets,
+            config,
+        };
+        for ball in world.config.balls.clone() {
+            world.spawn_ball(ball)?;
         }
+        Ok(world)
+    }
+
+    /// Adds a ball resting on the ground surface and returns its index for the ball queries.
+    /// The ball is given at unit scale, like the rest of the config. If the requested spot is
+    /// taken, e.g. by the wall, the ball is moved away from the wall until it fits.
+    pub fn spawn_ball(&mut self, ball: BallConfig) -> Result<usize, SpawnError> {
+        let step = ball.radius / 2.;
+        let mut x = ball.x;
+        while x + ball.radius < GROUND_HALF_WIDTH {
+            match self.try_spawn_ball(BallConfig { x, ..ball }) {
+                Err(SpawnError::Overlap { .. }) => x += step,
+                result => return result,
+            }
+        }
+        Err(SpawnError::NoFreeSpot)
+    }
+
+    /// Adds a ball resting on the ground surface exactly at the requested spot, or fails if it
+    /// would be embedded in another body, telling where at unit scale.
+    pub fn try_spawn_ball(&mut self, ball: BallConfig) -> Result<usize, SpawnError> {
+        let scale = self.config.scale;
+        let (ball_x, radius) = (ball.x * scale, ball.radius * scale);
+        let ball_y = self.ground_height_at(ball_x) + radius;
+        let position = Isometry2::translation(ball_x, ball_y);
+        if self.world_sets.penetrated_collider(&Ball::new(radius), &position, &[]).is_some() {
+            return Err(SpawnError::Overlap { x: ball.x, y: ball_y / scale });
+        }
+        let ball_mb = self.world_sets.create_dynamic_with_cb(
+            ball_x, ball_y, radius, radius, ColliderBuilder::ball(radius), 0.
+        );
+        ball_mb.set_ccd_enabled(&mut self.world_sets.rigid_body_set, self.config.physics.ball_ccd);
+        let velocity = vector![ball.velocity.0, ball.velocity.1] * self.config.scale;
+        ball_mb.set_linear_velocity(&mut self.world_sets.rigid_body_set, velocity);
+        self.balls.push(ball_mb);
+        Ok(self.balls.len() - 1)
+    }
+
+    pub fn config(&self) -> &WorldConfig {
+        &self.config
+    }
+
+    /// Height of the ground surface at the given x, in world coordinates.
+    pub fn ground_height_at(&self, x: f32) -> f32 {
+        let scale = self.config.scale;
+        self.hangman.ground_top(&self.world_sets.rigid_body_set) + self.config.ground.height_at(x / scale) * scale
+    }
+
+    /// Maps a world point into the unit square the arm can reach, whatever the world scale.
+    pub fn normalize(&self, point: (f32, f32)) -> (f32, f32) {
+        self.arm.normalization().normalize(point)
+    }
+
+    /// Scales a velocity like `normalize` does the positions.
+    pub fn normalize_velocity(&self, velocity: Vector2<f32>) -> (f32, f32) {
+        self.arm.normalization().scale((velocity.x, velocity.y))
+    }
+
+    /// The top of the basket floor, the centre of where a ball put in it lies, if there is one.
+    pub fn basket_position(&self) -> Option<Point2<f32>> {
+        self.basket.map(|basket| basket.current_centre(&self.world_sets.rigid_body_set))
+    }
+
+    /// Whether the centre of the first ball is between the side walls of the basket and below
+    /// their tops, false without a basket or a ball.
+    pub fn is_ball_in_basket(&self) -> bool {
+        let (Some(basket), Some(position), Some(ball)) =
+            (self.config.basket, self.basket_position(), self.ball_position())
+        else {
+            return false;
+        };
+        let ball = ball - position;
+        let scale = self.config.scale;
+        ball.x.abs() < basket.inner_width * scale / 2. && (0. ..basket.wall_height * scale).contains(&ball.y)
+    }
+
+    pub fn ball_count(&self) -> usize {
+        self.balls.len()
+    }
+
+    /// Position of the first ball, the one single-ball tasks are about, `None` without balls.
+    pub fn ball_position(&self) -> Option<Point2<f32>> {
+        (!self.balls.is_empty()).then(|| self.ball_position_of(0))
+    }
+
+    pub fn ball_velocity(&self) -> Option<Vector2<f32>> {
+        (!self.balls.is_empty()).then(|| self.ball_velocity_of(0))
+    }
+
+    pub fn ball_position_of(&self, index: usize) -> Point2<f32> {
+        self.balls[index].current_centre(&self.world_sets.rigid_body_set)
+    }
+
+    pub fn ball_velocity_of(&self, index: usize) -> Vector2<f32> {
+        self.balls[index].linear_velocity(&self.world_sets.rigid_body_set)
+    }
+
+    pub fn ball_positions(&self) -> Vec<Point2<f32>> {
+        (0..self.balls.len()).map(|i| self.ball_position_of(i)).collect()
+    }
+
+    /// Index of the ball nearest to the given point, if there are any balls.
+    pub fn closest_ball(&self, to: &Point2<f32>) -> Option<usize> {
+        self.ball_positions()
+            .iter()
+            .map(|position| distance(position, to))
+            .enumerate()
+            .min_by(|a, b| a.1.partial_cmp(&b.1).expect("ball distances not comparable"))
+            .map(|(index, _)| index)
     }
 
     /// Steps the physics simulation forward by one frame
@@ -156,6 +693,11 @@ impl PhysicsWorld {
             .apply_forearm_force(scaling_factor, &mut self.world_sets.rigid_body_set)
     }
 
+    pub fn apply_wrist_force(&mut self, scaling_factor: f32) {
+        self.arm
+            .apply_wrist_force(scaling_factor, &mut self.world_sets.rigid_body_set)
+    }
+
     pub fn apply_palm_force(&mut self, scaling_factor: f32) {
         self.arm
             .apply_palm_force(scaling_factor, &mut self.world_sets.rigid_body_set)
@@ -181,7 +723,18 @@ impl PhysicsWorld {
             .apply_upper_thumb_force(scaling_factor, &mut self.world_sets.rigid_body_set)
     }
 
+    /// Applies the force of one channel, numbered like `joint_impulses` and `arm_joint_angles`.
+    pub fn apply_joint_force(&mut self, channel: usize, scaling_factor: f32) {
+        self.arm
+            .apply_joint_force(channel, scaling_factor, &mut self.world_sets.rigid_body_set)
+    }
+
     // Farthest corners query methods
+    pub fn joint_farthest_corners(&self) -> Vec<Corners> {
+        self.arm
+            .joint_farthest_corners(&self.world_sets.rigid_body_set)
+    }
+
     pub fn tricep_farthest_corners(&self) -> Corners {
         self.arm
             .tricep_farthest_corners(&self.world_sets.rigid_body_set)
@@ -192,6 +745,11 @@ impl PhysicsWorld {
             .forearm_farthest_corners(&self.world_sets.rigid_body_set)
     }
 
+    pub fn wrist_farthest_corners(&self) -> Corners {
+        self.arm
+            .wrist_farthest_corners(&self.world_sets.rigid_body_set)
+    }
+
     pub fn palm_farthest_corners(&self) -> Corners {
         self.arm
             .palm_farthest_corners(&self.world_sets.rigid_body_set)
@@ -207,6 +765,12 @@ impl PhysicsWorld {
             .upper_index_finger_farthest_corners(&self.world_sets.rigid_body_set)
     }
 
+    /// The index fingertip, between the far corners of its upper segment.
+    pub fn index_fingertip(&self) -> (f32, f32) {
+        let (first, second) = self.upper_index_finger_farthest_corners();
+        ((first.0 + second.0) / 2., (first.1 + second.1) / 2.)
+    }
+
     pub fn lower_thumb_farthest_corners(&self) -> Corners {
         self.arm
             .lower_thumb_farthest_corners(&self.world_sets.rigid_body_set)
@@ -217,15 +781,123 @@ impl PhysicsWorld {
             .upper_thumb_farthest_corners(&self.world_sets.rigid_body_set)
     }
 
+    /// Touching pairs of an arm segment and a collider outside the arm, like the ground, the
+    /// wall or a ball, as of the last step.
+    pub fn arm_contact_count(&self) -> usize {
+        let arm = self.arm.body_handles();
+        let on_arm = |collider| {
+            self.world_sets
+                .collider_set
+                .get(collider)
+                .and_then(|collider| collider.parent())
+                .is_some_and(|parent| arm.contains(&parent))
+        };
+        self.context
+            .narrow_phase
+            .contact_pairs()
+            .filter(|pair| pair.has_any_active_contact)
+            .filter(|pair| on_arm(pair.collider1) != on_arm(pair.collider2))
+            .count()
+    }
+
+    /// Whether the index fingertip and the thumb tip, in that order, touch a collider outside
+    /// the arm, as of the last step.
+    pub fn fingertip_contacts(&self) -> [bool; 2] {
+        let arm = self.arm.body_handles();
+        let parent = |collider| self.world_sets.collider_set.get(collider).and_then(|collider| collider.parent());
+        let touching = |fingertip| {
+            self.context
+                .narrow_phase
+                .contact_pairs()
+                .filter(|pair| pair.has_any_active_contact)
+                .any(|pair| match (parent(pair.collider1), parent(pair.collider2)) {
+                    (Some(a), b) if a == fingertip => !b.is_some_and(|b| arm.contains(&b)),
+                    (a, Some(b)) if b == fingertip => !a.is_some_and(|a| arm.contains(&a)),
+                    _ => false,
+                })
+        };
+        self.arm.fingertip_handles().map(touching)
+    }
+
+    pub fn arm_joint_count(&self) -> usize {
+        self.arm.joint_count()
+    }
+
+    /// Impulses the solver applied through each arm joint during the last step, in force channel
+    /// order. Each is in the joint frame: linear x and y, then angular.
+    pub fn joint_impulses(&self) -> Vec<Vector3<f32>> {
+        self.arm.joint_impulses(&self.world_sets.impulse_joint_set)
+    }
+
+    /// Torque each force channel exerts around its joint, counter-clockwise positive, for logging
+    /// or penalizing effort. Forces stay applied across steps, so this keeps growing until the
+    /// arm is posed again.
+    pub fn joint_motor_torques(&self) -> Vec<f32> {
+        self.arm.motor_torques().to_vec()
+    }
+
+    /// How a grip intent is spread over the force channels, see `sim_for_ai::apply_forces_and_step`.
+    pub fn grip_synergy(&self) -> Vec<f32> {
+        self.arm.grip_synergy()
+    }
+
+    /// The scaling factor last applied on each force channel, zero until one is applied and
+    /// again after the arm is posed.
+    pub fn joint_applied_forces(&self) -> Vec<f32> {
+        self.arm.applied_forces().to_vec()
+    }
+
+    /// Teleports the arm into the given joint angles, one per force channel, at rest.
+    pub fn set_arm_pose(&mut self, joint_angles: &[f32]) {
+        self.arm.set_pose(&mut self.world_sets.rigid_body_set, joint_angles)
+    }
+
+    pub fn arm_joint_angles(&self) -> Vec<f32> {
+        self.arm.joint_angles(&self.world_sets.rigid_body_set)
+    }
+
+    /// How fast each arm joint turns, in radians per second, in force channel order.
+    pub fn arm_joint_angular_velocities(&self) -> Vec<f32> {
+        self.arm.joint_angular_velocities(&self.world_sets.rigid_body_set)
+    }
+
+    /// The poses of the shoulder and every arm segment hanging off it, then of the balls.
+    pub fn body_poses(&self) -> Vec<Pose> {
+        let rigid_body_set = &self.world_sets.rigid_body_set;
+        let arm = self.arm.body_handles().into_iter().map(|handle| Pose::from(rigid_body_set[handle].position()));
+        let balls = self.balls.iter().map(|ball| Pose::from(&ball.current_pose(rigid_body_set)));
+        arm.chain(balls).collect()
+    }
+
     pub fn all_arm_corners(&self) -> Vec<[Point2<f32>; 4]> {
         self.arm
             .all_corners(&self.world_sets.rigid_body_set)
     }
+
+    /// Poses of the rope segments from the anchor downwards; empty when there is no rope.
+    pub fn rope_segment_poses(&self) -> Vec<Isometry2<f32>> {
+        self.rope
+            .as_ref()
+            .map(|rope| rope.segment_poses(&self.world_sets.rigid_body_set))
+            .unwrap_or_default()
+    }
+
+    pub fn rope_corners(&self) -> Vec<[Point2<f32>; 4]> {
+        self.rope
+            .as_ref()
+            .map(|rope| rope.all_corners(&self.world_sets.rigid_body_set))
+            .unwrap_or_default()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::physics::world::PhysicsWorld;
+    use rapier2d::geometry::ColliderBuilder;
+    use crate::physics::rope::RopeConfig;
+    use rapier2d::na::point;
+    use rapier2d::prelude::nalgebra;
+    use crate::sim_for_ai::apply_forces_and_step;
+    use crate::physics::world::{ActionScaler, ArmConfig, BallConfig, BasketConfig, DragRegion, GroundError, GroundShape, PhysicsContextConfig, PhysicsWorld, SpawnError, WorldConfig, GROUND_HALF_WIDTH};
 
     #[test]
     fn test_physics_simulation() {
@@ -251,6 +923,7 @@ mod tests {
         println!("{:?}", corners);
         world.apply_tricep_force(0.001417);
         world.apply_forearm_force(-0.001417);
+        world.apply_wrist_force(-0.001417);
         world.apply_palm_force(0.001417);
         world.apply_lower_index_finger_force(-0.001417);
         world.apply_upper_index_finger_force(0.001417);
@@ -258,4 +931,275 @@ mod tests {
         world.apply_upper_thumb_force(0.001417);
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_ground_height_interpolation() {
+        let polyline = GroundShape::Polyline(vec![(-1., 0.), (0., -0.2), (1., 0.)]);
+        assert_eq!(polyline.height_at(-2.), 0.);
+        assert!((polyline.height_at(0.5) + 0.1).abs() < 1e-6);
+        let heightfield = GroundShape::Heightfield(vec![0., -0.4, 0.]);
+        assert!((heightfield.height_at(GROUND_HALF_WIDTH / 2.) + 0.2).abs() < 1e-6);
+        assert_eq!(GroundShape::Flat.height_at(3.), 0.);
+
+        assert_eq!(polyline.validate(), Ok(()));
+        assert_eq!(GroundShape::Heightfield(vec![0.]).validate(), Err(GroundError::TooFewPoints));
+        assert_eq!(GroundShape::Polyline(vec![(1., 0.), (0., 0.)]).validate(), Err(GroundError::Unsorted));
+        assert_eq!(GroundShape::Polyline(vec![(0., f32::NAN)]).validate(), Err(GroundError::NotFinite));
+        let empty = WorldConfig { ground: GroundShape::Polyline(Vec::new()), ..WorldConfig::default() };
+        assert!(matches!(PhysicsWorld::try_with_config(empty), Err(SpawnError::Ground(GroundError::TooFewPoints))));
+    }
+
+    #[test]
+    fn test_ball_rolls_into_valley() {
+        let ground = GroundShape::Polyline(vec![(0.5, 0.1), (1., -0.1), (1.5, 0.1)]);
+        let mut world = PhysicsWorld::with_config(WorldConfig { ground, ..WorldConfig::default() });
+        let radius = 0.03;
+        let start_x = 0.7;
+        let probe = world.world_sets.create_dynamic_with_cb(
+            start_x, world.ground_height_at(start_x) + radius * 2., radius, radius, ColliderBuilder::ball(radius), 0.
+        );
+        for _ in 0..2000 {
+            world.step();
+        }
+        let end = probe.current_centre(&world.world_sets.rigid_body_set);
+        assert!(end.x > start_x);
+        assert!(end.y < world.ground_height_at(start_x));
+        assert!(end.y > world.ground_height_at(end.x));
+    }
+
+    #[test]
+    fn test_rope_hangs_below_anchor() {
+        let rope_config = RopeConfig::default();
+        let mut world = PhysicsWorld::with_config(WorldConfig { rope: Some(rope_config.clone()), ..WorldConfig::default() });
+        for _ in 0..250 {
+            world.step();
+        }
+        let poses = world.rope_segment_poses();
+        assert_eq!(poses.len(), rope_config.segment_count);
+        assert_eq!(world.rope_corners().len(), rope_config.segment_count);
+        let rope_length = rope_config.segment_half_length * 2. * rope_config.segment_count as f32;
+        for pair in poses.windows(2) {
+            assert!(pair[1].translation.y < pair[0].translation.y);
+        }
+        let lowest = poses.last().unwrap().translation.y;
+        assert!(lowest > rope_config.anchor_y - rope_length * 1.01);
+        assert!(PhysicsWorld::new().rope_segment_poses().is_empty());
+    }
+
+    #[test]
+    fn test_drag_region_slows_falling_bodies() {
+        let falling_speed = |drag_region| {
+            let mut world = PhysicsWorld::with_config(WorldConfig { drag_region, ..WorldConfig::default() });
+            let radius = 0.03;
+            let probe = world.world_sets.create_dynamic_with_cb(
+                2., -1.7, radius, radius, ColliderBuilder::ball(radius), 0.
+            );
+            for _ in 0..25 {
+                world.step();
+            }
+            probe.linear_velocity(&world.world_sets.rigid_body_set).y.abs()
+        };
+        let dry = falling_speed(None);
+        let wet = falling_speed(Some(DragRegion::default()));
+        assert!(wet < dry * 0.9);
+    }
+
+    #[test]
+    fn test_multiple_balls() {
+        let balls = [0.6, 1.0, 1.4].map(|x| BallConfig { x, radius: 0.03, ..BallConfig::default() }).to_vec();
+        let mut world = PhysicsWorld::with_config(WorldConfig { balls, ..WorldConfig::default() });
+        let extra = world.spawn_ball(BallConfig { x: 2.0, radius: 0.05, ..BallConfig::default() }).unwrap();
+        assert_eq!(extra, 3);
+        assert_eq!(world.ball_count(), 4);
+        world.step();
+        assert_eq!(world.closest_ball(&point![0.9, -1.5]), Some(1));
+        assert_eq!(world.closest_ball(&point![5., -1.5]), Some(3));
+        let positions = world.ball_positions();
+        assert!((positions[3].y - world.ground_height_at(2.0) - 0.05).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_basket_catches_a_ball() {
+        assert_eq!(PhysicsWorld::new().basket_position(), None);
+        let basket = BasketConfig { x: 1.2, ..BasketConfig::default() };
+        let mut world = PhysicsWorld::with_config(WorldConfig { balls: Vec::new(), basket: Some(basket), ..WorldConfig::default() });
+        assert_eq!(world.basket_position(), Some(point![basket.x, basket.y]));
+        assert_eq!(world.ball_position(), None);
+        assert!(!world.is_ball_in_basket());
+        let radius = 0.03;
+        let probe = world.world_sets.create_dynamic_with_cb(
+            basket.x + 0.02, basket.y + 0.2, radius, radius, ColliderBuilder::ball(radius), 0.
+        );
+        world.balls.push(probe);
+        assert!(!world.is_ball_in_basket(), "the ball is above the basket");
+        for _ in 0..250 {
+            world.step();
+        }
+        let end = probe.current_centre(&world.world_sets.rigid_body_set);
+        assert!((end.x - basket.x).abs() < basket.inner_width / 2., "{end:?}");
+        assert!((end.y - basket.y - radius).abs() < 0.01, "{end:?}");
+        assert!(world.is_ball_in_basket());
+    }
+
+    #[test]
+    fn test_objects_in_the_ground_are_rejected() {
+        let basket = BasketConfig { y: -1.95, ..BasketConfig::default() };
+        let config = WorldConfig { balls: Vec::new(), basket: Some(basket), ..WorldConfig::default() };
+        assert_eq!(PhysicsWorld::try_with_config(config).err(), Some(SpawnError::Overlap { x: basket.x, y: basket.y }));
+
+        let rope = RopeConfig { anchor_y: -1.95, ..RopeConfig::default() };
+        let config = WorldConfig { rope: Some(rope.clone()), ..WorldConfig::default() };
+        assert_eq!(PhysicsWorld::try_with_config(config).err(), Some(SpawnError::Overlap { x: rope.anchor_x, y: rope.anchor_y }));
+
+        // reported at unit scale, as configured
+        let mut scaled = PhysicsWorld::with_config(WorldConfig { scale: 10., ..WorldConfig::default() });
+        let in_wall = BallConfig { x: 0., radius: 0.03, ..BallConfig::default() };
+        let Err(SpawnError::Overlap { x, y }) = scaled.try_spawn_ball(in_wall) else {
+            panic!("the ball was spawned in the wall");
+        };
+        assert_eq!(x, 0.);
+        assert!((y - (PhysicsWorld::new().ground_height_at(0.) + in_wall.radius)).abs() < 1e-4, "{y}");
+    }
+
+    #[test]
+    fn test_joint_impulses_carry_the_arm() {
+        let mut world = PhysicsWorld::new();
+        world.step();
+        let impulses = world.joint_impulses();
+        assert_eq!(impulses.len(), world.arm_joint_count());
+        let shoulder = impulses[0].xy().norm();
+        let fingertip = impulses[5].xy().norm();
+        assert!(shoulder > 0.);
+        assert!(shoulder > fingertip);
+    }
+
+    #[test]
+    fn test_ball_spawn_avoids_the_wall() {
+        let mut world = PhysicsWorld::new();
+        let wall_side = world.hangman.wall.get_far_side_centre(&world.world_sets.rigid_body_set).x;
+        let ball = world.ball_position().unwrap();
+        assert!(ball.x - BallConfig::default().radius >= wall_side - 0.0001);
+
+        let in_wall = BallConfig { x: 0., radius: 0.03, ..BallConfig::default() };
+        assert!(matches!(world.try_spawn_ball(in_wall), Err(SpawnError::Overlap { .. })));
+        assert!(matches!(world.try_spawn_ball(BallConfig { x: ball.x, radius: 0.03, ..BallConfig::default() }), Err(SpawnError::Overlap { .. })));
+        assert_eq!(world.try_spawn_ball(BallConfig { x: 1.5, radius: 0.03, ..BallConfig::default() }), Ok(1));
+    }
+
+    #[test]
+    fn test_scaled_world_normalizes_the_same() {
+        let mut unit = PhysicsWorld::new();
+        let mut scaled = PhysicsWorld::with_config(WorldConfig { scale: 10., ..WorldConfig::default() });
+        let palm_far_side = |world: &PhysicsWorld| {
+            let corners = world.palm_farthest_corners();
+            (world.normalize(corners.0), world.normalize(corners.1))
+        };
+        assert!((scaled.ball_position().unwrap().x - unit.ball_position().unwrap().x * 10.).abs() < 0.0001);
+        for _ in 0..50 {
+            unit.apply_forearm_force(0.5);
+            scaled.apply_forearm_force(0.5);
+            unit.step();
+            scaled.step();
+        }
+        let (unit_corners, scaled_corners) = (palm_far_side(&unit), palm_far_side(&scaled));
+        for (u, s) in [(unit_corners.0, scaled_corners.0), (unit_corners.1, scaled_corners.1)] {
+            assert!((u.0 - s.0).abs() < 0.02, "{u:?} vs {s:?}");
+            assert!((u.1 - s.1).abs() < 0.02, "{u:?} vs {s:?}");
+        }
+    }
+
+    #[test]
+    fn test_fast_preset_keeps_ccd_on_fingertips_and_balls() {
+        let config = WorldConfig { physics: PhysicsContextConfig::fast(), ..WorldConfig::default() };
+        let world = PhysicsWorld::with_config(config);
+        let bodies = &world.world_sets.rigid_body_set;
+        let with_ccd = bodies.iter().filter(|(_, body)| body.is_ccd_enabled()).count();
+        // the two fingertips and the ball
+        assert_eq!(with_ccd, 3);
+
+        let accurate = PhysicsWorld::new();
+        let dynamic = accurate.world_sets.rigid_body_set.iter().filter(|(_, body)| body.is_dynamic()).count();
+        let with_ccd = accurate.world_sets.rigid_body_set.iter().filter(|(_, body)| body.is_ccd_enabled()).count();
+        assert_eq!(with_ccd, dynamic);
+    }
+
+    #[test]
+    fn test_wrist_turns_the_palm_without_the_elbow() {
+        let mut idle = PhysicsWorld::new();
+        let mut turning = PhysicsWorld::new();
+        for _ in 0..25 {
+            turning.apply_wrist_force(1.);
+            idle.step();
+            turning.step();
+        }
+        let (idle, turning) = (idle.arm_joint_angles(), turning.arm_joint_angles());
+        let (elbow, wrist) = ((turning[1] - idle[1]).abs(), (turning[2] - idle[2]).abs());
+        assert!(wrist > 0.05, "wrist turned by {wrist}");
+        assert!(wrist > elbow * 2., "wrist {wrist} vs elbow {elbow}");
+    }
+
+    #[test]
+    fn test_finger_abduction_stays_within_its_limit() {
+        let arm = ArmConfig { finger_abduction: Some(0.2), ..ArmConfig::default() };
+        let mut world = PhysicsWorld::with_config(WorldConfig { arm, ..WorldConfig::default() });
+        assert_eq!(world.arm_joint_count(), 9);
+        assert_eq!(world.joint_farthest_corners().len(), 9);
+        world.apply_joint_force(4, 1.);
+        let mut widest: f32 = 0.;
+        for _ in 0..50 {
+            world.step();
+            widest = widest.max(world.arm_joint_angles()[4].abs());
+        }
+        assert!(widest > 0.05, "knuckle turned by {widest}");
+        assert!(widest < 0.2 + 0.02, "knuckle turned by {widest}");
+    }
+
+    #[test]
+    fn test_action_scaler_maps_outputs_to_ranges() {
+        let actions: ActionScaler = serde_json::from_str(r#"{"ranges": [[0, 0], [-0.5, 0.5]]}"#).unwrap();
+        assert_eq!(actions, ActionScaler::default().with_range(0, 0., 0.).with_range(1, -0.5, 0.5));
+        assert_eq!(actions.scale(1, 1.), 0.5);
+        assert_eq!(actions.scale(1, 0.), 0.);
+        assert_eq!(actions.scale(2, -1.), -1.);
+
+        let mut world = PhysicsWorld::with_config(WorldConfig { actions, ..WorldConfig::default() });
+        apply_forces_and_step(&mut world, &[1., 1.], None);
+        let torques = world.joint_motor_torques();
+        assert_eq!(torques[0], 0.);
+        assert!(torques[1].abs() > 0.);
+    }
+
+    #[test]
+    fn test_motor_torques_follow_the_channels() {
+        let mut world = PhysicsWorld::new();
+        assert!(world.joint_motor_torques().iter().all(|torque| *torque == 0.));
+        world.apply_forearm_force(1.);
+        world.apply_forearm_force(1.);
+        world.apply_joint_force(3, -1.);
+        let torques = world.joint_motor_torques();
+        assert_eq!(torques.len(), world.arm_joint_count());
+        assert!(torques[1].abs() > 0.);
+        assert!(torques[3].abs() > 0.);
+        assert!(torques[1].signum() != torques[3].signum());
+        assert_eq!(torques[0], 0.);
+
+        world.set_arm_pose(&vec![0.; world.arm_joint_count()]);
+        assert!(world.joint_motor_torques().iter().all(|torque| *torque == 0.));
+    }
+
+    #[test]
+    fn test_fingertips_touch_the_ground_the_arm_falls_to() {
+        let mut world = PhysicsWorld::new();
+        assert_eq!(world.fingertip_contacts(), [false, false]);
+        let mut fastest: f32 = 0.;
+        for _ in 0..240 {
+            world.step();
+            fastest = world.arm_joint_angular_velocities().iter().fold(fastest, |fastest, v| fastest.max(v.abs()));
+        }
+        assert!(fastest > 1., "joints turned at most {fastest} rad/s");
+        assert!(world.fingertip_contacts().iter().any(|touching| *touching));
+        assert!(world.arm_contact_count() > 0);
+
+        world.set_arm_pose(&vec![0.; world.arm_joint_count()]);
+        assert!(world.arm_joint_angular_velocities().iter().all(|velocity| *velocity == 0.));
+    }
+}
@@ -0,0 +1,233 @@
+use crate::base_ai::AI;
+use crate::evolution::Genome;
+use burn::prelude::Backend;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder, Recorder};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// Why a population archive could not be written or read.
+#[derive(Debug)]
+pub enum PopulationError {
+    Record(String),
+    Metadata(serde_json::Error),
+    /// The archive does not hold one sigma, operator and age per network.
+    Mismatch,
+}
+
+impl Display for PopulationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PopulationError::Record(e) => write!(f, "could not store the networks: {e}"),
+            PopulationError::Metadata(e) => write!(f, "invalid population metadata: {e}"),
+            PopulationError::Mismatch => {
+                write!(f, "population metadata does not match the networks")
+            }
+        }
+    }
+}
+
+impl Error for PopulationError {}
+
+impl From<serde_json::Error> for PopulationError {
+    fn from(e: serde_json::Error) -> Self {
+        PopulationError::Metadata(e)
+    }
+}
+
+/// Everything about the individuals besides their weights.
+#[derive(Debug, Deserialize, Serialize)]
+struct PopulationMetadata {
+    generation: usize,
+    sigmas: Vec<Vec<f64>>,
+    operators: Vec<Vec<Option<usize>>>,
+    /// Missing from archives saved before individuals had an age, all of them 0 then.
+    #[serde(default)]
+    ages: Vec<Vec<usize>>,
+}
+
+/// Whether the lists hold one value per network, island by island.
+fn one_per_network<R, T>(networks: &[Vec<R>], lists: &[Vec<T>]) -> bool {
+    networks.len() == lists.len()
+        && networks
+            .iter()
+            .zip(lists)
+            .all(|(island, list)| island.len() == list.len())
+}
+
+/// Every individual of every island and the generation they are in, kept in a single archive
+/// so a long run can be analyzed afterwards or resumed exactly as it was.
+#[derive(Clone, Debug)]
+pub struct Population<A> {
+    pub islands: Vec<Vec<Genome<A>>>,
+    pub generation: usize,
+}
+
+impl<A> Population<A> {
+    pub fn new(islands: Vec<Vec<Genome<A>>>, generation: usize) -> Self {
+        Self {
+            islands,
+            generation,
+        }
+    }
+
+    /// Writes the archive to `path`, with the recorder's extension.
+    pub fn save<B: Backend>(
+        &self,
+        path: impl AsRef<Path>,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> Result<(), PopulationError>
+    where
+        A: AI<B>,
+    {
+        let metadata = PopulationMetadata {
+            generation: self.generation,
+            sigmas: self
+                .islands
+                .iter()
+                .map(|island| island.iter().map(|genome| genome.sigma).collect())
+                .collect(),
+            operators: self
+                .islands
+                .iter()
+                .map(|island| island.iter().map(|genome| genome.operator).collect())
+                .collect(),
+            ages: self
+                .islands
+                .iter()
+                .map(|island| island.iter().map(|genome| genome.age).collect())
+                .collect(),
+        };
+        let networks: Vec<Vec<A::Record>> = self
+            .islands
+            .iter()
+            .map(|island| {
+                island
+                    .iter()
+                    .map(|genome| genome.ai().clone().into_record())
+                    .collect()
+            })
+            .collect();
+        recorder
+            .record(
+                (networks, serde_json::to_string(&metadata)?),
+                path.as_ref().to_path_buf(),
+            )
+            .map_err(|e| PopulationError::Record(e.to_string()))
+    }
+
+    /// Reads an archive written by `save`, loading the networks into copies of `sample`.
+    pub fn load<B: Backend>(
+        path: impl AsRef<Path>,
+        sample: &A,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+        device: &B::Device,
+    ) -> Result<Self, PopulationError>
+    where
+        A: AI<B>,
+    {
+        let (networks, metadata): (Vec<Vec<A::Record>>, String) = recorder
+            .load(path.as_ref().to_path_buf(), device)
+            .map_err(|e| PopulationError::Record(e.to_string()))?;
+        let metadata: PopulationMetadata = serde_json::from_str(&metadata)?;
+        // archives without ages have none at all
+        if !one_per_network(&networks, &metadata.sigmas)
+            || !one_per_network(&networks, &metadata.operators)
+            || !(metadata.ages.is_empty() || one_per_network(&networks, &metadata.ages))
+        {
+            return Err(PopulationError::Mismatch);
+        }
+
+        let mut ages = metadata.ages.into_iter();
+        let islands = networks
+            .into_iter()
+            .zip(metadata.sigmas)
+            .zip(metadata.operators)
+            .map(|((island, sigmas), operators)| {
+                let ages = ages.next().unwrap_or_default();
+                island
+                    .into_iter()
+                    .zip(sigmas)
+                    .zip(operators)
+                    .enumerate()
+                    .map(|(k, ((record, sigma), operator))| {
+                        let mut genome = Genome::new(sample.clone().load_record(record));
+                        genome.sigma = sigma;
+                        genome.operator = operator;
+                        genome.age = ages.get(k).copied().unwrap_or(0);
+                        genome
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(Self {
+            islands,
+            generation: metadata.generation,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+    use std::env::temp_dir;
+
+    #[test]
+    fn test_population_round_trip() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
+        let islands: Vec<Vec<_>> = (0..2)
+            .map(|i| {
+                (0..3)
+                    .map(|j| {
+                        let mut genome = Genome::new(SmallAI::<BE>::new(&device));
+                        genome.sigma = (i * 3 + j) as f64;
+                        genome.operator = Some(j);
+                        genome.age = i + j;
+                        genome
+                    })
+                    .collect()
+            })
+            .collect();
+        let path = temp_dir().join(format!("population_test_{}", std::process::id()));
+        Population::new(islands.clone(), 42)
+            .save(&path, &recorder)
+            .unwrap();
+
+        let loaded =
+            Population::load(&path, &SmallAI::<BE>::new(&device), &recorder, &device).unwrap();
+
+        // an archive missing an operator is not read as if the rest were in order
+        type Record = <SmallAI<BE> as burn::module::Module<BE>>::Record;
+        let (networks, metadata): (Vec<Vec<Record>>, String) =
+            recorder.load(path.clone(), &device).unwrap();
+        let mut metadata: PopulationMetadata = serde_json::from_str(&metadata).unwrap();
+        metadata.operators[1].pop();
+        recorder
+            .record(
+                (networks, serde_json::to_string(&metadata).unwrap()),
+                path.clone(),
+            )
+            .unwrap();
+        assert!(matches!(
+            Population::load(&path, &SmallAI::<BE>::new(&device), &recorder, &device),
+            Err(PopulationError::Mismatch)
+        ));
+        std::fs::remove_file(path.with_extension("mpk")).unwrap();
+        assert_eq!(loaded.generation, 42);
+        for (island, loaded_island) in islands.iter().zip(&loaded.islands) {
+            assert_eq!(island.len(), loaded_island.len());
+            for (genome, loaded_genome) in island.iter().zip(loaded_island) {
+                assert_eq!(genome.fingerprint(), loaded_genome.fingerprint());
+                assert_eq!(genome.sigma, loaded_genome.sigma);
+                assert_eq!(genome.operator, loaded_genome.operator);
+                assert_eq!(genome.age, loaded_genome.age);
+            }
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use crate::base_ai::AI;
+use crate::behavior::BehaviorDescriptor;
+use burn::prelude::Backend;
+use burn::record::{BinBytesRecorder, FullPrecisionSettings, Recorder};
+use std::collections::VecDeque;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Bumped whenever the messages change, so a worker built from other sources is turned away.
+pub const PROTOCOL_VERSION: u32 = 2;
+/// The longest message taken, far more than the largest network, so a corrupt or hostile length
+/// is refused rather than allocated.
+const MAX_FRAME_BYTES: u64 = 1 << 30;
+/// How long a worker may take over a network, or either side over sending a message, before
+/// its connection is given up.
+const TIMEOUT: Duration = Duration::from_secs(600);
+/// How long a coordinator waits for a new connection to introduce itself.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The network's record as bytes, to be sent to a worker.
+pub fn encode_network<B: Backend, A: AI<B>>(ai: &A) -> Vec<u8> {
+    BinBytesRecorder::<FullPrecisionSettings>::default()
+        .record(ai.clone().into_record(), ())
+        .expect("could not record the network")
+}
+
+/// Loads the bytes of [`encode_network`] into a copy of `sample`.
+pub fn decode_network<B: Backend, A: AI<B>>(
+    sample: A,
+    bytes: Vec<u8>,
+    device: &B::Device,
+) -> io::Result<A> {
+    let record = BinBytesRecorder::<FullPrecisionSettings>::default()
+        .load(bytes, device)
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))?;
+    Ok(sample.load_record(record))
+}
+
+/// Messages go as their length in 8 little endian bytes followed by the message.
+fn write_frame(stream: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    stream.write_all(&(bytes.len() as u64).to_le_bytes())?;
+    stream.write_all(bytes)?;
+    stream.flush()
+}
+
+fn read_frame(stream: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut length = [0; 8];
+    stream.read_exact(&mut length)?;
+    let length = u64::from_le_bytes(length);
+    if length > MAX_FRAME_BYTES {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("message of {length} bytes is too long"),
+        ));
+    }
+    let mut bytes = vec![0; length as usize];
+    stream.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, format!("malformed {what}"))
+}
+
+/// What a worker introduces itself with: the protocol version and the network it scores.
+fn hello(network: &str) -> Vec<u8> {
+    [&PROTOCOL_VERSION.to_le_bytes()[..], network.as_bytes()].concat()
+}
+
+/// The coordinator's side of the handshake: takes the connection only if the worker speaks
+/// the same protocol about the same network, and tells it whether it was taken.
+fn greet(stream: &mut TcpStream, network: &str) -> io::Result<()> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let accepted = read_frame(stream)? == hello(network);
+    write_frame(stream, &[accepted as u8])?;
+    if !accepted {
+        return Err(invalid("handshake"));
+    }
+    stream.set_read_timeout(Some(TIMEOUT))
+}
+
+/// A genome to score: the seed of the episode and the encoded network.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvaluationRequest {
+    pub seed: u64,
+    pub network: Vec<u8>,
+}
+
+impl EvaluationRequest {
+    fn to_bytes(&self) -> Vec<u8> {
+        [&self.seed.to_le_bytes()[..], &self.network].concat()
+    }
+
+    fn from_bytes(mut bytes: Vec<u8>) -> io::Result<Self> {
+        if bytes.len() < 8 {
+            return Err(invalid("evaluation request"));
+        }
+        let network = bytes.split_off(8);
+        let seed = u64::from_le_bytes(bytes.try_into().expect("8 bytes were split off"));
+        Ok(Self { seed, network })
+    }
+}
+
+/// What a worker sends back for a request.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EvaluationResult {
+    pub score: f32,
+    pub behavior: BehaviorDescriptor,
+}
+
+impl EvaluationResult {
+    fn to_bytes(self) -> Vec<u8> {
+        let behavior = self.behavior;
+        [
+            self.score.to_le_bytes(),
+            behavior.final_fingertip.0.to_le_bytes(),
+            behavior.final_fingertip.1.to_le_bytes(),
+            behavior.mean_height.to_le_bytes(),
+            behavior.contact_rate.to_le_bytes(),
+        ]
+        .concat()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() != 20 {
+            return Err(invalid("evaluation result"));
+        }
+        let float = |at: usize| f32::from_le_bytes(bytes[at..at + 4].try_into().expect("4 bytes"));
+        Ok(Self {
+            score: float(0),
+            behavior: BehaviorDescriptor {
+                final_fingertip: (float(4), float(8)),
+                mean_height: float(12),
+                contact_rate: float(16),
+            },
+        })
+    }
+}
+
+/// Sends one request to a worker and waits for its result.
+fn exchange(stream: &mut TcpStream, request: &EvaluationRequest) -> io::Result<EvaluationResult> {
+    write_frame(stream, &request.to_bytes())?;
+    EvaluationResult::from_bytes(&read_frame(stream)?)
+}
+
+/// The coordinator's side: workers connect to the address it listens on, whenever they
+/// start, and a generation is spread over whoever is connected. A worker process opens a
+/// connection for each of its cores.
+pub struct WorkerPool {
+    address: SocketAddr,
+    workers: Arc<Mutex<Vec<TcpStream>>>,
+}
+
+impl WorkerPool {
+    /// Starts accepting workers of the named network in the background. Workers of another
+    /// protocol version or network are turned away.
+    pub fn listen(address: impl ToSocketAddrs, network: &'static str) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let workers = Arc::new(Mutex::new(Vec::new()));
+        let accepted = Arc::clone(&workers);
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let accepted = Arc::clone(&accepted);
+                // each on its own, so a silent connection does not hold up the others
+                std::thread::spawn(move || {
+                    stream.set_nodelay(true).ok();
+                    let greeted = stream
+                        .set_write_timeout(Some(TIMEOUT))
+                        .and_then(|_| greet(&mut stream, network));
+                    match greeted {
+                        Ok(()) => accepted.lock().expect("worker list poisoned").push(stream),
+                        Err(e) => eprintln!("turning away {:?}: {e}", stream.peer_addr()),
+                    }
+                });
+            }
+        });
+        Ok(Self { address, workers })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Connections currently open.
+    pub fn len(&self) -> usize {
+        self.workers.lock().expect("worker list poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Scores the requests on the connected workers, each taking the next request as soon as
+    /// it is done with the last. A worker that fails is dropped and its request handed to
+    /// another; the requests left when none remain come back as `None`, to be scored locally.
+    pub fn evaluate(&self, requests: Vec<EvaluationRequest>) -> Vec<Option<EvaluationResult>> {
+        let workers = std::mem::take(&mut *self.workers.lock().expect("worker list poisoned"));
+        let mut results = vec![None; requests.len()];
+        let queue = Mutex::new(requests.into_iter().enumerate().collect::<VecDeque<_>>());
+        let scored = Mutex::new(Vec::new());
+        let survivors = Mutex::new(Vec::new());
+        std::thread::scope(|scope| {
+            for mut stream in workers {
+                let (queue, scored, survivors) = (&queue, &scored, &survivors);
+                scope.spawn(move || loop {
+                    let Some((index, request)) = queue.lock().expect("queue poisoned").pop_front()
+                    else {
+                        survivors.lock().expect("worker list poisoned").push(stream);
+                        return;
+                    };
+                    match exchange(&mut stream, &request) {
+                        Ok(result) => scored
+                            .lock()
+                            .expect("results poisoned")
+                            .push((index, result)),
+                        Err(e) => {
+                            eprintln!("dropping worker {:?}: {e}", stream.peer_addr());
+                            queue
+                                .lock()
+                                .expect("queue poisoned")
+                                .push_back((index, request));
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        self.workers
+            .lock()
+            .expect("worker list poisoned")
+            .extend(survivors.into_inner().expect("worker list poisoned"));
+        for (index, result) in scored.into_inner().expect("results poisoned") {
+            results[index] = Some(result);
+        }
+        results
+    }
+}
+
+/// Hangs up on the workers, which then stop serving.
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        for stream in self.workers.lock().expect("worker list poisoned").iter() {
+            stream.shutdown(Shutdown::Both).ok();
+        }
+    }
+}
+
+/// The worker's side of a connection: introduces itself with the network of `sample`, then
+/// loads every network it receives into a copy of `sample`, scores it with `score` and sends
+/// the result back, until the coordinator hangs up. Requests are waited for without a timeout,
+/// as the coordinator may be busy between generations. Returns how many networks were scored.
+pub fn serve<B: Backend, A: AI<B>>(
+    mut stream: TcpStream,
+    sample: &A,
+    device: &B::Device,
+    score: impl Fn(&A, u64) -> (f32, BehaviorDescriptor),
+) -> io::Result<usize> {
+    stream.set_nodelay(true).ok();
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    write_frame(&mut stream, &hello(sample.network_name()))?;
+    if read_frame(&mut stream)? != [1] {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "the coordinator does not take {} networks over protocol {PROTOCOL_VERSION}",
+                sample.network_name()
+            ),
+        ));
+    }
+    stream.set_read_timeout(None)?;
+    let mut served = 0;
+    loop {
+        let request = match read_frame(&mut stream) {
+            Ok(bytes) => EvaluationRequest::from_bytes(bytes)?,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(served),
+            Err(e) => return Err(e),
+        };
+        let ai = decode_network(sample.clone(), request.network, device)?;
+        let (score, behavior) = score(&ai, request.seed);
+        write_frame(
+            &mut stream,
+            &EvaluationResult { score, behavior }.to_bytes(),
+        )?;
+        served += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim_for_ai::OBSERVATION_SIZE;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+    use burn::prelude::Tensor;
+
+    type BE = NdArray<f32>;
+
+    #[test]
+    fn test_workers_score_the_requests() {
+        let device = NdArrayDevice::Cpu;
+        let ai = SmallAI::<BE>::new(&device);
+        let output = |ai: &SmallAI<BE>| {
+            let observation = Tensor::<BE, 1>::ones([OBSERVATION_SIZE], &NdArrayDevice::Cpu);
+            ai.apply(observation).into_data().to_vec::<f32>().unwrap()
+        };
+        let expected = output(&ai);
+
+        let pool = WorkerPool::listen("127.0.0.1:0", ai.network_name()).unwrap();
+        assert_eq!(
+            pool.evaluate(vec![EvaluationRequest {
+                seed: 0,
+                network: Vec::new()
+            }]),
+            vec![None]
+        );
+        let address = pool.address();
+        // a worker of another network is turned away at the handshake
+        let mut stranger = TcpStream::connect(address).unwrap();
+        write_frame(&mut stranger, &hello("other")).unwrap();
+        assert_eq!(read_frame(&mut stranger).unwrap(), [0]);
+        let worker = std::thread::spawn(move || {
+            let device = NdArrayDevice::Cpu;
+            let stream = TcpStream::connect(address).unwrap();
+            // the score tells which request it was for, and whether the network arrived intact
+            serve(stream, &SmallAI::<BE>::new(&device), &device, |ai, seed| {
+                let behavior = BehaviorDescriptor {
+                    contact_rate: seed as f32,
+                    ..Default::default()
+                };
+                (output(ai).iter().sum(), behavior)
+            })
+            .unwrap()
+        });
+        while pool.is_empty() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let network = encode_network(&ai);
+        let requests = (0..5)
+            .map(|seed| EvaluationRequest {
+                seed,
+                network: network.clone(),
+            })
+            .collect();
+        let results = pool.evaluate(requests);
+        for (seed, result) in results.into_iter().enumerate() {
+            let result = result.expect("the worker scores every request");
+            assert_eq!(result.score, expected.iter().sum::<f32>());
+            assert_eq!(result.behavior.contact_rate, seed as f32);
+        }
+        drop(pool);
+        assert_eq!(worker.join().unwrap(), 5);
+
+        // a length past the cap is refused before anything is allocated
+        let length = (MAX_FRAME_BYTES + 1).to_le_bytes();
+        assert!(read_frame(&mut &length[..]).is_err_and(|e| e.kind() == ErrorKind::InvalidData));
+    }
+}
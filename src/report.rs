@@ -0,0 +1,246 @@
+use crate::base_ai::AI;
+use crate::behavior::BehaviorDescriptor;
+use crate::evolution::Genome;
+use crate::schedule::OperatorTally;
+use crate::speciation::parameter_descriptor;
+use crate::telemetry::TimingSummary;
+use burn::prelude::Backend;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Bins of the score histograms.
+const HISTOGRAM_BINS: usize = 10;
+
+/// How many scores fall into each of equal bins between the lowest and the highest.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Histogram {
+    pub min: f32,
+    pub max: f32,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    pub fn of(scores: &[f32], bins: usize) -> Self {
+        let min = scores.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let mut counts = vec![0; bins];
+        for score in scores {
+            // all scores alike land in the first bin, the highest in the last
+            let bin = match max > min {
+                true => (((score - min) / (max - min) * bins as f32) as usize).min(bins - 1),
+                false => 0,
+            };
+            counts[bin] += 1;
+        }
+        Self { min, max, counts }
+    }
+}
+
+/// The mean distance of the descriptors to their centroid, 0 for an island of clones.
+pub fn diversity(descriptors: &[Vec<f32>]) -> f32 {
+    let Some(first) = descriptors.first() else {
+        return 0.;
+    };
+    let mut centroid = vec![0.; first.len()];
+    for descriptor in descriptors {
+        for (sum, value) in centroid.iter_mut().zip(descriptor) {
+            *sum += value / descriptors.len() as f32;
+        }
+    }
+    descriptors
+        .iter()
+        .map(|descriptor| {
+            descriptor
+                .iter()
+                .zip(&centroid)
+                .map(|(a, b)| (a - b).powi(2))
+                .sum::<f32>()
+                .sqrt()
+        })
+        .sum::<f32>()
+        / descriptors.len() as f32
+}
+
+/// The offspring of an operator, as `OperatorTally` counts them.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct OperatorReport {
+    pub name: String,
+    pub produced: usize,
+    pub selected: usize,
+}
+
+/// How one island did in a generation.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct IslandReport {
+    pub island: usize,
+    pub best_score: f32,
+    pub median_score: f32,
+    pub worst_score: f32,
+    pub histogram: Histogram,
+    /// `diversity` of the parameters, scaled like `parameter_descriptor` scales them.
+    pub diversity: f32,
+    pub best_behavior: BehaviorDescriptor,
+    pub operators: Vec<OperatorReport>,
+    /// The island's share of the evaluation time.
+    pub eval_ms: u128,
+    /// The seeds of the episodes the island was scored in.
+    #[serde(default)]
+    pub episode_seeds: Vec<u64>,
+}
+
+impl IslandReport {
+    /// The report of an island scored and ranked best first, with the behaviors in the same
+    /// order.
+    pub fn of<B: Backend, A: AI<B>>(
+        island: usize,
+        ranked: &[(f32, Genome<A>)],
+        behaviors: &[BehaviorDescriptor],
+        tallies: &[OperatorTally],
+        eval_time: Duration,
+        episode_seeds: Range<u64>,
+    ) -> Self {
+        let scores: Vec<f32> = ranked.iter().map(|(score, _)| *score).collect();
+        let descriptors: Vec<Vec<f32>> = ranked
+            .iter()
+            .map(|(_, genome)| parameter_descriptor(genome.ai()))
+            .collect();
+        Self {
+            island,
+            best_score: scores[0],
+            median_score: scores[scores.len() / 2],
+            worst_score: scores[scores.len() - 1],
+            histogram: Histogram::of(&scores, HISTOGRAM_BINS),
+            diversity: diversity(&descriptors),
+            best_behavior: behaviors[0],
+            operators: tallies
+                .iter()
+                .map(|tally| OperatorReport {
+                    name: tally.name.to_string(),
+                    produced: tally.produced,
+                    selected: tally.selected,
+                })
+                .collect(),
+            eval_ms: eval_time.as_millis(),
+            episode_seeds: episode_seeds.collect(),
+        }
+    }
+}
+
+/// Everything about a generation, written as JSON into the `reports` directory of the run,
+/// so dashboards and notebooks follow a run without parsing its output.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct GenerationReport {
+    pub generation: usize,
+    /// The best score of the run so far.
+    pub best_score: f32,
+    /// Individuals scored in the generation.
+    pub evaluations: usize,
+    pub eval_ms: u128,
+    /// From the start of the evaluation to the next generation being bred.
+    pub generation_ms: u128,
+    /// The times of the individuals scored in this process, missing when all were cached or
+    /// scored by workers.
+    #[serde(default)]
+    pub timing: Option<TimingSummary>,
+    pub islands: Vec<IslandReport>,
+}
+
+impl GenerationReport {
+    pub fn path(run_directory: &Path, generation: usize) -> PathBuf {
+        run_directory
+            .join("reports")
+            .join(format!("generation_{generation:05}.json"))
+    }
+
+    pub fn save(&self, run_directory: &Path) -> std::io::Result<()> {
+        let path = Self::path(run_directory, self.generation);
+        std::fs::create_dir_all(path.parent().expect("reports have a directory"))?;
+        std::fs::write(
+            path,
+            serde_json::to_string_pretty(self).map_err(std::io::Error::other)?,
+        )
+    }
+
+    pub fn load(run_directory: &Path, generation: usize) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(Self::path(run_directory, generation))?;
+        serde_json::from_str(&text).map_err(std::io::Error::other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+
+    #[test]
+    fn test_histogram_and_diversity() {
+        let histogram = Histogram::of(&[0., 0.1, 0.5, 0.95, 1.], 4);
+        assert_eq!(
+            (histogram.min, histogram.max, histogram.counts),
+            (0., 1., vec![2, 0, 1, 2])
+        );
+        assert_eq!(Histogram::of(&[0.3, 0.3], 4).counts, vec![2, 0, 0, 0]);
+
+        assert_eq!(diversity(&[vec![1., 2.], vec![1., 2.]]), 0.);
+        assert_eq!(diversity(&[vec![0., 0.], vec![2., 0.]]), 1.);
+    }
+
+    #[test]
+    fn test_report_round_trip() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let ranked: Vec<(f32, Genome<SmallAI<BE>>)> = [0.9, 0.5, 0.2]
+            .into_iter()
+            .map(|score| (score, Genome::new(SmallAI::new(&device))))
+            .collect();
+        let behaviors = vec![
+            BehaviorDescriptor {
+                final_fingertip: (0.5, 0.25),
+                mean_height: 0.3,
+                contact_rate: 0.25
+            };
+            3
+        ];
+        let tallies = [OperatorTally {
+            name: "jiggle",
+            produced: 2,
+            selected: 1,
+        }];
+        let island = IslandReport::of(
+            1,
+            &ranked,
+            &behaviors,
+            &tallies,
+            Duration::from_millis(42),
+            3..5,
+        );
+        assert_eq!(
+            (island.best_score, island.median_score, island.worst_score),
+            (0.9, 0.5, 0.2)
+        );
+        assert_eq!(island.histogram.counts.iter().sum::<usize>(), 3);
+        assert!(island.diversity > 0.);
+        assert_eq!(island.operators[0].name, "jiggle");
+        assert_eq!(island.episode_seeds, vec![3, 4]);
+
+        let report = GenerationReport {
+            generation: 7,
+            best_score: 0.9,
+            evaluations: 3,
+            eval_ms: 42,
+            generation_ms: 50,
+            timing: None,
+            islands: vec![island],
+        };
+        let directory = std::env::temp_dir().join(format!("report_test_{}", std::process::id()));
+        report.save(&directory).unwrap();
+        assert!(GenerationReport::path(&directory, 7).ends_with("reports/generation_00007.json"));
+        let loaded = GenerationReport::load(&directory, 7).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(loaded, report);
+    }
+}
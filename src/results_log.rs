@@ -0,0 +1,221 @@
+use crate::base_ai::AI;
+use crate::evolution::Genome;
+use burn::prelude::Backend;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+const CSV_HEADER: &str = "generation,island,best_score,median_score,worst_score,sigma,eval_ms,best_fingerprint,episode_seed";
+/// The header of the logs from before the episode seed column, which are read as well.
+const OLD_CSV_HEADER: &str =
+    "generation,island,best_score,median_score,worst_score,sigma,eval_ms,best_fingerprint";
+
+/// How one island did in one generation.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct GenerationRecord {
+    pub generation: usize,
+    pub island: usize,
+    pub best_score: f32,
+    pub median_score: f32,
+    pub worst_score: f32,
+    /// The best individual's.
+    pub sigma: f64,
+    pub eval_ms: u128,
+    /// The best individual's, as hex since JSON numbers lose the low bits of a `u64`.
+    pub best_fingerprint: String,
+    /// The first of the island's consecutive episode seeds the scores were taken in, 0 in the
+    /// logs from before islands had seeds of their own.
+    #[serde(default)]
+    pub episode_seed: u64,
+}
+
+impl GenerationRecord {
+    /// The record of an island scored and ranked best first in the episodes from `episode_seed`.
+    pub fn of<B: Backend, A: AI<B>>(
+        generation: usize,
+        island: usize,
+        ranked: &[(f32, Genome<A>)],
+        eval_time: Duration,
+        episode_seed: u64,
+    ) -> Self {
+        let (best_score, best) = ranked.first().expect("an island is never empty");
+        Self {
+            generation,
+            island,
+            best_score: *best_score,
+            median_score: ranked[ranked.len() / 2].0,
+            worst_score: ranked[ranked.len() - 1].0,
+            sigma: best.sigma,
+            eval_ms: eval_time.as_millis(),
+            best_fingerprint: format!("{:016x}", best.fingerprint()),
+            episode_seed,
+        }
+    }
+
+    fn from_csv_row(row: &str) -> Option<Self> {
+        let mut fields: Vec<&str> = row.split(',').collect();
+        // the logs from before the episode seed column have it as 0
+        if fields.len() == 8 {
+            fields.push("0");
+        }
+        let [generation, island, best_score, median_score, worst_score, sigma, eval_ms, best_fingerprint, episode_seed] =
+            fields[..]
+        else {
+            return None;
+        };
+        Some(Self {
+            generation: generation.parse().ok()?,
+            island: island.parse().ok()?,
+            best_score: best_score.parse().ok()?,
+            median_score: median_score.parse().ok()?,
+            worst_score: worst_score.parse().ok()?,
+            sigma: sigma.parse().ok()?,
+            eval_ms: eval_ms.parse().ok()?,
+            best_fingerprint: best_fingerprint.to_string(),
+            episode_seed: episode_seed.parse().ok()?,
+        })
+    }
+
+    fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{}",
+            self.generation,
+            self.island,
+            self.best_score,
+            self.median_score,
+            self.worst_score,
+            self.sigma,
+            self.eval_ms,
+            self.best_fingerprint,
+            self.episode_seed
+        )
+    }
+}
+
+/// Appends a record per island and generation to a file, as JSON lines when it is named
+/// `*.jsonl` and as CSV otherwise. A resumed run keeps adding to the same file.
+pub struct ResultsLog {
+    writer: BufWriter<File>,
+    jsonl: bool,
+}
+
+impl ResultsLog {
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let jsonl = path
+            .extension()
+            .is_some_and(|extension| extension == "jsonl");
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let empty = file.metadata()?.len() == 0;
+        let mut writer = BufWriter::new(file);
+        if empty && !jsonl {
+            writeln!(writer, "{CSV_HEADER}")?;
+        }
+        Ok(Self { writer, jsonl })
+    }
+
+    /// Writes the record through, so the file can be followed while the run goes on.
+    pub fn append(&mut self, record: &GenerationRecord) -> std::io::Result<()> {
+        if self.jsonl {
+            serde_json::to_writer(&mut self.writer, record)?;
+            writeln!(self.writer)?;
+        } else {
+            writeln!(self.writer, "{}", record.csv_row())?;
+        }
+        self.writer.flush()
+    }
+}
+
+/// Reads back the records of a results log, CSV or JSON lines by the name like `ResultsLog`
+/// wrote them.
+pub fn read_results(path: impl AsRef<Path>) -> std::io::Result<Vec<GenerationRecord>> {
+    let path = path.as_ref();
+    let jsonl = path
+        .extension()
+        .is_some_and(|extension| extension == "jsonl");
+    let text = std::fs::read_to_string(path)?;
+    let invalid = |line: &str| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("invalid results record: {line}"),
+        )
+    };
+    text.lines()
+        .filter(|line| !line.is_empty() && *line != CSV_HEADER && *line != OLD_CSV_HEADER)
+        .map(|line| match jsonl {
+            true => serde_json::from_str(line).map_err(|_| invalid(line)),
+            false => GenerationRecord::from_csv_row(line).ok_or_else(|| invalid(line)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+
+    #[test]
+    fn test_results_are_appended() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let ranked: Vec<(f32, Genome<SmallAI<BE>>)> = [0.9, 0.5, 0.2]
+            .into_iter()
+            .map(|score| (score, Genome::new(SmallAI::new(&device))))
+            .collect();
+        let record = GenerationRecord::of(3, 1, &ranked, Duration::from_millis(42), 5);
+        assert_eq!(
+            (record.best_score, record.median_score, record.worst_score),
+            (0.9, 0.5, 0.2)
+        );
+        assert_eq!(
+            record.best_fingerprint,
+            format!("{:016x}", ranked[0].1.fingerprint())
+        );
+
+        let directory = std::env::temp_dir().join(format!("results_log_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let csv = directory.join("results.csv");
+        let jsonl = directory.join("results.jsonl");
+        for _ in 0..2 {
+            ResultsLog::open(&csv).unwrap().append(&record).unwrap();
+            ResultsLog::open(&jsonl).unwrap().append(&record).unwrap();
+        }
+        let csv = std::fs::read_to_string(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_HEADER);
+        assert!(lines[1].starts_with("3,1,0.9,0.5,0.2,"));
+        let jsonl = std::fs::read_to_string(jsonl).unwrap();
+        let parsed: Vec<serde_json::Value> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0]["eval_ms"], 42);
+        assert_eq!(
+            read_results(directory.join("results.csv")).unwrap(),
+            vec![record.clone(), record.clone()]
+        );
+        assert_eq!(
+            read_results(directory.join("results.jsonl")).unwrap(),
+            vec![record.clone(), record.clone()]
+        );
+        // an old log keeps its header, and a resumed run adds rows with the seed column to it
+        let old = directory.join("old_results.csv");
+        std::fs::write(
+            &old,
+            format!("{OLD_CSV_HEADER}\n3,1,0.9,0.5,0.2,0.1,42,ab\n"),
+        )
+        .unwrap();
+        ResultsLog::open(&old).unwrap().append(&record).unwrap();
+        let records = read_results(&old).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].episode_seed, 0);
+        assert_eq!(records[1], record);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+use crate::manifest::Manifest;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// What is known of a saved network, written next to it as `<file stem>.json`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ModelMetadata {
+    pub score: f32,
+    pub generation: usize,
+    pub island: usize,
+    pub sigma: f64,
+}
+
+impl ModelMetadata {
+    /// The sidecar of the network saved at `model`.
+    pub fn path(model: &Path) -> PathBuf {
+        model.with_extension("json")
+    }
+
+    pub fn save(&self, model: &Path) -> std::io::Result<()> {
+        std::fs::write(
+            Self::path(model),
+            serde_json::to_string_pretty(self).map_err(std::io::Error::other)?,
+        )
+    }
+
+    /// `None` for a network saved without a sidecar or with an unreadable one.
+    pub fn load(model: &Path) -> Option<Self> {
+        serde_json::from_str(&std::fs::read_to_string(Self::path(model)).ok()?).ok()
+    }
+}
+
+/// Which saved networks are kept, given as a `[retention]` table of the training
+/// configuration. Without one every network ever saved is kept.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionPolicy {
+    /// The best scoring networks kept, going by the scores in the manifest.
+    pub keep_best: usize,
+    /// The most recently saved networks kept, whatever their score.
+    pub keep_recent: usize,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_best: 10,
+            keep_recent: 10,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// Deletes the saved networks of `network_name` the directory's manifest lists, with their
+    /// sidecars, that are neither among the best nor the most recent, nor in `protected`, the
+    /// files the hall of fame resumes from, and drops them from the manifest. Returns the
+    /// deleted networks.
+    pub fn apply(
+        &self,
+        directory: &Path,
+        network_name: &str,
+        protected: &[String],
+    ) -> std::io::Result<Vec<PathBuf>> {
+        let mut manifest = Manifest::load(directory)?;
+        let saved = manifest.latest(network_name);
+
+        let mut kept: HashSet<&str> = protected.iter().map(String::as_str).collect();
+        kept.extend(
+            saved
+                .iter()
+                .take(self.keep_recent)
+                .map(|entry| entry.file.as_str()),
+        );
+        let mut scored = saved.clone();
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+        kept.extend(
+            scored
+                .into_iter()
+                .take(self.keep_best)
+                .map(|entry| entry.file.as_str()),
+        );
+
+        let deleted: Vec<String> = saved
+            .iter()
+            .filter(|entry| !kept.contains(entry.file.as_str()))
+            .map(|entry| entry.file.clone())
+            .collect();
+        for file in &deleted {
+            let model = directory.join(file);
+            if model.exists() {
+                std::fs::remove_file(&model)?;
+            }
+            let sidecar = ModelMetadata::path(&model);
+            if sidecar.exists() {
+                std::fs::remove_file(sidecar)?;
+            }
+            manifest.remove(file);
+        }
+        manifest.save(directory)?;
+        Ok(deleted
+            .into_iter()
+            .map(|file| directory.join(file))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::ManifestEntry;
+    use std::env::temp_dir;
+
+    #[test]
+    fn test_retention_keeps_the_best_and_the_recent() {
+        let directory = temp_dir().join(format!("retention_test_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut manifest = Manifest::default();
+        let scores = [0.9, 0.1, 0.8, 0.2, 0.3, 0.1, 0.];
+        for (seq, score) in scores.into_iter().enumerate() {
+            let file = format!("best_Small AI_{seq}.mpk");
+            std::fs::write(directory.join(&file), []).unwrap();
+            ModelMetadata {
+                score,
+                generation: seq,
+                island: 0,
+                sigma: 0.1,
+            }
+            .save(&directory.join(&file))
+            .unwrap();
+            manifest.add(ManifestEntry {
+                file,
+                network: "Small AI".to_string(),
+                score,
+                seq,
+            });
+        }
+        std::fs::write(directory.join("best_Medium AI_0.mpk"), []).unwrap();
+        manifest.add(ManifestEntry {
+            file: "best_Medium AI_0.mpk".to_string(),
+            network: "Medium AI".to_string(),
+            score: 0.,
+            seq: 0,
+        });
+        manifest.save(&directory).unwrap();
+
+        let policy = RetentionPolicy {
+            keep_best: 2,
+            keep_recent: 2,
+        };
+        let mut deleted = policy
+            .apply(&directory, "Small AI", &["best_Small AI_1.mpk".to_string()])
+            .unwrap();
+        deleted.sort();
+        assert_eq!(
+            deleted,
+            vec![
+                directory.join("best_Small AI_3.mpk"),
+                directory.join("best_Small AI_4.mpk")
+            ]
+        );
+        assert!(!directory.join("best_Small AI_3.json").exists());
+        assert!(directory.join("best_Medium AI_0.mpk").exists());
+        let kept = [0, 1, 2, 5, 6].map(|seq| directory.join(format!("best_Small AI_{seq}.mpk")));
+        assert!(kept.iter().all(|model| model.exists()));
+        assert_eq!(ModelMetadata::load(&kept[2]).unwrap().score, 0.8);
+        let listed = Manifest::load(&directory).unwrap();
+        assert_eq!(listed.entries.len(), 6);
+        assert!(listed
+            .entries
+            .iter()
+            .all(|entry| entry.file != "best_Small AI_3.mpk"));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
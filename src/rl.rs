@@ -0,0 +1,178 @@
+use crate::base_ai::AI;
+use crate::fitness::{FitnessKind, ShapingConfig};
+use crate::physics::world::PhysicsWorld;
+use crate::sim_for_ai::{apply_forces_and_step, split_grip, EpisodeConfig};
+use burn::module::AutodiffModule;
+use burn::optim::{AdamConfig, GradientsParams, Optimizer};
+use burn::tensor::backend::AutodiffBackend;
+use burn::tensor::{Distribution, Tensor};
+
+/// Settings for vanilla policy gradient (REINFORCE with a normalized return baseline).
+/// The network output is the mean of a Gaussian policy with a fixed spread, so any `AI` can be
+/// trained, e.g. `SmallAI::with_init_std`, and then evaluated with `test_ai` like evolved ones.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PolicyGradientConfig {
+    pub episodes_per_update: usize,
+    pub steps_per_episode: usize,
+    pub learning_rate: f64,
+    pub discount: f32,
+    pub action_std: f32,
+    /// The objective whose task the episodes are played in and rewarded by.
+    pub fitness: FitnessKind,
+}
+
+impl Default for PolicyGradientConfig {
+    fn default() -> Self {
+        Self {
+            episodes_per_update: 4,
+            steps_per_episode: 500,
+            learning_rate: 1e-3,
+            discount: 0.99,
+            action_std: 0.2,
+            fitness: FitnessKind::default(),
+        }
+    }
+}
+
+/// One rolled out episode: the log-probability of every sampled action, still attached to the
+/// autodiff graph, and the reward that followed it.
+struct Episode<B: AutodiffBackend> {
+    log_probs: Vec<Tensor<B, 1>>,
+    rewards: Vec<f32>,
+}
+
+/// Runs the policy in the first episode of the configured objective's task, sampling actions
+/// around its output. Rewards are the per-step scores of the task, as `test_ai_with_fitness`
+/// scores the steps.
+fn roll_out<B: AutodiffBackend, A: AI<B>>(
+    policy: &A,
+    config: &PolicyGradientConfig,
+    device: &B::Device,
+) -> Episode<B> {
+    let mut task = config.fitness.task(ShapingConfig::default());
+    let mut world = PhysicsWorld::with_config(task.world_config(0, &EpisodeConfig::default()));
+    let mut tensor_input = Vec::new();
+    task.reset(&world);
+    policy.reset_state();
+
+    let variance = config.action_std * config.action_std;
+    let mut episode = Episode {
+        log_probs: Vec::new(),
+        rewards: Vec::new(),
+    };
+    for step in 0..config.steps_per_episode {
+        if task.terminated(&world, step) {
+            break;
+        }
+        task.observe(&world, &mut tensor_input);
+        let observation = Tensor::<B, 1>::from_floats(tensor_input.as_slice(), device);
+        let mean = policy.apply(observation);
+        let noise = Tensor::random(
+            mean.shape(),
+            Distribution::Normal(0., config.action_std as f64),
+            device,
+        );
+        let action = mean.clone().detach() + noise;
+        let log_prob = (action.clone() - mean)
+            .powi_scalar(2)
+            .sum()
+            .div_scalar(-2. * variance);
+
+        let data = action.to_data().convert::<f32>();
+        let (forces, grip) = split_grip(
+            policy,
+            data.as_slice().expect("sampled forces not available"),
+        );
+        apply_forces_and_step(&mut world, forces, grip);
+
+        episode.log_probs.push(log_prob);
+        episode.rewards.push(task.reward(&world));
+    }
+    episode
+}
+
+fn discounted_returns(rewards: &[f32], discount: f32) -> Vec<f32> {
+    let mut returns = vec![0.; rewards.len()];
+    let mut running = 0.;
+    for (i, reward) in rewards.iter().enumerate().rev() {
+        running = reward + discount * running;
+        returns[i] = running;
+    }
+    returns
+}
+
+/// Trains `policy` for the given number of updates and returns it together with the mean
+/// undiscounted episode return of every update.
+pub fn train_policy_gradient<B, A>(
+    mut policy: A,
+    config: &PolicyGradientConfig,
+    updates: usize,
+    device: &B::Device,
+) -> (A, Vec<f32>)
+where
+    B: AutodiffBackend,
+    A: AI<B> + AutodiffModule<B>,
+{
+    let mut optimizer = AdamConfig::new().init::<B, A>();
+    let mut mean_returns = Vec::with_capacity(updates);
+
+    for _ in 0..updates {
+        let episodes: Vec<Episode<B>> = (0..config.episodes_per_update)
+            .map(|_| roll_out(&policy, config, device))
+            .collect();
+        let total_reward: f32 = episodes.iter().flat_map(|e| e.rewards.iter()).sum();
+        mean_returns.push(total_reward / episodes.len() as f32);
+
+        let returns: Vec<f32> = episodes
+            .iter()
+            .flat_map(|e| discounted_returns(&e.rewards, config.discount))
+            .collect();
+        let mean = returns.iter().sum::<f32>() / returns.len() as f32;
+        let std =
+            (returns.iter().map(|r| (r - mean).powi(2)).sum::<f32>() / returns.len() as f32).sqrt();
+
+        let weighted: Vec<Tensor<B, 1>> = episodes
+            .into_iter()
+            .flat_map(|e| e.log_probs)
+            .zip(returns)
+            .map(|(log_prob, ret)| log_prob.mul_scalar((ret - mean) / (std + 1e-6)))
+            .collect();
+        let loss = Tensor::cat(weighted, 0).mean().neg();
+
+        let grads = GradientsParams::from_grads(loss.backward(), &policy);
+        policy = optimizer.step(config.learning_rate, policy, grads);
+    }
+    (policy, mean_returns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::{Autodiff, NdArray};
+
+    #[test]
+    fn test_discounted_returns() {
+        assert_eq!(discounted_returns(&[1., 1., 1.], 0.5), vec![1.75, 1.5, 1.]);
+    }
+
+    #[test]
+    fn test_policy_gradient_updates_the_policy() {
+        type BE = Autodiff<NdArray<f32>>;
+        let device = NdArrayDevice::Cpu;
+        let config = PolicyGradientConfig {
+            episodes_per_update: 2,
+            steps_per_episode: 10,
+            ..PolicyGradientConfig::default()
+        };
+        let policy = SmallAI::<BE>::with_init_std(&device, 0.1);
+        let observation = Tensor::<BE, 1>::ones([crate::sim_for_ai::OBSERVATION_SIZE], &device);
+        let before = policy.apply(observation.clone()).to_data();
+
+        let (policy, mean_returns) = train_policy_gradient(policy, &config, 2, &device);
+        assert_eq!(mean_returns.len(), 2);
+        assert!(mean_returns.iter().all(|r| r.is_finite()));
+        assert_ne!(policy.apply(observation).to_data(), before);
+    }
+}
@@ -0,0 +1,227 @@
+use crate::base_ai::{jiggle_linear, max_amp_for_linear, AI, INIT_STD};
+use crate::sim_for_ai::{ACTION_SIZE, OBSERVATION_SIZE};
+use crate::weights::{load_versioned, save_versioned};
+use burn::module::{Ignored, Module};
+use burn::nn::{Initializer, Linear, LinearConfig};
+use burn::prelude::Backend;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+use burn::tensor::activation::{relu, sigmoid, tanh};
+use burn::tensor::{Distribution, Tensor};
+use rand::rngs::StdRng;
+use std::sync::Mutex;
+
+const HIDDEN_SIZE: usize = 32;
+
+/// Hidden state carried between the steps of one episode. Clones start from a blank state,
+/// so offspring never share memory with their parents.
+#[derive(Debug, Default)]
+pub struct EpisodeState(Mutex<Option<Vec<f32>>>);
+
+impl Clone for EpisodeState {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl EpisodeState {
+    fn take(&self) -> Option<Vec<f32>> {
+        self.0.lock().expect("episode state poisoned").take()
+    }
+
+    fn store(&self, hidden: Vec<f32>) {
+        *self.0.lock().expect("episode state poisoned") = Some(hidden);
+    }
+}
+
+/// A GRU controller: the observation is encoded, fed through a gated recurrent cell together
+/// with the previous hidden state, and the new hidden state is mapped to the forces.
+#[derive(Module, Debug)]
+pub struct RnnAI<B: Backend> {
+    input: Linear<B>,
+    update_gate: Linear<B>,
+    reset_gate: Linear<B>,
+    candidate: Linear<B>,
+    output: Linear<B>,
+    state: Ignored<EpisodeState>,
+}
+
+impl<B: Backend> RnnAI<B> {
+    pub fn new(device: &B::Device) -> Self {
+        let input_config = LinearConfig::new(OBSERVATION_SIZE, HIDDEN_SIZE)
+            .with_bias(true)
+            .with_initializer(Initializer::Normal {
+                mean: 0.,
+                std: INIT_STD,
+            });
+
+        let gate_config = LinearConfig::new(HIDDEN_SIZE * 2, HIDDEN_SIZE)
+            .with_bias(true)
+            .with_initializer(Initializer::Normal {
+                mean: 0.,
+                std: INIT_STD,
+            });
+
+        let output_config = LinearConfig::new(HIDDEN_SIZE, ACTION_SIZE)
+            .with_bias(true)
+            .with_initializer(Initializer::Normal {
+                mean: 0.,
+                std: INIT_STD,
+            });
+
+        Self {
+            input: input_config.init(device),
+            update_gate: gate_config.init(device),
+            reset_gate: gate_config.init(device),
+            candidate: gate_config.init(device),
+            output: output_config.init(device),
+            state: Ignored(EpisodeState::default()),
+        }
+    }
+
+    fn from_layers(
+        input: Linear<B>,
+        update_gate: Linear<B>,
+        reset_gate: Linear<B>,
+        candidate: Linear<B>,
+        output: Linear<B>,
+    ) -> Self {
+        Self {
+            input,
+            update_gate,
+            reset_gate,
+            candidate,
+            output,
+            state: Ignored(EpisodeState::default()),
+        }
+    }
+}
+
+impl<B: Backend> AI<B> for RnnAI<B> {
+    fn jiggle(&self, d: &Distribution, rng: &mut StdRng) -> Self {
+        Self::from_layers(
+            jiggle_linear(&self.input, d, rng),
+            jiggle_linear(&self.update_gate, d, rng),
+            jiggle_linear(&self.reset_gate, d, rng),
+            jiggle_linear(&self.candidate, d, rng),
+            jiggle_linear(&self.output, d, rng),
+        )
+    }
+
+    fn apply(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
+        let device = input.device();
+        let hidden = match self.state.take() {
+            Some(hidden) => Tensor::<B, 1>::from_floats(hidden.as_slice(), &device),
+            None => Tensor::zeros([HIDDEN_SIZE], &device),
+        };
+        let x = relu(self.input.forward(input));
+
+        let x_h = Tensor::cat(vec![x.clone(), hidden.clone()], 0);
+        let update = sigmoid(self.update_gate.forward(x_h.clone()));
+        let reset = sigmoid(self.reset_gate.forward(x_h));
+        let candidate = tanh(
+            self.candidate
+                .forward(Tensor::cat(vec![x, reset * hidden.clone()], 0)),
+        );
+        let hidden = update.clone().neg().add_scalar(1.) * candidate + update * hidden;
+
+        let data = hidden.to_data().convert::<f32>();
+        self.state
+            .store(data.to_vec().expect("hidden state not readable"));
+        tanh(self.output.forward(hidden))
+    }
+
+    fn reset_state(&self) {
+        self.state.take();
+    }
+
+    fn max_amp(&self) -> f32 {
+        let all_maximums = [
+            max_amp_for_linear(&self.input),
+            max_amp_for_linear(&self.update_gate),
+            max_amp_for_linear(&self.reset_gate),
+            max_amp_for_linear(&self.candidate),
+            max_amp_for_linear(&self.output),
+        ];
+        all_maximums
+            .iter()
+            .max_by(|a, b| {
+                a.partial_cmp(b)
+                    .expect("max amplitude comparison failed across all layers")
+            })
+            .copied()
+            .expect("no max amplitude found across all layers")
+    }
+
+    fn save_file(&self, filename: &str, recorder: &NamedMpkFileRecorder<FullPrecisionSettings>) {
+        save_versioned(self, filename, recorder).expect("save failed");
+    }
+
+    fn load_a_file(
+        self,
+        filename: &str,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> Self {
+        load_versioned(self, filename, recorder).expect("load failed")
+    }
+
+    fn network_name(&self) -> &'static str {
+        "RnnAI"
+    }
+
+    fn layers(&self) -> Vec<(&'static str, Linear<B>)> {
+        vec![
+            ("input", self.input.clone()),
+            ("update_gate", self.update_gate.clone()),
+            ("reset_gate", self.reset_gate.clone()),
+            ("candidate", self.candidate.clone()),
+            ("output", self.output.clone()),
+        ]
+    }
+
+    fn activations(&self) -> Vec<&'static str> {
+        vec!["relu", "sigmoid", "sigmoid", "tanh", "tanh"]
+    }
+
+    /// The gates are parallel on the input, the candidate reads it through the reset gate, and
+    /// the output the new hidden state the update gate blends the candidate into. The hidden
+    /// state the gates also read is not a layer.
+    fn layer_inputs(&self) -> Vec<Vec<Option<usize>>> {
+        vec![
+            vec![None],
+            vec![Some(0)],
+            vec![Some(0)],
+            vec![Some(0), Some(2)],
+            vec![Some(1), Some(3)],
+        ]
+    }
+
+    fn with_layers(&self, layers: Vec<Linear<B>>) -> Self {
+        let [input, update_gate, reset_gate, candidate, output] =
+            <[Linear<B>; 5]>::try_from(layers).expect("RnnAI has five layers");
+        Self::from_layers(input, update_gate, reset_gate, candidate, output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+
+    #[test]
+    fn test_hidden_state_resets_between_episodes() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let rnn = RnnAI::<BE>::new(&device);
+        let observation = || Tensor::<BE, 1>::ones([OBSERVATION_SIZE], &device);
+
+        let first = rnn.apply(observation()).to_data();
+        let second = rnn.apply(observation()).to_data();
+        assert_ne!(first, second);
+
+        rnn.reset_state();
+        assert_eq!(rnn.apply(observation()).to_data(), first);
+        // offspring start with a blank memory
+        assert_eq!(rnn.clone().apply(observation()).to_data(), first);
+    }
+}
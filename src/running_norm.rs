@@ -0,0 +1,138 @@
+use burn::module::{Module, RunningState};
+use burn::prelude::Backend;
+use burn::tensor::Tensor;
+
+const EPSILON: f32 = 1e-5;
+/// Standardized inputs are cut here, so a rare extreme observation can't saturate the network.
+const CLIP: f32 = 5.;
+
+/// Per feature mean and spread of the observations a network has been shown, kept with
+/// Welford's online algorithm. Part of the network's record, so the statistics are saved and
+/// loaded with the weights.
+///
+/// Standardizing leaves the statistics as they are, so a network scores the same every time it
+/// plays an episode; they change only in the copies [`RunningNorm::updated`] makes.
+#[derive(Module, Debug)]
+pub struct RunningNorm<B: Backend> {
+    count: RunningState<Tensor<B, 1>>,
+    mean: RunningState<Tensor<B, 1>>,
+    m2: RunningState<Tensor<B, 1>>,
+}
+
+impl<B: Backend> RunningNorm<B> {
+    pub fn new(features: usize, device: &B::Device) -> Self {
+        Self {
+            count: RunningState::new(Tensor::zeros([features], device)),
+            mean: RunningState::new(Tensor::zeros([features], device)),
+            m2: RunningState::new(Tensor::zeros([features], device)),
+        }
+    }
+
+    /// A copy of the statistics so far that is updated separately, e.g. for an offspring.
+    pub fn detached(&self) -> Self {
+        Self {
+            count: RunningState::new(self.count.value_sync()),
+            mean: RunningState::new(self.mean.value_sync()),
+            m2: RunningState::new(self.m2.value_sync()),
+        }
+    }
+
+    pub fn mean(&self) -> Tensor<B, 1> {
+        self.mean.value_sync()
+    }
+
+    /// Population standard deviation of the observations seen, 1 for features not seen yet.
+    pub fn std(&self) -> Tensor<B, 1> {
+        let count = self.count.value_sync();
+        let unseen = count.clone().equal_elem(0.);
+        (self.m2.value_sync() / count.clamp_min(1.))
+            .add_scalar(EPSILON)
+            .sqrt()
+            .mask_fill(unseen, 1.)
+    }
+
+    /// The input standardized by the statistics, which it leaves as they are. Before any
+    /// observation the input is only clipped.
+    pub fn standardize(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
+        ((input - self.mean()) / self.std()).clamp(-CLIP, CLIP)
+    }
+
+    /// A copy with the observations folded into the statistics, in order, so the same
+    /// observations always give the same statistics.
+    pub fn updated(&self, observations: &[Vec<f32>]) -> Self {
+        let values = |state: &RunningState<Tensor<B, 1>>| -> Vec<f32> {
+            state
+                .value_sync()
+                .into_data()
+                .convert::<f32>()
+                .to_vec()
+                .expect("statistics are not f32")
+        };
+        let (mut count, mut mean, mut m2) =
+            (values(&self.count), values(&self.mean), values(&self.m2));
+        for observation in observations {
+            assert_eq!(
+                observation.len(),
+                mean.len(),
+                "observation of the wrong size"
+            );
+            for (i, &value) in observation.iter().enumerate() {
+                count[i] += 1.;
+                let delta = value - mean[i];
+                mean[i] += delta / count[i];
+                m2[i] += delta * (value - mean[i]);
+            }
+        }
+        let device = self.mean.value_sync().device();
+        let state =
+            |values: Vec<f32>| RunningState::new(Tensor::from_floats(values.as_slice(), &device));
+        Self {
+            count: state(count),
+            mean: state(mean),
+            m2: state(m2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+    use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+    use std::env::temp_dir;
+
+    #[test]
+    fn test_running_norm_tracks_and_saves_statistics() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let fresh = RunningNorm::<BE>::new(2, &device);
+        let input = Tensor::<BE, 1>::from_floats([0.5, -0.25], &device);
+        let unchanged: Vec<f32> = fresh.standardize(input).to_data().to_vec().unwrap();
+        assert_eq!(unchanged, vec![0.5, -0.25]);
+
+        let norm = fresh.updated(&[vec![1., 10.], vec![2., 20.], vec![3., 30.]]);
+        assert_eq!(
+            fresh.mean().to_data().to_vec::<f32>().unwrap(),
+            vec![0., 0.]
+        );
+        let mean: Vec<f32> = norm.mean().to_data().to_vec().unwrap();
+        assert_eq!(mean, vec![2., 20.]);
+        let std: Vec<f32> = norm.std().to_data().to_vec().unwrap();
+        assert!((std[0] - (2f32 / 3.).sqrt()).abs() < 1e-4, "{std:?}");
+        assert!((std[1] - (200f32 / 3.).sqrt()).abs() < 1e-3, "{std:?}");
+
+        let input = Tensor::<BE, 1>::from_floats([2., 20.], &device);
+        norm.standardize(input);
+        assert_eq!(norm.mean().to_data().to_vec::<f32>().unwrap(), mean);
+
+        let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
+        let path = temp_dir().join(format!("running_norm_test_{}", std::process::id()));
+        norm.clone().save_file(&path, &recorder).unwrap();
+        let loaded = RunningNorm::<BE>::new(2, &device)
+            .load_file(&path, &recorder, &device)
+            .unwrap();
+        std::fs::remove_file(path.with_extension("mpk")).unwrap();
+        assert_eq!(loaded.mean().to_data().to_vec::<f32>().unwrap(), mean);
+    }
+}
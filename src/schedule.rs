@@ -0,0 +1,427 @@
+use crate::base_ai::AI;
+use crate::crossover::{
+    AlternateLayers, Average, CombineWeightsAndBiases, CrossoverStrategy, Interleave,
+    InterleaveNeurons, KeepParent,
+};
+use crate::evolution::Genome;
+use burn::prelude::Backend;
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// Why an operator schedule could not be configured.
+#[derive(Debug)]
+pub enum ScheduleError {
+    Io(std::io::Error),
+    Parse(String),
+    UnknownOperator(String),
+}
+
+impl Display for ScheduleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleError::Io(e) => write!(f, "could not read operator schedule: {e}"),
+            ScheduleError::Parse(e) => write!(f, "could not parse operator schedule: {e}"),
+            ScheduleError::UnknownOperator(name) => write!(f, "no genetic operator named {name}"),
+        }
+    }
+}
+
+impl Error for ScheduleError {}
+
+impl From<std::io::Error> for ScheduleError {
+    fn from(e: std::io::Error) -> Self {
+        ScheduleError::Io(e)
+    }
+}
+
+/// Operator weights by name, e.g. read from `{"weights": {"prune": 0.5}, "adaptation_rate": 0.1}`.
+/// Operators left out keep their default weight.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ScheduleConfig {
+    #[serde(default)]
+    pub weights: HashMap<String, f32>,
+    pub adaptation_rate: Option<f32>,
+}
+
+impl ScheduleConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScheduleError> {
+        let text = std::fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(|e| ScheduleError::Parse(e.to_string()))
+    }
+}
+
+/// How many offspring of a generation an operator produced and how many of them made it into
+/// the surviving fraction.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OperatorTally {
+    pub name: &'static str,
+    pub produced: usize,
+    pub selected: usize,
+}
+
+impl OperatorTally {
+    pub fn success_rate(&self) -> Option<f32> {
+        (self.produced > 0).then(|| self.selected as f32 / self.produced as f32)
+    }
+}
+
+/// Operator tallies summed over generations and islands, to set the operator weights by.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OperatorStats {
+    pub tallies: Vec<OperatorTally>,
+}
+
+impl OperatorStats {
+    pub fn add(&mut self, tallies: &[OperatorTally]) {
+        for tally in tallies {
+            match self
+                .tallies
+                .iter_mut()
+                .find(|total| total.name == tally.name)
+            {
+                Some(total) => {
+                    total.produced += tally.produced;
+                    total.selected += tally.selected;
+                }
+                None => self.tallies.push(tally.clone()),
+            }
+        }
+    }
+}
+
+impl Display for OperatorStats {
+    /// `name selected/produced` per operator, with the success rate once there are offspring.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, tally) in self.tallies.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} {}/{}", tally.name, tally.selected, tally.produced)?;
+            if let Some(rate) = tally.success_rate() {
+                write!(f, " ({:.0}%)", rate * 100.)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A genetic operator producing one offspring from two parents.
+#[derive(Debug)]
+pub enum Operator<B: Backend, A: AI<B>> {
+    /// Recombination followed by the sigma scaled jiggle.
+    Crossover(Box<dyn CrossoverStrategy<B, A>>),
+    /// The mother with weights zeroed at the given probability, without further mutation.
+    Prune(f64),
+    /// The mother with every weight multiplied by the given factor, slightly below one, to hold
+    /// back the weight growth the jiggling drifts into.
+    Decay(f64),
+    /// The mother with a unit added to a random hidden layer, its weights drawn with the given
+    /// spread, so evolution can find how big a network has to be.
+    Grow(f64),
+    /// The mother without the weakest unit of a random hidden layer.
+    Shrink,
+}
+
+/// Named operators with the probabilities `make_offspring` picks them by. With an adaptation
+/// rate set, every generation moves the weights towards the operators whose offspring were
+/// selected among the fittest.
+#[derive(Debug)]
+pub struct OperatorSchedule<B: Backend, A: AI<B>> {
+    operators: Vec<(&'static str, f32, Operator<B, A>)>,
+    adaptation_rate: Option<f32>,
+}
+
+impl<B: Backend, A: AI<B>> Default for OperatorSchedule<B, A> {
+    fn default() -> Self {
+        Self {
+            operators: vec![
+                ("interleave", 5., Operator::Crossover(Box::new(Interleave))),
+                ("average", 4., Operator::Crossover(Box::new(Average))),
+                (
+                    "combine_weights_and_biases",
+                    1.,
+                    Operator::Crossover(Box::new(CombineWeightsAndBiases)),
+                ),
+                (
+                    "alternate_layers",
+                    1.,
+                    Operator::Crossover(Box::new(AlternateLayers)),
+                ),
+                // off unless configured, so runs breed the way they did before it existed
+                (
+                    "interleave_neurons",
+                    0.,
+                    Operator::Crossover(Box::new(InterleaveNeurons)),
+                ),
+                (
+                    "keep_mother",
+                    2.,
+                    Operator::Crossover(Box::new(KeepParent::Mother)),
+                ),
+                (
+                    "keep_father",
+                    2.,
+                    Operator::Crossover(Box::new(KeepParent::Father)),
+                ),
+                ("prune", 1., Operator::Prune(0.05)),
+                ("decay", 0.5, Operator::Decay(0.99)),
+                // off unless configured, as most networks have a fixed shape
+                ("grow", 0., Operator::Grow(0.01)),
+                ("shrink", 0., Operator::Shrink),
+            ],
+            adaptation_rate: None,
+        }
+    }
+}
+
+impl<B: Backend, A: AI<B>> OperatorSchedule<B, A> {
+    /// Adds an operator, or replaces the one with the same name.
+    pub fn with_operator(
+        mut self,
+        name: &'static str,
+        weight: f32,
+        operator: Operator<B, A>,
+    ) -> Self {
+        self.operators.retain(|(n, _, _)| *n != name);
+        self.operators.push((name, weight, operator));
+        self
+    }
+
+    pub fn with_adaptation_rate(mut self, rate: Option<f32>) -> Self {
+        self.adaptation_rate = rate;
+        self
+    }
+
+    pub fn configured(mut self, config: &ScheduleConfig) -> Result<Self, ScheduleError> {
+        for (name, weight) in &config.weights {
+            let (_, w, _) = self
+                .operators
+                .iter_mut()
+                .find(|(n, _, _)| n == name)
+                .ok_or_else(|| ScheduleError::UnknownOperator(name.clone()))?;
+            *w = *weight;
+        }
+        if config.adaptation_rate.is_some() {
+            self.adaptation_rate = config.adaptation_rate;
+        }
+        Ok(self)
+    }
+
+    pub fn adaptation_rate(&self) -> Option<f32> {
+        self.adaptation_rate
+    }
+
+    /// The current weights, which adaptation may have moved, as a configuration restoring them.
+    pub fn config(&self) -> ScheduleConfig {
+        ScheduleConfig {
+            weights: self
+                .operators
+                .iter()
+                .map(|(name, weight, _)| (name.to_string(), *weight))
+                .collect(),
+            adaptation_rate: self.adaptation_rate,
+        }
+    }
+
+    pub fn weight(&self, name: &str) -> Option<f32> {
+        self.operators
+            .iter()
+            .find(|(n, _, _)| *n == name)
+            .map(|(_, w, _)| *w)
+    }
+
+    pub fn operator(&self, index: usize) -> &Operator<B, A> {
+        &self.operators[index].2
+    }
+
+    /// Index of an operator picked in proportion to the weights, or any of them alike when none
+    /// is weighted.
+    pub fn pick(&self, rng: &mut StdRng) -> usize {
+        let total: f32 = self.operators.iter().map(|(_, w, _)| w).sum();
+        if total <= 0. {
+            return rng.random_range(0..self.operators.len());
+        }
+        let mut roll = rng.random_range(0.0..total);
+        for (i, (_, weight, _)) in self.operators.iter().enumerate() {
+            if roll < *weight {
+                return i;
+            }
+            roll -= weight;
+        }
+        self.operators
+            .iter()
+            .rposition(|(_, weight, _)| *weight > 0.)
+            .expect("no operator has a weight")
+    }
+
+    /// Index of an operator other than the crossovers, picked in proportion to the weights, or
+    /// `None` when none of them is weighted.
+    pub fn pick_mutation(&self, rng: &mut StdRng) -> Option<usize> {
+        let mutations = || {
+            self.operators
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, weight, operator))| {
+                    *weight > 0. && !matches!(operator, Operator::Crossover(_))
+                })
+        };
+        let total: f32 = mutations().map(|(_, (_, weight, _))| weight).sum();
+        if total <= 0. {
+            return None;
+        }
+        let mut roll = rng.random_range(0.0..total);
+        for (i, (_, weight, _)) in mutations() {
+            if roll < *weight {
+                return Some(i);
+            }
+            roll -= weight;
+        }
+        mutations().next_back().map(|(i, _)| i)
+    }
+
+    /// Counts each operator's offspring in a generation sorted best first, and how many of them
+    /// are among the `number_of_fittest` best.
+    pub fn tally(
+        &self,
+        ranked: &[(f32, Genome<A>)],
+        number_of_fittest: usize,
+    ) -> Vec<OperatorTally> {
+        let mut tallies: Vec<OperatorTally> = self
+            .operators
+            .iter()
+            .map(|(name, _, _)| OperatorTally {
+                name,
+                ..OperatorTally::default()
+            })
+            .collect();
+        for (rank, (_, genome)) in ranked.iter().enumerate() {
+            if let Some(op) = genome.operator {
+                tallies[op].produced += 1;
+                if rank < number_of_fittest {
+                    tallies[op].selected += 1;
+                }
+            }
+        }
+        tallies
+    }
+
+    /// Rewards the operators by the share of their offspring among the `number_of_fittest`
+    /// best of a generation sorted best first.
+    pub fn adapt(&mut self, ranked: &[(f32, Genome<A>)], number_of_fittest: usize) {
+        let Some(rate) = self.adaptation_rate else {
+            return;
+        };
+        let tallies = self.tally(ranked, number_of_fittest);
+        if tallies.iter().all(|tally| tally.produced == 0) {
+            return;
+        }
+        let produced: Vec<f32> = tallies.iter().map(|tally| tally.produced as f32).collect();
+        let selected: Vec<f32> = tallies.iter().map(|tally| tally.selected as f32).collect();
+
+        // smoothed selection rates, so operators without offspring are neither rewarded nor lost
+        let success: Vec<f32> = selected
+            .iter()
+            .zip(&produced)
+            .map(|(s, p)| (s + 1.) / (p + 2.))
+            .collect();
+        let total_weight: f32 = self.operators.iter().map(|(_, w, _)| w).sum();
+        let mean_success = self
+            .operators
+            .iter()
+            .zip(&success)
+            .map(|((_, w, _), s)| w * s)
+            .sum::<f32>()
+            / total_weight;
+        for ((_, weight, _), success) in self.operators.iter_mut().zip(success) {
+            *weight *= 1. - rate + rate * success / mean_success;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+    use rand::SeedableRng;
+    use std::collections::HashSet;
+
+    type BE = NdArray<f32>;
+
+    #[test]
+    fn test_adapt_rewards_selected_operators() {
+        let device = NdArrayDevice::Cpu;
+        let config: ScheduleConfig =
+            serde_json::from_str(r#"{"weights": {"prune": 5}, "adaptation_rate": 0.5}"#).unwrap();
+        let mut schedule = OperatorSchedule::<BE, SmallAI<BE>>::default()
+            .configured(&config)
+            .unwrap();
+        assert_eq!(schedule.weight("prune"), Some(5.));
+
+        let genome = |operator| {
+            let mut genome = Genome::new(SmallAI::new(&device));
+            genome.operator = Some(operator);
+            genome
+        };
+        // interleave children make the cut, average children don't
+        let ranked = vec![
+            (0.9, genome(0)),
+            (0.8, genome(0)),
+            (0.2, genome(1)),
+            (0.1, genome(1)),
+        ];
+        schedule.adapt(&ranked, 2);
+        assert!(schedule.weight("interleave").unwrap() > 5.);
+        assert!(schedule.weight("average").unwrap() < 4.);
+
+        let mut stats = OperatorStats::default();
+        stats.add(&schedule.tally(&ranked, 2));
+        stats.add(&schedule.tally(&ranked, 1));
+        assert_eq!(
+            stats.tallies[0],
+            OperatorTally {
+                name: "interleave",
+                produced: 4,
+                selected: 3
+            }
+        );
+        assert_eq!(stats.tallies[1].success_rate(), Some(0.));
+        assert_eq!(stats.tallies[2].success_rate(), None);
+        assert!(stats
+            .to_string()
+            .starts_with("interleave 3/4 (75%), average 0/4 (0%)"));
+
+        let unknown = ScheduleConfig {
+            weights: HashMap::from([("mutate".to_string(), 1.)]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            OperatorSchedule::<BE, SmallAI<BE>>::default().configured(&unknown),
+            Err(ScheduleError::UnknownOperator(_))
+        ));
+    }
+
+    #[test]
+    fn test_pick_without_weights_is_uniform() {
+        let schedule = OperatorSchedule::<BE, SmallAI<BE>>::default();
+        let config = ScheduleConfig {
+            weights: schedule
+                .config()
+                .weights
+                .into_keys()
+                .map(|name| (name, 0.))
+                .collect(),
+            ..Default::default()
+        };
+        let schedule = schedule.configured(&config).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        let picked: HashSet<usize> = (0..1000).map(|_| schedule.pick(&mut rng)).collect();
+        assert_eq!(picked.len(), schedule.operators.len());
+    }
+}
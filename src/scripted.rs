@@ -0,0 +1,245 @@
+use crate::base_ai::AI;
+use crate::distill::{DistillationConfig, TeacherSample};
+use crate::physics::world::PhysicsWorld;
+use crate::sim_for_ai::{apply_forces_and_step, EpisodeConfig, Task};
+use burn::prelude::Backend;
+use serde::{Deserialize, Serialize};
+
+/// A hand-written controller that swings the arm into a fixed reaching pose and then closes
+/// the finger and thumb. It does not look at the ball, so it is no good at the task, but its
+/// moves are more purposeful than those of random weights.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScriptedController {
+    /// Joint angles, one per channel, the joints are pulled towards. Joints past the last,
+    /// as of an arm with more of them, are pulled straight.
+    pub reach_pose: Vec<f32>,
+    /// Force per radian the joints are off the pose.
+    pub gain: f32,
+    /// Step from which the grip closes.
+    pub pinch_after: usize,
+}
+
+impl Default for ScriptedController {
+    fn default() -> Self {
+        Self {
+            reach_pose: vec![-0.9, -0.9, -0.3, -0.6, 0., 0., 0., 0.],
+            gain: 2.,
+            pinch_after: 200,
+        }
+    }
+}
+
+impl ScriptedController {
+    /// The forces of the given physics step, one per joint of the world's arm: each joint is
+    /// pulled towards the reach pose, and once the pinch is due the grip synergy is added on top.
+    pub fn action(&self, world: &PhysicsWorld, step: usize) -> Vec<f32> {
+        let pinch = if step >= self.pinch_after {
+            world.grip_synergy()
+        } else {
+            vec![0.; world.arm_joint_count()]
+        };
+        world
+            .arm_joint_angles()
+            .iter()
+            .enumerate()
+            .zip(pinch)
+            .map(|((joint, angle), grip)| {
+                let target = self.reach_pose.get(joint).copied().unwrap_or(0.);
+                (self.gain * (target - angle) + grip).clamp(-1., 1.)
+            })
+            .collect()
+    }
+
+    /// Drives the first episodes of the task as the run plays them and records an observation
+    /// and the controller's action for every action a network would take, up to
+    /// `config.steps_per_episode` of them per episode.
+    pub fn collect_samples(
+        &self,
+        config: &DistillationConfig,
+        task: &mut dyn Task,
+        episode: &EpisodeConfig,
+    ) -> Vec<TeacherSample> {
+        let mut samples = Vec::with_capacity(config.episodes * config.steps_per_episode);
+        for seed in 0..config.episodes as u64 {
+            let mut world = PhysicsWorld::with_config(task.world_config(seed, episode));
+            task.reset(&world);
+            let mut steps = 0;
+            for _ in 0..config.steps_per_episode {
+                if steps >= episode.steps || task.terminated(&world, steps) {
+                    break;
+                }
+                let mut observation = Vec::new();
+                task.observe(&world, &mut observation);
+                let action = self.action(&world, steps);
+                for _ in 0..episode.action_repeat.clamp(1, episode.steps - steps) {
+                    apply_forces_and_step(&mut world, &action, None);
+                    steps += 1;
+                }
+                samples.push(TeacherSample {
+                    observation,
+                    action,
+                });
+            }
+        }
+        samples
+    }
+}
+
+/// Supervised pretraining of the initial population on the scripted controller, given as a
+/// `[warm_start]` table of the training configuration.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WarmStartConfig {
+    /// Episodes the controller is recorded in.
+    pub episodes: usize,
+    pub steps_per_episode: usize,
+    pub epochs: usize,
+    pub batch_size: usize,
+    pub learning_rate: f64,
+    /// Share of every island fitted to the controller, the rest stay random for diversity.
+    pub share: f32,
+}
+
+impl Default for WarmStartConfig {
+    fn default() -> Self {
+        Self {
+            episodes: 2,
+            steps_per_episode: 400,
+            epochs: 10,
+            batch_size: 64,
+            learning_rate: 1e-3,
+            share: 0.25,
+        }
+    }
+}
+
+impl WarmStartConfig {
+    pub fn distillation(&self) -> DistillationConfig {
+        DistillationConfig {
+            episodes: self.episodes,
+            steps_per_episode: self.steps_per_episode,
+            epochs: self.epochs,
+            batch_size: self.batch_size,
+            learning_rate: self.learning_rate,
+        }
+    }
+
+    /// How many of an island of the given size are fitted, at least one.
+    pub fn fitted_count(&self, island_size: usize) -> usize {
+        ((self.share * island_size as f32).round() as usize).clamp(1, island_size)
+    }
+}
+
+/// The mean squared error of the network's actions against the samples.
+pub fn imitation_error<B: Backend, A: AI<B>>(
+    network: &A,
+    samples: &[TeacherSample],
+    device: &B::Device,
+) -> f32 {
+    let total: f32 = samples
+        .iter()
+        .map(|sample| {
+            let action: Vec<f32> = network
+                .apply(burn::tensor::Tensor::from_floats(
+                    sample.observation.as_slice(),
+                    device,
+                ))
+                .to_data()
+                .convert::<f32>()
+                .to_vec()
+                .expect("network forces not available");
+            action
+                .iter()
+                .zip(&sample.action)
+                .map(|(a, b)| (a - b).powi(2))
+                .sum::<f32>()
+                / action.len() as f32
+        })
+        .sum();
+    total / samples.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fitness::{FitnessKind, ShapingConfig};
+    use crate::physics::world::{ArmConfig, WorldConfig};
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_controller_reaches_for_the_pose() {
+        let controller = ScriptedController::default();
+        let world = PhysicsWorld::new();
+        let action = controller.action(&world, 0);
+        assert_eq!(action.len(), world.arm_joint_count());
+        // at rest every joint is at zero, so the first pull is towards the pose, unpinched
+        assert_eq!(action, vec![-1., -1., -0.6, -1., 0., 0., 0., 0.]);
+        assert_eq!(controller.action(&world, 200)[4..], [-1., -1., 1., 1.]);
+
+        let config = DistillationConfig {
+            episodes: 2,
+            steps_per_episode: 30,
+            ..DistillationConfig::default()
+        };
+        let mut task = FitnessKind::default().task(ShapingConfig::default());
+        let samples = controller.collect_samples(&config, &mut *task, &EpisodeConfig::default());
+        assert_eq!(samples.len(), 60);
+        assert!(samples
+            .iter()
+            .all(|sample| sample.action.iter().all(|force| force.abs() <= 1.)));
+        // with action repeats a sample stands for as many physics steps
+        let repeated = EpisodeConfig {
+            action_repeat: 4,
+            steps: 100,
+            ..EpisodeConfig::default()
+        };
+        assert_eq!(
+            controller
+                .collect_samples(&config, &mut *task, &repeated)
+                .len(),
+            50
+        );
+
+        // every joint of a longer arm is driven
+        let arm = ArmConfig {
+            finger_abduction: Some(0.2),
+            ..ArmConfig::default()
+        };
+        let abducting = PhysicsWorld::with_config(WorldConfig {
+            arm,
+            ..WorldConfig::default()
+        });
+        assert_eq!(
+            controller.action(&abducting, 0).len(),
+            abducting.arm_joint_count()
+        );
+    }
+
+    #[test]
+    fn test_imitation_gets_closer_to_the_controller() {
+        let device = NdArrayDevice::Cpu;
+        let config = DistillationConfig {
+            episodes: 1,
+            steps_per_episode: 20,
+            epochs: 30,
+            batch_size: 10,
+            ..DistillationConfig::default()
+        };
+        let mut task = FitnessKind::default().task(ShapingConfig::default());
+        let samples = ScriptedController::default().collect_samples(
+            &config,
+            &mut *task,
+            &EpisodeConfig::default(),
+        );
+        let network = SmallAI::<NdArray<f32>>::with_init_std(&device, 0.1);
+        let before = imitation_error(&network, &samples, &device);
+        let fitted = network
+            .imitate(&samples, &config, &device, &mut StdRng::seed_from_u64(0))
+            .unwrap();
+        assert!(imitation_error(&fitted, &samples, &device) < before);
+    }
+}
@@ -0,0 +1,198 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+/// How parents are picked among the individuals of a generation, sorted best first. The
+/// selection pressure, how strongly the better ones are preferred, is up to the strategy.
+pub trait SelectionStrategy: Debug {
+    /// Index of a parent, given the scores best first and how many are the fittest.
+    fn select(&self, scores: &[f32], number_of_fittest: usize, rng: &mut StdRng) -> usize;
+
+    /// A mother and a father that is not her clone by the fingerprints, or the same one twice
+    /// when there is nobody else.
+    fn select_pair(
+        &self,
+        scores: &[f32],
+        fingerprints: &[u64],
+        number_of_fittest: usize,
+        rng: &mut StdRng,
+    ) -> (usize, usize) {
+        let mother = self.select(scores, number_of_fittest, rng);
+        if scores.len() < 2 {
+            return (mother, mother);
+        }
+        // bounded, as a strategy may favour one individual almost exclusively
+        let father = (0..scores.len() * 4)
+            .map(|_| self.select(scores, number_of_fittest, rng))
+            .find(|&father| fingerprints[father] != fingerprints[mother])
+            .unwrap_or(mother);
+        (mother, father)
+    }
+}
+
+/// Only the fittest are parents, all of them equally likely.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Truncation;
+
+impl SelectionStrategy for Truncation {
+    fn select(&self, _scores: &[f32], number_of_fittest: usize, rng: &mut StdRng) -> usize {
+        rng.random_range(0..number_of_fittest.max(1))
+    }
+
+    fn select_pair(
+        &self,
+        _scores: &[f32],
+        fingerprints: &[u64],
+        number_of_fittest: usize,
+        rng: &mut StdRng,
+    ) -> (usize, usize) {
+        if number_of_fittest < 2 {
+            (0, 0)
+        } else {
+            crate::evolution::make_distinct_by(&fingerprints[..number_of_fittest], rng)
+                .unwrap_or((0, 0))
+        }
+    }
+}
+
+/// Everyone is a parent with a probability proportional to their score, roulette wheel style.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Roulette;
+
+impl SelectionStrategy for Roulette {
+    fn select(&self, scores: &[f32], _number_of_fittest: usize, rng: &mut StdRng) -> usize {
+        let total: f32 = scores.iter().map(|score| score.max(0.)).sum();
+        if total <= 0. {
+            return rng.random_range(0..scores.len());
+        }
+        let mut roll = rng.random_range(0.0..total);
+        for (i, score) in scores.iter().enumerate() {
+            if roll < score.max(0.) {
+                return i;
+            }
+            roll -= score.max(0.);
+        }
+        scores
+            .iter()
+            .rposition(|score| *score > 0.)
+            .expect("some score is positive")
+    }
+}
+
+/// Everyone is a parent with a probability falling linearly with their rank, so the pressure
+/// does not depend on how far apart the scores are. The best is `pressure` times as likely as
+/// the average, between 1 for no preference and 2 for the worst never chosen.
+#[derive(Copy, Clone, Debug)]
+pub struct Rank {
+    pub pressure: f64,
+}
+
+impl SelectionStrategy for Rank {
+    fn select(&self, scores: &[f32], _number_of_fittest: usize, rng: &mut StdRng) -> usize {
+        let n = scores.len();
+        if n < 2 {
+            return 0;
+        }
+        let weight = |rank: usize| {
+            (2. - self.pressure)
+                + 2. * (self.pressure - 1.) * (n - 1 - rank) as f64 / (n - 1) as f64
+        };
+        let mut roll = rng.random_range(0.0..n as f64);
+        for rank in 0..n {
+            if roll < weight(rank) {
+                return rank;
+            }
+            roll -= weight(rank);
+        }
+        (0..n)
+            .rposition(|rank| weight(rank) > 0.)
+            .expect("the best has a weight")
+    }
+}
+
+/// The selection strategy of a run, by name in the training configuration.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Selection {
+    #[default]
+    Truncation,
+    Roulette,
+    Rank,
+}
+
+impl Selection {
+    pub fn strategy(self, pressure: f64) -> Box<dyn SelectionStrategy> {
+        match self {
+            Selection::Truncation => Box::new(Truncation),
+            Selection::Roulette => Box::new(Roulette),
+            Selection::Rank => Box::new(Rank { pressure }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn counts(
+        strategy: &dyn SelectionStrategy,
+        scores: &[f32],
+        number_of_fittest: usize,
+    ) -> Vec<usize> {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut counts = vec![0; scores.len()];
+        for _ in 0..10_000 {
+            counts[strategy.select(scores, number_of_fittest, &mut rng)] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_selection_pressure() {
+        let scores = [0.6, 0.3, 0.1, 0.];
+        let truncation = counts(&Truncation, &scores, 2);
+        assert!(
+            truncation[0] > 4_000 && truncation[1] > 4_000 && truncation[2] == 0,
+            "{truncation:?}"
+        );
+
+        let roulette = counts(&Roulette, &scores, 2);
+        assert!(
+            roulette[0] > 5_500 && roulette[2] > 700 && roulette[3] == 0,
+            "{roulette:?}"
+        );
+
+        let rank = counts(&Rank { pressure: 2. }, &scores, 2);
+        assert!(
+            rank[0] > rank[1] && rank[1] > rank[2] && rank[3] == 0,
+            "{rank:?}"
+        );
+        let flat = counts(&Rank { pressure: 1. }, &scores, 2);
+        assert!(flat.iter().all(|&count| count > 2_000), "{flat:?}");
+
+        let mut rng = StdRng::seed_from_u64(3);
+        for strategy in [Selection::Truncation, Selection::Roulette, Selection::Rank] {
+            let (mother, father) =
+                strategy
+                    .strategy(1.5)
+                    .select_pair(&scores, &[1, 2, 3, 4], 2, &mut rng);
+            assert_ne!(mother, father);
+            // the first two are clones, so only the others are fathers to them
+            let (mother, father) =
+                strategy
+                    .strategy(1.5)
+                    .select_pair(&scores, &[1, 1, 3, 4], 3, &mut rng);
+            assert!(
+                mother == father || [1, 1, 3, 4][mother] != [1, 1, 3, 4][father],
+                "{strategy:?}"
+            );
+        }
+        assert_eq!(
+            Truncation.select_pair(&scores, &[7, 7, 7, 7], 3, &mut rng),
+            (0, 0)
+        );
+        assert_eq!(Roulette.select_pair(&[0.5], &[1], 1, &mut rng), (0, 0));
+    }
+}
@@ -1,8 +1,223 @@
-use burn::prelude::{Backend, Tensor};
 use crate::base_ai::AI;
-use crate::physics::arm::{normalize_x, normalize_y};
+use crate::behavior::{BehaviorDescriptor, BehaviorRecorder};
+use crate::fitness::{Fitness, FitnessKind, PoseRetention, ShapingConfig};
+use crate::physics::trajectory::TrajectoryRecorder;
+use crate::physics::world::{ArmConfig, BallConfig, PhysicsWorld, WorldConfig, BASE_JOINT_COUNT};
 use crate::physics::Corners;
-use crate::physics::world::PhysicsWorld;
+use burn::prelude::{Backend, Tensor};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// One force channel per joint of the default arm, see `action_size` for the others.
+pub const ACTION_SIZE: usize = BASE_JOINT_COUNT;
+/// The previous step's forces appended to the observation with the `action-feedback` feature,
+/// a cheap memory for the feed-forward networks.
+pub const FEEDBACK_SIZE: usize = if cfg!(feature = "action-feedback") {
+    ACTION_SIZE
+} else {
+    0
+};
+/// The stacked frames of the far corners of every segment and the ball and basket slots, plus
+/// the ball velocity, the joint velocities, the fingertip contacts and the action feedback.
+pub const OBSERVATION_SIZE: usize =
+    observation_size(ACTION_SIZE, OBSERVATION_FRAMES) + FEEDBACK_SIZE;
+/// Steps the observation looks back over, the current one included, see `FrameStack`. A
+/// compile-time constant every network and saved layout is sized for: looking back further means
+/// changing it and rebuilding, which makes networks saved before unloadable.
+pub const OBSERVATION_FRAMES: usize = 2;
+/// The ball position and its distance to the basket, in every frame.
+pub const FRAME_OBJECT_SLOTS: usize = 4;
+/// Whether the index fingertip and the thumb tip touch something outside the arm, 1 or 0.
+pub const CONTACT_SLOTS: usize = 2;
+/// Joint speed, in radians per second, observed as 1. Faster joints are clamped to it.
+const MAX_JOINT_SPEED: f32 = 20.;
+
+/// Simulation steps of an episode by default.
+pub const EPISODE_STEPS: usize = 500;
+
+/// How long an episode runs and how often the network acts in it, given as an `[episode]`
+/// table of the training configuration.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EpisodeConfig {
+    /// Physics steps of an episode, unless its task ends it earlier.
+    pub steps: usize,
+    /// Physics steps the forces of one network inference are applied for, at least 1. The
+    /// network is run that many times less often, for faster episodes and steadier forces.
+    #[serde(alias = "physics_steps_per_action")]
+    pub action_repeat: usize,
+    /// Where the ball is put, given as an `[episode.ball]` table.
+    pub ball: BallSpawnConfig,
+}
+
+impl Default for EpisodeConfig {
+    fn default() -> Self {
+        Self {
+            steps: EPISODE_STEPS,
+            action_repeat: 1,
+            ball: BallSpawnConfig::default(),
+        }
+    }
+}
+
+impl EpisodeConfig {
+    /// The world an episode starts in, with the ball put down as drawn from the seed. Unless
+    /// `[episode.ball]` is configured, episode 0 is the default world, the one all scores were
+    /// taken in before there were episodes.
+    pub fn world_config(&self, seed: u64) -> WorldConfig {
+        if seed == 0 && self.ball == BallSpawnConfig::default() {
+            return WorldConfig::default();
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (low, high) = self.ball.x;
+        let x = if low < high {
+            rng.random_range(low..high)
+        } else {
+            low
+        };
+        let speed = self.ball.max_speed;
+        let velocity = match speed > 0. {
+            true => (
+                rng.random_range(-speed..=speed),
+                rng.random_range(0. ..=speed),
+            ),
+            false => (0., 0.),
+        };
+        WorldConfig {
+            balls: vec![BallConfig {
+                x,
+                velocity,
+                ..BallConfig::default()
+            }],
+            ..WorldConfig::default()
+        }
+    }
+}
+
+/// How the ball is put down in the episodes but episode 0, drawn from the episode seed so the
+/// networks learn to go for the ball rather than for one place.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BallSpawnConfig {
+    /// The range the ball's x is drawn from, within the arm's reach by default.
+    pub x: (f32, f32),
+    /// The ball is thrown to either side and up by up to this speed, or put down at rest
+    /// with 0.
+    pub max_speed: f32,
+}
+
+impl Default for BallSpawnConfig {
+    fn default() -> Self {
+        Self {
+            x: (0.05, 0.3),
+            max_speed: 0.,
+        }
+    }
+}
+
+/// Length of a frame of `FrameStack` for an arm with the given number of joints.
+pub const fn frame_size(joint_count: usize) -> usize {
+    joint_count * 4 + FRAME_OBJECT_SLOTS
+}
+
+/// Observation length for an arm with the given number of joints and frames stacked.
+pub const fn observation_size(joint_count: usize, frames: usize) -> usize {
+    frames * frame_size(joint_count) + 2 + joint_count + CONTACT_SLOTS
+}
+
+/// Forces a network sends an arm built from the config, one per joint: `ACTION_SIZE` for the
+/// default arm.
+pub fn action_size(arm: &ArmConfig) -> usize {
+    arm.joint_count()
+}
+
+/// Observation length of a world with an arm built from the config: `OBSERVATION_SIZE` for
+/// the default arm.
+pub fn arm_observation_size(arm: &ArmConfig) -> usize {
+    let joints = action_size(arm);
+    let feedback = if cfg!(feature = "action-feedback") {
+        joints
+    } else {
+        0
+    };
+    observation_size(joints, OBSERVATION_FRAMES) + feedback
+}
+
+/// A network that does not fit the simulation.
+#[derive(Debug, PartialEq)]
+pub enum ShapeError {
+    /// The input layers read another number of values than the observation has.
+    Observation {
+        network: &'static str,
+        found: usize,
+        expected: usize,
+    },
+    /// Neither one force per joint, nor one more for the grip intent.
+    Forces {
+        network: &'static str,
+        found: usize,
+        expected: usize,
+    },
+}
+
+impl Display for ShapeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShapeError::Observation {
+                network,
+                found,
+                expected,
+            } => {
+                write!(
+                    f,
+                    "{network} reads {found} observation values, the simulation builds {expected}"
+                )
+            }
+            ShapeError::Forces {
+                network,
+                found,
+                expected,
+            } => {
+                write!(f, "{network} sends {found} forces, the arm takes {expected} and an optional grip intent")
+            }
+        }
+    }
+}
+
+impl Error for ShapeError {}
+
+/// Checks the network against the observation and the forces of the simulation up front, as a
+/// mismatch otherwise panics deep inside burn with nothing but tensor shapes to go by. The sizes
+/// are the ones the layers reading the observation and sending the forces are built with, and
+/// the ones expected are those of a world with an arm built from the config.
+pub fn validate_network<A, B: Backend>(network: &A, arm: &ArmConfig) -> Result<(), ShapeError>
+where
+    A: AI<B>,
+{
+    let expected = arm_observation_size(arm);
+    if network.input_size() != expected {
+        return Err(ShapeError::Observation {
+            network: network.network_name(),
+            found: network.input_size(),
+            expected,
+        });
+    }
+    let expected = action_size(arm);
+    if !(expected..=expected + 1).contains(&network.output_size()) {
+        return Err(ShapeError::Forces {
+            network: network.network_name(),
+            found: network.output_size(),
+            expected,
+        });
+    }
+    Ok(())
+}
 
 fn add_to_input(tensor_input: &mut Vec<f32>, corners: Corners) {
     for coord in [corners.0 .0, corners.0 .1, corners.1 .0, corners.1 .1] {
@@ -10,28 +225,72 @@ fn add_to_input(tensor_input: &mut Vec<f32>, corners: Corners) {
     }
 }
 
-fn add_to_input_normalized(tensor_input: &mut Vec<f32>, corners: Corners) {
+fn add_to_input_normalized(world: &PhysicsWorld, tensor_input: &mut Vec<f32>, corners: Corners) {
     for corner in [corners.0, corners.1] {
-        tensor_input.push(normalize_x(corner.0));
-        tensor_input.push(normalize_y(corner.1));
+        let (x, y) = world.normalize(corner);
+        tensor_input.push(x);
+        tensor_input.push(y);
     }
 }
 
-fn saved_to_both(tensor_input: &mut Vec<f32>, saved_corners: &mut Vec<f32>, corners: Corners) {
-    add_to_input_normalized(tensor_input, corners);
-    add_to_input_normalized(saved_corners, corners);
+/// The normalized ball position and the distance from it to the basket, zeros for whatever the
+/// world lacks.
+fn add_objects_normalized(world: &PhysicsWorld, tensor_input: &mut Vec<f32>) {
+    let Some(ball) = world.ball_position() else {
+        tensor_input.extend([0.; FRAME_OBJECT_SLOTS]);
+        return;
+    };
+    let (ball_x, ball_y) = world.normalize((ball.x, ball.y));
+    let (to_basket_x, to_basket_y) = match world.basket_position() {
+        Some(basket) => {
+            let (basket_x, basket_y) = world.normalize((basket.x, basket.y));
+            (basket_x - ball_x, basket_y - ball_y)
+        }
+        None => (0., 0.),
+    };
+    tensor_input.extend([ball_x, ball_y, to_basket_x, to_basket_y]);
+}
+
+/// The last `OBSERVATION_FRAMES` frames of the observation in a ring buffer, oldest first, the
+/// newest pushing the oldest out. A frame is the normalized far corners of every segment then
+/// the object slots.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameStack {
+    frames: VecDeque<Vec<f32>>,
 }
 
-fn capture_world_state(world: &PhysicsWorld) -> [Corners; 7] {
-    [
-        world.tricep_farthest_corners(),
-        world.forearm_farthest_corners(),
-        world.palm_farthest_corners(),
-        world.lower_index_finger_farthest_corners(),
-        world.upper_index_finger_farthest_corners(),
-        world.lower_thumb_farthest_corners(),
-        world.upper_thumb_farthest_corners(),
-    ]
+impl FrameStack {
+    /// Fills every frame with the world as it is, as if it had stood still before the episode.
+    pub fn reset(&mut self, world: &PhysicsWorld) {
+        let frame = Self::capture(world);
+        self.frames.clear();
+        self.frames
+            .extend(std::iter::repeat_n(frame, OBSERVATION_FRAMES));
+    }
+
+    fn capture(world: &PhysicsWorld) -> Vec<f32> {
+        let mut frame = Vec::new();
+        on_captured_state(world, |corners| {
+            add_to_input_normalized(world, &mut frame, corners)
+        });
+        add_objects_normalized(world, &mut frame);
+        frame
+    }
+
+    /// Pushes the current frame, over a reset from it if nothing was pushed yet.
+    fn push(&mut self, world: &PhysicsWorld) {
+        if self.frames.is_empty() {
+            return self.reset(world);
+        }
+        if self.frames.len() == OBSERVATION_FRAMES {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(Self::capture(world));
+    }
+}
+
+fn capture_world_state(world: &PhysicsWorld) -> Vec<Corners> {
+    world.joint_farthest_corners()
 }
 
 fn on_captured_state<FN>(world: &PhysicsWorld, mut action: FN)
@@ -43,146 +302,547 @@ where
     }
 }
 
-fn save_world_state(world: &PhysicsWorld, save_location: &mut Vec<f32>) {
+pub(crate) fn save_world_state(world: &PhysicsWorld, save_location: &mut Vec<f32>) {
     on_captured_state(world, |corners| add_to_input(save_location, corners));
 }
 
-pub fn single_simulation_step<B: Backend, A: AI<B>>(
+/// Fills `tensor_input` with the network input for the current world state, pushed into
+/// `frames` first: the normalized corners of every segment in each frame, oldest first, then the
+/// ball position and distance to the basket in each frame, the ball velocity, the normalized
+/// joint velocities, the fingertip contacts, then with the `action-feedback` feature the forces
+/// of the previous step.
+pub fn build_observation(
     tensor_input: &mut Vec<f32>,
-    previous_corners: &mut Vec<f32>,
-    world: &mut PhysicsWorld,
-    network: &A,
-    device: &B::Device,
+    frames: &mut FrameStack,
+    world: &PhysicsWorld,
 ) {
     tensor_input.clear();
-    tensor_input.extend(previous_corners.as_slice());
-    previous_corners.clear();
+    frames.push(world);
+    for frame in &frames.frames {
+        tensor_input.extend_from_slice(&frame[..frame.len() - FRAME_OBJECT_SLOTS]);
+    }
+    for frame in &frames.frames {
+        tensor_input.extend_from_slice(&frame[frame.len() - FRAME_OBJECT_SLOTS..]);
+    }
 
-    on_captured_state(&world, |corners| {
-        saved_to_both(tensor_input, previous_corners, corners)
-    });
+    let (velocity_x, velocity_y) = world
+        .ball_velocity()
+        .map_or((0., 0.), |velocity| world.normalize_velocity(velocity));
+    tensor_input.extend([velocity_x, velocity_y]);
 
-    // previous ball x
-    tensor_input.push(0.0);
-    // previous ball y
-    tensor_input.push(0.0);
-    // previous distance to basket x
-    tensor_input.push(0.0);
-    // previous distance to basket y
-    tensor_input.push(0.0);
-
-    // ball x
-    tensor_input.push(0.0);
-    // ball y
-    tensor_input.push(0.0);
-    // distance to basket x
-    tensor_input.push(0.0);
-    // distance to basket y
-    tensor_input.push(0.0);
-    let tensor = Tensor::<B, 1>::from_floats(tensor_input.as_slice(), device);
-    let data = network.apply(tensor).to_data();
-    let forces: &[f32] = data.as_slice().expect("ai requested forces not available");
-
-    world.apply_tricep_force(forces[0]);
-    world.apply_forearm_force(forces[1]);
-    world.apply_palm_force(forces[2]);
-    world.apply_lower_index_finger_force(forces[3]);
-    world.apply_upper_index_finger_force(forces[4]);
-    world.apply_lower_thumb_force(forces[5]);
-    world.apply_upper_thumb_force(forces[6]);
+    let joint_velocities = world.arm_joint_angular_velocities();
+    tensor_input.extend(
+        joint_velocities
+            .iter()
+            .map(|velocity| (velocity / MAX_JOINT_SPEED).clamp(-1., 1.)),
+    );
+    tensor_input.extend(
+        world
+            .fingertip_contacts()
+            .map(|touching| if touching { 1. } else { 0. }),
+    );
+
+    if cfg!(feature = "action-feedback") {
+        tensor_input.extend(world.joint_applied_forces());
+    }
+}
+
+/// The joint forces of a network's output and its grip intent, if it has one, see `AI::grips`.
+pub fn split_grip<'a, B: Backend>(
+    network: &impl AI<B>,
+    output: &'a [f32],
+) -> (&'a [f32], Option<f32>) {
+    match output.split_last() {
+        Some((grip, forces)) if network.grips() => (forces, Some(*grip)),
+        _ => (output, None),
+    }
+}
+
+/// Applies one force per channel, mapped by the world's action scaler, and advances the world
+/// by a step. Forces past the last channel are left out. A grip intent is added along the
+/// world's grip synergy, closing the finger and thumb together when positive.
+pub fn apply_forces_and_step(world: &mut PhysicsWorld, forces: &[f32], grip: Option<f32>) {
+    let forces = &forces[..forces.len().min(world.arm_joint_count())];
+    let synergy = grip.map(|intent| {
+        world
+            .grip_synergy()
+            .iter()
+            .map(|direction| intent * direction)
+            .collect::<Vec<f32>>()
+    });
+    for (channel, force) in forces.iter().enumerate() {
+        let force = match &synergy {
+            Some(synergy) => (force + synergy[channel]).clamp(-1., 1.),
+            None => *force,
+        };
+        let force = world.config().actions.scale(channel, force);
+        world.apply_joint_force(channel, force);
+    }
     world.step();
 }
 
-pub fn prepare_simulation() -> (PhysicsWorld, Vec<f32>, Vec<f32>) {
-    let world = PhysicsWorld::new();
+/// The world an episode of the default configuration starts in, with the ball elsewhere within
+/// reach but in episode 0.
+pub fn episode_world(seed: u64) -> PhysicsWorld {
+    PhysicsWorld::with_config(EpisodeConfig::default().world_config(seed))
+}
 
-    let mut previous_corners = Vec::new();
+/// How the scores of an individual's episodes make up its fitness.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EpisodeAggregate {
+    #[default]
+    Mean,
+    /// Conditional value at risk: the mean of the given share of the worst episodes, favoring
+    /// individuals that do well in all of them.
+    Cvar(f32),
+}
 
-    on_captured_state(&world, |corners| {
-        add_to_input_normalized(&mut previous_corners, corners)
-    });
+impl EpisodeAggregate {
+    pub fn apply(self, scores: &[f32]) -> f32 {
+        let worst = match self {
+            EpisodeAggregate::Mean => scores.len(),
+            EpisodeAggregate::Cvar(share) => {
+                ((share * scores.len() as f32).ceil() as usize).clamp(1, scores.len())
+            }
+        };
+        let mut sorted = scores.to_vec();
+        sorted.sort_by(f32::total_cmp);
+        sorted[..worst].iter().sum::<f32>() / worst as f32
+    }
+
+    /// The fitness of the rollouts of the episodes, with their behavior averaged.
+    pub fn combine(self, rollouts: &[(f32, BehaviorDescriptor)]) -> (f32, BehaviorDescriptor) {
+        let scores: Vec<f32> = rollouts.iter().map(|(score, _)| *score).collect();
+        let behaviors: Vec<BehaviorDescriptor> =
+            rollouts.iter().map(|(_, behavior)| *behavior).collect();
+        (self.apply(&scores), BehaviorDescriptor::mean(&behaviors))
+    }
+}
+
+pub fn prepare_simulation() -> (PhysicsWorld, FrameStack, Vec<f32>) {
+    prepare_episode(0)
+}
+
+/// The world of the episode with the observation state for its first step.
+pub fn prepare_episode(seed: u64) -> (PhysicsWorld, FrameStack, Vec<f32>) {
+    let world = episode_world(seed);
 
-    (world, previous_corners, Vec::new())
+    let mut frames = FrameStack::default();
+    frames.reset(&world);
+
+    (world, frames, Vec::new())
 }
 
 pub fn test_ai<A, B: Backend>(network: &A, device: &B::Device) -> f32
 where
     A: AI<B>,
 {
-    let (mut world, mut previous_corners, mut tensor_input) = prepare_simulation();
-
-    let mut init_state: Vec<f32> = Vec::new();
-    let mut previous_state: Vec<f32> = Vec::new();
-    save_world_state(&world, &mut init_state);
-    let mut saved_steps_scores: Vec<f32> = Vec::new();
-
-    for _ in 0..500 {
-        previous_state.clear();
-        save_world_state(&world, &mut previous_state);
-        single_simulation_step(
-            &mut tensor_input,
-            &mut previous_corners,
-            &mut world,
-            network,
-            device,
-        );
-        saved_steps_scores.push(scorer(&init_state, &previous_state, &world));
+    test_ai_with_behavior(network, device).0
+}
+
+/// Scores the network like `test_ai` and describes what its rollout did.
+pub fn test_ai_with_behavior<A, B: Backend>(
+    network: &A,
+    device: &B::Device,
+) -> (f32, BehaviorDescriptor)
+where
+    A: AI<B>,
+{
+    test_ai_in_episode(network, 0, device)
+}
+
+/// Scores the network in the episode of the seed and describes what its rollout did, with the
+/// evaluation context of the calling thread.
+pub fn test_ai_in_episode<A, B: Backend>(
+    network: &A,
+    seed: u64,
+    device: &B::Device,
+) -> (f32, BehaviorDescriptor)
+where
+    A: AI<B>,
+{
+    test_ai_with_fitness(
+        network,
+        seed,
+        FitnessKind::default(),
+        ShapingConfig::default(),
+        &EpisodeConfig::default(),
+        device,
+    )
+}
+
+/// `test_ai_in_episode` with the steps scored by the given objective and shaping terms, run as
+/// configured.
+pub fn test_ai_with_fitness<A, B: Backend>(
+    network: &A,
+    seed: u64,
+    fitness: FitnessKind,
+    shaping: ShapingConfig,
+    episode: &EpisodeConfig,
+    device: &B::Device,
+) -> (f32, BehaviorDescriptor)
+where
+    A: AI<B>,
+{
+    EVAL_CONTEXT.with(|context| {
+        context
+            .borrow_mut()
+            .evaluate(network, seed, fitness, shaping, episode, device)
+    })
+}
+
+/// `test_ai_with_fitness` with every physics step of the rollout recorded into `directory`, in
+/// the file `TrajectoryRecorder::path` names after the network's name, e.g. the stem of its
+/// file, its fingerprint and the seed.
+#[allow(clippy::too_many_arguments)]
+pub fn record_ai_with_fitness<A, B: Backend>(
+    network: &A,
+    name: &str,
+    seed: u64,
+    fitness: FitnessKind,
+    shaping: ShapingConfig,
+    episode: &EpisodeConfig,
+    directory: &Path,
+    device: &B::Device,
+) -> std::io::Result<(f32, BehaviorDescriptor)>
+where
+    A: AI<B>,
+{
+    let mut recorder = TrajectoryRecorder::create(directory, name, network.fingerprint(), seed)?;
+    let mut task = fitness.task(shaping);
+    let mut world = PhysicsWorld::with_config(task.world_config(seed, episode));
+    let mut rewards = Vec::new();
+    let mut behavior = BehaviorRecorder::default();
+    run_episode(
+        task.as_mut(),
+        episode,
+        &mut world,
+        network,
+        device,
+        &mut Vec::new(),
+        &mut rewards,
+        |steps, world, observation, forces| {
+            behavior.record(world);
+            recorder.record(steps, observation, forces, world);
+        },
+    );
+    recorder.finish()?;
+    Ok((episode_score(&mut rewards), behavior.finish(&world)))
+}
+
+/// The observations the network is given in the episode of the seed, one per action, for
+/// folding into the statistics it standardizes its input by.
+pub fn observe_ai_with_fitness<A, B: Backend>(
+    network: &A,
+    seed: u64,
+    fitness: FitnessKind,
+    shaping: ShapingConfig,
+    episode: &EpisodeConfig,
+    device: &B::Device,
+) -> Vec<Vec<f32>>
+where
+    A: AI<B>,
+{
+    let mut task = fitness.task(shaping);
+    let mut world = PhysicsWorld::with_config(task.world_config(seed, episode));
+    let action_repeat = episode.action_repeat.max(1);
+    let mut observations = Vec::new();
+    run_episode(
+        task.as_mut(),
+        episode,
+        &mut world,
+        network,
+        device,
+        &mut Vec::new(),
+        &mut Vec::new(),
+        |steps, _, observation, _| {
+            if (steps - 1).is_multiple_of(action_repeat) {
+                observations.push(observation.to_vec());
+            }
+        },
+    );
+    observations
+}
+
+thread_local! {
+    static EVAL_CONTEXT: RefCell<EvalContext> = RefCell::default();
+}
+
+/// What an episode asks of the network: the world it is played in, what the network sees of
+/// it, what each step is worth and when it is over. The rollout loop, `run_episode`, is the
+/// same for all of them.
+pub trait Task {
+    /// The world of the episode of the seed as configured, unless the task needs more in it.
+    fn world_config(&self, seed: u64, episode: &EpisodeConfig) -> WorldConfig {
+        episode.world_config(seed)
     }
-    let last_score = *saved_steps_scores.last().expect("saved steps scores empty");
+    /// Takes in the world the episode starts in.
+    fn reset(&mut self, world: &PhysicsWorld);
+    /// Fills `observation` with the network input for the world as it is now.
+    fn observe(&mut self, world: &PhysicsWorld, observation: &mut Vec<f32>);
+    /// What the action the world just took its steps with is worth, between 0 and 1 but for a
+    /// bonus on the action that ends the episode.
+    fn reward(&mut self, world: &PhysicsWorld) -> f32;
+    /// Whether the episode is over before its configured steps, after the given number of
+    /// physics steps. Never, unless the task says otherwise.
+    fn terminated(&self, _world: &PhysicsWorld, _steps: usize) -> bool {
+        false
+    }
+}
 
-    saved_steps_scores.sort_by(|a, b| a.partial_cmp(b).expect("saved scores not comparable"));
+/// The task the arm has always been evolved on: it sees its segments now and in the previous
+/// step, and runs for all the steps of the episode. The steps are scored by pose retention,
+/// keeping the arm where it started, unless it is given another objective.
+pub struct PoseRetentionTask {
+    fitness: Box<dyn Fitness>,
+    frames: FrameStack,
+}
 
-    (saved_steps_scores[saved_steps_scores.len() / 2] * 10.0
-        + last_score * 5.
-        + saved_steps_scores[0]
-        + saved_steps_scores[saved_steps_scores.len() - 1])
-        / 17.
+impl PoseRetentionTask {
+    /// The task with its steps scored by the given objective.
+    pub fn scored_by(fitness: Box<dyn Fitness>) -> Self {
+        Self {
+            fitness,
+            frames: FrameStack::default(),
+        }
+    }
 }
 
-pub fn mape(init_state: &Vec<f32>, prev_state: &Vec<f32>) -> f32 {
-    init_state
-        .iter()
-        .zip(prev_state.iter())
-        .map(|(a, b)| ((a - b) / a).abs())
-        .sum::<f32>()
-        / init_state.len() as f32
+impl Default for PoseRetentionTask {
+    fn default() -> Self {
+        Self::scored_by(Box::new(PoseRetention::default()))
+    }
 }
 
-fn scorer(init_state: &Vec<f32>, prev_state: &Vec<f32>, world: &PhysicsWorld) -> f32 {
-    let mut end_state: Vec<f32> = Vec::new();
-    save_world_state(world, &mut end_state);
+impl Task for PoseRetentionTask {
+    fn reset(&mut self, world: &PhysicsWorld) {
+        self.frames.reset(world);
+        self.fitness.reset(world);
+    }
+
+    fn observe(&mut self, world: &PhysicsWorld, observation: &mut Vec<f32>) {
+        build_observation(observation, &mut self.frames, world);
+    }
 
-    let mape_init = mape(init_state, &end_state);
+    fn reward(&mut self, world: &PhysicsWorld) -> f32 {
+        self.fitness.score_step(world)
+    }
+}
 
-    let mape_prev = mape(prev_state, &end_state);
+/// Runs the network in the task from the world until the episode or the task is over, leaving
+/// the reward of every action in `rewards`. `on_step` sees the world after every physics step
+/// with the steps taken, and the observation and the forces of the action it was taken with.
+#[allow(clippy::too_many_arguments)]
+pub fn run_episode<A, B: Backend, T: Task + ?Sized>(
+    task: &mut T,
+    episode: &EpisodeConfig,
+    world: &mut PhysicsWorld,
+    network: &A,
+    device: &B::Device,
+    observation: &mut Vec<f32>,
+    rewards: &mut Vec<f32>,
+    mut on_step: impl FnMut(usize, &PhysicsWorld, &[f32], &[f32]),
+) where
+    A: AI<B>,
+{
+    validate_network(network, &world.config().arm).unwrap_or_else(|e| panic!("{e}"));
+    task.reset(world);
+    network.reset_state();
+    rewards.clear();
+    let mut steps = 0;
+    while steps < episode.steps && !task.terminated(world, steps) {
+        task.observe(world, observation);
+        let tensor = Tensor::<B, 1>::from_floats(observation.as_slice(), device);
+        let data = network.apply(tensor).to_data().convert::<f32>();
+        let forces = data.as_slice().expect("ai requested forces not available");
+        let (joint_forces, grip) = split_grip(network, forces);
+        for _ in 0..episode.action_repeat.clamp(1, episode.steps - steps) {
+            apply_forces_and_step(world, joint_forces, grip);
+            steps += 1;
+            on_step(steps, world, observation, forces);
+        }
+        rewards.push(task.reward(world));
+    }
+}
 
-    ((1. / (mape_init + 1.)) + (1. / (mape_prev + 1.))) / 2.
+/// The score of a rollout from the rewards of its steps: mostly the median, then the last one,
+/// with the worst and the best.
+pub fn episode_score(rewards: &mut [f32]) -> f32 {
+    let last = *rewards.last().expect("an episode has steps");
+    rewards.sort_by(|a, b| a.partial_cmp(b).expect("saved scores not comparable"));
+    (rewards[rewards.len() / 2] * 10.0 + last * 5. + rewards[0] + rewards[rewards.len() - 1]) / 17.
 }
 
-pub fn visual_ai<A, B: Backend>(network: &A, device: &B::Device)
+/// Scores the network in the task, starting from the world, and describes what its rollout did.
+pub fn test_ai_in_task<A, B: Backend, T: Task + ?Sized>(
+    network: &A,
+    task: &mut T,
+    episode: &EpisodeConfig,
+    mut world: PhysicsWorld,
+    device: &B::Device,
+) -> (f32, BehaviorDescriptor)
 where
     A: AI<B>,
 {
-    let (mut world, mut previous_corners, mut tensor_input) = prepare_simulation();
+    let mut rewards = Vec::new();
+    let mut behavior = BehaviorRecorder::default();
+    run_episode(
+        task,
+        episode,
+        &mut world,
+        network,
+        device,
+        &mut Vec::new(),
+        &mut rewards,
+        |_, world, _, _| behavior.record(world),
+    );
+    (episode_score(&mut rewards), behavior.finish(&world))
+}
+
+/// What the task of an evaluation context is built from and its worlds are configured by.
+type Scoring = (FitnessKind, ShapingConfig, EpisodeConfig);
+
+/// What a worker thread keeps between rollouts: the task of the objective last scored by, the
+/// untouched world of every episode of it scored in, cloned instead of built anew, and the
+/// buffers a rollout fills at every step.
+#[derive(Default)]
+pub struct EvalContext {
+    worlds: HashMap<u64, PhysicsWorld>,
+    task: Option<(Scoring, Box<dyn Task>)>,
+    tensor_input: Vec<f32>,
+    step_scores: Vec<f32>,
+}
 
-    for i in 0..500 {
-        if i % 5 == 0 {
-            println!("{:?}", world.all_arm_corners());
+impl EvalContext {
+    /// Scores the network in the episode of the seed and describes what its rollout did.
+    pub fn evaluate<A, B: Backend>(
+        &mut self,
+        network: &A,
+        seed: u64,
+        kind: FitnessKind,
+        shaping: ShapingConfig,
+        episode: &EpisodeConfig,
+        device: &B::Device,
+    ) -> (f32, BehaviorDescriptor)
+    where
+        A: AI<B>,
+    {
+        if self
+            .task
+            .as_ref()
+            .is_none_or(|(current, _)| *current != (kind, shaping, *episode))
+        {
+            self.task = Some(((kind, shaping, *episode), kind.task(shaping)));
+            self.worlds.clear();
         }
-        single_simulation_step(
-            &mut tensor_input,
-            &mut previous_corners,
+        let (_, task) = self.task.as_mut().expect("task just built");
+        let config = task.world_config(seed, episode);
+        let mut world = self
+            .worlds
+            .entry(seed)
+            .or_insert_with(|| PhysicsWorld::with_config(config))
+            .clone();
+        let mut behavior = BehaviorRecorder::default();
+        let (observation, rewards) = (&mut self.tensor_input, &mut self.step_scores);
+        run_episode(
+            task.as_mut(),
+            episode,
             &mut world,
             network,
             device,
+            observation,
+            rewards,
+            |_, world, _, _| behavior.record(world),
         );
+        (
+            episode_score(&mut self.step_scores),
+            behavior.finish(&world),
+        )
     }
 }
 
+pub fn mape(init_state: &[f32], prev_state: &[f32]) -> f32 {
+    init_state
+        .iter()
+        .zip(prev_state.iter())
+        .map(|(a, b)| ((a - b) / a).abs())
+        .sum::<f32>()
+        / init_state.len() as f32
+}
+
+/// Prints the arm and the rope every fifth step of the episode of the seed in the default task.
+pub fn visual_ai<A, B: Backend>(
+    network: &A,
+    name: &str,
+    episode: &EpisodeConfig,
+    seed: u64,
+    trajectories: Option<&Path>,
+    device: &B::Device,
+) -> std::io::Result<()>
+where
+    A: AI<B>,
+{
+    visual_ai_in_task(
+        network,
+        name,
+        &mut PoseRetentionTask::default(),
+        episode,
+        seed,
+        trajectories,
+        device,
+    )
+}
+
+/// Prints the arm and the rope every fifth step of the episode of the seed in the task, and
+/// with `trajectories` records every step into that directory like `record_ai_with_fitness`.
+pub fn visual_ai_in_task<A, B: Backend, T: Task + ?Sized>(
+    network: &A,
+    name: &str,
+    task: &mut T,
+    episode: &EpisodeConfig,
+    seed: u64,
+    trajectories: Option<&Path>,
+    device: &B::Device,
+) -> std::io::Result<()>
+where
+    A: AI<B>,
+{
+    let mut recorder = trajectories
+        .map(|directory| TrajectoryRecorder::create(directory, name, network.fingerprint(), seed))
+        .transpose()?;
+    let print_frame = |world: &PhysicsWorld| {
+        let mut frame = world.all_arm_corners();
+        frame.extend(world.rope_corners());
+        println!("{:?}", frame);
+    };
+    let mut world = PhysicsWorld::with_config(task.world_config(seed, episode));
+    print_frame(&world);
+    run_episode(
+        task,
+        episode,
+        &mut world,
+        network,
+        device,
+        &mut Vec::new(),
+        &mut Vec::new(),
+        |steps, world, observation, forces| {
+            if let Some(recorder) = &mut recorder {
+                recorder.record(steps, observation, forces, world);
+            }
+            if steps % 5 == 0 {
+                print_frame(world);
+            }
+        },
+    );
+    recorder.map_or(Ok(()), TrajectoryRecorder::finish)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::ai::BigAI;
+    use crate::small_ai::SmallAI;
     use burn::backend::ndarray::NdArrayDevice;
     use burn::backend::NdArray;
     use std::time::SystemTime;
@@ -198,4 +858,492 @@ mod tests {
 
         println!("Treat: {treat}");
     }
+
+    #[test]
+    fn test_validate_network() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let arm = ArmConfig::default();
+        let network = SmallAI::<BE>::new(&device);
+        assert_eq!(validate_network(&network, &arm), Ok(()));
+        assert_eq!(
+            validate_network(&crate::attn_ai::AttnAI::<BE>::new(&device), &arm),
+            Ok(())
+        );
+        assert_eq!(
+            validate_network(&crate::grip_ai::GripAI::<BE>::new(&device), &arm),
+            Ok(())
+        );
+        assert_eq!(
+            validate_network(&crate::aux_ai::AuxAI::<BE>::new(&device), &arm),
+            Ok(())
+        );
+
+        let mut layers: Vec<_> = network
+            .layers()
+            .into_iter()
+            .map(|(_, layer)| layer)
+            .collect();
+        let [d_in, d_out] = layers[0].weight.dims();
+        layers[0] = burn::nn::LinearConfig::new(d_in - 2, d_out).init(&device);
+        let narrow = network.with_layers(layers);
+        let error = validate_network(&narrow, &arm).unwrap_err();
+        assert_eq!(
+            error,
+            ShapeError::Observation {
+                network: "Small AI",
+                found: OBSERVATION_SIZE - 2,
+                expected: OBSERVATION_SIZE
+            }
+        );
+        // the tokens are cut from the observation as wide as the embedding reads them
+        let attn = crate::attn_ai::AttnAI::<BE>::new(&device);
+        let mut layers: Vec<_> = attn.layers().into_iter().map(|(_, layer)| layer).collect();
+        let [d_in, d_out] = layers[0].weight.dims();
+        layers[0] = burn::nn::LinearConfig::new(d_in + 1, d_out).init(&device);
+        assert!(matches!(
+            validate_network(&attn.with_layers(layers), &arm),
+            Err(ShapeError::Observation { found, .. }) if found != OBSERVATION_SIZE
+        ));
+        assert!(error
+            .to_string()
+            .contains(&format!("the simulation builds {OBSERVATION_SIZE}")));
+        // two arms' observations don't fit a world with one
+        assert_eq!(
+            validate_network(&crate::mirrored_ai::MirroredAI::new(network.clone()), &arm),
+            Err(ShapeError::Observation {
+                network: "Small AI",
+                found: 2 * OBSERVATION_SIZE,
+                expected: OBSERVATION_SIZE
+            })
+        );
+
+        // the knuckle of finger abduction takes a force of its own
+        let abducting = ArmConfig {
+            finger_abduction: Some(0.2),
+            ..ArmConfig::default()
+        };
+        assert_eq!(action_size(&abducting), ACTION_SIZE + 1);
+        assert!(matches!(
+            validate_network(&network, &abducting),
+            Err(ShapeError::Observation { expected, .. }) if expected == arm_observation_size(&abducting)
+        ));
+        let sized = SmallAI::<BE>::for_arm(&device, &abducting);
+        assert_eq!(validate_network(&sized, &abducting), Ok(()));
+        let world = PhysicsWorld::with_config(WorldConfig {
+            arm: abducting,
+            ..WorldConfig::default()
+        });
+        let episode = EpisodeConfig {
+            steps: 5,
+            ..EpisodeConfig::default()
+        };
+        test_ai_in_task(
+            &sized,
+            &mut PoseRetentionTask::default(),
+            &episode,
+            world,
+            &device,
+        );
+    }
+
+    #[test]
+    fn test_observation_feeds_back_the_previous_forces() {
+        let (mut world, mut frames, mut tensor_input) = prepare_simulation();
+        let forces: Vec<f32> = (0..ACTION_SIZE)
+            .map(|channel| channel as f32 / ACTION_SIZE as f32)
+            .collect();
+        apply_forces_and_step(&mut world, &forces, None);
+        assert_eq!(world.joint_applied_forces(), forces);
+
+        build_observation(&mut tensor_input, &mut frames, &world);
+        assert_eq!(tensor_input.len(), OBSERVATION_SIZE);
+        assert_eq!(
+            tensor_input[observation_size(ACTION_SIZE, OBSERVATION_FRAMES)..],
+            forces[..FEEDBACK_SIZE]
+        );
+    }
+
+    #[test]
+    fn test_observation_shows_the_ball_and_the_basket() {
+        let (mut world, mut frames, mut tensor_input) = prepare_simulation();
+        build_observation(&mut tensor_input, &mut frames, &world);
+        let corners_end = OBSERVATION_FRAMES * ACTION_SIZE * 4;
+        let (previous, current) = (
+            (OBSERVATION_FRAMES - 2) * FRAME_OBJECT_SLOTS,
+            (OBSERVATION_FRAMES - 1) * FRAME_OBJECT_SLOTS,
+        );
+        let slots = corners_end..corners_end + OBSERVATION_FRAMES * FRAME_OBJECT_SLOTS + 2;
+        let ball = world.ball_position().unwrap();
+        let objects = &tensor_input[slots.clone()][current..];
+        assert_eq!(
+            objects[..2],
+            <[f32; 2]>::from(world.normalize((ball.x, ball.y)))
+        );
+        // no basket to go to
+        assert_eq!(objects[2..4], [0., 0.]);
+
+        let config = WorldConfig {
+            basket: Some(Default::default()),
+            ..WorldConfig::default()
+        };
+        world = PhysicsWorld::with_config(config);
+        frames.reset(&world);
+        for _ in 0..2 {
+            world.step();
+            build_observation(&mut tensor_input, &mut frames, &world);
+        }
+        let objects = &tensor_input[slots.clone()];
+        let (ball, basket) = (
+            world.ball_position().unwrap(),
+            world.basket_position().unwrap(),
+        );
+        let ((ball_x, ball_y), (basket_x, basket_y)) = (
+            world.normalize((ball.x, ball.y)),
+            world.normalize((basket.x, basket.y)),
+        );
+        assert_eq!(
+            objects[current..current + 4],
+            [ball_x, ball_y, basket_x - ball_x, basket_y - ball_y]
+        );
+        assert_ne!(
+            objects[previous..previous + 4],
+            objects[current..current + 4],
+            "the ball settles"
+        );
+        assert_eq!(
+            objects[current + 4..],
+            <[f32; 2]>::from(world.normalize_velocity(world.ball_velocity().unwrap()))
+        );
+
+        let joints = &tensor_input[slots.end..slots.end + ACTION_SIZE];
+        let velocities = world.arm_joint_angular_velocities();
+        assert!(joints
+            .iter()
+            .zip(&velocities)
+            .all(|(joint, velocity)| *joint == (velocity / MAX_JOINT_SPEED).clamp(-1., 1.)));
+        assert!(
+            joints.iter().any(|joint| *joint != 0.),
+            "the arm starts falling"
+        );
+        // the fingertips hang in the air
+        assert_eq!(
+            tensor_input
+                [slots.end + ACTION_SIZE..observation_size(ACTION_SIZE, OBSERVATION_FRAMES)],
+            [0., 0.]
+        );
+    }
+
+    #[test]
+    fn test_frames_stack_the_last_steps() {
+        let mut world = PhysicsWorld::new();
+        let mut frames = FrameStack::default();
+        let mut tensor_input = Vec::new();
+        build_observation(&mut tensor_input, &mut frames, &world);
+        assert_eq!(tensor_input.len(), OBSERVATION_SIZE);
+        let corners = ACTION_SIZE * 4;
+        let first = tensor_input[..corners].to_vec();
+        // standing still before the first step
+        assert_eq!(tensor_input[corners..corners * 2], first[..]);
+
+        for _ in 0..5 {
+            world.step();
+        }
+        build_observation(&mut tensor_input, &mut frames, &world);
+        let second = tensor_input[corners..corners * 2].to_vec();
+        assert_ne!(first, second, "the arm falls");
+        // the oldest frame gave way
+        assert_eq!(tensor_input[..corners], first[..]);
+
+        world.step();
+        build_observation(&mut tensor_input, &mut frames, &world);
+        assert_eq!(tensor_input[..corners], second[..]);
+    }
+
+    #[test]
+    fn test_episodes_move_the_ball() {
+        let default = PhysicsWorld::new().ball_position();
+        assert_eq!(episode_world(0).ball_position(), default);
+        assert_eq!(
+            episode_world(3).ball_position(),
+            episode_world(3).ball_position()
+        );
+        assert_ne!(episode_world(3).ball_position(), default);
+        assert_ne!(
+            episode_world(3).ball_position(),
+            episode_world(4).ball_position()
+        );
+
+        let thrown = EpisodeConfig {
+            ball: BallSpawnConfig {
+                x: (0.2, 0.2),
+                max_speed: 0.5,
+            },
+            ..EpisodeConfig::default()
+        };
+        let ball = thrown.world_config(3).balls[0];
+        assert_eq!(ball, thrown.world_config(3).balls[0]);
+        assert_eq!(ball.x, 0.2);
+        assert_eq!(thrown.world_config(0).balls[0].x, 0.2);
+        assert!(
+            ball.velocity.0.abs() <= 0.5
+                && (0. ..=0.5).contains(&ball.velocity.1)
+                && ball.velocity != (0., 0.)
+        );
+        let world = PhysicsWorld::with_config(thrown.world_config(3));
+        assert!((world.ball_velocity().unwrap().x - ball.velocity.0).abs() < 1e-6);
+        assert_eq!(
+            EpisodeConfig::default().world_config(3).balls[0].velocity,
+            (0., 0.)
+        );
+    }
+
+    #[test]
+    fn test_episode_aggregate() {
+        let scores = [0.9, 0.1, 0.5, 0.3];
+        assert_eq!(EpisodeAggregate::Mean.apply(&scores), 0.45);
+        assert_eq!(EpisodeAggregate::Cvar(0.5).apply(&scores), 0.2);
+        assert_eq!(EpisodeAggregate::Cvar(0.).apply(&scores), 0.1);
+
+        let behavior = |x: f32, contact_rate: f32| BehaviorDescriptor {
+            final_fingertip: (x, 0.5),
+            mean_height: x,
+            contact_rate,
+        };
+        let (score, combined) = EpisodeAggregate::Cvar(0.5)
+            .combine(&[(0.2, behavior(0.25, 1.)), (0.6, behavior(0.75, 0.))]);
+        assert_eq!(score, 0.2);
+        assert_eq!(combined, behavior(0.5, 0.5));
+    }
+
+    #[test]
+    fn test_reused_context_keeps_the_episode_worlds_untouched() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let network = SmallAI::<BE>::with_init_std(&device, 0.1);
+        let mut context = EvalContext::default();
+        for seed in [3, 4, 3] {
+            let (score, _) = context.evaluate(
+                &network,
+                seed,
+                FitnessKind::PoseRetention,
+                ShapingConfig::default(),
+                &EpisodeConfig::default(),
+                &device,
+            );
+            assert!(score > 0. && score <= 1., "{score}");
+        }
+        assert_eq!(context.worlds.len(), 2);
+        // rollouts step copies, the kept world is still at the start of the episode
+        let fresh = episode_world(3);
+        assert_eq!(
+            context.worlds[&3].all_arm_corners(),
+            fresh.all_arm_corners()
+        );
+        assert_eq!(context.worlds[&3].ball_position(), fresh.ball_position());
+    }
+
+    #[test]
+    fn test_scores_repeat_until_the_observations_are_folded_in() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let network = SmallAI::<BE>::with_init_std(&device, 0.1);
+        let episode = EpisodeConfig {
+            steps: 20,
+            action_repeat: 2,
+            ..EpisodeConfig::default()
+        };
+        let score = |network: &SmallAI<BE>| {
+            test_ai_with_fitness(
+                network,
+                1,
+                FitnessKind::default(),
+                ShapingConfig::default(),
+                &episode,
+                &device,
+            )
+            .0
+        };
+        let first = score(&network);
+        assert_eq!(score(&network), first);
+
+        let observations = observe_ai_with_fitness(
+            &network,
+            1,
+            FitnessKind::default(),
+            ShapingConfig::default(),
+            &episode,
+            &device,
+        );
+        assert_eq!(observations.len(), 10);
+        assert_eq!(score(&network), first);
+        let standardized = network.with_observations(&observations);
+        assert_eq!(score(&standardized), score(&standardized));
+        assert_ne!(score(&standardized), first);
+    }
+
+    /// Ten steps, every one rewarded alike.
+    #[derive(Default)]
+    struct ShortTask {
+        resets: usize,
+    }
+
+    impl Task for ShortTask {
+        fn reset(&mut self, _world: &PhysicsWorld) {
+            self.resets += 1;
+        }
+
+        fn observe(&mut self, _world: &PhysicsWorld, observation: &mut Vec<f32>) {
+            observation.clear();
+            observation.resize(OBSERVATION_SIZE, 0.);
+        }
+
+        fn reward(&mut self, _world: &PhysicsWorld) -> f32 {
+            0.5
+        }
+
+        fn terminated(&self, _world: &PhysicsWorld, steps: usize) -> bool {
+            steps >= 10
+        }
+    }
+
+    #[test]
+    fn test_rollouts_run_any_task() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let network = SmallAI::<BE>::with_init_std(&device, 0.1);
+        let mut task = ShortTask::default();
+        let mut world = episode_world(0);
+        let (mut rewards, mut steps) = (Vec::new(), Vec::new());
+        let episode = EpisodeConfig::default();
+        run_episode(
+            &mut task,
+            &episode,
+            &mut world,
+            &network,
+            &device,
+            &mut Vec::new(),
+            &mut rewards,
+            |step, _, _, _| steps.push(step),
+        );
+        assert_eq!((task.resets, rewards.len()), (1, 10));
+        assert_eq!(steps, (1..=10).collect::<Vec<_>>());
+        assert_eq!(
+            test_ai_in_task(&network, &mut task, &episode, episode_world(0), &device).0,
+            0.5
+        );
+
+        // a coarser control rate rewards every action, an episode shorter than the task ends it
+        let coarse = EpisodeConfig {
+            steps: 7,
+            action_repeat: 3,
+            ..EpisodeConfig::default()
+        };
+        run_episode(
+            &mut task,
+            &coarse,
+            &mut episode_world(0),
+            &network,
+            &device,
+            &mut Vec::new(),
+            &mut rewards,
+            |_, _, _, _| {},
+        );
+        assert_eq!(rewards.len(), 3);
+
+        assert_eq!(
+            episode_score(&mut [0.2, 0.4, 0.1]),
+            (0.2 * 10. + 0.1 * 5. + 0.1 + 0.4) / 17.
+        );
+        // the default task scores like the evaluation context
+        let (score, _) = test_ai_in_task(
+            &network,
+            &mut PoseRetentionTask::default(),
+            &episode,
+            episode_world(0),
+            &device,
+        );
+        assert!(score > 0. && score <= 1., "{score}");
+    }
+
+    #[test]
+    fn test_rollouts_are_recorded_step_by_step() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let network = SmallAI::<BE>::with_init_std(&device, 0.1);
+        let episode = EpisodeConfig {
+            steps: 6,
+            action_repeat: 2,
+            ..EpisodeConfig::default()
+        };
+        let directory =
+            std::env::temp_dir().join(format!("trajectory_test_{}", std::process::id()));
+        let (score, _) = record_ai_with_fitness(
+            &network,
+            "best_small_ai_7",
+            3,
+            FitnessKind::default(),
+            ShapingConfig::default(),
+            &episode,
+            &directory,
+            &device,
+        )
+        .unwrap();
+        let unrecorded = test_ai_with_fitness(
+            &network,
+            3,
+            FitnessKind::default(),
+            ShapingConfig::default(),
+            &episode,
+            &device,
+        )
+        .0;
+        assert_eq!(score, unrecorded);
+
+        let fingerprint = network.fingerprint();
+        let path = TrajectoryRecorder::path(&directory, "best_small_ai_7", fingerprint, 3);
+        assert!(path.ends_with(format!(
+            "trajectory_best_small_ai_7_{fingerprint:016x}_seed_3.jsonl"
+        )));
+        assert_eq!(
+            crate::physics::trajectory::trajectory_name("models/best_small_ai_7.mpk"),
+            "best_small_ai_7"
+        );
+        let steps = crate::physics::trajectory::read_trajectory(&path).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(
+            steps.iter().map(|step| step.step).collect::<Vec<_>>(),
+            (1..=6).collect::<Vec<_>>()
+        );
+        let world = episode_world(3);
+        for step in &steps {
+            assert_eq!(
+                (step.observation.len(), step.action.len()),
+                (OBSERVATION_SIZE, ACTION_SIZE)
+            );
+            assert_eq!(step.poses.len(), world.body_poses().len());
+        }
+        // an action is held for its repeats
+        assert_eq!(
+            (&steps[0].action, &steps[0].observation),
+            (&steps[1].action, &steps[1].observation)
+        );
+        assert_ne!(steps[0].poses, steps[1].poses);
+    }
+
+    #[test]
+    fn test_rollout_behavior() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let network = SmallAI::<BE>::with_init_std(&device, 0.1);
+        let (score, behavior) = test_ai_with_behavior(&network, &device);
+        assert!(score > 0.);
+        assert_eq!(behavior.to_vec().len(), 4);
+        assert!(behavior.mean_height.is_finite());
+        assert_eq!(behavior.distance(&behavior), 0.);
+
+        // without forces the arm slumps against the wall and onto the ground
+        let idle = test_ai_with_behavior(&SmallAI::<BE>::with_init_std(&device, 0.), &device).1;
+        assert!(idle.contact_rate > 0., "{idle:?}");
+    }
 }
@@ -1,69 +1,46 @@
-use crate::base_ai::{
-    average_bw_linear, combine_bw_linear, interleave_bw_linear, jiggle_linear, max_amp_for_linear,
-    AI,
-};
+use crate::base_ai::{jiggle_linear, max_amp_for_linear, AI, INIT_STD};
+use crate::distill::{distill_copy, DistillationConfig, TeacherSample};
+use crate::physics::world::ArmConfig;
+use crate::running_norm::RunningNorm;
+use crate::sim_for_ai::{action_size, arm_observation_size, ACTION_SIZE, OBSERVATION_SIZE};
+use crate::weights::{load_versioned, save_versioned};
+use burn::backend::Autodiff;
 use burn::module::Module;
 use burn::nn::{Initializer, Linear, LinearConfig};
 use burn::prelude::Backend;
 use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
 use burn::tensor::activation::{relu, tanh};
 use burn::tensor::{Distribution, Tensor};
+use rand::rngs::StdRng;
 
 #[derive(Module, Debug)]
 pub struct SmallAI<B: Backend> {
     input: Linear<B>,
     output: Linear<B>,
     hidden: Linear<B>,
+    norm: RunningNorm<B>,
 }
 impl<B: Backend> AI<B> for SmallAI<B> {
-    fn jiggle(&self, d: &Distribution) -> Self {
+    fn jiggle(&self, d: &Distribution, rng: &mut StdRng) -> Self {
         Self {
-            input: jiggle_linear(&self.input, &d),
-            output: jiggle_linear(&self.output, &d),
-            hidden: jiggle_linear(&self.hidden, &d),
+            input: jiggle_linear(&self.input, d, rng),
+            output: jiggle_linear(&self.output, d, rng),
+            hidden: jiggle_linear(&self.hidden, d, rng),
+            norm: self.norm.detached(),
         }
     }
-    fn offspring(&self, other_parent: &Self, d: &Distribution) -> Self {
-        Self {
-            input: combine_bw_linear(&self.input, &other_parent.input),
-            output: combine_bw_linear(&self.output, &other_parent.output),
-            hidden: combine_bw_linear(&self.hidden, &other_parent.hidden),
-        }
-        .jiggle(d)
-    }
 
-    fn offspring_iw(&self, other_parent: &Self, d: &Distribution) -> Self {
-        Self {
-            input: interleave_bw_linear(&self.input, &other_parent.input),
-            output: interleave_bw_linear(&self.output, &other_parent.output),
-            hidden: interleave_bw_linear(&self.hidden, &other_parent.hidden),
-        }
-        .jiggle(d)
-    }
-
-    fn offspring_aw(&self, other_parent: &Self, d: &Distribution) -> Self {
-        Self {
-            input: average_bw_linear(&self.input, &other_parent.input),
-            output: average_bw_linear(&self.output, &other_parent.output),
-            hidden: average_bw_linear(&self.hidden, &other_parent.hidden),
-        }
-        .jiggle(d)
+    fn apply(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
+        let x = relu(self.input.forward(self.norm.standardize(input)));
+        let x = relu(self.hidden.forward(x));
+        tanh(self.output.forward(x))
     }
 
-    fn offspring_layers(&self, other_parent: &Self, d: &Distribution) -> Self {
+    fn with_observations(&self, observations: &[Vec<f32>]) -> Self {
         Self {
-            input: self.input.clone(),
-            output: other_parent.output.clone(),
-            hidden: self.hidden.clone(),
+            norm: self.norm.updated(observations),
+            ..self.clone()
         }
-        .jiggle(d)
-    }
-
-    fn apply(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
-        let x = relu(self.input.forward(input));
-        let x = relu(self.hidden.forward(x));
-        let x = tanh(self.output.forward(x));
-        x
     }
 
     fn max_amp(&self) -> f32 {
@@ -75,7 +52,7 @@ impl<B: Backend> AI<B> for SmallAI<B> {
         all_maximums
             .iter()
             .max_by(|a, b| {
-                a.partial_cmp(&b)
+                a.partial_cmp(b)
                     .expect("max amplitude comparison failed across all layers")
             })
             .copied()
@@ -83,9 +60,7 @@ impl<B: Backend> AI<B> for SmallAI<B> {
     }
 
     fn save_file(&self, filename: &str, recorder: &NamedMpkFileRecorder<FullPrecisionSettings>) {
-        self.clone()
-            .save_file(filename, recorder)
-            .expect("save failed");
+        save_versioned(self, filename, recorder).expect("save failed");
     }
 
     fn load_a_file(
@@ -93,34 +68,97 @@ impl<B: Backend> AI<B> for SmallAI<B> {
         filename: &str,
         recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
     ) -> Self {
-        let device = self.input.devices()[0].clone();
-        self.load_file(filename, recorder, &device)
-            .expect("load failed")
+        load_versioned(self, filename, recorder).expect("load failed")
     }
 
     fn network_name(&self) -> &'static str {
         "Small AI"
     }
+
+    fn imitate(
+        &self,
+        samples: &[TeacherSample],
+        config: &DistillationConfig,
+        device: &B::Device,
+        rng: &mut StdRng,
+    ) -> Option<Self> {
+        Some(distill_copy(
+            self,
+            SmallAI::<Autodiff<B>>::new(device),
+            samples,
+            config,
+            device,
+            rng,
+        ))
+    }
+
+    fn layers(&self) -> Vec<(&'static str, Linear<B>)> {
+        vec![
+            ("input", self.input.clone()),
+            ("hidden", self.hidden.clone()),
+            ("output", self.output.clone()),
+        ]
+    }
+
+    fn activations(&self) -> Vec<&'static str> {
+        vec!["relu", "relu", "tanh"]
+    }
+
+    fn with_layers(&self, layers: Vec<Linear<B>>) -> Self {
+        let [input, hidden, output] =
+            <[Linear<B>; 3]>::try_from(layers).expect("SmallAI has three layers");
+        Self {
+            input,
+            output,
+            hidden,
+            norm: self.norm.detached(),
+        }
+    }
+
+    fn hidden_sizes(&self) -> Vec<usize> {
+        vec![self.input.weight.dims()[1], self.hidden.weight.dims()[1]]
+    }
 }
 
 impl<B: Backend> SmallAI<B> {
     pub fn new(device: &B::Device) -> Self {
-        let input_config = LinearConfig::new(64, 128)
+        Self::with_init_std(device, INIT_STD)
+    }
+
+    /// Weights drawn with the given spread. Gradient training needs a small one, as unit spread
+    /// drives the output tanh into saturation where it has no gradient.
+    pub fn with_init_std(device: &B::Device, std: f64) -> Self {
+        Self::sized(device, std, OBSERVATION_SIZE, ACTION_SIZE)
+    }
+
+    /// Sized for a world with an arm built from the config, such as one with finger abduction.
+    pub fn for_arm(device: &B::Device, arm: &ArmConfig) -> Self {
+        Self::sized(
+            device,
+            INIT_STD,
+            arm_observation_size(arm),
+            action_size(arm),
+        )
+    }
+
+    fn sized(device: &B::Device, std: f64, observation_size: usize, action_size: usize) -> Self {
+        let input_config = LinearConfig::new(observation_size, 128)
             .with_bias(true)
-            .with_initializer(Initializer::Normal { mean: 0., std: 1. });
+            .with_initializer(Initializer::Normal { mean: 0., std });
 
-        let output_config = LinearConfig::new(14, 7)
+        let output_config = LinearConfig::new(14, action_size)
             .with_bias(true)
-            .with_initializer(Initializer::Normal { mean: 0., std: 1. });
+            .with_initializer(Initializer::Normal { mean: 0., std });
 
         let hidden_config = LinearConfig::new(128, 14)
             .with_bias(true)
-            .with_initializer(Initializer::Normal { mean: 0., std: 1. });
+            .with_initializer(Initializer::Normal { mean: 0., std });
 
         Self {
             input: input_config.init(device),
             output: output_config.init(device),
             hidden: hidden_config.init(device),
+            norm: RunningNorm::new(observation_size, device),
         }
     }
 }
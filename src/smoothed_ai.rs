@@ -0,0 +1,215 @@
+use crate::base_ai::AI;
+use crate::distill::{DistillationConfig, TeacherSample};
+use burn::module::{Ignored, Module};
+use burn::nn::Linear;
+use burn::prelude::Backend;
+use burn::record::{FullPrecisionSettings, NamedMpkFileRecorder};
+use burn::tensor::{Distribution, Tensor};
+use rand::rngs::StdRng;
+use std::marker::PhantomData;
+use std::sync::{Mutex, MutexGuard};
+
+/// The forces sent in the previous step. Like the recurrent hidden state, clones start without.
+#[derive(Debug, Default)]
+pub struct FilterState(Mutex<Option<Vec<f32>>>);
+
+impl Clone for FilterState {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl FilterState {
+    fn previous(&self) -> MutexGuard<'_, Option<Vec<f32>>> {
+        self.0.lock().expect("filter state poisoned")
+    }
+}
+
+/// Wraps a network and low-pass filters its forces with an exponential moving average, so the
+/// arm is not driven with forces jumping every step. Each step moves the forces `smoothing` of
+/// the way towards what the network asks for: 1 passes them through, smaller values smooth more.
+/// The first step of an episode is sent as is. Saving and loading only touch the inner network.
+#[derive(Module, Debug)]
+pub struct SmoothedAI<B: Backend, A: Module<B>> {
+    inner: A,
+    smoothing: Ignored<f32>,
+    state: Ignored<FilterState>,
+    backend: PhantomData<B>,
+}
+
+impl<B: Backend, A: AI<B>> SmoothedAI<B, A> {
+    pub fn new(inner: A, smoothing: f32) -> Self {
+        assert!(
+            smoothing > 0. && smoothing <= 1.,
+            "smoothing has to be in (0, 1], got {smoothing}"
+        );
+        Self {
+            inner,
+            smoothing: Ignored(smoothing),
+            state: Ignored(FilterState::default()),
+            backend: PhantomData,
+        }
+    }
+
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+}
+
+impl<B: Backend, A: AI<B>> AI<B> for SmoothedAI<B, A> {
+    fn jiggle(&self, d: &Distribution, rng: &mut StdRng) -> Self {
+        Self::new(self.inner.jiggle(d, rng), *self.smoothing)
+    }
+
+    fn apply(&self, input: Tensor<B, 1>) -> Tensor<B, 1> {
+        let device = input.device();
+        let forces = self.inner.apply(input);
+        if *self.smoothing == 1. {
+            return forces;
+        }
+        let forces: Vec<f32> = forces
+            .into_data()
+            .convert::<f32>()
+            .to_vec()
+            .expect("ai requested forces not available");
+        let mut previous = self.state.previous();
+        let smoothed = match previous.take() {
+            Some(previous) => previous
+                .iter()
+                .zip(forces)
+                .map(|(previous, force)| previous + (force - previous) * *self.smoothing)
+                .collect(),
+            None => forces,
+        };
+        *previous = Some(smoothed.clone());
+        Tensor::from_floats(smoothed.as_slice(), &device)
+    }
+
+    fn reset_state(&self) {
+        self.inner.reset_state();
+        *self.state.previous() = None;
+    }
+
+    fn max_amp(&self) -> f32 {
+        self.inner.max_amp()
+    }
+
+    fn with_observations(&self, observations: &[Vec<f32>]) -> Self {
+        Self::new(self.inner.with_observations(observations), *self.smoothing)
+    }
+
+    fn train_auxiliary(&self, device: &B::Device, rng: &mut StdRng) -> Self {
+        Self::new(self.inner.train_auxiliary(device, rng), *self.smoothing)
+    }
+
+    fn imitate(
+        &self,
+        samples: &[TeacherSample],
+        config: &DistillationConfig,
+        device: &B::Device,
+        rng: &mut StdRng,
+    ) -> Option<Self> {
+        let fitted = self.inner.imitate(samples, config, device, rng)?;
+        Some(Self::new(fitted, *self.smoothing))
+    }
+
+    fn save_file(&self, filename: &str, recorder: &NamedMpkFileRecorder<FullPrecisionSettings>) {
+        AI::save_file(&self.inner, filename, recorder);
+    }
+
+    fn load_a_file(
+        self,
+        filename: &str,
+        recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+    ) -> Self {
+        let smoothing = *self.smoothing;
+        Self::new(self.inner.load_a_file(filename, recorder), smoothing)
+    }
+
+    fn network_name(&self) -> &'static str {
+        self.inner.network_name()
+    }
+
+    fn layers(&self) -> Vec<(&'static str, Linear<B>)> {
+        self.inner.layers()
+    }
+
+    fn activations(&self) -> Vec<&'static str> {
+        self.inner.activations()
+    }
+
+    fn layer_inputs(&self) -> Vec<Vec<Option<usize>>> {
+        self.inner.layer_inputs()
+    }
+
+    fn force_layers(&self) -> Vec<usize> {
+        self.inner.force_layers()
+    }
+
+    fn input_size(&self) -> usize {
+        self.inner.input_size()
+    }
+
+    fn output_size(&self) -> usize {
+        self.inner.output_size()
+    }
+
+    fn grips(&self) -> bool {
+        self.inner.grips()
+    }
+
+    fn hidden_sizes(&self) -> Vec<usize> {
+        self.inner.hidden_sizes()
+    }
+
+    fn frozen_layers(&self) -> Vec<bool> {
+        self.inner.frozen_layers()
+    }
+
+    fn with_layers(&self, layers: Vec<Linear<B>>) -> Self {
+        Self::new(self.inner.with_layers(layers), *self.smoothing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim_for_ai::OBSERVATION_SIZE;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+
+    #[test]
+    fn test_forces_move_gradually() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let network = SmallAI::<BE>::with_init_std(&device, 0.1);
+        // a copy with its own running norm gives the unfiltered forces
+        let twin = network.with_layers(
+            network
+                .layers()
+                .into_iter()
+                .map(|(_, layer)| layer)
+                .collect(),
+        );
+        let smoothed = SmoothedAI::new(network, 0.25);
+        let forces = |network: &dyn Fn(Tensor<BE, 1>) -> Tensor<BE, 1>, input: f32| -> Vec<f32> {
+            network(Tensor::full([OBSERVATION_SIZE], input, &device))
+                .to_data()
+                .to_vec()
+                .unwrap()
+        };
+
+        let first = forces(&|input| smoothed.apply(input), 1.);
+        assert_eq!(first, forces(&|input| twin.apply(input), 1.));
+        let second = forces(&|input| smoothed.apply(input), 100.);
+        let clean = forces(&|input| twin.apply(input), 100.);
+        assert_ne!(first, clean);
+        for ((first, second), clean) in first.iter().zip(&second).zip(&clean) {
+            assert!((second - (first + (clean - first) * 0.25)).abs() < 1e-5);
+        }
+
+        smoothed.reset_state();
+        assert!(smoothed.state.previous().is_none());
+    }
+}
@@ -0,0 +1,143 @@
+use crate::base_ai::AI;
+use burn::prelude::Backend;
+use serde::{Deserialize, Serialize};
+
+/// What individuals are compared by when they are grouped into species.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Descriptor {
+    /// The flattened parameters, the distance being the root mean square difference.
+    #[default]
+    Parameters,
+    /// The behavior descriptor of the scoring rollout.
+    Behavior,
+}
+
+/// Groups an island's individuals into species of similar ones and divides their scores by the
+/// size of their species, so a crowd of near copies of the best doesn't crowd out a different
+/// individual that is weaker for now.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpeciationConfig {
+    /// Distance within which an individual joins a species.
+    pub threshold: f32,
+    #[serde(default)]
+    pub descriptor: Descriptor,
+}
+
+/// The network's parameters scaled so Euclidean distances between them are root mean square
+/// differences, the same for any network size.
+pub fn parameter_descriptor<B: Backend>(ai: &impl AI<B>) -> Vec<f32> {
+    let parameters = ai.to_flat_vec();
+    let scale = 1. / (parameters.len().max(1) as f32).sqrt();
+    parameters.into_iter().map(|p| p * scale).collect()
+}
+
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// The species of every individual. An individual joins the first species whose founder is
+/// within the threshold, and founds a new one otherwise, so given individuals sorted best
+/// first every species is represented by its best member.
+pub fn speciate(descriptors: &[Vec<f32>], threshold: f32) -> Vec<usize> {
+    let mut founders: Vec<usize> = Vec::new();
+    descriptors
+        .iter()
+        .enumerate()
+        .map(|(i, descriptor)| {
+            founders
+                .iter()
+                .position(|&founder| distance(&descriptors[founder], descriptor) <= threshold)
+                .unwrap_or_else(|| {
+                    founders.push(i);
+                    founders.len() - 1
+                })
+        })
+        .collect()
+}
+
+/// The individuals with their scores divided by the size of their species, sorted best first.
+/// Scores below 0 are shifted up to 0 first, as dividing a negative score would raise it. The
+/// best by raw score stays first, level with the best shared score, so sharing never costs the
+/// island its elite.
+pub fn share_fitness<T>(scored: Vec<(f32, T)>, species: &[usize]) -> Vec<(f32, T)> {
+    let mut sizes = vec![0; species.iter().max().map_or(0, |max| max + 1)];
+    for &s in species {
+        sizes[s] += 1;
+    }
+    let lowest = scored.iter().map(|(score, _)| *score).fold(0_f32, f32::min);
+    let best = scored
+        .iter()
+        .enumerate()
+        .max_by(|(i, a), (j, b)| a.0.total_cmp(&b.0).then(j.cmp(i)))
+        .map(|(i, _)| i);
+    let mut shared: Vec<(bool, f32, T)> = scored
+        .into_iter()
+        .zip(species)
+        .enumerate()
+        .map(|(i, ((score, individual), &s))| {
+            (
+                Some(i) == best,
+                (score - lowest) / sizes[s] as f32,
+                individual,
+            )
+        })
+        .collect();
+    shared.sort_by(|a, b| {
+        b.0.cmp(&a.0).then(
+            b.1.partial_cmp(&a.1)
+                .expect("ai score should be comparable"),
+        )
+    });
+    let top = shared
+        .iter()
+        .map(|(_, score, _)| *score)
+        .fold(0_f32, f32::max);
+    shared
+        .into_iter()
+        .map(|(best, score, individual)| (if best { top } else { score }, individual))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fitness_is_shared_within_species() {
+        let descriptors = vec![vec![0., 0.], vec![0.1, 0.], vec![3., 0.], vec![0., 0.2]];
+        let species = speciate(&descriptors, 0.5);
+        assert_eq!(species, vec![0, 0, 1, 0]);
+
+        let scored = vec![
+            (0.9, "best"),
+            (0.8, "copy"),
+            (0.5, "different"),
+            (0.6, "another copy"),
+        ];
+        let shared = share_fitness(scored, &species);
+        let order: Vec<&str> = shared.iter().map(|(_, name)| *name).collect();
+        // the best stays first however crowded its species, level with the different one
+        assert_eq!(order, vec!["best", "different", "copy", "another copy"]);
+        assert_eq!(shared[0].0, 0.5);
+        assert!((shared[2].0 - 0.8 / 3.).abs() < 1e-6);
+
+        // negative scores are shifted to 0 before they are divided, so a crowd sinks
+        let scored = vec![
+            (-1., "best"),
+            (-2., "copy"),
+            (-3., "copy"),
+            (-2., "different"),
+        ];
+        let shared = share_fitness(scored, &[0, 0, 0, 1]);
+        let order: Vec<&str> = shared.iter().map(|(_, name)| *name).collect();
+        assert_eq!(order, vec!["best", "different", "copy", "copy"]);
+        assert!(shared.iter().all(|(score, _)| *score >= 0.));
+        assert_eq!(shared[3].0, 0.);
+    }
+}
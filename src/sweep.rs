@@ -0,0 +1,314 @@
+use crate::results_log::GenerationRecord;
+use crate::schedule::ScheduleConfig;
+use crate::train_config::{TrainConfig, TrainConfigError};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// A hyperparameter sweep, read from a TOML file such as
+///
+/// ```toml
+/// initial_sigma = [0.05, 0.15]
+/// best_proportion = [0.1, 0.25]
+/// operator_weights = [{}, { prune = 0.5 }]
+/// parallel = 2
+///
+/// [base]
+/// generations = 20
+/// islands = 2
+/// ```
+///
+/// Every combination of the listed values is trained from the `base` configuration. Settings
+/// without values keep the base's.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SweepConfig {
+    /// The configuration every run starts from, with few generations, short runs being the
+    /// point.
+    pub base: TrainConfig,
+    pub initial_sigma: Vec<f64>,
+    pub smallest_sigma: Vec<f64>,
+    pub sigma_tau: Vec<f64>,
+    pub best_proportion: Vec<f32>,
+    pub island_population: Vec<usize>,
+    /// Operator weights by name, the operators left out keeping their default weight.
+    pub operator_weights: Vec<HashMap<String, f32>>,
+    /// Runs trained at the same time.
+    pub parallel: usize,
+    /// Where every run gets a directory of its own.
+    pub directory: PathBuf,
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        Self {
+            base: TrainConfig::default(),
+            initial_sigma: Vec::new(),
+            smallest_sigma: Vec::new(),
+            sigma_tau: Vec::new(),
+            best_proportion: Vec::new(),
+            island_population: Vec::new(),
+            operator_weights: Vec::new(),
+            parallel: 1,
+            directory: PathBuf::from("sweep"),
+        }
+    }
+}
+
+/// One combination of the swept values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepRun {
+    pub config: TrainConfig,
+    pub schedule: ScheduleConfig,
+    /// The swept values, as `name=value` pairs.
+    pub label: String,
+}
+
+/// How a run ended up, from its results log.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SweepOutcome {
+    pub best_score: f32,
+    /// The best island median of the last generation.
+    pub median_score: f32,
+    pub generations: usize,
+}
+
+impl SweepOutcome {
+    /// `None` for a run that logged nothing.
+    pub fn of(records: &[GenerationRecord]) -> Option<Self> {
+        let last = records.iter().map(|record| record.generation).max()?;
+        let best_score = records
+            .iter()
+            .map(|record| record.best_score)
+            .fold(f32::MIN, f32::max);
+        let median_score = records
+            .iter()
+            .filter(|record| record.generation == last)
+            .map(|record| record.median_score)
+            .fold(f32::MIN, f32::max);
+        let first = records.iter().map(|record| record.generation).min()?;
+        Some(Self {
+            best_score,
+            median_score,
+            generations: last - first + 1,
+        })
+    }
+
+    /// Reads the JSON lines results log of a run.
+    pub fn read(path: impl AsRef<Path>) -> std::io::Result<Option<Self>> {
+        let records: Vec<GenerationRecord> = std::fs::read_to_string(path)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(std::io::Error::other)?;
+        Ok(Self::of(&records))
+    }
+}
+
+/// Every run once for each value, labelled `name=value`, or the runs as they are when there
+/// are no values.
+fn vary<T>(
+    runs: Vec<SweepRun>,
+    values: &[T],
+    label: impl Fn(&T) -> String,
+    set: impl Fn(&mut SweepRun, &T),
+) -> Vec<SweepRun> {
+    if values.is_empty() {
+        return runs;
+    }
+    runs.iter()
+        .flat_map(|run| {
+            values.iter().map(|value| {
+                let mut run = run.clone();
+                set(&mut run, value);
+                if !run.label.is_empty() {
+                    run.label.push(' ');
+                }
+                run.label.push_str(&label(value));
+                run
+            })
+        })
+        .collect()
+}
+
+fn weights_label(weights: &HashMap<String, f32>) -> String {
+    let mut weights: Vec<String> = weights
+        .iter()
+        .map(|(name, weight)| format!("{name}:{weight}"))
+        .collect();
+    weights.sort();
+    format!("[{}]", weights.join(" "))
+}
+
+impl SweepConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TrainConfigError> {
+        let text = std::fs::read_to_string(path)?;
+        let sweep: Self =
+            toml::from_str(&text).map_err(|e| TrainConfigError::Parse(e.to_string()))?;
+        sweep.base.validate()?;
+        Ok(sweep)
+    }
+
+    /// Every combination of the swept values, the last setting varying fastest.
+    pub fn runs(&self) -> Vec<SweepRun> {
+        let runs = vec![SweepRun {
+            config: self.base.clone(),
+            schedule: ScheduleConfig::default(),
+            label: String::new(),
+        }];
+        let runs = vary(
+            runs,
+            &self.initial_sigma,
+            |v| format!("initial_sigma={v}"),
+            |run, v| run.config.initial_sigma = *v,
+        );
+        let runs = vary(
+            runs,
+            &self.smallest_sigma,
+            |v| format!("smallest_sigma={v}"),
+            |run, v| run.config.smallest_sigma = *v,
+        );
+        let runs = vary(
+            runs,
+            &self.sigma_tau,
+            |v| format!("sigma_tau={v}"),
+            |run, v| run.config.sigma_tau = *v,
+        );
+        let runs = vary(
+            runs,
+            &self.best_proportion,
+            |v| format!("best_proportion={v}"),
+            |run, v| run.config.best_proportion = *v,
+        );
+        let runs = vary(
+            runs,
+            &self.island_population,
+            |v| format!("island_population={v}"),
+            |run, v| run.config.island_population = *v,
+        );
+        vary(
+            runs,
+            &self.operator_weights,
+            |weights| format!("operators={}", weights_label(weights)),
+            |run, weights| run.schedule.weights = weights.clone(),
+        )
+    }
+}
+
+/// The runs ranked by their best score, one line each, with the ones that logged nothing last.
+pub fn comparison_table(runs: &[SweepRun], outcomes: &[Option<SweepOutcome>]) -> String {
+    let mut ranked: Vec<(&SweepRun, &Option<SweepOutcome>)> = runs.iter().zip(outcomes).collect();
+    ranked.sort_by(|(_, a), (_, b)| {
+        let best =
+            |outcome: &Option<SweepOutcome>| outcome.map_or(f32::MIN, |outcome| outcome.best_score);
+        best(b).total_cmp(&best(a))
+    });
+    let mut table = format!(
+        "{:>4} {:>10} {:>10} {:>11}  settings\n",
+        "rank", "best", "median", "generations"
+    );
+    for (rank, (run, outcome)) in ranked.into_iter().enumerate() {
+        let settings = if run.label.is_empty() {
+            "base"
+        } else {
+            &run.label
+        };
+        match outcome {
+            Some(outcome) => writeln!(
+                table,
+                "{:>4} {:>10.5} {:>10.5} {:>11}  {settings}",
+                rank + 1,
+                outcome.best_score,
+                outcome.median_score,
+                outcome.generations
+            ),
+            None => writeln!(
+                table,
+                "{:>4} {:>10} {:>10} {:>11}  {settings}",
+                rank + 1,
+                "-",
+                "-",
+                "-"
+            ),
+        }
+        .expect("writing to a string");
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        generation: usize,
+        island: usize,
+        best_score: f32,
+        median_score: f32,
+    ) -> GenerationRecord {
+        GenerationRecord {
+            generation,
+            island,
+            best_score,
+            median_score,
+            worst_score: 0.,
+            sigma: 0.1,
+            eval_ms: 1,
+            best_fingerprint: String::new(),
+            episode_seed: 0,
+        }
+    }
+
+    #[test]
+    fn test_sweep_runs_every_combination() {
+        let sweep: SweepConfig = toml::from_str(
+            "initial_sigma = [0.05, 0.15]\nisland_population = [20, 40, 60]\noperator_weights = [{}, { prune = 0.5 }]\n\n[base]\nislands = 2",
+        )
+        .unwrap();
+        let runs = sweep.runs();
+        assert_eq!(runs.len(), 12);
+        assert!(runs
+            .iter()
+            .all(|run| run.config.islands == 2 && run.config.generations == 100));
+        assert_eq!(
+            runs[3].label,
+            "initial_sigma=0.05 island_population=40 operators=[prune:0.5]"
+        );
+        assert_eq!(
+            (
+                runs[3].config.initial_sigma,
+                runs[3].config.island_population
+            ),
+            (0.05, 40)
+        );
+        assert_eq!(runs[3].schedule.weights["prune"], 0.5);
+        assert_eq!(SweepConfig::default().runs().len(), 1);
+        // the runs' configurations are handed over as TOML
+        let config: TrainConfig =
+            toml::from_str(&toml::to_string(&runs[3].config).unwrap()).unwrap();
+        assert_eq!(config, runs[3].config);
+
+        let good = SweepOutcome::of(&[
+            record(0, 0, 0.2, 0.1),
+            record(1, 0, 0.5, 0.2),
+            record(1, 1, 0.3, 0.3),
+        ]);
+        assert_eq!(
+            good,
+            Some(SweepOutcome {
+                best_score: 0.5,
+                median_score: 0.3,
+                generations: 2
+            })
+        );
+        let table = comparison_table(
+            &runs[..3],
+            &[SweepOutcome::of(&[record(0, 0, 0.1, 0.1)]), None, good],
+        );
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("   1    0.50000") && lines[1].ends_with(&runs[2].label));
+        assert!(lines[3].contains(" - ") && lines[3].ends_with(&runs[1].label));
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// An evaluation taking more than this many times the median one is a straggler.
+const STRAGGLER_FACTOR: f64 = 2.;
+
+/// How long scoring one individual took and how many physics steps it simulated.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct EvaluationTiming {
+    pub wall: Duration,
+    pub physics_steps: usize,
+}
+
+impl EvaluationTiming {
+    pub fn steps_per_second(&self) -> f64 {
+        self.physics_steps as f64 / self.wall.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// The evaluation times of a generation, to find the rollouts holding it up.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TimingSummary {
+    pub evaluations: usize,
+    pub mean_ms: f64,
+    pub median_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+    /// All the simulated steps over all the evaluation time, per core.
+    pub steps_per_second: f64,
+    /// Evaluations taking more than twice the median.
+    pub stragglers: usize,
+    /// Index of the slowest evaluation among the timed ones.
+    pub slowest: usize,
+}
+
+impl TimingSummary {
+    /// `None` when nothing was evaluated, e.g. every score came from the cache.
+    pub fn of(timings: &[EvaluationTiming]) -> Option<Self> {
+        if timings.is_empty() {
+            return None;
+        }
+        let milliseconds: Vec<f64> = timings
+            .iter()
+            .map(|timing| timing.wall.as_secs_f64() * 1000.)
+            .collect();
+        let mut sorted = milliseconds.clone();
+        sorted.sort_by(f64::total_cmp);
+        let percentile = |share: f64| sorted[((sorted.len() - 1) as f64 * share).round() as usize];
+        let median_ms = percentile(0.5);
+        let (slowest, max_ms) = milliseconds
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .expect("timings are not empty");
+        let total_seconds: f64 = timings.iter().map(|timing| timing.wall.as_secs_f64()).sum();
+        let total_steps: usize = timings.iter().map(|timing| timing.physics_steps).sum();
+        Some(Self {
+            evaluations: timings.len(),
+            mean_ms: milliseconds.iter().sum::<f64>() / timings.len() as f64,
+            median_ms,
+            p95_ms: percentile(0.95),
+            max_ms,
+            steps_per_second: total_steps as f64 / total_seconds.max(f64::EPSILON),
+            stragglers: milliseconds
+                .iter()
+                .filter(|&&ms| ms > STRAGGLER_FACTOR * median_ms)
+                .count(),
+            slowest,
+        })
+    }
+}
+
+impl Display for TimingSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} evaluations, mean {:.1} ms, median {:.1} ms, p95 {:.1} ms, max {:.1} ms, {:.0} steps/s, {} stragglers",
+            self.evaluations, self.mean_ms, self.median_ms, self.p95_ms, self.max_ms, self.steps_per_second, self.stragglers
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing_summary() {
+        assert_eq!(TimingSummary::of(&[]), None);
+        let timing = |ms| EvaluationTiming {
+            wall: Duration::from_millis(ms),
+            physics_steps: 500,
+        };
+        assert_eq!(timing(250).steps_per_second(), 2000.);
+
+        let timings: Vec<EvaluationTiming> = [10, 12, 11, 50, 9].map(timing).to_vec();
+        let summary = TimingSummary::of(&timings).unwrap();
+        assert_eq!(
+            (summary.evaluations, summary.stragglers, summary.slowest),
+            (5, 1, 3)
+        );
+        assert!((summary.median_ms - 11.).abs() < 1e-9);
+        assert!((summary.max_ms - 50.).abs() < 1e-9);
+        assert!((summary.mean_ms - 18.4).abs() < 1e-9);
+        assert!((summary.steps_per_second - 2500. / 0.092).abs() < 1e-6);
+        assert!(summary.to_string().contains("1 stragglers"));
+    }
+}
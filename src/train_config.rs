@@ -0,0 +1,631 @@
+use crate::alps::AlpsConfig;
+use crate::base_ai::{MutationConfig, INITIAL_SD, SIGMA_TAU, SMALLEST_SD};
+use crate::evolution::{Annealing, Budget, EarlyStopping, MigrantChoice, MigrantSlot, Migration};
+use crate::fitness::{FitnessKind, ShapingConfig};
+use crate::fitness_cache::FitnessCacheConfig;
+use crate::map_elites::MapElitesConfig;
+use crate::novelty::NoveltyConfig;
+use crate::physics::world::{BallConfig, PhysicsWorld, GROUND_HALF_WIDTH};
+use crate::retention::RetentionPolicy;
+use crate::scripted::WarmStartConfig;
+use crate::selection::Selection;
+use crate::sim_for_ai::{EpisodeAggregate, EpisodeConfig};
+use crate::speciation::SpeciationConfig;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// First seed of the confirmation episodes, above every seed `TrainConfig::episode_seeds` hands
+/// out, so a candidate is confirmed in episodes it was not selected in.
+const CONFIRMATION_SEED: u64 = 1 << 63;
+
+/// Why a training configuration could not be read.
+#[derive(Debug)]
+pub enum TrainConfigError {
+    Io(std::io::Error),
+    Parse(String),
+    /// A setting the run could not go with.
+    Invalid(String),
+}
+
+impl Display for TrainConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrainConfigError::Io(e) => write!(f, "could not read training configuration: {e}"),
+            TrainConfigError::Parse(e) => write!(f, "could not parse training configuration: {e}"),
+            TrainConfigError::Invalid(e) => write!(f, "invalid training configuration: {e}"),
+        }
+    }
+}
+
+impl Error for TrainConfigError {}
+
+impl From<std::io::Error> for TrainConfigError {
+    fn from(e: std::io::Error) -> Self {
+        TrainConfigError::Io(e)
+    }
+}
+
+/// The settings of an evolution run, read from a TOML file such as
+///
+/// ```toml
+/// islands = 8
+/// generations = 500
+/// initial_sigma = 0.1
+/// ```
+///
+/// Settings left out keep the defaults, which are the values runs used before they were
+/// configurable.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrainConfig {
+    pub islands: usize,
+    pub island_population: usize,
+    /// Island sizes over the run as `[generation, size]` points in order, e.g.
+    /// `[[0, 200], [100, 50]]` for large exploratory islands shrinking to small exploitative
+    /// ones, interpolated linearly between the points and held before and after them.
+    /// `island_population` throughout when empty.
+    pub population_schedule: Vec<(usize, usize)>,
+    pub generations: usize,
+    /// Share of every island that parents the offspring.
+    pub best_proportion: f32,
+    /// How many of the best survive into the next generation unmodified, at the back of the
+    /// island where migrants never go. The whole `best_proportion` share when left out.
+    pub elites: Option<usize>,
+    /// Fresh random individuals every generation, at the front of the island, where migrants
+    /// and hall of fame members are put as well.
+    pub random_per_generation: usize,
+    /// How parents are picked: `truncation` among the fittest only, `roulette` in proportion to
+    /// the scores or `rank` by the position in the ranking.
+    pub selection: Selection,
+    /// How strongly `rank` selection prefers the better individuals, between 1 and 2.
+    pub selection_pressure: f64,
+    /// Species of similar individuals sharing their fitness, given as a `[speciation]` table.
+    pub speciation: Option<SpeciationConfig>,
+    /// Novelty search, ranking by the novelty of the behaviors blended with the scores, given
+    /// as a `[novelty]` table.
+    pub novelty: Option<NoveltyConfig>,
+    /// An archive of the best individual of every region of the final fingertip positions,
+    /// given as a `[map_elites]` table.
+    pub map_elites: Option<MapElitesConfig>,
+    /// The islands as age layers from the youngest to the oldest, given as an `[alps]` table.
+    /// The layers exchange no migrants and are injected no members of the hall of fame or the
+    /// MAP-Elites archive, which would put long evolved individuals into the youngest layer.
+    pub alps: Option<AlpsConfig>,
+    /// Scores kept for individuals evaluated before, given as a `[fitness_cache]` table.
+    pub fitness_cache: Option<FitnessCacheConfig>,
+    /// Part of a fresh population fitted to a scripted controller before the first
+    /// generation, given as a `[warm_start]` table.
+    pub warm_start: Option<WarmStartConfig>,
+    /// Episodes every individual is scored in, each with the ball elsewhere, the same ones
+    /// every generation.
+    pub episodes: usize,
+    /// How the episodes' scores make the fitness: `"mean"`, or `{ cvar = <share> }` for the
+    /// mean of the worst share of them.
+    pub episode_aggregate: EpisodeAggregate,
+    /// How long every episode runs and how often the networks act in it, given as an
+    /// `[episode]` table.
+    pub episode: EpisodeConfig,
+    /// Every island scored in episodes of its own, derived from the run's seed, so the islands
+    /// see decorrelated evaluation noise. Otherwise all are scored from episode 0, the default
+    /// world.
+    pub island_seeds: bool,
+    /// What the steps are scored by: `"pose_retention"`, `"reach"`, `"grasp_and_lift"`, or
+    /// `{ ball_in_basket = { x = <x>, y = <y> } }`, the basket task with its basket there, won
+    /// after `hold_steps = <n>` with `bonus = <b>` if they are given.
+    pub fitness: FitnessKind,
+    /// Terms added to every step's reward, given as a `[shaping]` table.
+    pub shaping: ShapingConfig,
+    /// How many tries per offspring to find one that is not a duplicate.
+    pub duplicate_retries: usize,
+    pub initial_sigma: f64,
+    pub smallest_sigma: f64,
+    /// Learning rate of the log-normal sigma mutation.
+    pub sigma_tau: f64,
+    /// A sigma decaying from the initial to the smallest one, `{ exponential = { rate = <r> } }`
+    /// or `{ cosine = { generations = <n> } }`, in place of the self-adapted one.
+    pub annealing: Option<Annealing>,
+    /// Mutation spread of the output layer relative to the others.
+    pub output_layer_scale: f64,
+    /// Generations between two migrations, 0 for isolated islands.
+    pub island_crossing_interval: usize,
+    /// Migrants exchanged between the islands at every migration.
+    pub island_crossings: usize,
+    /// `crossover` migrants are offspring of parents from two islands, `copy` ones are moved
+    /// over as they are.
+    pub migration: Migration,
+    /// Whether migrants replace `fresh` random individuals or `offspring`.
+    pub migrant_slot: MigrantSlot,
+    /// Whether migrants are `random` ones of the fittest, or chosen by their scores
+    /// `standardized` within their island.
+    pub migrant_choice: MigrantChoice,
+    pub hall_of_fame_size: usize,
+    /// Episodes a candidate for the hall of fame is scored in again before it is admitted with
+    /// the mean score, so a single lucky rollout is neither saved nor taken for the best.
+    pub confirmation_episodes: Option<usize>,
+    pub hall_of_fame_injection_interval: usize,
+    /// Which saved networks are kept, given as a `[retention]` table, all of them without.
+    pub retention: Option<RetentionPolicy>,
+    /// Generations without the best or the median score improving after which the run stops.
+    pub patience: Option<usize>,
+    /// A score at which the run stops.
+    pub target_score: Option<f32>,
+    /// Hours of wall-clock time after which the run stops.
+    pub max_hours: Option<f64>,
+    /// Rollouts after which the run stops, every episode and local search step included.
+    pub max_evaluations: Option<usize>,
+    /// Generations between two checkpoints of a run. Without `--checkpoint <path>` only the
+    /// latest is kept in the model directory.
+    pub checkpoint_interval: usize,
+    /// Where the networks are saved and resumed from.
+    pub model_dir: PathBuf,
+    /// Where every island's scores are logged each generation, as CSV, or as JSON lines when
+    /// the file is named `*.jsonl`.
+    pub results_log: Option<PathBuf>,
+    /// Where TensorBoard event files with the score distributions, operator success and
+    /// evaluation throughput are written.
+    pub metrics_dir: Option<PathBuf>,
+}
+
+const BEST_PROPORTION: f32 = 0.25;
+const ISLAND_POPULATION: usize = 100;
+const ALWAYS_RAND_COUNT: usize = 3;
+/// How many tries per offspring to find one that is not a duplicate.
+const DUPLICATE_RETRIES: usize = 4;
+
+impl Default for TrainConfig {
+    fn default() -> Self {
+        Self {
+            islands: 5,
+            island_population: ISLAND_POPULATION,
+            population_schedule: Vec::new(),
+            generations: 100,
+            best_proportion: BEST_PROPORTION,
+            elites: None,
+            random_per_generation: ALWAYS_RAND_COUNT,
+            selection: Selection::Truncation,
+            selection_pressure: 1.5,
+            speciation: None,
+            novelty: None,
+            map_elites: None,
+            alps: None,
+            fitness_cache: None,
+            warm_start: None,
+            episodes: 1,
+            episode_aggregate: EpisodeAggregate::Mean,
+            episode: EpisodeConfig::default(),
+            island_seeds: false,
+            fitness: FitnessKind::PoseRetention,
+            shaping: ShapingConfig::default(),
+            duplicate_retries: DUPLICATE_RETRIES,
+            initial_sigma: INITIAL_SD,
+            smallest_sigma: SMALLEST_SD,
+            sigma_tau: SIGMA_TAU,
+            annealing: None,
+            output_layer_scale: 0.5,
+            island_crossing_interval: 10,
+            island_crossings: 10,
+            migration: Migration::Crossover,
+            migrant_slot: MigrantSlot::Fresh,
+            migrant_choice: MigrantChoice::Random,
+            hall_of_fame_size: 10,
+            confirmation_episodes: None,
+            hall_of_fame_injection_interval: 10,
+            retention: None,
+            patience: None,
+            target_score: None,
+            max_hours: None,
+            max_evaluations: None,
+            checkpoint_interval: 10,
+            model_dir: PathBuf::from("."),
+            results_log: None,
+            metrics_dir: None,
+        }
+    }
+}
+
+impl TrainConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TrainConfigError> {
+        let text = std::fs::read_to_string(path)?;
+        let config: Self =
+            toml::from_str(&text).map_err(|e| TrainConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Rejects the settings that parse but that a run would fail on.
+    pub fn validate(&self) -> Result<(), TrainConfigError> {
+        let invalid = |what: &str| Err(TrainConfigError::Invalid(what.to_string()));
+        if self.hall_of_fame_injection_interval == 0 {
+            return invalid("hall_of_fame_injection_interval has to be at least 1");
+        }
+        if self
+            .max_hours
+            .is_some_and(|hours| !(hours.is_finite() && hours >= 0.))
+        {
+            return invalid("max_hours has to be a finite non-negative number of hours");
+        }
+        if self.confirmation_episodes == Some(0) {
+            return invalid("confirmation_episodes has to be at least 1");
+        }
+        if self.episode.steps == 0 {
+            return invalid("episode.steps has to be at least 1");
+        }
+        let ball = &self.episode.ball;
+        if !(ball.max_speed.is_finite() && ball.max_speed >= 0.) {
+            return invalid("episode.ball.max_speed has to be a finite non-negative speed");
+        }
+        // the ball is put down on the ground, and moved towards its far end if the spot is taken
+        let (low, high) = ball.x;
+        let radius = BallConfig::default().radius;
+        if !(low <= high && -GROUND_HALF_WIDTH < low - radius && high + radius < GROUND_HALF_WIDTH)
+        {
+            return invalid("episode.ball.x has to be an ascending range on the ground");
+        }
+        if self
+            .map_elites
+            .as_ref()
+            .is_some_and(|map_elites| map_elites.bins == 0)
+        {
+            return invalid("map_elites.bins has to be at least 1");
+        }
+        let world = self
+            .fitness
+            .task(self.shaping)
+            .world_config(0, &self.episode);
+        if let Err(e) = PhysicsWorld::try_with_config(world) {
+            return invalid(&format!("the world of the fitness cannot be built: {e}"));
+        }
+        Ok(())
+    }
+
+    /// How many individuals every island has in `generation`, always more than the fresh random
+    /// ones.
+    pub fn island_size(&self, generation: usize) -> usize {
+        let schedule = &self.population_schedule;
+        let size = match schedule.iter().position(|&(from, _)| from > generation) {
+            None => schedule
+                .last()
+                .map_or(self.island_population, |&(_, size)| size),
+            Some(0) => schedule[0].1,
+            Some(next) => {
+                let (from, start) = schedule[next - 1];
+                let (to, end) = schedule[next];
+                let progress = (generation - from) as f64 / (to - from) as f64;
+                (start as f64 + progress * (end as f64 - start as f64)).round() as usize
+            }
+        };
+        size.max(self.random_per_generation + 1)
+    }
+
+    /// How many of an island's individuals parent the next generation.
+    pub fn number_of_fittest(&self, island_size: usize) -> usize {
+        (self.best_proportion * island_size as f32) as usize
+    }
+
+    /// How many of an island's individuals survive a generation as they are, leaving room
+    /// for the fresh random ones.
+    pub fn elite_count(&self, island_size: usize) -> usize {
+        self.elites
+            .unwrap_or_else(|| self.number_of_fittest(island_size))
+            .min(island_size.saturating_sub(self.random_per_generation))
+    }
+
+    /// The mutation settings of the run, with the sigma schedule and the output layer scale.
+    pub fn mutation(&self) -> MutationConfig {
+        MutationConfig {
+            initial_sigma: self.initial_sigma,
+            smallest_sigma: self.smallest_sigma,
+            sigma_tau: self.sigma_tau,
+            ..MutationConfig::default()
+        }
+        .with_layer_scale("output", self.output_layer_scale)
+    }
+
+    /// The sigma the offspring bred in `generation` get when the run anneals.
+    pub fn annealed_sigma(&self, generation: usize) -> Option<f64> {
+        self.annealing
+            .map(|annealing| annealing.sigma(generation, &self.mutation()))
+    }
+
+    /// Whether the islands exchange migrants once the generation is over, never when they are
+    /// age layers.
+    pub fn migrates_after(&self, generation: usize) -> bool {
+        self.alps.is_none()
+            && self.island_crossing_interval > 0
+            && (generation + 1).is_multiple_of(self.island_crossing_interval)
+    }
+
+    /// Whether the hall of fame and the MAP-Elites archive are injected back into the islands
+    /// once the generation is over, never when they are age layers.
+    pub fn injects_after(&self, generation: usize) -> bool {
+        self.alps.is_none() && generation.is_multiple_of(self.hall_of_fame_injection_interval)
+    }
+
+    /// The seeds of the episodes the island's individuals are scored in, the same ones every
+    /// generation.
+    pub fn episode_seeds(&self, run_seed: u64, island: usize) -> Range<u64> {
+        let first = match self.island_seeds {
+            // drawn by a generator seeded with both, which unlike the standard hasher stays the
+            // same across Rust releases; shifted, so the episodes never run past the last seed
+            true => {
+                let mut seed = [0; 32];
+                seed[..8].copy_from_slice(&run_seed.to_le_bytes());
+                seed[8..16].copy_from_slice(&(island as u64).to_le_bytes());
+                StdRng::from_seed(seed).random::<u64>() >> 16
+            }
+            false => 0,
+        };
+        first..first + self.episodes as u64
+    }
+
+    /// The seeds of the episodes the candidates for the hall of fame are scored in again.
+    pub fn confirmation_seeds(&self, episodes: usize) -> Range<u64> {
+        CONFIRMATION_SEED..CONFIRMATION_SEED + episodes as u64
+    }
+
+    pub fn early_stopping(&self) -> EarlyStopping {
+        EarlyStopping::new(self.patience, self.target_score)
+    }
+
+    /// The time and evaluation budget, starting now.
+    pub fn budget(&self) -> Budget {
+        Budget::new(
+            self.max_hours
+                .map(|hours| Duration::from_secs_f64(hours * 3600.)),
+            self.max_evaluations,
+        )
+    }
+}
+
+/// What a run was started with, written next to its networks so it can be replicated.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RunMetadata {
+    pub seed: u64,
+    /// The command line, without the program.
+    pub args: Vec<String>,
+    pub config: TrainConfig,
+}
+
+impl RunMetadata {
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(
+            path,
+            serde_json::to_string_pretty(self).map_err(std::io::Error::other)?,
+        )
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TrainConfigError> {
+        let text = std::fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(|e| TrainConfigError::Parse(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_settings_keep_their_defaults() {
+        let config: TrainConfig = toml::from_str("islands = 8\ninitial_sigma = 0.1\n").unwrap();
+        assert_eq!(
+            config,
+            TrainConfig {
+                islands: 8,
+                initial_sigma: 0.1,
+                ..TrainConfig::default()
+            }
+        );
+        assert_eq!(config.mutation().initial_sigma, 0.1);
+        assert_eq!(config.mutation().scale_for("output"), 0.5);
+        assert_eq!(config.number_of_fittest(100), 25);
+        assert_eq!(config.elite_count(100), 25);
+        let elitist = TrainConfig {
+            elites: Some(2),
+            ..TrainConfig::default()
+        };
+        assert_eq!(
+            (elitist.number_of_fittest(100), elitist.elite_count(100)),
+            (25, 2)
+        );
+        assert_eq!(
+            TrainConfig {
+                elites: Some(9),
+                ..TrainConfig::default()
+            }
+            .elite_count(10),
+            7
+        );
+        assert!(toml::from_str::<TrainConfig>("island = 8").is_err());
+
+        let config: TrainConfig =
+            toml::from_str("island_crossing_interval = 3\nmigration = \"copy\"").unwrap();
+        assert_eq!(config.migration, Migration::Copy);
+        let migrating: Vec<usize> = (0..9)
+            .filter(|&generation| config.migrates_after(generation))
+            .collect();
+        assert_eq!(migrating, vec![2, 5, 8]);
+        let isolated = TrainConfig {
+            island_crossing_interval: 0,
+            ..config
+        };
+        assert!(!(0..9).any(|generation| isolated.migrates_after(generation)));
+
+        let speciated: TrainConfig =
+            toml::from_str("[speciation]\nthreshold = 0.5\ndescriptor = \"behavior\"").unwrap();
+        assert_eq!(
+            speciated.speciation.unwrap().descriptor,
+            crate::speciation::Descriptor::Behavior
+        );
+        let gridded: TrainConfig = toml::from_str("[map_elites]\nbins = 20").unwrap();
+        assert_eq!(gridded.map_elites.unwrap().injections, 1);
+        let layered: TrainConfig =
+            toml::from_str("[alps]\nage_gap = 5\nscheme = \"linear\"").unwrap();
+        assert!(!(0..9).any(|generation| {
+            TrainConfig {
+                island_crossing_interval: 1,
+                ..layered.clone()
+            }
+            .migrates_after(generation)
+                || layered.injects_after(generation)
+        }));
+        assert_eq!(layered.alps.unwrap().age_cap(1), 10);
+        let warm: TrainConfig = toml::from_str("[warm_start]\nshare = 0.5\nepochs = 3").unwrap();
+        let warm_start = warm.warm_start.unwrap();
+        assert_eq!(
+            (
+                warm_start.distillation().epochs,
+                warm_start.fitted_count(10)
+            ),
+            (3, 5)
+        );
+        let averaged: TrainConfig =
+            toml::from_str("episodes = 4\nepisode_aggregate = { cvar = 0.25 }").unwrap();
+        assert_eq!(
+            (averaged.episodes, averaged.episode_aggregate),
+            (4, EpisodeAggregate::Cvar(0.25))
+        );
+        assert_eq!(averaged.episode_seeds(7, 1), 0..4);
+        let coarse: TrainConfig = toml::from_str("[episode]\naction_repeat = 4").unwrap();
+        assert_eq!(
+            (coarse.episode.steps, coarse.episode.action_repeat),
+            (500, 4)
+        );
+        let aliased: TrainConfig =
+            toml::from_str("[episode]\nphysics_steps_per_action = 4").unwrap();
+        assert_eq!(aliased.episode, coarse.episode);
+        let thrown: TrainConfig =
+            toml::from_str("[episode.ball]\nx = [0.1, 0.2]\nmax_speed = 0.3").unwrap();
+        assert_eq!(
+            (thrown.episode.ball.x, thrown.episode.ball.max_speed),
+            ((0.1, 0.2), 0.3)
+        );
+        let seeded = TrainConfig {
+            island_seeds: true,
+            ..averaged.clone()
+        };
+        assert_eq!(seeded.episode_seeds(7, 1), seeded.episode_seeds(7, 1));
+        assert_eq!(seeded.episode_seeds(7, 1).count(), 4);
+        assert_ne!(seeded.episode_seeds(7, 1), seeded.episode_seeds(7, 2));
+        assert_ne!(seeded.episode_seeds(7, 1), seeded.episode_seeds(8, 1));
+        assert!(seeded.episode_seeds(7, 1).end <= seeded.confirmation_seeds(3).start);
+        assert_eq!(seeded.confirmation_seeds(3).count(), 3);
+        let basket: TrainConfig =
+            toml::from_str("fitness = { ball_in_basket = { x = 0.5, y = -1.5 } }").unwrap();
+        assert_eq!(
+            basket.fitness,
+            FitnessKind::BallInBasket {
+                x: 0.5,
+                y: -1.5,
+                hold_steps: crate::basket_task::DEFAULT_HOLD_STEPS,
+                bonus: crate::basket_task::DEFAULT_BONUS
+            }
+        );
+        assert_eq!(
+            toml::from_str::<TrainConfig>("fitness = \"reach\"")
+                .unwrap()
+                .fitness,
+            FitnessKind::Reach
+        );
+        let shaped: TrainConfig = toml::from_str("[shaping]\nreach = 0.2").unwrap();
+        assert_eq!(
+            shaped.shaping,
+            ShapingConfig {
+                reach: 0.2,
+                energy: 0.,
+                jerk: 0.
+            }
+        );
+
+        let scheduled: TrainConfig =
+            toml::from_str("population_schedule = [[10, 200], [110, 50]]").unwrap();
+        let sizes: Vec<usize> = [0, 10, 60, 110, 500]
+            .map(|generation| scheduled.island_size(generation))
+            .to_vec();
+        assert_eq!(sizes, vec![200, 200, 125, 50, 50]);
+        assert_eq!(TrainConfig::default().island_size(7), ISLAND_POPULATION);
+        let tiny = TrainConfig {
+            population_schedule: vec![(0, 1)],
+            ..TrainConfig::default()
+        };
+        assert_eq!(tiny.island_size(0), ALWAYS_RAND_COUNT + 1);
+
+        let annealed: TrainConfig =
+            toml::from_str("annealing = { exponential = { rate = 0.5 } }").unwrap();
+        assert_eq!(annealed.annealed_sigma(1), Some(INITIAL_SD / 2.));
+        assert_eq!(TrainConfig::default().annealed_sigma(1), None);
+
+        let metadata = RunMetadata {
+            seed: 7,
+            args: vec!["--seed".into(), "7".into()],
+            config: averaged,
+        };
+        let path = std::env::temp_dir().join(format!("run_metadata_{}.json", std::process::id()));
+        metadata.save(&path).unwrap();
+        assert_eq!(RunMetadata::load(&path).unwrap(), metadata);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_unusable_settings_are_rejected() {
+        assert!(TrainConfig::default().validate().is_ok());
+        let path = std::env::temp_dir().join(format!("unusable_{}.toml", std::process::id()));
+        std::fs::write(&path, "hall_of_fame_injection_interval = 0").unwrap();
+        assert!(matches!(
+            TrainConfig::load(&path),
+            Err(TrainConfigError::Invalid(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+        for ball in [
+            "max_speed = inf",
+            "max_speed = -1.0",
+            "x = [0.3, 0.1]",
+            "x = [0.1, 12.0]",
+            "x = [nan, 0.1]",
+        ] {
+            let config: TrainConfig = toml::from_str(&format!("[episode.ball]\n{ball}")).unwrap();
+            assert!(
+                matches!(config.validate(), Err(TrainConfigError::Invalid(_))),
+                "{ball}"
+            );
+        }
+        for hours in [-1., f64::NAN, f64::INFINITY] {
+            let timeless = TrainConfig {
+                max_hours: Some(hours),
+                ..TrainConfig::default()
+            };
+            assert!(
+                matches!(timeless.validate(), Err(TrainConfigError::Invalid(_))),
+                "{hours}"
+            );
+        }
+        let unconfirmed: TrainConfig = toml::from_str("confirmation_episodes = 0").unwrap();
+        assert!(matches!(
+            unconfirmed.validate(),
+            Err(TrainConfigError::Invalid(_))
+        ));
+        let endless: TrainConfig = toml::from_str("[episode]\nsteps = 0").unwrap();
+        assert!(matches!(
+            endless.validate(),
+            Err(TrainConfigError::Invalid(_))
+        ));
+        let gridless: TrainConfig = toml::from_str("[map_elites]\nbins = 0").unwrap();
+        assert!(matches!(
+            gridless.validate(),
+            Err(TrainConfigError::Invalid(_))
+        ));
+        let sunken_basket = TrainConfig {
+            fitness: "ball_in_basket:0.6:-1.95".parse().unwrap(),
+            ..TrainConfig::default()
+        };
+        assert!(matches!(
+            sunken_basket.validate(),
+            Err(TrainConfigError::Invalid(_))
+        ));
+    }
+}
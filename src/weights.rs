@@ -0,0 +1,812 @@
+use crate::base_ai::AI;
+use crate::sim_for_ai::{ACTION_SIZE, CONTACT_SLOTS, FRAME_OBJECT_SLOTS, OBSERVATION_SIZE};
+use burn::module::Param;
+use burn::nn::Linear;
+use burn::prelude::Backend;
+use burn::record::{
+    BurnRecord, FullPrecisionSettings, NamedMpkFileRecorder, Record, Recorder, RecorderError,
+};
+use burn::tensor::{Distribution, Tensor, TensorData};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use safetensors::tensor::TensorView;
+use safetensors::{Dtype, SafeTensors};
+use serde::de::DeserializeOwned;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// Version of the layout networks are saved in, stored next to burn's own metadata. Files
+/// without one predate versioning and are read as version 1. Bump it when a network's record
+/// changes shape, and list what the observation gained in `observation_insertion`.
+///
+/// 1. The segment corners of two frames, then the ball and basket slots of both.
+/// 2. The ball velocity after the ball and basket slots.
+/// 3. The joint velocities and the fingertip contacts after the ball velocity. Stacking the
+///    frames in a `FrameStack` kept the layout of two frames as it was.
+pub const MODEL_FORMAT_VERSION: u32 = 3;
+
+/// Where `version` inserted observation values into the layout of the version before, and how
+/// many. The action feedback comes last in every version.
+fn observation_insertion(version: u32) -> (usize, usize) {
+    // every layout so far has two frames of the default arm's corners and object slots
+    let frames = 2 * (4 * ACTION_SIZE + FRAME_OBJECT_SLOTS);
+    match version {
+        2 => (frames, 2),
+        3 => (frames + 2, ACTION_SIZE + CONTACT_SLOTS),
+        _ => unreachable!("format version {version} kept the observation as it was"),
+    }
+}
+
+/// For every observation value in the layout of `version`, its row in the current layout.
+fn observation_rows(version: u32, rows: usize) -> Vec<usize> {
+    (version + 1..=MODEL_FORMAT_VERSION).fold((0..rows).collect(), |rows, version| {
+        let (offset, count) = observation_insertion(version);
+        rows.into_iter()
+            .map(|row| if row < offset { row } else { row + count })
+            .collect()
+    })
+}
+
+/// Why external weights could not be loaded into a network.
+#[derive(Debug)]
+pub enum WeightsError {
+    Io(std::io::Error),
+    Parse(String),
+    MissingLayer(String),
+    /// Expected and found shapes, as `[outputs, inputs]` for weights and `[outputs]` for biases.
+    Shape {
+        layer: String,
+        expected: Vec<usize>,
+        found: Vec<usize>,
+    },
+    Record(String),
+    /// Saved by a newer version of the crate.
+    Version(u32),
+}
+
+impl Display for WeightsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WeightsError::Io(e) => write!(f, "could not read weights: {e}"),
+            WeightsError::Parse(e) => write!(f, "could not parse weights: {e}"),
+            WeightsError::MissingLayer(layer) => write!(f, "no weights for layer {layer}"),
+            WeightsError::Shape {
+                layer,
+                expected,
+                found,
+            } => {
+                write!(
+                    f,
+                    "layer {layer} expects shape {expected:?}, found {found:?}"
+                )
+            }
+            WeightsError::Record(e) => write!(f, "could not read the saved network: {e}"),
+            WeightsError::Version(version) => {
+                write!(
+                    f,
+                    "saved in format version {version}, only up to {MODEL_FORMAT_VERSION} is known"
+                )
+            }
+        }
+    }
+}
+
+impl Error for WeightsError {}
+
+impl From<std::io::Error> for WeightsError {
+    fn from(e: std::io::Error) -> Self {
+        WeightsError::Io(e)
+    }
+}
+
+impl From<RecorderError> for WeightsError {
+    fn from(e: RecorderError) -> Self {
+        WeightsError::Record(e.to_string())
+    }
+}
+
+impl From<safetensors::SafeTensorError> for WeightsError {
+    fn from(e: safetensors::SafeTensorError) -> Self {
+        WeightsError::Parse(e.to_string())
+    }
+}
+
+/// File format networks are saved in. Both share the `best_<network>_<seq>` naming, only the
+/// extension differs.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum SaveFormat {
+    /// burn's own named MessagePack records.
+    #[default]
+    Mpk,
+    /// `<layer>.weight` and `<layer>.bias` tensors in PyTorch's layout, readable from Python.
+    Safetensors,
+}
+
+impl SaveFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SaveFormat::Mpk => "mpk",
+            SaveFormat::Safetensors => "safetensors",
+        }
+    }
+}
+
+/// One layer in the layout PyTorch uses: a row of input weights per output, then the biases.
+#[derive(Deserialize)]
+struct JsonLayer {
+    weight: Vec<Vec<f32>>,
+    bias: Option<Vec<f32>>,
+}
+
+/// Builds a layer shaped like `like` from PyTorch-ordered `[outputs, inputs]` weights.
+pub(crate) fn linear_from_rows<B: Backend>(
+    like: &Linear<B>,
+    layer: &str,
+    weight: Vec<f32>,
+    weight_shape: [usize; 2],
+    bias: Option<Vec<f32>>,
+) -> Result<Linear<B>, WeightsError> {
+    let device = like.weight.device();
+    let [d_in, d_out] = like.weight.dims();
+    if weight_shape != [d_out, d_in] {
+        return Err(WeightsError::Shape {
+            layer: layer.to_string(),
+            expected: vec![d_out, d_in],
+            found: weight_shape.to_vec(),
+        });
+    }
+    let weight =
+        Tensor::<B, 2>::from_data(TensorData::new(weight, weight_shape), &device).transpose();
+    let bias = match (bias, &like.bias) {
+        (Some(bias), Some(_)) if bias.len() != d_out => {
+            return Err(WeightsError::Shape {
+                layer: layer.to_string(),
+                expected: vec![d_out],
+                found: vec![bias.len()],
+            });
+        }
+        (Some(bias), Some(_)) => Some(Param::from_tensor(Tensor::<B, 1>::from_data(
+            TensorData::new(bias, [d_out]),
+            &device,
+        ))),
+        (None, Some(_)) => return Err(WeightsError::MissingLayer(format!("{layer}.bias"))),
+        (_, None) => None,
+    };
+    Ok(Linear {
+        weight: Param::from_tensor(weight),
+        bias,
+    })
+}
+
+/// Loads weights from a JSON object with one entry per layer name (see `AI::layers`), each
+/// holding a `weight` matrix as a list of rows, one row per output, and a `bias` list.
+/// This is the layout of a PyTorch `state_dict` converted with `tolist()`.
+pub fn import_json<B: Backend, A: AI<B>>(
+    network: &A,
+    path: impl AsRef<Path>,
+) -> Result<A, WeightsError> {
+    let text = std::fs::read_to_string(path)?;
+    let mut json: HashMap<String, JsonLayer> =
+        serde_json::from_str(&text).map_err(|e| WeightsError::Parse(e.to_string()))?;
+    let network = shaped_like_saved(network, |name| {
+        json.get(name).map(|layer| layer.weight.len())
+    });
+    let layers = network
+        .layers()
+        .into_iter()
+        .map(|(name, like)| {
+            let layer = json
+                .remove(name)
+                .ok_or_else(|| WeightsError::MissingLayer(name.to_string()))?;
+            let rows = layer.weight.len();
+            let columns = layer.weight.first().map_or(0, Vec::len);
+            if layer.weight.iter().any(|row| row.len() != columns) {
+                return Err(WeightsError::Parse(format!(
+                    "ragged weight rows in layer {name}"
+                )));
+            }
+            let weight = layer.weight.into_iter().flatten().collect();
+            linear_from_rows(&like, name, weight, [rows, columns], layer.bias)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(network.with_layers(layers))
+}
+
+fn tensor_bytes<B: Backend, const D: usize>(tensor: Tensor<B, D>) -> Vec<u8> {
+    tensor
+        .into_data()
+        .convert::<f32>()
+        .to_vec::<f32>()
+        .expect("weights are not f32")
+        .into_iter()
+        .flat_map(f32::to_le_bytes)
+        .collect()
+}
+
+fn view_floats(view: &TensorView, name: &str) -> Result<Vec<f32>, WeightsError> {
+    if view.dtype() != Dtype::F32 {
+        return Err(WeightsError::Parse(format!(
+            "{name} is {:?}, not F32",
+            view.dtype()
+        )));
+    }
+    Ok(view
+        .data()
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes(bytes.try_into().expect("four bytes")))
+        .collect())
+}
+
+/// Writes every layer as `<layer>.weight` (`[outputs, inputs]`) and `<layer>.bias` tensors.
+pub fn export_safetensors<B: Backend, A: AI<B>>(
+    network: &A,
+    path: impl AsRef<Path>,
+) -> Result<(), WeightsError> {
+    let mut tensors = Vec::new();
+    for (name, layer) in network.layers() {
+        let [d_in, d_out] = layer.weight.dims();
+        tensors.push((
+            format!("{name}.weight"),
+            vec![d_out, d_in],
+            tensor_bytes(layer.weight.val().transpose()),
+        ));
+        if let Some(bias) = layer.bias {
+            tensors.push((
+                format!("{name}.bias"),
+                vec![d_out],
+                tensor_bytes(bias.val()),
+            ));
+        }
+    }
+    let views = tensors
+        .iter()
+        .map(|(name, shape, bytes)| {
+            Ok((
+                name.clone(),
+                TensorView::new(Dtype::F32, shape.clone(), bytes)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, WeightsError>>()?;
+    std::fs::write(path, safetensors::serialize(views, &None)?)?;
+    Ok(())
+}
+
+/// Loads weights written by `export_safetensors`, or by PyTorch's `safetensors.torch.save_file`.
+pub fn import_safetensors<B: Backend, A: AI<B>>(
+    network: &A,
+    path: impl AsRef<Path>,
+) -> Result<A, WeightsError> {
+    let bytes = std::fs::read(path)?;
+    let file = SafeTensors::deserialize(&bytes)?;
+    let network = shaped_like_saved(network, |name| {
+        let weight = file.tensor(&format!("{name}.weight")).ok()?;
+        weight.shape().first().copied()
+    });
+    let layers = network
+        .layers()
+        .into_iter()
+        .map(|(name, like)| {
+            let weight_name = format!("{name}.weight");
+            let weight = file
+                .tensor(&weight_name)
+                .map_err(|_| WeightsError::MissingLayer(weight_name.clone()))?;
+            let weight_shape = match weight.shape() {
+                [rows, columns] => [*rows, *columns],
+                other => {
+                    return Err(WeightsError::Shape {
+                        layer: name.to_string(),
+                        expected: vec![0, 0],
+                        found: other.to_vec(),
+                    })
+                }
+            };
+            let bias_name = format!("{name}.bias");
+            let bias = file
+                .tensor(&bias_name)
+                .ok()
+                .map(|bias| view_floats(&bias, &bias_name))
+                .transpose()?;
+            linear_from_rows(
+                &like,
+                name,
+                view_floats(&weight, &weight_name)?,
+                weight_shape,
+                bias,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(network.with_layers(layers))
+}
+
+/// burn's record with the format version added.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(serialize = "I: Serialize", deserialize = "I: DeserializeOwned"))]
+struct VersionedRecord<I, B: Backend> {
+    #[serde(default)]
+    format_version: u32,
+    #[serde(flatten)]
+    record: BurnRecord<I, B>,
+}
+
+#[derive(Deserialize)]
+struct SavedParam {
+    param: TensorData,
+}
+
+#[derive(Deserialize)]
+struct SavedLinear {
+    weight: SavedParam,
+    bias: Option<SavedParam>,
+}
+
+/// A field of a saved network: a layer, the members of an ensemble, or something a migration
+/// rebuilds fresh, like the observation statistics.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedField {
+    Linear(SavedLinear),
+    Members(Vec<HashMap<String, SavedField>>),
+    Other(IgnoredAny),
+}
+
+fn saved_floats(param: SavedParam) -> Vec<f32> {
+    param
+        .param
+        .convert::<f32>()
+        .to_vec()
+        .expect("saved weights are not f32")
+}
+
+/// Builds a layer shaped like `like` from weights in burn's `[inputs, outputs]` layout.
+fn linear_from_saved<B: Backend>(
+    like: &Linear<B>,
+    layer: &str,
+    weight: Vec<f32>,
+    bias: Option<SavedParam>,
+) -> Result<Linear<B>, WeightsError> {
+    let device = like.weight.device();
+    let [d_in, d_out] = like.weight.dims();
+    let weight = Tensor::<B, 2>::from_data(TensorData::new(weight, [d_in, d_out]), &device);
+    let bias = match (bias, &like.bias) {
+        (Some(bias), Some(_)) if bias.param.shape != [d_out] => {
+            return Err(WeightsError::Shape {
+                layer: format!("{layer}.bias"),
+                expected: vec![d_out],
+                found: bias.param.shape,
+            })
+        }
+        (Some(bias), Some(_)) => Some(Param::from_tensor(Tensor::<B, 1>::from_data(
+            TensorData::new(saved_floats(bias), [d_out]),
+            &device,
+        ))),
+        (None, Some(_)) => return Err(WeightsError::MissingLayer(format!("{layer}.bias"))),
+        (_, None) => None,
+    };
+    Ok(Linear {
+        weight: Param::from_tensor(weight),
+        bias,
+    })
+}
+
+/// A saved layer shaped exactly like `like`.
+fn exact_linear<B: Backend>(
+    like: &Linear<B>,
+    layer: &str,
+    saved: SavedLinear,
+) -> Result<Linear<B>, WeightsError> {
+    let [d_in, d_out] = like.weight.dims();
+    if saved.weight.param.shape != [d_in, d_out] {
+        return Err(WeightsError::Shape {
+            layer: layer.to_string(),
+            expected: vec![d_in, d_out],
+            found: saved.weight.param.shape,
+        });
+    }
+    linear_from_saved(like, layer, saved_floats(saved.weight), saved.bias)
+}
+
+/// Builds a layer reading the current observation from one saved reading the observation of
+/// `version`. Every saved input row goes to where its value is now and the values added since
+/// get zero weights, so the migrated network acts like the saved one.
+fn migrate_input_linear<B: Backend>(
+    like: &Linear<B>,
+    layer: &str,
+    saved: SavedLinear,
+    version: u32,
+) -> Result<Linear<B>, WeightsError> {
+    let [d_in, d_out] = like.weight.dims();
+    let inserted: usize = (version + 1..=MODEL_FORMAT_VERSION)
+        .map(|version| observation_insertion(version).1)
+        .sum();
+    let saved_in = d_in - inserted;
+    if saved.weight.param.shape != [saved_in, d_out] {
+        return Err(WeightsError::Shape {
+            layer: layer.to_string(),
+            expected: vec![saved_in, d_out],
+            found: saved.weight.param.shape,
+        });
+    }
+    let mut weight = vec![0.; d_in * d_out];
+    let rows = observation_rows(version, saved_in);
+    for (row, values) in rows
+        .into_iter()
+        .zip(saved_floats(saved.weight).chunks(d_out))
+    {
+        weight[row * d_out..(row + 1) * d_out].copy_from_slice(values);
+    }
+    linear_from_saved(like, layer, weight, saved.bias)
+}
+
+/// The template with its hidden layers grown or shrunk to the given widths, as far as it can
+/// be: frozen layers and the last unit of a layer stay.
+fn with_hidden_sizes<B: Backend, A: AI<B>>(template: &A, sizes: &[usize]) -> A {
+    let mut template = template.clone();
+    let mut rng = StdRng::seed_from_u64(0);
+    for (hidden, &size) in sizes.iter().enumerate() {
+        loop {
+            let current = template.hidden_sizes()[hidden];
+            let resized = match current.cmp(&size) {
+                Ordering::Less => {
+                    template.grow_hidden(hidden, &Distribution::Normal(0., 0.), &mut rng)
+                }
+                Ordering::Greater => template.shrink_hidden(hidden),
+                Ordering::Equal => break,
+            };
+            if resized.hidden_sizes()[hidden] == current {
+                break;
+            }
+            template = resized;
+        }
+    }
+    template
+}
+
+/// The network with its hidden layers as wide as a saved file has them, given the outputs of
+/// each saved layer by name. Evolution may have grown or shrunk them since the network was
+/// built.
+fn shaped_like_saved<B: Backend, A: AI<B>>(
+    network: &A,
+    outputs: impl Fn(&str) -> Option<usize>,
+) -> A {
+    let sizes: Vec<usize> = network
+        .hidden_sizes()
+        .into_iter()
+        .zip(network.layers())
+        .map(|(size, (name, _))| outputs(name).unwrap_or(size))
+        .collect();
+    with_hidden_sizes(network, &sizes)
+}
+
+/// Whether the loaded network is shaped like the template, up to the hidden layers evolution
+/// grew or shrank.
+fn fits<B: Backend, A: AI<B>>(loaded: &A, template: &A) -> bool {
+    let template = with_hidden_sizes(template, &loaded.hidden_sizes());
+    let shapes = |network: &A| -> Vec<_> {
+        network
+            .layers()
+            .into_iter()
+            .map(|(_, layer)| layer.weight.dims())
+            .collect()
+    };
+    shapes(loaded) == shapes(&template)
+}
+
+/// Saves the network's record with the current `MODEL_FORMAT_VERSION`.
+pub fn save_versioned<B: Backend, A: AI<B>>(
+    network: &A,
+    filename: &str,
+    recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+) -> Result<(), WeightsError> {
+    let item = network
+        .clone()
+        .into_record()
+        .into_item::<FullPrecisionSettings>();
+    let record = VersionedRecord {
+        format_version: MODEL_FORMAT_VERSION,
+        record: BurnRecord::<_, B>::new::<NamedMpkFileRecorder<FullPrecisionSettings>>(item),
+    };
+    Recorder::<B>::save_item(recorder, record, PathBuf::from(filename))?;
+    Ok(())
+}
+
+/// Loads a network saved by `save_versioned`. Files of an older version are migrated: every
+/// layer is read by name, the layers reading the observation get the rows of the values added
+/// since with `migrate_input_linear`, and everything else, like the observation statistics,
+/// starts afresh. Any other difference from the network's shape is an error.
+pub fn load_versioned<B: Backend, A: AI<B>>(
+    network: A,
+    filename: &str,
+    recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+) -> Result<A, WeightsError> {
+    let device = network.devices()[0].clone();
+    let current = Recorder::<B>::load_item::<
+        VersionedRecord<<A::Record as Record<B>>::Item<FullPrecisionSettings>, B>,
+    >(recorder, &mut PathBuf::from(filename));
+    match current {
+        Ok(saved) if saved.format_version > MODEL_FORMAT_VERSION => {
+            return Err(WeightsError::Version(saved.format_version))
+        }
+        Ok(saved) if saved.format_version == MODEL_FORMAT_VERSION => {
+            let loaded = network
+                .clone()
+                .load_record(A::Record::from_item(saved.record.item, &device));
+            if fits(&loaded, &network) {
+                return Ok(loaded);
+            }
+        }
+        // older records, or ones missing fields added since, are migrated below
+        _ => {}
+    }
+    migrate(network, filename, recorder)
+}
+
+fn migrate<B: Backend, A: AI<B>>(
+    network: A,
+    filename: &str,
+    recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+) -> Result<A, WeightsError> {
+    let saved = Recorder::<B>::load_item::<VersionedRecord<HashMap<String, SavedField>, B>>(
+        recorder,
+        &mut PathBuf::from(filename),
+    )?;
+    if saved.format_version > MODEL_FORMAT_VERSION {
+        return Err(WeightsError::Version(saved.format_version));
+    }
+    let version = saved.format_version.max(1);
+    let mut fields = saved.record.item;
+    // an ensemble saves its members as a list, their layers are taken one member after the other
+    let mut members: VecDeque<_> = match fields.remove("members") {
+        Some(SavedField::Members(members)) if !members.is_empty() => members.into(),
+        _ => VecDeque::from([fields]),
+    };
+    let mut layers = Vec::new();
+    for (name, _) in network.layers() {
+        if !members[0].contains_key(name) && members.len() > 1 {
+            members.pop_front();
+        }
+        match members[0].remove(name) {
+            Some(SavedField::Linear(saved)) => layers.push(saved),
+            _ => return Err(WeightsError::MissingLayer(name.to_string())),
+        }
+    }
+    // the hidden layers are as wide as evolution left them
+    let hidden_sizes: Vec<usize> = layers
+        .iter()
+        .take(network.hidden_sizes().len())
+        .map(|saved| saved.weight.param.shape.get(1).copied().unwrap_or(0))
+        .collect();
+    let network = with_hidden_sizes(&network, &hidden_sizes);
+    let layers = network
+        .layers()
+        .into_iter()
+        .zip(layers)
+        .map(|((name, like), saved)| {
+            if version < MODEL_FORMAT_VERSION && like.weight.dims()[0] == OBSERVATION_SIZE {
+                migrate_input_linear(&like, name, saved, version)
+            } else {
+                exact_linear(&like, name, saved)
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(network.with_layers(layers))
+}
+
+/// Saves under `filename` plus the format's extension.
+pub fn save_as<B: Backend, A: AI<B>>(
+    network: &A,
+    filename: &str,
+    format: SaveFormat,
+    recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+) {
+    match format {
+        SaveFormat::Mpk => network.save_file(filename, recorder),
+        SaveFormat::Safetensors => {
+            export_safetensors(network, format!("{filename}.{}", format.extension()))
+                .expect("save failed")
+        }
+    }
+}
+
+/// Loads a saved network, picking the format from the file extension.
+pub fn load_saved<B: Backend, A: AI<B>>(
+    network: A,
+    filename: &str,
+    recorder: &NamedMpkFileRecorder<FullPrecisionSettings>,
+) -> A {
+    if filename.ends_with(SaveFormat::Safetensors.extension()) {
+        import_safetensors(&network, filename).expect("load failed")
+    } else if filename.ends_with(".json") {
+        import_json(&network, filename).expect("load failed")
+    } else {
+        network.load_a_file(filename, recorder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::small_ai::SmallAI;
+    use burn::backend::ndarray::NdArrayDevice;
+    use burn::backend::NdArray;
+    use burn::module::Module;
+    use std::env::temp_dir;
+
+    fn rows(outputs: usize, inputs: usize, value: f32) -> Vec<Vec<f32>> {
+        vec![vec![value; inputs]; outputs]
+    }
+
+    #[test]
+    fn test_import_json() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let network = SmallAI::<BE>::new(&device);
+        let [input, hidden, output] =
+            [("input", 0.01), ("hidden", 0.02), ("output", 0.03)].map(|(name, value)| {
+                let (_, layer) = network
+                    .layers()
+                    .into_iter()
+                    .find(|(n, _)| *n == name)
+                    .unwrap();
+                let [d_in, d_out] = layer.weight.dims();
+                (name, rows(d_out, d_in, value), vec![value; d_out])
+            });
+        let json: serde_json::Map<String, serde_json::Value> = [input, hidden, output]
+            .into_iter()
+            .map(|(name, weight, bias)| {
+                (
+                    name.to_string(),
+                    serde_json::json!({ "weight": weight, "bias": bias }),
+                )
+            })
+            .collect();
+        let path = temp_dir().join(format!("import_json_small_ai_{}.json", std::process::id()));
+        std::fs::write(&path, serde_json::Value::Object(json).to_string()).unwrap();
+
+        let imported = import_json(&network, &path).unwrap();
+        assert!((imported.max_amp() - 0.03).abs() < 1e-6);
+
+        // burn's own [inputs, outputs] order is rejected
+        let (_, first) = imported.layers().into_iter().next().unwrap();
+        let [d_in, d_out] = first.weight.dims();
+        let wrong = serde_json::json!({ "input": { "weight": rows(d_in, d_out, 0.), "bias": vec![0.; d_out] } });
+        std::fs::write(&path, wrong.to_string()).unwrap();
+        assert!(matches!(
+            import_json(&network, &path),
+            Err(WeightsError::Shape { .. })
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_safetensors_round_trip() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let network = SmallAI::<BE>::new(&device);
+        let path = temp_dir().join(format!(
+            "round_trip_small_ai_{}.safetensors",
+            std::process::id()
+        ));
+        export_safetensors(&network, &path).unwrap();
+        let loaded = import_safetensors(&SmallAI::<BE>::new(&device), &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let observation =
+            Tensor::<BE, 1>::ones([crate::sim_for_ai::OBSERVATION_SIZE], &device) * 0.01;
+        assert_eq!(
+            loaded.apply(observation.clone()).to_data(),
+            network.apply(observation).to_data()
+        );
+        assert_eq!(loaded.max_amp(), network.max_amp());
+
+        // a network evolution grew and shrank loads into the default one at its own widths
+        let mut rng = StdRng::seed_from_u64(2);
+        let resized = network
+            .grow_hidden(1, &Distribution::Normal(0., 0.01), &mut rng)
+            .grow_hidden(1, &Distribution::Normal(0., 0.01), &mut rng)
+            .shrink_hidden(0);
+        export_safetensors(&resized, &path).unwrap();
+        let loaded = import_safetensors(&SmallAI::<BE>::new(&device), &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.hidden_sizes(), resized.hidden_sizes());
+        assert_eq!(loaded.to_flat_vec(), resized.to_flat_vec());
+    }
+
+    #[test]
+    fn test_old_files_are_migrated() {
+        type BE = NdArray<f32>;
+        let device = NdArrayDevice::Cpu;
+        let recorder = NamedMpkFileRecorder::<FullPrecisionSettings>::new();
+        let network = SmallAI::<BE>::new(&device);
+        let path = temp_dir().join(format!("versioned_small_ai_{}", std::process::id()));
+        let filename = path.to_str().unwrap();
+        save_versioned(&network, filename, &recorder).unwrap();
+        let loaded = load_versioned(SmallAI::<BE>::new(&device), filename, &recorder).unwrap();
+        assert_eq!(loaded.to_flat_vec(), network.to_flat_vec());
+        // evolution may have grown the hidden layers since
+        let grown = network.grow_hidden(
+            0,
+            &Distribution::Normal(0., 0.01),
+            &mut StdRng::seed_from_u64(1),
+        );
+        save_versioned(&grown, filename, &recorder).unwrap();
+        let loaded = load_versioned(SmallAI::<BE>::new(&device), filename, &recorder).unwrap();
+        assert_eq!(loaded.to_flat_vec(), grown.to_flat_vec());
+
+        // a file from before versioning, when the observation had neither the ball velocity,
+        // nor the joint velocities and contacts
+        let narrowed = |rows: usize| {
+            let mut layers: Vec<Linear<BE>> = network
+                .layers()
+                .into_iter()
+                .map(|(_, layer)| layer)
+                .collect();
+            layers[0] = Linear {
+                weight: Param::from_tensor(layers[0].weight.val().narrow(0, 0, rows)),
+                bias: layers[0].bias.clone(),
+            };
+            network.with_layers(layers)
+        };
+        let (_, input) = network.layers().into_iter().next().unwrap();
+        let [d_in, d_out] = input.weight.dims();
+        let inserted = 2 + ACTION_SIZE + CONTACT_SLOTS;
+        Module::save_file(narrowed(d_in - inserted), &path, &recorder).unwrap();
+        let migrated = load_versioned(SmallAI::<BE>::new(&device), filename, &recorder).unwrap();
+        let (_, migrated_input) = migrated.layers().into_iter().next().unwrap();
+        assert_eq!(migrated_input.weight.dims(), [d_in, d_out]);
+        let frames = 2 * (4 * ACTION_SIZE + FRAME_OBJECT_SLOTS);
+        let rows = |layer: &Linear<BE>, rows: std::ops::Range<usize>| {
+            layer.weight.val().slice([rows, 0..d_out]).to_data()
+        };
+        assert_eq!(rows(&migrated_input, 0..frames), rows(&input, 0..frames));
+        assert_eq!(
+            migrated_input
+                .weight
+                .val()
+                .narrow(0, frames, inserted)
+                .abs()
+                .sum()
+                .into_scalar(),
+            0.
+        );
+        assert_eq!(
+            migrated.layers()[2].1.weight.val().to_data(),
+            network.layers()[2].1.weight.val().to_data()
+        );
+
+        // a file of version 2 keeps its ball velocity rows where they are
+        let versioned = |network: SmallAI<BE>, format_version| VersionedRecord {
+            format_version,
+            record: BurnRecord::<_, BE>::new::<NamedMpkFileRecorder<FullPrecisionSettings>>(
+                network.into_record().into_item::<FullPrecisionSettings>(),
+            ),
+        };
+        let version_2 = versioned(narrowed(d_in - ACTION_SIZE - CONTACT_SLOTS), 2);
+        Recorder::<BE>::save_item(&recorder, version_2, path.clone()).unwrap();
+        let migrated = load_versioned(SmallAI::<BE>::new(&device), filename, &recorder).unwrap();
+        let (_, migrated_input) = migrated.layers().into_iter().next().unwrap();
+        assert_eq!(
+            rows(&migrated_input, 0..frames + 2),
+            rows(&input, 0..frames + 2)
+        );
+
+        // a layout no version had is rejected rather than guessed at
+        Module::save_file(narrowed(d_in - 2), &path, &recorder).unwrap();
+        assert!(matches!(
+            load_versioned(SmallAI::<BE>::new(&device), filename, &recorder),
+            Err(WeightsError::Shape { .. })
+        ));
+
+        let newer = versioned(network.clone(), MODEL_FORMAT_VERSION + 1);
+        Recorder::<BE>::save_item(&recorder, newer, path.clone()).unwrap();
+        assert!(matches!(
+            load_versioned(SmallAI::<BE>::new(&device), filename, &recorder),
+            Err(WeightsError::Version(_))
+        ));
+        std::fs::remove_file(path.with_extension("mpk")).unwrap();
+    }
+}